@@ -0,0 +1,241 @@
+use std::marker::PhantomData;
+
+use futures_util::future::join_all;
+use log::warn;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::arr::ArrService;
+use crate::config::models::{default_instance, ArrInstanceConfig};
+use crate::config::SharedConfig;
+use crate::http_error::{Error, Result};
+
+/// `?instance=` selector accepted by every `/api/sonarr/*` and `/api/radarr/*`
+/// route. Without it the default instance is used (or all instances, for
+/// merged endpoints).
+#[derive(Deserialize)]
+pub struct InstanceQuery {
+    pub instance: Option<String>,
+}
+
+/// HTTP client for one kind of *arr service, across all its instances.
+pub struct ArrClient<S: ArrService> {
+    pub http: Client,
+    pub config: SharedConfig,
+    service: PhantomData<S>,
+}
+
+impl<S: ArrService> ArrClient<S> {
+    pub fn new(config: SharedConfig) -> Self {
+        let http = Client::builder()
+            .timeout(std::time::Duration::from_secs(120))
+            .build()
+            .expect("Failed to create HTTP client");
+        Self { http, config, service: PhantomData }
+    }
+
+    /// All instances with a URL and API key.
+    pub fn instances(&self) -> Vec<ArrInstanceConfig> {
+        self.config
+            .read()
+            .map(|cfg| S::instances(&cfg).iter().filter(|i| i.is_configured()).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The instance named `name`, or the default instance.
+    pub fn instance(&self, name: Option<&str>) -> Result<ArrInstanceConfig> {
+        let instances = self.instances();
+        match name.filter(|n| !n.is_empty()) {
+            Some(name) => instances
+                .into_iter()
+                .find(|i| i.label().eq_ignore_ascii_case(name))
+                .ok_or_else(|| Error::NotFound(format!("{} instance '{}' not found", S::NAME, name))),
+            None => default_instance(&instances)
+                .cloned()
+                .ok_or_else(|| Error::ServiceUnavailable(format!("{} is not configured", S::NAME))),
+        }
+    }
+
+    /// Instances a merged endpoint should query: the named one, or all of them.
+    pub fn targets(&self, name: Option<&str>) -> Result<Vec<ArrInstanceConfig>> {
+        if name.is_some_and(|n| !n.is_empty()) {
+            return Ok(vec![self.instance(name)?]);
+        }
+        let instances = self.instances();
+        if instances.is_empty() {
+            return Err(Error::ServiceUnavailable(format!("{} is not configured", S::NAME)));
+        }
+        Ok(instances)
+    }
+
+    fn url(instance: &ArrInstanceConfig, path: &str) -> String {
+        format!("{}/api/v3{}", instance.url.trim_end_matches('/'), path)
+    }
+
+    pub fn get(&self, instance: &ArrInstanceConfig, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .get(Self::url(instance, path))
+            .header("X-Api-Key", &instance.api_key)
+    }
+
+    pub fn post(&self, instance: &ArrInstanceConfig, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .post(Self::url(instance, path))
+            .header("X-Api-Key", &instance.api_key)
+            .header("Content-Type", "application/json")
+    }
+
+    pub fn put(&self, instance: &ArrInstanceConfig, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .put(Self::url(instance, path))
+            .header("X-Api-Key", &instance.api_key)
+            .header("Content-Type", "application/json")
+    }
+
+    pub fn delete(&self, instance: &ArrInstanceConfig, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .delete(Self::url(instance, path))
+            .header("X-Api-Key", &instance.api_key)
+            .header("Content-Type", "application/json")
+    }
+
+    /// Send a request and parse the response as `T`. `what` names the
+    /// resource in error messages ("series", "queue", ...).
+    /// Non-2xx responses are mapped the same way `PlexClient::send_json` does.
+    pub async fn send_json<T: DeserializeOwned>(&self, req: reqwest::RequestBuilder, what: &str) -> Result<T> {
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| Error::UpstreamError(format!("{} request for {} failed: {}", S::NAME, what, e)))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(status_error::<S>(status, what, &body));
+        }
+
+        let body = resp
+            .json()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", what, e))?;
+        Ok(body)
+    }
+
+    /// Like `send_json` for endpoints that answer with an empty body.
+    pub async fn send(&self, req: reqwest::RequestBuilder, what: &str) -> Result<()> {
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| Error::UpstreamError(format!("{} request for {} failed: {}", S::NAME, what, e)))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(status_error::<S>(status, what, &body));
+        }
+        Ok(())
+    }
+
+    /// GET `path` from every target instance concurrently. With a single
+    /// target errors are returned; when merging, failing instances are
+    /// logged and left out so one offline server doesn't hide the rest.
+    pub async fn get_each<T: DeserializeOwned>(
+        &self,
+        targets: &[ArrInstanceConfig],
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<(ArrInstanceConfig, T)>> {
+        let requests = targets.iter().map(|instance| async move {
+            let req = self.get(instance, path).query(query);
+            let what = format!("{} from {} '{}'", path, S::NAME, instance.label());
+            let body: T = self.send_json(req, &what).await?;
+            Ok::<_, Error>((instance.clone(), body))
+        });
+
+        let results = join_all(requests).await;
+        if results.len() == 1 {
+            return results.into_iter().collect();
+        }
+        Ok(results
+            .into_iter()
+            .filter_map(|r| r.map_err(|e| warn!("{}", e)).ok())
+            .collect())
+    }
+}
+
+/// Serialize typed items and add an `instance` field to each so merged
+/// results can be told apart.
+pub fn tag_instance<T: serde::Serialize>(items: &[T], instance: &ArrInstanceConfig) -> Vec<serde_json::Value> {
+    items
+        .iter()
+        .filter_map(|item| serde_json::to_value(item).ok())
+        .map(|mut item| {
+            if let Some(obj) = item.as_object_mut() {
+                obj.insert("instance".to_string(), serde_json::json!(instance.label()));
+            }
+            item
+        })
+        .collect()
+}
+
+/// One entry of the validation error array Sonarr and Radarr return with a 400.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidationFailure {
+    #[serde(default)]
+    property_name: String,
+    #[serde(default)]
+    error_message: String,
+}
+
+/// Map a non-2xx *arr response to an error the frontend can act on.
+fn status_error<S: ArrService>(status: reqwest::StatusCode, what: &str, body: &str) -> Error {
+    use reqwest::StatusCode;
+
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Error::Unauthorized(format!(
+            "{} rejected the API key. Check it in settings.",
+            S::NAME
+        )),
+        StatusCode::NOT_FOUND => Error::NotFound(format!("{} not found in {}", what, S::NAME)),
+        StatusCode::BAD_REQUEST | StatusCode::CONFLICT | StatusCode::UNPROCESSABLE_ENTITY => {
+            Error::BadRequest(format!("{} rejected the request: {}", S::NAME, rejection_message(body)))
+        }
+        _ => Error::UpstreamError(format!(
+            "{} returned HTTP {} for {}: {}",
+            S::NAME,
+            status.as_u16(),
+            what,
+            &body[..body.floor_char_boundary(200)]
+        )),
+    }
+}
+
+/// Validation failures come back as `[{propertyName, errorMessage}, ...]`,
+/// other errors as `{message}` or plain text.
+fn rejection_message(body: &str) -> String {
+    if let Ok(failures) = serde_json::from_str::<Vec<ValidationFailure>>(body) {
+        let messages: Vec<String> = failures
+            .into_iter()
+            .filter(|f| !f.error_message.is_empty())
+            .map(|f| match f.property_name.is_empty() {
+                true => f.error_message,
+                false => format!("{}: {}", f.property_name, f.error_message),
+            })
+            .collect();
+        if !messages.is_empty() {
+            return messages.join("; ");
+        }
+    }
+    if let Some(message) = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["message"].as_str().map(str::to_string))
+    {
+        return message;
+    }
+    match body.trim() {
+        "" => "no details given".to_string(),
+        text => text[..text.floor_char_boundary(200)].to_string(),
+    }
+}
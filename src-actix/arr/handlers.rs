@@ -0,0 +1,117 @@
+//! Routes that are identical for Sonarr and Radarr. Registered per service
+//! with `configure::<Sonarr>` / `configure::<Radarr>`.
+
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+
+use crate::arr::client::{tag_instance, ArrClient, InstanceQuery};
+use crate::arr::models::{Command, CommandRequest, GrabRequest, QualityProfile, QueuePage, RootFolder};
+use crate::arr::ArrService;
+use crate::cache::{self, ResponseCache};
+use crate::commands::CommandTracker;
+use crate::http_error::Result;
+use crate::plex::client::PlexClient;
+
+async fn quality_profiles<S: ArrService>(
+    req: HttpRequest,
+    client: web::Data<ArrClient<S>>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+) -> Result<impl Responder> {
+    let instance = client.instance(inst.instance.as_deref())?;
+    let key = ResponseCache::key(S::CACHE, &format!("{}:/qualityprofile", instance.label()), &req);
+    let entry = cache.get_or_fetch(key, S::CACHE, || async {
+        let profiles: Vec<QualityProfile> = client
+            .send_json(client.get(&instance, "/qualityprofile"), "quality profiles")
+            .await?;
+        Ok(serde_json::to_value(profiles).map_err(anyhow::Error::from)?)
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, S::CACHE))
+}
+
+async fn root_folders<S: ArrService>(
+    req: HttpRequest,
+    client: web::Data<ArrClient<S>>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+) -> Result<impl Responder> {
+    let instance = client.instance(inst.instance.as_deref())?;
+    let key = ResponseCache::key(S::CACHE, &format!("{}:/rootfolder", instance.label()), &req);
+    let entry = cache.get_or_fetch(key, S::CACHE, || async {
+        let folders: Vec<RootFolder> = client
+            .send_json(client.get(&instance, "/rootfolder"), "root folders")
+            .await?;
+        Ok(serde_json::to_value(folders).map_err(anyhow::Error::from)?)
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, S::CACHE))
+}
+
+/// Download queue of the selected instance, or of all instances merged into
+/// a single page when none is given.
+async fn queue<S: ArrService>(
+    client: web::Data<ArrClient<S>>,
+    inst: web::Query<InstanceQuery>,
+) -> Result<impl Responder> {
+    let targets = client.targets(inst.instance.as_deref())?;
+    let mut query = vec![("pageSize", "50")];
+    query.extend_from_slice(S::QUEUE_INCLUDES);
+    let results = client.get_each::<QueuePage>(&targets, "/queue", &query).await?;
+
+    let records: Vec<serde_json::Value> = results
+        .iter()
+        .flat_map(|(instance, page)| tag_instance(&page.records, instance))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "page": 1,
+        "pageSize": records.len(),
+        "totalRecords": records.len(),
+        "records": records,
+    })))
+}
+
+/// Queue a command upstream and follow it in `/api/commands` until it finishes.
+async fn run_command<S: ArrService>(
+    req: HttpRequest,
+    client: web::Data<ArrClient<S>>,
+    tracker: web::Data<CommandTracker>,
+    inst: web::Query<InstanceQuery>,
+    body: web::Json<CommandRequest>,
+) -> Result<impl Responder> {
+    let instance = client.instance(inst.instance.as_deref())?;
+    let command = body.into_inner();
+    command.validate()?;
+
+    let queued: Command = client
+        .send_json(client.post(&instance, "/command").json(&command), "command")
+        .await?;
+    let user = PlexClient::user_from_request(&req).map(|(id, _)| id);
+    tracker.track(S::SOURCE, instance.label(), &queued, user);
+
+    Ok(HttpResponse::Ok().json(queued))
+}
+
+async fn grab_release<S: ArrService>(
+    client: web::Data<ArrClient<S>>,
+    inst: web::Query<InstanceQuery>,
+    body: web::Json<GrabRequest>,
+) -> Result<impl Responder> {
+    let instance = client.instance(inst.instance.as_deref())?;
+    let grab = body.into_inner();
+    grab.validate()?;
+
+    let body: serde_json::Value = client
+        .send_json(client.post(&instance, "/release").json(&grab), "release")
+        .await?;
+
+    Ok(HttpResponse::Ok().json(body))
+}
+
+pub fn configure<S: ArrService>(cfg: &mut web::ServiceConfig) {
+    cfg.route("/qualityprofile", web::get().to(quality_profiles::<S>))
+        .route("/rootfolder", web::get().to(root_folders::<S>))
+        .route("/queue", web::get().to(queue::<S>))
+        .route("/command", web::post().to(run_command::<S>))
+        .route("/release", web::post().to(grab_release::<S>));
+}
@@ -0,0 +1,50 @@
+//! Shared core for the Sonarr and Radarr integrations. Both speak the same
+//! v3 API for profiles, root folders, queue, commands and releases, so those
+//! handlers live here once, generic over the service.
+
+pub mod client;
+pub mod handlers;
+pub mod models;
+pub mod releases;
+
+use crate::cache::{self, CacheRoute};
+use crate::config::models::{AppConfig, ArrInstanceConfig};
+use crate::events::EventSource;
+
+/// Compile-time description of a Sonarr-compatible service.
+pub trait ArrService: Send + Sync + 'static {
+    /// Display name used in error messages.
+    const NAME: &'static str;
+    const SOURCE: EventSource;
+    const CACHE: CacheRoute;
+    /// Extra query parameters for `/queue` so records carry their media.
+    const QUEUE_INCLUDES: &'static [(&'static str, &'static str)];
+
+    fn instances(cfg: &AppConfig) -> &[ArrInstanceConfig];
+}
+
+pub struct Sonarr;
+pub struct Radarr;
+
+impl ArrService for Sonarr {
+    const NAME: &'static str = "Sonarr";
+    const SOURCE: EventSource = EventSource::Sonarr;
+    const CACHE: CacheRoute = cache::SONARR;
+    const QUEUE_INCLUDES: &'static [(&'static str, &'static str)] =
+        &[("includeSeries", "true"), ("includeEpisode", "true")];
+
+    fn instances(cfg: &AppConfig) -> &[ArrInstanceConfig] {
+        &cfg.sonarr
+    }
+}
+
+impl ArrService for Radarr {
+    const NAME: &'static str = "Radarr";
+    const SOURCE: EventSource = EventSource::Radarr;
+    const CACHE: CacheRoute = cache::RADARR;
+    const QUEUE_INCLUDES: &'static [(&'static str, &'static str)] = &[("includeMovie", "true")];
+
+    fn instances(cfg: &AppConfig) -> &[ArrInstanceConfig] {
+        &cfg.radarr
+    }
+}
@@ -0,0 +1,269 @@
+//! Typed resources shared by Sonarr and Radarr. Every struct defaults missing
+//! fields and ignores unknown ones so upstream schema additions don't break
+//! deserialization; what we serialize back is a stable projection.
+
+use serde::{Deserialize, Serialize};
+
+use crate::http_error::{Error, Result};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Image {
+    pub cover_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct QualityProfile {
+    pub id: u64,
+    pub name: String,
+    pub upgrade_allowed: bool,
+    pub cutoff: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RootFolder {
+    pub id: u64,
+    pub path: String,
+    pub accessible: bool,
+    pub free_space: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Quality {
+    pub id: u64,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<u32>,
+}
+
+/// Quality plus revision, as attached to releases, files and queue records.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct QualityModel {
+    pub quality: Quality,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Language {
+    pub id: i64,
+    pub name: String,
+}
+
+/// A release found by a manual search.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Release {
+    pub guid: String,
+    pub title: String,
+    pub indexer: String,
+    pub indexer_id: u64,
+    pub size: u64,
+    pub age: i64,
+    pub age_hours: f64,
+    pub age_minutes: f64,
+    pub quality: QualityModel,
+    pub languages: Vec<Language>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seeders: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leechers: Option<u64>,
+    pub protocol: String,
+    pub approved: bool,
+    pub rejected: bool,
+    pub rejections: Vec<String>,
+    pub publish_date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info_url: Option<String>,
+    pub indexer_flags: i64,
+    pub custom_format_score: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub season_number: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub episode_numbers: Vec<u64>,
+    pub full_season: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct QueueRecord {
+    pub id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub season_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub movie_id: Option<u64>,
+    /// Filled in by the `QUEUE_INCLUDES` query flags.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub movie: Option<serde_json::Value>,
+    pub title: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracked_download_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracked_download_state: Option<String>,
+    pub size: f64,
+    pub sizeleft: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeleft: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_completion_time: Option<String>,
+    pub protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_client: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexer: Option<String>,
+    pub quality: QualityModel,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub status_messages: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct QueuePage {
+    pub page: u64,
+    pub page_size: u64,
+    pub total_records: u64,
+    pub records: Vec<QueueRecord>,
+}
+
+/// Body of `POST /command`. Command-specific fields (`seriesId`,
+/// `episodeIds`, `movieIds`, ...) are passed through untouched.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CommandRequest {
+    pub name: String,
+    #[serde(flatten)]
+    pub params: serde_json::Map<String, serde_json::Value>,
+}
+
+impl CommandRequest {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(Error::BadRequest("Command name is required".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Body of `POST /release`: grab a release from a previous search.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrabRequest {
+    #[serde(default)]
+    pub guid: String,
+    #[serde(default)]
+    pub indexer_id: u64,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl GrabRequest {
+    pub fn validate(&self) -> Result<()> {
+        if self.guid.trim().is_empty() {
+            return Err(Error::BadRequest("Release guid is required".to_string()));
+        }
+        require_id("indexerId", self.indexer_id)
+    }
+}
+
+pub fn require_id(field: &str, value: u64) -> Result<()> {
+    if value == 0 {
+        return Err(Error::BadRequest(format!("{} is required", field)));
+    }
+    Ok(())
+}
+
+pub fn require_text(field: &str, value: &str) -> Result<()> {
+    if value.trim().is_empty() {
+        return Err(Error::BadRequest(format!("{} is required", field)));
+    }
+    Ok(())
+}
+
+/// Root folders must be absolute (`/tv`, `C:\TV` or a UNC share).
+pub fn require_absolute_path(field: &str, value: &str) -> Result<()> {
+    require_text(field, value)?;
+    let bytes = value.as_bytes();
+    let windows_drive = bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'\\' | b'/');
+    if value.starts_with('/') || value.starts_with("\\\\") || windows_drive {
+        Ok(())
+    } else {
+        Err(Error::BadRequest(format!("{} must be an absolute path, got '{}'", field, value)))
+    }
+}
+
+/// `?fields=id,title` keeps only the listed top-level fields.
+#[derive(Deserialize)]
+pub struct FieldsQuery {
+    pub fields: Option<String>,
+}
+
+impl FieldsQuery {
+    pub fn apply(&self, value: serde_json::Value) -> serde_json::Value {
+        let Some(fields) = self.fields.as_deref().filter(|f| !f.trim().is_empty()) else {
+            return value;
+        };
+        let keep: Vec<&str> = fields.split(',').map(|f| f.trim()).filter(|f| !f.is_empty()).collect();
+        let project = |item: serde_json::Value| match item {
+            serde_json::Value::Object(map) => serde_json::Value::Object(
+                map.into_iter().filter(|(k, _)| keep.contains(&k.as_str())).collect(),
+            ),
+            other => other,
+        };
+        match value {
+            serde_json::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(project).collect()),
+            other => project(other),
+        }
+    }
+}
+
+/// A queued or finished command, from `POST /command` or `GET /command/{id}`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Command {
+    pub id: u64,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_name: Option<String>,
+    /// `queued`, `started`, `completed`, `failed`, `aborted`, `cancelled` or `orphaned`.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queued: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<String>,
+    pub body: serde_json::Value,
+}
+
+impl Command {
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status.as_str(), "completed" | "failed" | "aborted" | "cancelled" | "orphaned")
+    }
+}
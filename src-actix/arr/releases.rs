@@ -0,0 +1,204 @@
+//! Filtering, sorting and paging for manual-search results. A full-season
+//! search can return thousands of releases, so the browser only ever gets
+//! one page of them.
+
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::arr::models::Release;
+use crate::http_error::{Error, Result};
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReleaseSort {
+    Size,
+    Seeders,
+    Indexer,
+    CustomFormatScore,
+    Age,
+    Quality,
+    Title,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Query parameters accepted by `GET /api/{sonarr,radarr}/release` next to
+/// the service-specific ids. Without any of them the upstream order is kept.
+/// `indexer`, `language`, `quality` and `protocol` take comma-separated
+/// values and match any of them.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseFilter {
+    /// Case-insensitive substring of the release title.
+    pub search: Option<String>,
+    pub indexer: Option<String>,
+    pub language: Option<String>,
+    pub quality: Option<String>,
+    /// `torrent` or `usenet`.
+    pub protocol: Option<String>,
+    /// `true` for rejected releases only, `false` for approved ones only.
+    pub rejected: Option<bool>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub min_seeders: Option<u64>,
+    pub min_score: Option<i64>,
+    pub sort: Option<ReleaseSort>,
+    pub order: Option<SortOrder>,
+    pub page: Option<usize>,
+    pub page_size: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseView {
+    #[serde(flatten)]
+    pub release: Release,
+    /// One-line summary of `rejections`, absent for approved releases.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejection_summary: Option<String>,
+}
+
+/// Distinct values across the unfiltered results, for filter dropdowns.
+#[derive(Debug, Default, Serialize)]
+pub struct ReleaseFacets {
+    pub indexers: BTreeSet<String>,
+    pub languages: BTreeSet<String>,
+    pub qualities: BTreeSet<String>,
+    pub protocols: BTreeSet<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleasePage {
+    pub page: usize,
+    pub page_size: usize,
+    /// Releases left after filtering.
+    pub total_records: usize,
+    /// Releases returned by the indexers before filtering.
+    pub unfiltered_records: usize,
+    pub rejected_records: usize,
+    pub facets: ReleaseFacets,
+    pub records: Vec<ReleaseView>,
+}
+
+impl ReleaseFilter {
+    pub fn validate(&self) -> Result<()> {
+        if self.page == Some(0) {
+            return Err(Error::BadRequest("page starts at 1".to_string()));
+        }
+        if let Some(size) = self.page_size.filter(|s| *s == 0 || *s > MAX_PAGE_SIZE) {
+            return Err(Error::BadRequest(format!("pageSize must be between 1 and {}, got {}", MAX_PAGE_SIZE, size)));
+        }
+        if self.min_size.zip(self.max_size).is_some_and(|(min, max)| min > max) {
+            return Err(Error::BadRequest("minSize must not be larger than maxSize".to_string()));
+        }
+        Ok(())
+    }
+
+    fn matches(&self, release: &Release) -> bool {
+        let contains = |haystack: &str, needle: &Option<String>| {
+            needle.as_deref().is_none_or(|n| haystack.to_lowercase().contains(&n.to_lowercase()))
+        };
+        let equals = |value: &str, wanted: &Option<String>| {
+            wanted.as_deref().is_none_or(|w| w.split(',').any(|w| value.eq_ignore_ascii_case(w.trim())))
+        };
+
+        contains(&release.title, &self.search)
+            && equals(&release.indexer, &self.indexer)
+            && equals(&release.quality.quality.name, &self.quality)
+            && equals(&release.protocol, &self.protocol)
+            && (self.language.is_none() || release.languages.iter().any(|lang| equals(&lang.name, &self.language)))
+            && self.rejected.is_none_or(|r| release.rejected == r)
+            && self.min_size.is_none_or(|min| release.size >= min)
+            && self.max_size.is_none_or(|max| release.size <= max)
+            && self.min_seeders.is_none_or(|min| release.seeders.unwrap_or(0) >= min)
+            && self.min_score.is_none_or(|min| release.custom_format_score >= min)
+    }
+
+    fn compare(sort: ReleaseSort, a: &Release, b: &Release) -> Ordering {
+        match sort {
+            ReleaseSort::Size => a.size.cmp(&b.size),
+            ReleaseSort::Seeders => a.seeders.unwrap_or(0).cmp(&b.seeders.unwrap_or(0)),
+            ReleaseSort::Indexer => a.indexer.to_lowercase().cmp(&b.indexer.to_lowercase()),
+            ReleaseSort::CustomFormatScore => a.custom_format_score.cmp(&b.custom_format_score),
+            ReleaseSort::Age => a.age_minutes.total_cmp(&b.age_minutes),
+            ReleaseSort::Quality => a.quality.quality.resolution.unwrap_or(0).cmp(&b.quality.quality.resolution.unwrap_or(0)),
+            ReleaseSort::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        }
+    }
+
+    /// Filter, sort and page `releases`.
+    pub fn apply(&self, releases: Vec<Release>) -> ReleasePage {
+        let unfiltered_records = releases.len();
+        let mut facets = ReleaseFacets::default();
+        for release in &releases {
+            facets.indexers.insert(release.indexer.clone());
+            facets.qualities.insert(release.quality.quality.name.clone());
+            facets.protocols.insert(release.protocol.clone());
+            facets.languages.extend(release.languages.iter().map(|l| l.name.clone()));
+        }
+
+        let mut matching: Vec<Release> = releases.into_iter().filter(|r| self.matches(r)).collect();
+        if let Some(sort) = self.sort {
+            // Text sorts read best A→Z, numbers biggest first
+            let default_order = match sort {
+                ReleaseSort::Indexer | ReleaseSort::Title | ReleaseSort::Age => SortOrder::Asc,
+                _ => SortOrder::Desc,
+            };
+            let order = self.order.unwrap_or(default_order);
+            matching.sort_by(|a, b| match order {
+                SortOrder::Asc => Self::compare(sort, a, b),
+                SortOrder::Desc => Self::compare(sort, b, a),
+            });
+        }
+
+        let total_records = matching.len();
+        let rejected_records = matching.iter().filter(|r| r.rejected).count();
+        let page = self.page.unwrap_or(1);
+        let page_size = self.page_size.unwrap_or(DEFAULT_PAGE_SIZE);
+        let records = matching
+            .into_iter()
+            .skip((page - 1).saturating_mul(page_size))
+            .take(page_size)
+            .map(|release| ReleaseView {
+                rejection_summary: rejection_summary(&release.rejections),
+                release,
+            })
+            .collect();
+
+        ReleasePage {
+            page,
+            page_size,
+            total_records,
+            unfiltered_records,
+            rejected_records,
+            facets,
+            records,
+        }
+    }
+}
+
+/// "Quality not wanted; Size too large (+2 more)" from the raw reasons.
+pub fn rejection_summary(rejections: &[String]) -> Option<String> {
+    let mut reasons: Vec<&str> = Vec::new();
+    for reason in rejections.iter().map(|r| r.trim().trim_end_matches('.')) {
+        if !reason.is_empty() && !reasons.contains(&reason) {
+            reasons.push(reason);
+        }
+    }
+    match reasons.len() {
+        0 => None,
+        1 | 2 => Some(reasons.join("; ")),
+        n => Some(format!("{}; {} (+{} more)", reasons[0], reasons[1], n - 2)),
+    }
+}
@@ -1 +1,17 @@
 pub mod plex_auth;
+
+use actix_web::HttpRequest;
+use crate::config::SharedConfig;
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+
+/// Verify the requesting user is the admin. Returns Err(Unauthorized) if not.
+pub fn require_admin(req: &HttpRequest, config: &SharedConfig) -> Result<()> {
+    let (user_id, _) = PlexClient::user_from_request(req)
+        .ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))?;
+    let cfg = config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+    if user_id != cfg.plex.admin_user_id {
+        return Err(Error::Unauthorized("Admin access required".to_string()));
+    }
+    Ok(())
+}
@@ -382,11 +382,11 @@ async fn initial_setup(
     cfg.plex.url = body.plex_url.trim_end_matches('/').to_string();
     cfg.plex.token = token;
     cfg.plex.admin_user_id = user_id;
-    if let Some(sonarr) = body.sonarr {
-        cfg.sonarr = sonarr;
+    if let Some(sonarr) = body.sonarr.filter(|i| !i.url.is_empty()) {
+        cfg.sonarr = vec![ArrInstanceConfig { is_default: true, ..sonarr }];
     }
-    if let Some(radarr) = body.radarr {
-        cfg.radarr = radarr;
+    if let Some(radarr) = body.radarr.filter(|i| !i.url.is_empty()) {
+        cfg.radarr = vec![ArrInstanceConfig { is_default: true, ..radarr }];
     }
     if let Some(download_clients) = body.download_clients {
         cfg.download_clients = download_clients;
@@ -0,0 +1,348 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use actix_web::{delete, get, web, HttpRequest, HttpResponse, Responder};
+use dashmap::DashMap;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::auth::require_admin;
+use crate::config::SharedConfig;
+use crate::events::{EventBus, EventKind, EventSource};
+use crate::http_error::Result;
+use crate::plex::client::PlexClient;
+
+/// A cacheable upstream route. The name doubles as the key prefix and as the
+/// key for `CacheConfig::ttl_overrides`.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheRoute {
+    pub name: &'static str,
+    pub ttl_secs: u64,
+    /// Responses differ per Plex user, so the user ID is part of the key.
+    pub per_user: bool,
+    /// Safe for shared caches (no user data) — sent as `Cache-Control: public`.
+    pub public: bool,
+}
+
+pub const TMDB_LISTS: CacheRoute = CacheRoute { name: "tmdb.lists", ttl_secs: 6 * 3600, per_user: false, public: true };
+pub const TMDB_DETAIL: CacheRoute = CacheRoute { name: "tmdb.detail", ttl_secs: 24 * 3600, per_user: false, public: true };
+pub const TMDB_SEARCH: CacheRoute = CacheRoute { name: "tmdb.search", ttl_secs: 3600, per_user: false, public: true };
+pub const PLEX_HUBS: CacheRoute = CacheRoute { name: "plex.hubs", ttl_secs: 30, per_user: true, public: false };
+pub const PLEX_RECOMMENDATIONS: CacheRoute = CacheRoute { name: "plex.recommendations", ttl_secs: 15 * 60, per_user: true, public: false };
+pub const PLEX_LIBRARY: CacheRoute = CacheRoute { name: "plex.library", ttl_secs: 5 * 60, per_user: false, public: false };
+/// Library listings filtered on the user's own watch state.
+pub const PLEX_LIBRARY_UNWATCHED: CacheRoute = CacheRoute { name: "plex.library.unwatched", ttl_secs: 30, per_user: true, public: false };
+pub const SONARR: CacheRoute = CacheRoute { name: "sonarr", ttl_secs: 60, per_user: false, public: false };
+pub const RADARR: CacheRoute = CacheRoute { name: "radarr", ttl_secs: 60, per_user: false, public: false };
+/// Manual-search results, kept just long enough to page through them
+/// without searching the indexers again.
+pub const SONARR_RELEASES: CacheRoute = CacheRoute { name: "sonarr.releases", ttl_secs: 120, per_user: false, public: false };
+pub const RADARR_RELEASES: CacheRoute = CacheRoute { name: "radarr.releases", ttl_secs: 120, per_user: false, public: false };
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub body: serde_json::Value,
+    pub etag: String,
+    pub stored_at: i64,
+    pub expires_at: i64,
+    /// Whether this lookup was served from the cache (not persisted).
+    #[serde(skip)]
+    pub hit: bool,
+}
+
+impl CacheEntry {
+    fn new(body: serde_json::Value, ttl_secs: u64) -> Self {
+        let mut hasher = DefaultHasher::new();
+        body.to_string().hash(&mut hasher);
+        let now = chrono::Utc::now().timestamp();
+        Self {
+            body,
+            etag: format!("\"{:016x}\"", hasher.finish()),
+            stored_at: now,
+            expires_at: now + ttl_secs as i64,
+            hit: false,
+        }
+    }
+
+    fn remaining_secs(&self) -> i64 {
+        (self.expires_at - chrono::Utc::now().timestamp()).max(0)
+    }
+
+    fn is_expired(&self) -> bool {
+        self.remaining_secs() == 0
+    }
+}
+
+/// In-memory TTL cache for upstream JSON responses, with an optional on-disk snapshot.
+pub struct ResponseCache {
+    entries: DashMap<String, CacheEntry>,
+    config: SharedConfig,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+fn snapshot_path() -> PathBuf {
+    crate::config::data_dir().join("cache.json")
+}
+
+impl ResponseCache {
+    pub fn new(config: SharedConfig) -> Self {
+        let cache = Self {
+            entries: DashMap::new(),
+            config,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        };
+        if cache.persist_enabled() {
+            cache.load_snapshot();
+        }
+        cache
+    }
+
+    fn enabled(&self) -> bool {
+        self.config.read().map(|c| c.cache.enabled).unwrap_or(false)
+    }
+
+    fn persist_enabled(&self) -> bool {
+        !cfg!(test) && self.config.read().map(|c| c.cache.persist).unwrap_or(false)
+    }
+
+    /// TTL for a route, honouring any override from config.
+    fn ttl_for(&self, route: CacheRoute) -> u64 {
+        self.config
+            .read()
+            .ok()
+            .and_then(|c| c.cache.ttl_overrides.get(route.name).copied())
+            .unwrap_or(route.ttl_secs)
+    }
+
+    /// Build a cache key from the route, the upstream path (including query)
+    /// and, for per-user routes, the requesting user.
+    pub fn key(route: CacheRoute, upstream: &str, req: &HttpRequest) -> String {
+        if route.per_user {
+            let user = PlexClient::user_from_request(req)
+                .map(|(id, _)| id.to_string())
+                .unwrap_or_else(|| "admin".to_string());
+            format!("{}:{}|{}", route.name, upstream, user)
+        } else {
+            format!("{}:{}", route.name, upstream)
+        }
+    }
+
+    /// Return the cached entry for `key`, or run `fetch` and cache its result.
+    /// Errors are never cached.
+    pub async fn get_or_fetch<F, Fut>(&self, key: String, route: CacheRoute, fetch: F) -> Result<CacheEntry>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<serde_json::Value>>,
+    {
+        if !self.enabled() {
+            return Ok(CacheEntry::new(fetch().await?, 0));
+        }
+
+        if let Some(entry) = self.entries.get(&key).filter(|e| !e.is_expired()) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            let mut entry = entry.clone();
+            entry.hit = true;
+            return Ok(entry);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let entry = CacheEntry::new(fetch().await?, self.ttl_for(route));
+        self.entries.insert(key, entry.clone());
+        Ok(entry)
+    }
+
+    /// Drop every entry whose key starts with `prefix`. Returns how many were removed.
+    pub fn invalidate_prefix(&self, prefix: &str) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|k, _| !k.starts_with(prefix));
+        before - self.entries.len()
+    }
+
+    pub fn clear(&self) -> usize {
+        let count = self.entries.len();
+        self.entries.clear();
+        count
+    }
+
+    fn purge_expired(&self) {
+        self.entries.retain(|_, e| !e.is_expired());
+    }
+
+    fn load_snapshot(&self) {
+        let path = snapshot_path();
+        let Ok(contents) = std::fs::read_to_string(&path) else { return };
+        match serde_json::from_str::<HashMap<String, CacheEntry>>(&contents) {
+            Ok(map) => {
+                for (k, v) in map.into_iter().filter(|(_, v)| !v.is_expired()) {
+                    self.entries.insert(k, v);
+                }
+                info!("Loaded {} cached responses from {}", self.entries.len(), path.display());
+            }
+            Err(e) => warn!("Ignoring unreadable cache snapshot {}: {}", path.display(), e),
+        }
+    }
+
+    fn save_snapshot(&self) -> anyhow::Result<()> {
+        let map: HashMap<String, CacheEntry> = self
+            .entries
+            .iter()
+            .map(|e| (e.key().clone(), e.value().clone()))
+            .collect();
+        let path = snapshot_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_vec(&map)?)?;
+        Ok(())
+    }
+}
+
+/// Turn a cache entry into a response, answering `If-None-Match` with 304.
+pub fn respond(req: &HttpRequest, entry: &CacheEntry, route: CacheRoute) -> HttpResponse {
+    let cache_control = format!(
+        "{}, max-age={}",
+        if route.public { "public" } else { "private" },
+        entry.remaining_secs()
+    );
+    let not_modified = req
+        .headers()
+        .get("If-None-Match")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == entry.etag));
+
+    let mut builder = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+    builder
+        .insert_header(("ETag", entry.etag.clone()))
+        .insert_header(("Cache-Control", cache_control))
+        .insert_header(("X-Cache", if entry.hit { "HIT" } else { "MISS" }));
+
+    if not_modified {
+        builder.finish()
+    } else {
+        builder.json(&entry.body)
+    }
+}
+
+/// Invalidate cached responses when webhooks report upstream changes.
+pub async fn run_invalidation(cache: web::Data<ResponseCache>, events: web::Data<EventBus>) {
+    let mut rx = events.subscribe();
+    loop {
+        let event = match rx.recv().await {
+            Ok(e) => e,
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                // We missed events, so we can't know what changed.
+                cache.clear();
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let prefixes: &[&str] = match (event.source, event.kind) {
+            (EventSource::Plex, EventKind::LibraryNew) => &["plex."],
+            (EventSource::Plex, EventKind::MediaPlay) => &["plex.hubs", "plex.library.unwatched"],
+            (EventSource::Sonarr, EventKind::Download | EventKind::Rename | EventKind::Grab) => &["sonarr", "plex.library"],
+            (EventSource::Radarr, EventKind::Download | EventKind::Rename | EventKind::Grab) => &["radarr", "plex.library"],
+            (EventSource::Sonarr, EventKind::Command) => &["sonarr"],
+            (EventSource::Radarr, EventKind::Command) => &["radarr"],
+            _ => &[],
+        };
+        for prefix in prefixes {
+            let removed = cache.invalidate_prefix(prefix);
+            debug!("Invalidated {} cached responses under '{}'", removed, prefix);
+        }
+    }
+}
+
+/// Every minute drop expired entries and, if enabled, write a snapshot to disk.
+pub async fn run_maintenance(cache: web::Data<ResponseCache>) {
+    let mut interval = actix_web::rt::time::interval(std::time::Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+        cache.purge_expired();
+        if !cache.persist_enabled() {
+            continue;
+        }
+        if let Err(e) = cache.save_snapshot() {
+            warn!("Failed to write cache snapshot: {}", e);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Admin endpoints
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+struct CacheEntryInfo {
+    key: String,
+    expires_in: i64,
+    stored_at: i64,
+    size: usize,
+}
+
+#[get("")]
+async fn cache_stats(
+    req: HttpRequest,
+    config: web::Data<SharedConfig>,
+    cache: web::Data<ResponseCache>,
+) -> Result<impl Responder> {
+    require_admin(&req, &config)?;
+    let mut entries: Vec<CacheEntryInfo> = cache
+        .entries
+        .iter()
+        .filter(|e| !e.is_expired())
+        .map(|e| CacheEntryInfo {
+            key: e.key().clone(),
+            expires_in: e.remaining_secs(),
+            stored_at: e.stored_at,
+            size: e.body.to_string().len(),
+        })
+        .collect();
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "enabled": cache.enabled(),
+        "persist": cache.persist_enabled(),
+        "hits": cache.hits.load(Ordering::Relaxed),
+        "misses": cache.misses.load(Ordering::Relaxed),
+        "total_entries": entries.len(),
+        "total_bytes": entries.iter().map(|e| e.size).sum::<usize>(),
+        "entries": entries,
+    })))
+}
+
+#[derive(Deserialize)]
+struct FlushQuery {
+    prefix: Option<String>,
+}
+
+/// Flush the whole cache, or only keys starting with `?prefix=`.
+#[delete("")]
+async fn flush_cache(
+    req: HttpRequest,
+    config: web::Data<SharedConfig>,
+    cache: web::Data<ResponseCache>,
+    query: web::Query<FlushQuery>,
+) -> Result<impl Responder> {
+    require_admin(&req, &config)?;
+    let removed = match query.prefix.as_deref() {
+        Some(prefix) if !prefix.is_empty() => cache.invalidate_prefix(prefix),
+        _ => cache.clear(),
+    };
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "removed": removed })))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/cache").service(cache_stats).service(flush_cache));
+}
@@ -0,0 +1,85 @@
+//! Minimal RFC 5545 writer for the calendar feed.
+
+use chrono::Utc;
+
+use crate::calendar::model::{CalendarEvent, CalendarEventKind};
+
+/// Escape TEXT values (backslash, semicolon, comma and newlines).
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line at 75 octets, continuing with CRLF + space.
+fn fold(line: &str, out: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += len;
+    }
+    out.push_str("\r\n");
+}
+
+fn summary(event: &CalendarEvent) -> String {
+    let suffix = match event.kind {
+        CalendarEventKind::Episode => None,
+        CalendarEventKind::CinemaRelease => Some("in cinemas"),
+        CalendarEventKind::DigitalRelease => Some("digital release"),
+        CalendarEventKind::PhysicalRelease => Some("physical release"),
+    };
+    match (&event.subtitle, suffix) {
+        (Some(sub), _) => format!("{} - {}", event.title, sub),
+        (None, Some(suffix)) => format!("{} ({})", event.title, suffix),
+        (None, None) => event.title.clone(),
+    }
+}
+
+pub fn render(name: &str, events: &[CalendarEvent]) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut out = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//Playarr//Calendar//EN",
+        "CALSCALE:GREGORIAN",
+        "METHOD:PUBLISH",
+    ] {
+        fold(line, &mut out);
+    }
+    fold(&format!("X-WR-CALNAME:{}", escape(name)), &mut out);
+
+    for event in events {
+        fold("BEGIN:VEVENT", &mut out);
+        fold(&format!("UID:{}@playarr", event.id), &mut out);
+        fold(&format!("DTSTAMP:{}", stamp), &mut out);
+        if event.all_day {
+            fold(&format!("DTSTART;VALUE=DATE:{}", event.start.format("%Y%m%d")), &mut out);
+            fold(&format!("DTEND;VALUE=DATE:{}", event.end.format("%Y%m%d")), &mut out);
+        } else {
+            fold(&format!("DTSTART:{}", event.start.format("%Y%m%dT%H%M%SZ")), &mut out);
+            fold(&format!("DTEND:{}", event.end.format("%Y%m%dT%H%M%SZ")), &mut out);
+        }
+        fold(&format!("SUMMARY:{}", escape(&summary(event))), &mut out);
+        let status = if event.in_plex {
+            "Available in Plex"
+        } else if event.has_file {
+            "Downloaded"
+        } else {
+            "Not downloaded yet"
+        };
+        fold(&format!("DESCRIPTION:{}", escape(status)), &mut out);
+        fold("TRANSP:TRANSPARENT", &mut out);
+        fold("END:VEVENT", &mut out);
+    }
+
+    fold("END:VCALENDAR", &mut out);
+    out
+}
@@ -0,0 +1,358 @@
+//! Unified calendar: Sonarr episodes and Radarr releases normalized into one
+//! event model, marked with whether they are already in Plex, plus a per-user
+//! iCal feed.
+
+pub mod ical;
+pub mod model;
+
+use std::collections::{HashMap, HashSet};
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::{Duration, NaiveDate, Utc};
+use log::warn;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::cache::{self, ResponseCache};
+use crate::calendar::model::{CalendarEvent, CalendarEventKind};
+use crate::config::{save_config, SharedConfig};
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+use crate::plex::guid::{guid_id, item_guids};
+use crate::radarr::client::RadarrClient;
+use crate::sonarr::client::SonarrClient;
+
+const DEFAULT_WINDOW_DAYS: i64 = 30;
+const MAX_WINDOW_DAYS: i64 = 366;
+
+/// Window served by the iCal feed, relative to today.
+const FEED_PAST_DAYS: i64 = 14;
+const FEED_FUTURE_DAYS: i64 = 90;
+
+#[derive(Deserialize)]
+struct CalendarQuery {
+    /// First day, `YYYY-MM-DD` (default today).
+    start: Option<String>,
+    /// Last day, inclusive (default 30 days after `start`).
+    end: Option<String>,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| Error::BadRequest(format!("Invalid date '{}', expected YYYY-MM-DD", value)))
+}
+
+fn parse_range(start: Option<&str>, end: Option<&str>) -> Result<(NaiveDate, NaiveDate)> {
+    let from = match start {
+        Some(s) => parse_date(s)?,
+        None => Utc::now().date_naive(),
+    };
+    let to = match end {
+        Some(e) => parse_date(e)?,
+        None => from + Duration::days(DEFAULT_WINDOW_DAYS),
+    };
+    if to < from {
+        return Err(Error::BadRequest("end must not be before start".to_string()));
+    }
+    if (to - from).num_days() > MAX_WINDOW_DAYS {
+        return Err(Error::BadRequest(format!("Range is limited to {} days", MAX_WINDOW_DAYS)));
+    }
+    Ok((from, to))
+}
+
+// ---------------------------------------------------------------------------
+// Plex presence
+// ---------------------------------------------------------------------------
+
+/// TMDB IDs of movies and TVDB IDs of shows in Plex, mapped to rating keys.
+#[derive(Default)]
+struct PlexIndex {
+    movies: HashMap<u64, String>,
+    shows: HashMap<u64, String>,
+}
+
+async fn fetch_guid_index(plex: &PlexClient) -> Result<Value> {
+    let sections = plex.send_json(plex.get("/library/sections")?).await?;
+    let mut movies = serde_json::Map::new();
+    let mut shows = serde_json::Map::new();
+
+    let directories = sections["MediaContainer"]["Directory"].as_array().cloned().unwrap_or_default();
+    for section in directories {
+        let (target, scheme) = match section["type"].as_str() {
+            Some("movie") => (&mut movies, "tmdb"),
+            Some("show") => (&mut shows, "tvdb"),
+            _ => continue,
+        };
+        let Some(key) = section["key"].as_str() else { continue };
+        let req = plex
+            .get(&format!("/library/sections/{}/all", key))?
+            .query(&[("includeGuids", "1")]);
+        let body = plex.send_json(req).await?;
+        for item in body["MediaContainer"]["Metadata"].as_array().into_iter().flatten() {
+            let Some(rating_key) = item["ratingKey"].as_str() else { continue };
+            for id in item_guids(item).iter().filter_map(|g| guid_id(g, scheme)) {
+                target.insert(id.to_string(), Value::String(rating_key.to_string()));
+            }
+        }
+    }
+
+    Ok(serde_json::json!({ "movies": movies, "shows": shows }))
+}
+
+async fn plex_index(req: &HttpRequest, plex: &PlexClient, cache: &ResponseCache) -> PlexIndex {
+    let key = ResponseCache::key(cache::PLEX_LIBRARY, "/calendar/guid-index", req);
+    let entry = match cache.get_or_fetch(key, cache::PLEX_LIBRARY, || fetch_guid_index(plex)).await {
+        Ok(entry) => entry,
+        Err(e) => {
+            warn!("Calendar could not read Plex libraries: {}", e);
+            return PlexIndex::default();
+        }
+    };
+    let read = |field: &str| -> HashMap<u64, String> {
+        entry.body[field]
+            .as_object()
+            .map(|m| {
+                m.iter()
+                    .filter_map(|(k, v)| Some((k.parse().ok()?, v.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    PlexIndex {
+        movies: read("movies"),
+        shows: read("shows"),
+    }
+}
+
+/// `(season, episode) -> rating key` for every episode of a Plex show.
+async fn show_episodes(
+    req: &HttpRequest,
+    plex: &PlexClient,
+    cache: &ResponseCache,
+    show_key: &str,
+) -> HashMap<(u64, u64), String> {
+    let path = format!("/library/metadata/{}/allLeaves", show_key);
+    let key = ResponseCache::key(cache::PLEX_LIBRARY, &path, req);
+    let entry = cache
+        .get_or_fetch(key, cache::PLEX_LIBRARY, || async { plex.send_json(plex.get(&path)?).await })
+        .await;
+    let Ok(entry) = entry else { return HashMap::new() };
+
+    entry.body["MediaContainer"]["Metadata"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|e| {
+                    let season = e["parentIndex"].as_u64()?;
+                    let episode = e["index"].as_u64()?;
+                    Some(((season, episode), e["ratingKey"].as_str()?.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Fill in `in_plex` / `plex_rating_key` for every event.
+async fn mark_in_plex(req: &HttpRequest, plex: &PlexClient, cache: &ResponseCache, events: &mut [CalendarEvent]) {
+    let index = plex_index(req, plex, cache).await;
+
+    let shows: HashSet<String> = events
+        .iter()
+        .filter_map(|e| e.tvdb_id.and_then(|id| index.shows.get(&id)).cloned())
+        .collect();
+    let mut episodes: HashMap<String, HashMap<(u64, u64), String>> = HashMap::new();
+    for show in shows {
+        let leaves = show_episodes(req, plex, cache, &show).await;
+        episodes.insert(show, leaves);
+    }
+
+    for event in events.iter_mut() {
+        let rating_key = match event.kind {
+            CalendarEventKind::Episode => event
+                .tvdb_id
+                .and_then(|id| index.shows.get(&id))
+                .and_then(|show| episodes.get(show))
+                .zip(event.season_number.zip(event.episode_number))
+                .and_then(|(leaves, ep)| leaves.get(&ep).cloned()),
+            _ => event.tmdb_id.and_then(|id| index.movies.get(&id).cloned()),
+        };
+        event.in_plex = rating_key.is_some();
+        event.plex_rating_key = rating_key;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Collection
+// ---------------------------------------------------------------------------
+
+async fn collect_events(
+    req: &HttpRequest,
+    sonarr: &SonarrClient,
+    radarr: &RadarrClient,
+    plex: &PlexClient,
+    cache: &ResponseCache,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<CalendarEvent> {
+    // The *arr calendars treat `end` as exclusive
+    let start = from.format("%Y-%m-%d").to_string();
+    let end = (to + Duration::days(1)).format("%Y-%m-%d").to_string();
+
+    let sonarr_query = [("start", start.as_str()), ("end", end.as_str()), ("includeSeries", "true")];
+    let radarr_query = [("start", start.as_str()), ("end", end.as_str())];
+    let sonarr_targets = sonarr.instances();
+    let radarr_targets = radarr.instances();
+    let (episodes, movies) = tokio::join!(
+        sonarr.get_each::<Vec<Value>>(&sonarr_targets, "/calendar", &sonarr_query),
+        radarr.get_each::<Vec<Value>>(&radarr_targets, "/calendar", &radarr_query),
+    );
+    let episodes = episodes.unwrap_or_else(|e| {
+        warn!("Calendar skipped Sonarr: {}", e);
+        Vec::new()
+    });
+    let movies = movies.unwrap_or_else(|e| {
+        warn!("Calendar skipped Radarr: {}", e);
+        Vec::new()
+    });
+
+    let mut events: Vec<CalendarEvent> = Vec::new();
+    for (instance, body) in &episodes {
+        events.extend(
+            body.iter()
+                .filter_map(|item| model::from_sonarr(item, instance.label()))
+                .filter(|e| (from..=to).contains(&e.start.date_naive())),
+        );
+    }
+    for (instance, body) in &movies {
+        for item in body {
+            events.extend(model::from_radarr(item, instance.label(), from, to));
+        }
+    }
+
+    mark_in_plex(req, plex, cache, &mut events).await;
+    events.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.title.cmp(&b.title)));
+    events
+}
+
+#[get("")]
+async fn calendar(
+    req: HttpRequest,
+    sonarr: web::Data<SonarrClient>,
+    radarr: web::Data<RadarrClient>,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    query: web::Query<CalendarQuery>,
+) -> Result<impl Responder> {
+    PlexClient::user_from_request(&req)
+        .ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))?;
+    let (from, to) = parse_range(query.start.as_deref(), query.end.as_deref())?;
+    let events = collect_events(&req, &sonarr, &radarr, &plex, &cache, from, to).await;
+    Ok(HttpResponse::Ok().json(events))
+}
+
+// ---------------------------------------------------------------------------
+// iCal feed
+// ---------------------------------------------------------------------------
+
+fn feed_response(req: &HttpRequest, token: &str) -> HttpResponse {
+    let path = format!("/api/calendar/feed/{}.ics", token);
+    let info = req.connection_info();
+    HttpResponse::Ok().json(serde_json::json!({
+        "path": path,
+        "url": format!("{}://{}{}", info.scheme(), info.host(), path),
+    }))
+}
+
+fn set_feed_token(config: &SharedConfig, user_id: i64, rotate: bool) -> Result<String> {
+    let mut cfg = config.write().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+    let key = user_id.to_string();
+    if let Some(token) = cfg.calendar.feed_tokens.get(&key)
+        && !rotate
+    {
+        return Ok(token.clone());
+    }
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    cfg.calendar.feed_tokens.insert(key, token.clone());
+    save_config(&cfg)?;
+    Ok(token)
+}
+
+/// The signed-in user's feed URL, created on first use.
+#[get("/feed")]
+async fn get_feed(req: HttpRequest, config: web::Data<SharedConfig>) -> Result<impl Responder> {
+    let (user_id, _) = PlexClient::user_from_request(&req)
+        .ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))?;
+    let token = set_feed_token(&config, user_id, false)?;
+    Ok(feed_response(&req, &token))
+}
+
+/// Issue the signed-in user a new feed URL; their old one stops working.
+#[post("/feed/reset")]
+async fn reset_feed(req: HttpRequest, config: web::Data<SharedConfig>) -> Result<impl Responder> {
+    let (user_id, _) = PlexClient::user_from_request(&req)
+        .ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))?;
+    let token = set_feed_token(&config, user_id, true)?;
+    Ok(feed_response(&req, &token))
+}
+
+/// Whether `user_id` may still use the server: the admin, or one of the
+/// admin's Plex friends. Feeds outlive sessions, so this is checked on
+/// every fetch rather than at sign-in.
+async fn has_access(plex: &PlexClient, config: &SharedConfig, user_id: &str) -> Result<bool> {
+    let admin_user_id = config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?.plex.admin_user_id;
+    if user_id == admin_user_id.to_string() {
+        return Ok(true);
+    }
+    let friends = plex.fetch_friends().await?;
+    Ok(friends.iter().any(|f| f["id"].as_i64().is_some_and(|id| id.to_string() == user_id)))
+}
+
+/// The iCal feed itself. Authenticated by the token in the URL.
+#[get("/feed/{file}")]
+async fn feed_ics(
+    req: HttpRequest,
+    config: web::Data<SharedConfig>,
+    sonarr: web::Data<SonarrClient>,
+    radarr: web::Data<RadarrClient>,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let not_found = || Error::NotFound("Unknown calendar feed".to_string());
+    let token = path.strip_suffix(".ics").ok_or_else(not_found)?;
+    let owner = {
+        let cfg = config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+        cfg.calendar.feed_tokens.iter()
+            .find(|(_, t)| !token.is_empty() && t.as_str() == token)
+            .map(|(user_id, _)| user_id.clone())
+            .ok_or_else(not_found)?
+    };
+    if !has_access(&plex, &config, &owner).await? {
+        // The owner was removed from the server; retire their feed for good
+        let mut cfg = config.write().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+        cfg.calendar.feed_tokens.remove(&owner);
+        save_config(&cfg)?;
+        return Err(not_found());
+    }
+
+    let today = Utc::now().date_naive();
+    let from = today - Duration::days(FEED_PAST_DAYS);
+    let to = today + Duration::days(FEED_FUTURE_DAYS);
+    let events = collect_events(&req, &sonarr, &radarr, &plex, &cache, from, to).await;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .insert_header(("Cache-Control", "private, max-age=900"))
+        .body(ical::render("Playarr", &events)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/calendar")
+            .service(calendar)
+            .service(get_feed)
+            .service(reset_feed)
+            .service(feed_ics),
+    );
+}
@@ -0,0 +1,162 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::events::EventSource;
+
+/// Episodes without a runtime are assumed to run this long.
+const DEFAULT_RUNTIME_MINS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarEventKind {
+    Episode,
+    CinemaRelease,
+    DigitalRelease,
+    PhysicalRelease,
+}
+
+/// One entry in the unified calendar: an episode airing or a movie release.
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarEvent {
+    /// Stable across requests, used as the iCal UID.
+    pub id: String,
+    pub kind: CalendarEventKind,
+    pub source: EventSource,
+    pub instance: String,
+    /// Series or movie title.
+    pub title: String,
+    /// Episode label, e.g. "S01E05 · Ozymandias".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Movie releases only have a date, not a time.
+    pub all_day: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub season_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tvdb_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tmdb_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imdb_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poster: Option<String>,
+    pub monitored: bool,
+    pub has_file: bool,
+    pub in_plex: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plex_rating_key: Option<String>,
+}
+
+fn poster(images: &Value) -> Option<String> {
+    images
+        .as_array()?
+        .iter()
+        .find(|i| i["coverType"] == "poster")
+        .and_then(|i| i["remoteUrl"].as_str().or_else(|| i["url"].as_str()))
+        .map(|s| s.to_string())
+}
+
+fn non_zero(value: &Value) -> Option<u64> {
+    value.as_u64().filter(|v| *v > 0)
+}
+
+fn non_empty(value: &Value) -> Option<String> {
+    value.as_str().filter(|s| !s.is_empty()).map(|s| s.to_string())
+}
+
+/// Normalize one item from Sonarr's `/calendar?includeSeries=true`.
+pub fn from_sonarr(item: &Value, instance: &str) -> Option<CalendarEvent> {
+    let start: DateTime<Utc> = item["airDateUtc"].as_str()?.parse().ok()?;
+    let series = &item["series"];
+    let runtime = item["runtime"]
+        .as_i64()
+        .or_else(|| series["runtime"].as_i64())
+        .filter(|r| *r > 0)
+        .unwrap_or(DEFAULT_RUNTIME_MINS);
+    let season = item["seasonNumber"].as_u64();
+    let episode = item["episodeNumber"].as_u64();
+
+    let mut subtitle = match (season, episode) {
+        (Some(s), Some(e)) => format!("S{:02}E{:02}", s, e),
+        _ => String::new(),
+    };
+    if let Some(name) = item["title"].as_str().filter(|t| !t.is_empty()) {
+        if !subtitle.is_empty() {
+            subtitle.push_str(" · ");
+        }
+        subtitle.push_str(name);
+    }
+
+    Some(CalendarEvent {
+        id: format!("sonarr-{}-episode-{}", instance, item["id"]),
+        kind: CalendarEventKind::Episode,
+        source: EventSource::Sonarr,
+        instance: instance.to_string(),
+        title: series["title"].as_str().unwrap_or("Unknown series").to_string(),
+        subtitle: Some(subtitle).filter(|s| !s.is_empty()),
+        start,
+        end: start + chrono::Duration::minutes(runtime),
+        all_day: false,
+        season_number: season,
+        episode_number: episode,
+        tvdb_id: non_zero(&series["tvdbId"]),
+        tmdb_id: non_zero(&series["tmdbId"]),
+        imdb_id: non_empty(&series["imdbId"]),
+        poster: poster(&series["images"]),
+        monitored: item["monitored"].as_bool().unwrap_or(false),
+        has_file: item["hasFile"].as_bool().unwrap_or(false),
+        in_plex: false,
+        plex_rating_key: None,
+    })
+}
+
+/// Normalize one item from Radarr's `/calendar`, which carries up to three
+/// release dates. Only dates inside `[from, to]` produce events.
+pub fn from_radarr(item: &Value, instance: &str, from: NaiveDate, to: NaiveDate) -> Vec<CalendarEvent> {
+    let releases = [
+        ("inCinemas", CalendarEventKind::CinemaRelease, "cinema"),
+        ("digitalRelease", CalendarEventKind::DigitalRelease, "digital"),
+        ("physicalRelease", CalendarEventKind::PhysicalRelease, "physical"),
+    ];
+
+    releases
+        .iter()
+        .filter_map(|(field, kind, label)| {
+            let date = item[*field]
+                .as_str()?
+                .parse::<DateTime<Utc>>()
+                .ok()?
+                .date_naive();
+            if date < from || date > to {
+                return None;
+            }
+            let start = date.and_hms_opt(0, 0, 0)?.and_utc();
+            Some(CalendarEvent {
+                id: format!("radarr-{}-movie-{}-{}", instance, item["id"], label),
+                kind: *kind,
+                source: EventSource::Radarr,
+                instance: instance.to_string(),
+                title: item["title"].as_str().unwrap_or("Unknown movie").to_string(),
+                subtitle: None,
+                start,
+                end: start + chrono::Duration::days(1),
+                all_day: true,
+                season_number: None,
+                episode_number: None,
+                tvdb_id: None,
+                tmdb_id: non_zero(&item["tmdbId"]),
+                imdb_id: non_empty(&item["imdbId"]),
+                poster: poster(&item["images"]),
+                monitored: item["monitored"].as_bool().unwrap_or(false),
+                has_file: item["hasFile"].as_bool().unwrap_or(false),
+                in_plex: false,
+                plex_rating_key: None,
+            })
+        })
+        .collect()
+}
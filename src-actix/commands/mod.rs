@@ -0,0 +1,254 @@
+//! Follows Sonarr/Radarr commands started from Playarr until they finish.
+//! `POST /command` only queues work upstream; the tracker polls each command
+//! id, keeps a short history for `/api/commands` and publishes an activity
+//! event when one completes so the UI hears about it over `/api/events/ws`.
+
+use std::collections::VecDeque;
+use std::sync::RwLock;
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::Serialize;
+
+use crate::arr::client::ArrClient;
+use crate::arr::models::Command;
+use crate::arr::ArrService;
+use crate::events::{ActivityEvent, EventBus, EventKind, EventSource};
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+use crate::radarr::client::RadarrClient;
+use crate::sonarr::client::SonarrClient;
+
+/// Commands kept for the list, finished ones are dropped oldest first.
+const HISTORY_CAPACITY: usize = 100;
+const POLL_INTERVAL_SECS: u64 = 2;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackedCommand {
+    /// `{service}:{instance}:{commandId}`, unique across instances.
+    pub id: String,
+    pub source: EventSource,
+    pub instance: String,
+    pub command_id: u64,
+    pub name: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub queued_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<i64>,
+    /// Plex user id of whoever started it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub triggered_by: Option<i64>,
+    /// Releases a finished search sent to the download client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grabbed: Option<u64>,
+}
+
+/// The count in the closing message of Sonarr/Radarr searches,
+/// "Series search completed. 3 reports downloaded."
+fn grabbed_count(message: &str) -> Option<u64> {
+    let words: Vec<&str> = message.split_whitespace().map(|w| w.trim_end_matches(['.', ','])).collect();
+    words.windows(3).find_map(|w| match w {
+        [count, "report" | "reports", "downloaded" | "grabbed"] => count.parse().ok(),
+        _ => None,
+    })
+}
+
+impl TrackedCommand {
+    pub fn is_finished(&self) -> bool {
+        self.ended_at.is_some()
+    }
+
+    fn apply(&mut self, command: &Command) {
+        self.status = command.status.clone();
+        self.result = command.result.clone();
+        self.message = command.message.clone().filter(|m| !m.is_empty());
+        self.started_at = command.started.or(self.started_at);
+        if command.is_finished() {
+            let ended = command.ended.unwrap_or_else(Utc::now);
+            self.ended_at = Some(ended);
+            self.duration_ms = Some((ended - self.started_at.unwrap_or(self.queued_at)).num_milliseconds().max(0));
+            self.grabbed = self.message.as_deref().and_then(grabbed_count);
+        }
+    }
+
+    /// "SeriesSearch completed in 12s, 3 releases grabbed", with Sonarr's
+    /// own summary as message.
+    fn completion_event(&self) -> ActivityEvent {
+        let seconds = self.duration_ms.unwrap_or(0) as f64 / 1000.0;
+        let mut title = format!("{} {} in {:.0}s", self.name, self.status, seconds.ceil());
+        match self.grabbed {
+            Some(1) => title.push_str(", 1 release grabbed"),
+            Some(count) => title.push_str(&format!(", {} releases grabbed", count)),
+            None => {}
+        }
+        let mut event = ActivityEvent::new(self.source, EventKind::Command, &self.name, title);
+        event.message = self.message.clone();
+        event
+    }
+}
+
+pub struct CommandTracker {
+    commands: RwLock<VecDeque<TrackedCommand>>,
+}
+
+impl CommandTracker {
+    pub fn new() -> Self {
+        Self {
+            commands: RwLock::new(VecDeque::with_capacity(HISTORY_CAPACITY)),
+        }
+    }
+
+    /// Start following a command that was just queued upstream.
+    pub fn track(&self, source: EventSource, instance: &str, command: &Command, user: Option<i64>) -> TrackedCommand {
+        let service = match source {
+            EventSource::Sonarr => "sonarr",
+            EventSource::Radarr => "radarr",
+            EventSource::Plex => "plex",
+        };
+        let mut tracked = TrackedCommand {
+            id: format!("{}:{}:{}", service, instance, command.id),
+            source,
+            instance: instance.to_string(),
+            command_id: command.id,
+            name: command.command_name.clone().unwrap_or_else(|| command.name.clone()),
+            status: command.status.clone(),
+            result: None,
+            message: None,
+            queued_at: command.queued.unwrap_or_else(Utc::now),
+            started_at: None,
+            ended_at: None,
+            duration_ms: None,
+            triggered_by: user,
+            grabbed: None,
+        };
+        tracked.apply(command);
+
+        if let Ok(mut commands) = self.commands.write() {
+            commands.retain(|c| c.id != tracked.id);
+            commands.push_front(tracked.clone());
+            // Never evict something still running
+            while commands.len() > HISTORY_CAPACITY {
+                match commands.iter().rposition(|c| c.is_finished()) {
+                    Some(index) => commands.remove(index),
+                    None => break,
+                };
+            }
+        }
+        tracked
+    }
+
+    /// Newest first.
+    pub fn list(&self) -> Vec<TrackedCommand> {
+        self.commands.read().map(|c| c.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn get(&self, id: &str) -> Option<TrackedCommand> {
+        self.commands.read().ok()?.iter().find(|c| c.id == id).cloned()
+    }
+
+    fn pending(&self, source: EventSource) -> Vec<TrackedCommand> {
+        self.commands
+            .read()
+            .map(|c| c.iter().filter(|c| c.source == source && !c.is_finished()).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Store the latest upstream state. Returns the command if it just finished.
+    fn update(&self, id: &str, command: &Command) -> Option<TrackedCommand> {
+        let mut commands = self.commands.write().ok()?;
+        let tracked = commands.iter_mut().find(|c| c.id == id)?;
+        let was_finished = tracked.is_finished();
+        tracked.apply(command);
+        (!was_finished && tracked.is_finished()).then(|| tracked.clone())
+    }
+
+    /// Poll every unfinished command once and publish the ones that completed.
+    pub async fn refresh(&self, sonarr: &SonarrClient, radarr: &RadarrClient, events: &EventBus) {
+        let (sonarr_done, radarr_done) = tokio::join!(self.poll(sonarr), self.poll(radarr));
+        for tracked in sonarr_done.into_iter().chain(radarr_done) {
+            debug!("{} finished on {} '{}': {}", tracked.name, tracked.instance, tracked.command_id, tracked.status);
+            events.publish(tracked.completion_event());
+        }
+    }
+
+    async fn poll<S: ArrService>(&self, client: &ArrClient<S>) -> Vec<TrackedCommand> {
+        let mut finished = Vec::new();
+        for tracked in self.pending(S::SOURCE) {
+            let latest = match client.instance(Some(&tracked.instance)) {
+                Ok(instance) => {
+                    client
+                        .send_json::<Command>(client.get(&instance, &format!("/command/{}", tracked.command_id)), "command")
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            let command = match latest {
+                Ok(command) => command,
+                // Purged upstream (restart, housekeeping) or the instance was removed
+                Err(Error::NotFound(_)) => Command {
+                    status: "orphaned".to_string(),
+                    message: Some(format!("{} no longer knows this command", S::NAME)),
+                    ..Default::default()
+                },
+                Err(e) => {
+                    warn!("Failed to poll {} command {}: {}", S::NAME, tracked.command_id, e);
+                    continue;
+                }
+            };
+            finished.extend(self.update(&tracked.id, &command));
+        }
+        finished
+    }
+}
+
+/// Poll unfinished commands in the background so completion events go out
+/// even when nobody has the command list open.
+pub async fn run_polling(
+    tracker: web::Data<CommandTracker>,
+    sonarr: web::Data<SonarrClient>,
+    radarr: web::Data<RadarrClient>,
+    events: web::Data<EventBus>,
+) {
+    let mut interval = actix_web::rt::time::interval(std::time::Duration::from_secs(POLL_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        tracker.refresh(&sonarr, &radarr, &events).await;
+    }
+}
+
+/// As of the last `run_polling` pass, at most a couple of seconds old.
+#[get("/commands")]
+async fn list_commands(req: HttpRequest, tracker: web::Data<CommandTracker>) -> Result<impl Responder> {
+    PlexClient::user_from_request(&req)
+        .ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))?;
+    Ok(HttpResponse::Ok().json(tracker.list()))
+}
+
+#[get("/commands/{id}")]
+async fn get_command(
+    req: HttpRequest,
+    tracker: web::Data<CommandTracker>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    PlexClient::user_from_request(&req)
+        .ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))?;
+    let id = path.into_inner();
+    let command = tracker
+        .get(&id)
+        .ok_or_else(|| Error::NotFound(format!("Command '{}' not found", id)))?;
+    Ok(HttpResponse::Ok().json(command))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_commands).service(get_command);
+}
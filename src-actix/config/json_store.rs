@@ -0,0 +1,73 @@
+//! State kept as JSON files in the data directory (`watchlist.json`,
+//! `playback.json`, `offline.json`).
+
+use std::path::PathBuf;
+use std::sync::{LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// A value held in memory and rewritten whole to its file on `save`. A
+/// missing or unreadable file starts from the default instead of failing
+/// startup.
+pub struct JsonFileStore<T> {
+    value: RwLock<T>,
+    path: Option<PathBuf>,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> JsonFileStore<T> {
+    /// Backed by `file_name` in the data directory, starting from whatever
+    /// is on disk.
+    pub fn open(file_name: &str) -> Self {
+        let path = super::data_dir().join(file_name);
+        let value = match std::fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str(&contents) {
+                Ok(value) => {
+                    info!("Loaded {}", path.display());
+                    value
+                }
+                Err(e) => {
+                    warn!("Ignoring unreadable {}: {}", path.display(), e);
+                    T::default()
+                }
+            },
+            Err(_) => T::default(),
+        };
+        Self {
+            value: RwLock::new(value),
+            path: Some(path),
+        }
+    }
+
+    /// Never reads or writes a file, so tests leave the real data alone.
+    pub fn in_memory() -> Self {
+        Self {
+            value: RwLock::new(T::default()),
+            path: None,
+        }
+    }
+
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
+        self.value.read()
+    }
+
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+        self.value.write()
+    }
+
+    /// Write the current value out. Takes the read lock, so release any
+    /// write guard first.
+    pub fn save(&self) {
+        let Some(ref path) = self.path else { return };
+        let Ok(value) = self.value.read() else { return };
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(std::fs::write(path, serde_json::to_vec(&*value)?)?));
+        if let Err(e) = result {
+            warn!("Failed to save {}: {}", path.display(), e);
+        }
+    }
+}
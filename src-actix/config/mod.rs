@@ -1,3 +1,4 @@
+pub mod json_store;
 pub mod models;
 
 use std::path::PathBuf;
@@ -15,6 +16,15 @@ pub fn config_path() -> PathBuf {
     base.join("playarr").join("config.toml")
 }
 
+/// Directory next to the config file where Playarr keeps its other state
+/// (caches, stores). Created on demand by callers.
+pub fn data_dir() -> PathBuf {
+    config_path()
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
 /// Load config from disk, or return default if not found.
 pub fn load_config() -> AppConfig {
     let path = config_path();
@@ -1,15 +1,33 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppConfig {
     #[serde(default)]
     pub plex: PlexConfig,
+    /// Sonarr instances. A single `[sonarr]` table from older configs is read as one instance.
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub sonarr: Vec<SonarrConfig>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub radarr: Vec<RadarrConfig>,
     #[serde(default)]
-    pub sonarr: SonarrConfig,
-    #[serde(default)]
-    pub radarr: RadarrConfig,
+    pub tmdb: TmdbConfig,
     #[serde(default)]
     pub download_clients: Vec<DownloadClientConfig>,
+    /// Admin-defined transcode profiles. Empty means the built-in presets.
+    #[serde(default)]
+    pub transcode_profiles: Vec<TranscodeProfile>,
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub calendar: CalendarConfig,
+    #[serde(default)]
+    pub offline: OfflineConfig,
+    #[serde(default)]
+    pub images: ImagesConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,20 +58,74 @@ impl Default for PlexConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct SonarrConfig {
+/// One Sonarr or Radarr server, e.g. a separate 4K or anime instance.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ArrInstanceConfig {
+    #[serde(default)]
+    pub name: String,
     #[serde(default)]
     pub url: String,
     #[serde(default)]
     pub api_key: String,
+    /// Used when a request does not name an instance.
+    #[serde(default)]
+    pub is_default: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+pub type SonarrConfig = ArrInstanceConfig;
+pub type RadarrConfig = ArrInstanceConfig;
+
+impl ArrInstanceConfig {
+    /// Name shown to clients; instances migrated from a single-instance
+    /// config have no name.
+    pub fn label(&self) -> &str {
+        if self.name.is_empty() {
+            "default"
+        } else {
+            &self.name
+        }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        !self.url.is_empty() && !self.api_key.is_empty()
+    }
+}
+
+/// The instance flagged as default, or the first one.
+pub fn default_instance(instances: &[ArrInstanceConfig]) -> Option<&ArrInstanceConfig> {
+    instances.iter().find(|i| i.is_default).or_else(|| instances.first())
+}
+
+/// Accepts either a single value or a list.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
 }
 
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<ArrInstanceConfig>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match OneOrMany::<ArrInstanceConfig>::deserialize(deserializer)? {
+        // An empty legacy table means "not configured"
+        OneOrMany::One(instance) if instance.url.is_empty() => Vec::new(),
+        OneOrMany::One(instance) => vec![ArrInstanceConfig { is_default: true, ..instance }],
+        OneOrMany::Many(instances) => instances,
+    })
+}
+
+/// TMDB credentials. Either a v3 API key or a v4 read access token works;
+/// the token is used when both are set.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct RadarrConfig {
-    #[serde(default)]
-    pub url: String,
+pub struct TmdbConfig {
     #[serde(default)]
     pub api_key: String,
+    #[serde(default)]
+    pub read_access_token: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,13 +158,207 @@ pub enum DownloadClientType {
     Transmission,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleMode {
+    /// Burn all subtitles into the video (forces a video transcode).
+    Burn,
+    /// Serve text subtitles as WebVTT sidecars; only bitmap formats are burned.
+    #[default]
+    Sidecar,
+    None,
+}
+
+/// A named transcode target used by `/media/{id}/stream?profile=`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TranscodeProfile {
+    pub name: String,
+    pub max_bitrate_kbps: u32,
+    /// Maximum output resolution as `WIDTHxHEIGHT`, e.g. `1920x1080`.
+    pub max_resolution: String,
+    #[serde(default = "default_video_codecs")]
+    pub video_codecs: Vec<String>,
+    #[serde(default = "default_audio_codecs")]
+    pub audio_codecs: Vec<String>,
+    #[serde(default)]
+    pub subtitle_mode: SubtitleMode,
+    #[serde(default = "default_audio_channels")]
+    pub max_audio_channels: u32,
+}
+
+fn default_video_codecs() -> Vec<String> {
+    vec!["h264".to_string()]
+}
+
+fn default_audio_codecs() -> Vec<String> {
+    vec!["aac".to_string()]
+}
+
+fn default_audio_channels() -> u32 {
+    2
+}
+
+impl TranscodeProfile {
+    pub fn new(name: &str, max_bitrate_kbps: u32, max_resolution: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            max_bitrate_kbps,
+            max_resolution: max_resolution.to_string(),
+            video_codecs: default_video_codecs(),
+            audio_codecs: default_audio_codecs(),
+            subtitle_mode: SubtitleMode::default(),
+            max_audio_channels: default_audio_channels(),
+        }
+    }
+
+    /// Presets used when no profiles are configured. Names match the legacy
+    /// `quality` values so existing clients keep working.
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::new("4k", 20000, "3840x2160"),
+            Self::new("1080p-high", 20000, "1920x1080"),
+            Self::new("1080p-medium", 12000, "1920x1080"),
+            Self::new("1080p", 10000, "1920x1080"),
+            Self::new("1080p-low", 8000, "1920x1080"),
+            Self::new("720p-high", 4000, "1280x720"),
+            Self::new("720p-medium", 3000, "1280x720"),
+            Self::new("720p", 2000, "1280x720"),
+            Self::new("480p", 1500, "720x480"),
+            Self::new("360p", 700, "480x360"),
+            Self::new("original", 200000, "1920x1080"),
+        ]
+    }
+
+    /// Output height in pixels, parsed from `max_resolution`.
+    pub fn max_height(&self) -> Option<u32> {
+        self.max_resolution.split_once('x')?.1.parse().ok()
+    }
+}
+
+/// Shared secrets that Sonarr, Radarr and Plex must present when pushing
+/// events to `/api/webhooks/{source}`. An empty secret disables that receiver.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WebhookConfig {
+    #[serde(default)]
+    pub sonarr_secret: String,
+    #[serde(default)]
+    pub radarr_secret: String,
+    #[serde(default)]
+    pub plex_secret: String,
+}
+
+/// Secret tokens for the per-user iCal feeds, keyed by Plex user ID.
+/// The token in the feed URL stands in for the session cookie, which
+/// calendar apps cannot send.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CalendarConfig {
+    #[serde(default)]
+    pub feed_tokens: HashMap<String, String>,
+}
+
+/// Server-side response cache for Plex, TMDB and *arr calls.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Snapshot the cache to disk so it survives restarts.
+    #[serde(default)]
+    pub persist: bool,
+    /// Per-route TTL overrides in seconds, keyed by route name (e.g. "tmdb.lists").
+    #[serde(default)]
+    pub ttl_overrides: HashMap<String, u64>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            persist: false,
+            ttl_overrides: HashMap::new(),
+        }
+    }
+}
+
+/// Downloads kept on the server for offline viewing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineConfig {
+    /// Total space finished and running downloads may use.
+    #[serde(default = "default_offline_quota_gb")]
+    pub quota_gb: u64,
+    /// Finished downloads are deleted this many days after they complete.
+    #[serde(default = "default_offline_expiry_days")]
+    pub expiry_days: u32,
+    /// Where files go. Empty means `offline` in the data directory.
+    #[serde(default)]
+    pub path: String,
+}
+
+fn default_offline_quota_gb() -> u64 {
+    50
+}
+
+fn default_offline_expiry_days() -> u32 {
+    7
+}
+
+impl Default for OfflineConfig {
+    fn default() -> Self {
+        Self {
+            quota_gb: default_offline_quota_gb(),
+            expiry_days: default_offline_expiry_days(),
+            path: String::new(),
+        }
+    }
+}
+
+/// Resized images served by `/api/images`, kept on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImagesConfig {
+    /// Size cap for the cache; the least recently used images go first.
+    #[serde(default = "default_image_cache_mb")]
+    pub cache_mb: u64,
+    /// Encoder quality (1-100) when a request doesn't give one.
+    #[serde(default = "default_image_quality")]
+    pub quality: u8,
+    /// Where images go. Empty means `images` in the data directory.
+    #[serde(default)]
+    pub path: String,
+}
+
+fn default_image_cache_mb() -> u64 {
+    1024
+}
+
+fn default_image_quality() -> u8 {
+    75
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        Self {
+            cache_mb: default_image_cache_mb(),
+            quality: default_image_quality(),
+            path: String::new(),
+        }
+    }
+}
+
 /// A redacted version of AppConfig for API responses (hides secrets)
 #[derive(Debug, Serialize)]
 pub struct RedactedAppConfig {
     pub plex: RedactedPlexConfig,
+    /// Default Sonarr instance, kept for clients that predate multiple instances.
     pub sonarr: RedactedSonarrConfig,
     pub radarr: RedactedRadarrConfig,
+    pub sonarr_instances: Vec<RedactedArrInstanceConfig>,
+    pub radarr_instances: Vec<RedactedArrInstanceConfig>,
+    pub tmdb: RedactedTmdbConfig,
     pub download_clients: Vec<RedactedDownloadClientConfig>,
+    pub transcode_profiles: Vec<TranscodeProfile>,
+    pub webhooks: RedactedWebhookConfig,
+    pub cache: CacheConfig,
+    pub offline: OfflineConfig,
+    pub images: ImagesConfig,
 }
 
 #[derive(Debug, Serialize)]
@@ -113,6 +379,40 @@ pub struct RedactedRadarrConfig {
     pub has_api_key: bool,
 }
 
+#[derive(Debug, Serialize)]
+pub struct RedactedArrInstanceConfig {
+    pub name: String,
+    pub url: String,
+    pub has_api_key: bool,
+    pub is_default: bool,
+    pub tags: Vec<String>,
+}
+
+impl From<&ArrInstanceConfig> for RedactedArrInstanceConfig {
+    fn from(i: &ArrInstanceConfig) -> Self {
+        Self {
+            name: i.label().to_string(),
+            url: i.url.clone(),
+            has_api_key: !i.api_key.is_empty(),
+            is_default: i.is_default,
+            tags: i.tags.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RedactedTmdbConfig {
+    pub has_api_key: bool,
+    pub has_read_access_token: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RedactedWebhookConfig {
+    pub has_sonarr_secret: bool,
+    pub has_radarr_secret: bool,
+    pub has_plex_secret: bool,
+}
+
 #[derive(Debug, Serialize)]
 pub struct RedactedDownloadClientConfig {
     pub name: String,
@@ -125,6 +425,15 @@ pub struct RedactedDownloadClientConfig {
 }
 
 impl AppConfig {
+    /// Configured transcode profiles, or the built-in presets when none are set.
+    pub fn effective_transcode_profiles(&self) -> Vec<TranscodeProfile> {
+        if self.transcode_profiles.is_empty() {
+            TranscodeProfile::builtin()
+        } else {
+            self.transcode_profiles.clone()
+        }
+    }
+
     pub fn redacted(&self) -> RedactedAppConfig {
         RedactedAppConfig {
             plex: RedactedPlexConfig {
@@ -132,12 +441,18 @@ impl AppConfig {
                 has_token: !self.plex.token.is_empty(),
             },
             sonarr: RedactedSonarrConfig {
-                url: self.sonarr.url.clone(),
-                has_api_key: !self.sonarr.api_key.is_empty(),
+                url: default_instance(&self.sonarr).map(|i| i.url.clone()).unwrap_or_default(),
+                has_api_key: default_instance(&self.sonarr).is_some_and(|i| !i.api_key.is_empty()),
             },
             radarr: RedactedRadarrConfig {
-                url: self.radarr.url.clone(),
-                has_api_key: !self.radarr.api_key.is_empty(),
+                url: default_instance(&self.radarr).map(|i| i.url.clone()).unwrap_or_default(),
+                has_api_key: default_instance(&self.radarr).is_some_and(|i| !i.api_key.is_empty()),
+            },
+            sonarr_instances: self.sonarr.iter().map(Into::into).collect(),
+            radarr_instances: self.radarr.iter().map(Into::into).collect(),
+            tmdb: RedactedTmdbConfig {
+                has_api_key: !self.tmdb.api_key.is_empty(),
+                has_read_access_token: !self.tmdb.read_access_token.is_empty(),
             },
             download_clients: self
                 .download_clients
@@ -151,6 +466,15 @@ impl AppConfig {
                     enabled: c.enabled,
                 })
                 .collect(),
+            transcode_profiles: self.effective_transcode_profiles(),
+            webhooks: RedactedWebhookConfig {
+                has_sonarr_secret: !self.webhooks.sonarr_secret.is_empty(),
+                has_radarr_secret: !self.webhooks.radarr_secret.is_empty(),
+                has_plex_secret: !self.webhooks.plex_secret.is_empty(),
+            },
+            cache: self.cache.clone(),
+            offline: self.offline.clone(),
+            images: self.images.clone(),
         }
     }
 }
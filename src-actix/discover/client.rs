@@ -0,0 +1,91 @@
+use reqwest::Client;
+use crate::config::SharedConfig;
+use crate::http_error::{Error, Result};
+
+pub const TMDB_BASE: &str = "https://api.themoviedb.org/3";
+
+/// Credentials used to authenticate against TMDB. A v4 read access token is
+/// preferred over a v3 API key when both are set.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TmdbAuth {
+    ApiKey(String),
+    ReadToken(String),
+}
+
+impl TmdbAuth {
+    pub fn resolve(api_key: &str, read_token: &str) -> Option<Self> {
+        if !read_token.is_empty() {
+            Some(Self::ReadToken(read_token.to_string()))
+        } else if !api_key.is_empty() {
+            Some(Self::ApiKey(api_key.to_string()))
+        } else {
+            None
+        }
+    }
+
+    pub fn apply(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self {
+            Self::ApiKey(key) => req.query(&[("api_key", key.as_str())]),
+            Self::ReadToken(token) => req.bearer_auth(token),
+        }
+    }
+}
+
+pub struct TmdbClient {
+    pub http: Client,
+    pub config: SharedConfig,
+    base_url: String,
+    /// `TMDB_API_KEY` from the environment, used when nothing is saved in
+    /// config so existing deployments keep working.
+    env_api_key: Option<String>,
+}
+
+impl TmdbClient {
+    pub fn new(config: SharedConfig) -> Self {
+        let mut client = Self::with_base_url(config, TMDB_BASE);
+        client.env_api_key = std::env::var("TMDB_API_KEY").ok().filter(|k| !k.is_empty());
+        client
+    }
+
+    pub fn with_base_url(config: SharedConfig, base_url: &str) -> Self {
+        let http = Client::builder()
+            .timeout(std::time::Duration::from_secs(15))
+            .build()
+            .expect("Failed to create HTTP client");
+        Self {
+            http,
+            config,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            env_api_key: None,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn resolve_auth(&self, api_key: &str, read_token: &str) -> Option<TmdbAuth> {
+        TmdbAuth::resolve(api_key, read_token)
+            .or_else(|| self.env_api_key.clone().map(TmdbAuth::ApiKey))
+    }
+
+    pub fn auth(&self) -> Option<TmdbAuth> {
+        let cfg = self.config.read().ok()?;
+        self.resolve_auth(&cfg.tmdb.api_key, &cfg.tmdb.read_access_token)
+    }
+
+    /// Fail with `ServiceUnavailable` when no TMDB credentials are configured.
+    pub fn ensure_configured(&self) -> Result<TmdbAuth> {
+        self.auth().ok_or_else(|| {
+            Error::ServiceUnavailable(
+                "TMDB is not configured. Add an API key or read access token in Settings.".to_string(),
+            )
+        })
+    }
+
+    pub fn get(&self, path: &str) -> Result<reqwest::RequestBuilder> {
+        let auth = self.ensure_configured()?;
+        let url = format!("{}{}", self.base_url, path);
+        Ok(auth.apply(self.http.get(&url)))
+    }
+}
@@ -1,14 +1,30 @@
-use actix_web::{get, web, HttpResponse, Responder};
-use crate::http_error::Result;
-
-const TMDB_BASE: &str = "https://api.themoviedb.org/3";
-const TMDB_API_KEY: &str = env!("TMDB_API_KEY");
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use crate::cache::{self, ResponseCache};
+use crate::discover::client::TmdbClient;
+use crate::http_error::{Error, Result};
+
+/// The error for a TMDB response outside 2xx. Returned from inside the
+/// cache fetch so a rate limit or bad key is never cached as a result.
+fn status_error(status: reqwest::StatusCode, path: &str) -> Error {
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        Error::Unauthorized("TMDB rejected the configured credentials".to_string())
+    } else {
+        Error::UpstreamError(format!("TMDB returned HTTP {} for {}", status.as_u16(), path))
+    }
+}
 
-fn tmdb_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(15))
-        .build()
-        .expect("Failed to create HTTP client")
+async fn fetch_json(request: reqwest::RequestBuilder, path: &str) -> Result<serde_json::Value> {
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(status_error(resp.status(), path));
+    }
+    Ok(resp
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?)
 }
 
 #[derive(serde::Deserialize)]
@@ -17,7 +33,13 @@ struct SearchQuery {
 }
 
 #[get("/search")]
-async fn search(query: web::Query<SearchQuery>) -> Result<impl Responder> {
+async fn search(
+    req: HttpRequest,
+    cache: web::Data<ResponseCache>,
+    tmdb: web::Data<TmdbClient>,
+    query: web::Query<SearchQuery>,
+) -> Result<impl Responder> {
+    tmdb.ensure_configured()?;
     if query.q.trim().is_empty() {
         return Ok(HttpResponse::Ok().json(serde_json::json!({
             "movies": [],
@@ -25,125 +47,109 @@ async fn search(query: web::Query<SearchQuery>) -> Result<impl Responder> {
         })));
     }
 
-    let client = tmdb_client();
-    let resp = client
-        .get(format!("{}/search/multi", TMDB_BASE))
-        .query(&[
-            ("api_key", TMDB_API_KEY),
-            ("query", query.q.as_str()),
+    let q = query.q.trim();
+    let key = ResponseCache::key(cache::TMDB_SEARCH, &format!("/search/multi?query={}", q), &req);
+    let entry = cache.get_or_fetch(key, cache::TMDB_SEARCH, || async move {
+        let request = tmdb.get("/search/multi")?.query(&[
+            ("query", q),
             ("include_adult", "false"),
-        ])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
-
-    let results = resp["results"].as_array().cloned().unwrap_or_default();
-    let movies: Vec<&serde_json::Value> = results
-        .iter()
-        .filter(|r| r["media_type"].as_str() == Some("movie"))
-        .collect();
-    let tv: Vec<&serde_json::Value> = results
-        .iter()
-        .filter(|r| r["media_type"].as_str() == Some("tv"))
-        .collect();
-
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "movies": movies,
-        "tv": tv
-    })))
+        ]);
+        let resp = fetch_json(request, "/search/multi").await?;
+
+        let results = resp["results"].as_array().cloned().unwrap_or_default();
+        let movies: Vec<&serde_json::Value> = results
+            .iter()
+            .filter(|r| r["media_type"].as_str() == Some("movie"))
+            .collect();
+        let tv: Vec<&serde_json::Value> = results
+            .iter()
+            .filter(|r| r["media_type"].as_str() == Some("tv"))
+            .collect();
+
+        Ok(serde_json::json!({
+            "movies": movies,
+            "tv": tv
+        }))
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::TMDB_SEARCH))
 }
 
 #[get("/trending")]
-async fn trending() -> Result<impl Responder> {
-    let client = tmdb_client();
-    let movies = client
-        .get(format!("{}/trending/movie/week", TMDB_BASE))
-        .query(&[("api_key", TMDB_API_KEY)])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+async fn trending(
+    req: HttpRequest,
+    cache: web::Data<ResponseCache>,
+    tmdb: web::Data<TmdbClient>,
+) -> Result<impl Responder> {
+    tmdb.ensure_configured()?;
+    let key = ResponseCache::key(cache::TMDB_LISTS, "/trending/week", &req);
+    let entry = cache.get_or_fetch(key, cache::TMDB_LISTS, || async move {
+        let movies = fetch_json(tmdb.get("/trending/movie/week")?, "/trending/movie/week").await?;
 
-    let tv = client
-        .get(format!("{}/trending/tv/week", TMDB_BASE))
-        .query(&[("api_key", TMDB_API_KEY)])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+        let tv = fetch_json(tmdb.get("/trending/tv/week")?, "/trending/tv/week").await?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "movies": movies["results"],
-        "tv": tv["results"]
-    })))
+        Ok(serde_json::json!({
+            "movies": movies["results"],
+            "tv": tv["results"]
+        }))
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::TMDB_LISTS))
 }
 
 #[get("/upcoming")]
-async fn upcoming() -> Result<impl Responder> {
-    let client = tmdb_client();
-    let movies = client
-        .get(format!("{}/movie/upcoming", TMDB_BASE))
-        .query(&[("api_key", TMDB_API_KEY)])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+async fn upcoming(
+    req: HttpRequest,
+    cache: web::Data<ResponseCache>,
+    tmdb: web::Data<TmdbClient>,
+) -> Result<impl Responder> {
+    tmdb.ensure_configured()?;
+    let key = ResponseCache::key(cache::TMDB_LISTS, "/movie/upcoming", &req);
+    let entry = cache.get_or_fetch(key, cache::TMDB_LISTS, || async move {
+        let movies = fetch_json(tmdb.get("/movie/upcoming")?, "/movie/upcoming").await?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "movies": movies["results"]
-    })))
+        Ok(serde_json::json!({
+            "movies": movies["results"]
+        }))
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::TMDB_LISTS))
 }
 
 #[get("/recent")]
-async fn recent() -> Result<impl Responder> {
-    let now = chrono::Utc::now();
-    let month_ago = (now - chrono::Duration::days(30)).format("%Y-%m-%d").to_string();
-    let today = now.format("%Y-%m-%d").to_string();
-
-    let client = tmdb_client();
-    let movies = client
-        .get(format!("{}/discover/movie", TMDB_BASE))
-        .query(&[
-            ("api_key", TMDB_API_KEY),
+async fn recent(
+    req: HttpRequest,
+    cache: web::Data<ResponseCache>,
+    tmdb: web::Data<TmdbClient>,
+) -> Result<impl Responder> {
+    tmdb.ensure_configured()?;
+    let key = ResponseCache::key(cache::TMDB_LISTS, "/discover/recent", &req);
+    let entry = cache.get_or_fetch(key, cache::TMDB_LISTS, || async move {
+        let now = chrono::Utc::now();
+        let month_ago = (now - chrono::Duration::days(30)).format("%Y-%m-%d").to_string();
+        let today = now.format("%Y-%m-%d").to_string();
+
+        let request = tmdb.get("/discover/movie")?.query(&[
             ("sort_by", "popularity.desc"),
             ("primary_release_date.gte", month_ago.as_str()),
             ("primary_release_date.lte", today.as_str()),
-        ])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+        ]);
+        let movies = fetch_json(request, "/discover/movie").await?;
 
-    let tv = client
-        .get(format!("{}/discover/tv", TMDB_BASE))
-        .query(&[
-            ("api_key", TMDB_API_KEY),
+        let request = tmdb.get("/discover/tv")?.query(&[
             ("sort_by", "popularity.desc"),
             ("first_air_date.gte", month_ago.as_str()),
             ("first_air_date.lte", today.as_str()),
-        ])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+        ]);
+        let tv = fetch_json(request, "/discover/tv").await?;
+
+        Ok(serde_json::json!({
+            "movies": movies["results"],
+            "tv": tv["results"]
+        }))
+    }).await?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "movies": movies["results"],
-        "tv": tv["results"]
-    })))
+    Ok(cache::respond(&req, &entry, cache::TMDB_LISTS))
 }
 
 #[derive(serde::Deserialize)]
@@ -156,8 +162,12 @@ struct LogoQuery {
 
 #[get("/logo")]
 async fn logo(
+    req: HttpRequest,
+    cache: web::Data<ResponseCache>,
+    tmdb: web::Data<TmdbClient>,
     query: web::Query<LogoQuery>,
 ) -> Result<impl Responder> {
+    tmdb.ensure_configured()?;
     let media_type = match query.media_type.as_str() {
         "movie" => "movie",
         "tv" => "tv",
@@ -166,48 +176,55 @@ async fn logo(
         )),
     };
 
-    let client = tmdb_client();
-
-    // Use the requested language, default to "en"
     let lang = query.lang.as_deref().unwrap_or("en");
-    let image_languages = format!("{},null", lang);
-
-    let resp = client
-        .get(format!("{}/{}/{}/images", TMDB_BASE, media_type, query.tmdb_id))
-        .query(&[
-            ("api_key", TMDB_API_KEY),
-            ("include_image_languages", image_languages.as_str()),
-        ])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?;
-
-    if resp.status() == reqwest::StatusCode::NOT_FOUND {
-        return Ok(HttpResponse::Ok().json(serde_json::json!({
-            "logo_path": null
-        })));
-    }
+    let upstream = format!("/{}/{}/images?lang={}", media_type, query.tmdb_id, lang);
+    let key = ResponseCache::key(cache::TMDB_DETAIL, &upstream, &req);
+    let entry = cache.get_or_fetch(key, cache::TMDB_DETAIL, || async move {
+        // Use the requested language, default to "en"
+        let lang = query.lang.as_deref().unwrap_or("en");
+        let image_languages = format!("{},null", lang);
+
+        let resp = tmdb
+            .get(&format!("/{}/{}/images", media_type, query.tmdb_id))?
+            .query(&[
+                ("include_image_languages", image_languages.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(serde_json::json!({
+                "logo_path": null
+            }));
+        }
+        if !resp.status().is_success() {
+            return Err(status_error(resp.status(), resp.url().path()));
+        }
 
-    let body = resp
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
-
-    // Prefer a logo matching the exact requested language,
-    // then fall back to language-neutral (null) logos
-    let logo_path = body["logos"]
-        .as_array()
-        .and_then(|logos| {
-            logos.iter()
-                .find(|l| l["iso_639_1"].as_str() == Some(lang))
-                .or_else(|| logos.iter().find(|l| l["iso_639_1"].is_null()))
-        })
-        .and_then(|logo| logo["file_path"].as_str())
-        .map(|s| s.to_string());
-
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "logo_path": logo_path
-    })))
+        let body = resp
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+
+        // Prefer a logo matching the exact requested language,
+        // then fall back to language-neutral (null) logos
+        let logo_path = body["logos"]
+            .as_array()
+            .and_then(|logos| {
+                logos.iter()
+                    .find(|l| l["iso_639_1"].as_str() == Some(lang))
+                    .or_else(|| logos.iter().find(|l| l["iso_639_1"].is_null()))
+            })
+            .and_then(|logo| logo["file_path"].as_str())
+            .map(|s| s.to_string());
+
+        Ok(serde_json::json!({
+            "logo_path": logo_path
+        }))
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::TMDB_DETAIL))
 }
 
 #[derive(serde::Deserialize)]
@@ -219,8 +236,12 @@ struct VideosQuery {
 
 #[get("/videos")]
 async fn videos(
+    req: HttpRequest,
+    cache: web::Data<ResponseCache>,
+    tmdb: web::Data<TmdbClient>,
     query: web::Query<VideosQuery>,
 ) -> Result<impl Responder> {
+    tmdb.ensure_configured()?;
     let media_type = match query.media_type.as_str() {
         "movie" => "movie",
         "tv" => "tv",
@@ -229,117 +250,156 @@ async fn videos(
         )),
     };
 
-    let client = tmdb_client();
-    let resp = client
-        .get(format!("{}/{}/{}/videos", TMDB_BASE, media_type, query.tmdb_id))
-        .query(&[("api_key", TMDB_API_KEY)])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?;
+    let key = ResponseCache::key(cache::TMDB_DETAIL, &format!("/{}/{}/videos", media_type, query.tmdb_id), &req);
+    let entry = cache.get_or_fetch(key, cache::TMDB_DETAIL, || async move {
+        let resp = tmdb
+            .get(&format!("/{}/{}/videos", media_type, query.tmdb_id))?
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?;
 
-    if resp.status() == reqwest::StatusCode::NOT_FOUND {
-        return Ok(HttpResponse::Ok().json(serde_json::json!({ "results": [] })));
-    }
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(serde_json::json!({ "results": [] }));
+        }
+        if !resp.status().is_success() {
+            return Err(status_error(resp.status(), resp.url().path()));
+        }
 
-    let body = resp
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
-
-    let results: Vec<&serde_json::Value> = body["results"]
-        .as_array()
-        .map(|arr| {
-            arr.iter()
-                .filter(|v| {
-                    v["site"].as_str() == Some("YouTube")
-                        && matches!(v["type"].as_str(), Some("Trailer") | Some("Teaser"))
-                })
-                .collect()
-        })
-        .unwrap_or_default();
-
-    Ok(HttpResponse::Ok().json(serde_json::json!({ "results": results })))
+        let body = resp
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+
+        let results: Vec<&serde_json::Value> = body["results"]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .filter(|v| {
+                        v["site"].as_str() == Some("YouTube")
+                            && matches!(v["type"].as_str(), Some("Trailer") | Some("Teaser"))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(serde_json::json!({ "results": results }))
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::TMDB_DETAIL))
 }
 
 #[get("/movie/{id}")]
 async fn movie_detail(
+    req: HttpRequest,
+    cache: web::Data<ResponseCache>,
+    tmdb: web::Data<TmdbClient>,
     path: web::Path<u64>,
 ) -> Result<impl Responder> {
+    tmdb.ensure_configured()?;
     let id = path.into_inner();
-    let client = tmdb_client();
-    let resp = client
-        .get(format!("{}/movie/{}", TMDB_BASE, id))
-        .query(&[
-            ("api_key", TMDB_API_KEY),
-            ("append_to_response", "credits,videos,external_ids"),
-        ])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?;
 
-    if resp.status() == reqwest::StatusCode::NOT_FOUND {
-        return Err(crate::http_error::Error::NotFound("Movie not found".to_string()));
-    }
+    let key = ResponseCache::key(cache::TMDB_DETAIL, &format!("/movie/{}", id), &req);
+    let entry = cache.get_or_fetch(key, cache::TMDB_DETAIL, || async move {
+        let resp = tmdb
+            .get(&format!("/movie/{}", id))?
+            .query(&[
+                ("append_to_response", "credits,videos,external_ids"),
+            ])
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(crate::http_error::Error::NotFound("Movie not found".to_string()));
+        }
+        if !resp.status().is_success() {
+            return Err(status_error(resp.status(), resp.url().path()));
+        }
 
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+        let body: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
 
-    Ok(HttpResponse::Ok().json(body))
+        Ok(body)
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::TMDB_DETAIL))
 }
 
 #[get("/tv/{id}")]
 async fn tv_detail(
+    req: HttpRequest,
+    cache: web::Data<ResponseCache>,
+    tmdb: web::Data<TmdbClient>,
     path: web::Path<u64>,
 ) -> Result<impl Responder> {
+    tmdb.ensure_configured()?;
     let id = path.into_inner();
-    let client = tmdb_client();
-    let resp = client
-        .get(format!("{}/tv/{}", TMDB_BASE, id))
-        .query(&[
-            ("api_key", TMDB_API_KEY),
-            ("append_to_response", "credits,videos,external_ids"),
-        ])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?;
 
-    if resp.status() == reqwest::StatusCode::NOT_FOUND {
-        return Err(crate::http_error::Error::NotFound("TV show not found".to_string()));
-    }
+    let key = ResponseCache::key(cache::TMDB_DETAIL, &format!("/tv/{}", id), &req);
+    let entry = cache.get_or_fetch(key, cache::TMDB_DETAIL, || async move {
+        let resp = tmdb
+            .get(&format!("/tv/{}", id))?
+            .query(&[
+                ("append_to_response", "credits,videos,external_ids"),
+            ])
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(crate::http_error::Error::NotFound("TV show not found".to_string()));
+        }
+        if !resp.status().is_success() {
+            return Err(status_error(resp.status(), resp.url().path()));
+        }
 
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+        let body: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+
+        Ok(body)
+    }).await?;
 
-    Ok(HttpResponse::Ok().json(body))
+    Ok(cache::respond(&req, &entry, cache::TMDB_DETAIL))
 }
 
 #[get("/tv/{id}/season/{season_number}")]
 async fn tv_season(
+    req: HttpRequest,
+    cache: web::Data<ResponseCache>,
+    tmdb: web::Data<TmdbClient>,
     path: web::Path<(u64, u32)>,
 ) -> Result<impl Responder> {
+    tmdb.ensure_configured()?;
     let (id, season_number) = path.into_inner();
-    let client = tmdb_client();
-    let resp = client
-        .get(format!("{}/tv/{}/season/{}", TMDB_BASE, id, season_number))
-        .query(&[("api_key", TMDB_API_KEY)])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?;
 
-    if resp.status() == reqwest::StatusCode::NOT_FOUND {
-        return Err(crate::http_error::Error::NotFound("Season not found".to_string()));
-    }
+    let key = ResponseCache::key(cache::TMDB_DETAIL, &format!("/tv/{}/season/{}", id, season_number), &req);
+    let entry = cache.get_or_fetch(key, cache::TMDB_DETAIL, || async move {
+        let resp = tmdb
+            .get(&format!("/tv/{}/season/{}", id, season_number))?
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("TMDB request failed: {}", e))?;
 
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(crate::http_error::Error::NotFound("Season not found".to_string()));
+        }
+        if !resp.status().is_success() {
+            return Err(status_error(resp.status(), resp.url().path()));
+        }
+
+        let body: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
 
-    Ok(HttpResponse::Ok().json(body))
+        Ok(body)
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::TMDB_DETAIL))
 }
 
 /// Stream a YouTube video through rusty_ytdl so the frontend can play it
@@ -401,54 +461,55 @@ async fn youtube_stream(
 /// TMDB genre IDs: Action=28, Comedy=35, Drama=18, Horror=27, Sci-Fi=878,
 /// Thriller=53, Romance=10749, Animation=16, Documentary=99, Crime=80
 #[get("/by-genre")]
-async fn by_genre() -> Result<impl Responder> {
-    let client = tmdb_client();
-
-    let genres = [
-        (28, "Action"),
-        (35, "Comedy"),
-        (878, "Sci-Fi"),
-        (27, "Horror"),
-        (10749, "Romance"),
-        (99, "Documentary"),
-    ];
-
-    let futures: Vec<_> = genres.iter().map(|(id, name)| {
-        let client = client.clone();
-        let genre_id = id.to_string();
-        let genre_name = name.to_string();
-        async move {
-            // Fetch movies for this genre
-            let movies = client
-                .get(format!("{}/discover/movie", TMDB_BASE))
-                .query(&[
-                    ("api_key", TMDB_API_KEY),
+async fn by_genre(
+    req: HttpRequest,
+    cache: web::Data<ResponseCache>,
+    tmdb: web::Data<TmdbClient>,
+) -> Result<impl Responder> {
+    tmdb.ensure_configured()?;
+    let key = ResponseCache::key(cache::TMDB_LISTS, "/discover/by-genre", &req);
+    let entry = cache.get_or_fetch(key, cache::TMDB_LISTS, || async move {
+        let genres = [
+            (28, "Action"),
+            (35, "Comedy"),
+            (878, "Sci-Fi"),
+            (27, "Horror"),
+            (10749, "Romance"),
+            (99, "Documentary"),
+        ];
+
+        let futures: Vec<_> = genres.iter().map(|(id, name)| {
+            let genre_id = id.to_string();
+            let genre_name = name.to_string();
+            let request = tmdb.get("/discover/movie").map(|r| {
+                r.query(&[
                     ("sort_by", "popularity.desc"),
                     ("with_genres", genre_id.as_str()),
                     ("vote_count.gte", "100"),
                     ("page", "1"),
                 ])
-                .send()
-                .await
-                .ok()?
-                .json::<serde_json::Value>()
-                .await
-                .ok()?;
-
-            let items = movies["results"].as_array()?.clone();
-            if items.is_empty() { return None; }
-            Some(serde_json::json!({
-                "genre": genre_name,
-                "genre_id": genre_id,
-                "items": items
-            }))
-        }
-    }).collect();
-
-    let results = futures_util::future::join_all(futures).await;
-    let groups: Vec<_> = results.into_iter().flatten().collect();
-
-    Ok(HttpResponse::Ok().json(groups))
+            });
+            async move {
+                // Fetch movies for this genre
+                let movies = fetch_json(request?, "/discover/movie").await?;
+
+                let items = movies["results"].as_array().cloned().unwrap_or_default();
+                if items.is_empty() { return Ok(None); }
+                Ok(Some(serde_json::json!({
+                    "genre": genre_name,
+                    "genre_id": genre_id,
+                    "items": items
+                })))
+            }
+        }).collect();
+
+        let results = futures_util::future::join_all(futures).await;
+        let groups: Vec<_> = results.into_iter().collect::<Result<Vec<_>>>()?.into_iter().flatten().collect();
+
+        Ok(serde_json::json!(groups))
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::TMDB_LISTS))
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
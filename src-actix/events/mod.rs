@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+use std::sync::RwLock;
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use actix_ws::Message;
+use futures_util::StreamExt;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::http_error::{self, Result};
+use crate::plex::client::PlexClient;
+
+/// Maximum number of events kept for the activity feed.
+const ACTIVITY_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventSource {
+    Sonarr,
+    Radarr,
+    Plex,
+}
+
+/// Normalized event category. The original upstream event name is kept
+/// alongside in `ActivityEvent::event_type`.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Grab,
+    Download,
+    Rename,
+    Health,
+    Test,
+    MediaPlay,
+    LibraryNew,
+    /// A Sonarr/Radarr command started from Playarr finished.
+    Command,
+    Other,
+}
+
+/// External identifiers of the media an event refers to, if any.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct MediaIds {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tmdb_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tvdb_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imdb_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plex_rating_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEvent {
+    pub id: Uuid,
+    pub source: EventSource,
+    pub kind: EventKind,
+    pub event_type: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub media: MediaIds,
+    pub received_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ActivityEvent {
+    pub fn new(source: EventSource, kind: EventKind, event_type: &str, title: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            source,
+            kind,
+            event_type: event_type.to_string(),
+            title,
+            message: None,
+            media: MediaIds::default(),
+            received_at: chrono::Utc::now(),
+        }
+    }
+}
+
+/// In-process event bus. Webhook receivers publish here; the activity feed,
+/// cache invalidation and live notifications consume from it.
+pub struct EventBus {
+    sender: broadcast::Sender<ActivityEvent>,
+    recent: RwLock<VecDeque<ActivityEvent>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(64);
+        Self {
+            sender,
+            recent: RwLock::new(VecDeque::with_capacity(ACTIVITY_CAPACITY)),
+        }
+    }
+
+    /// Record an event in the activity feed and notify all subscribers.
+    pub fn publish(&self, event: ActivityEvent) {
+        debug!("Event from {:?}: {} ({})", event.source, event.event_type, event.title);
+        if let Ok(mut recent) = self.recent.write() {
+            recent.push_front(event.clone());
+            recent.truncate(ACTIVITY_CAPACITY);
+        }
+        // No receivers is not an error — nobody is listening right now.
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ActivityEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Most recent events first.
+    pub fn recent(&self, limit: usize) -> Vec<ActivityEvent> {
+        self.recent
+            .read()
+            .map(|r| r.iter().take(limit).cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize)]
+struct ActivityQuery {
+    limit: Option<usize>,
+}
+
+#[get("/activity")]
+async fn activity_feed(
+    req: HttpRequest,
+    events: web::Data<EventBus>,
+    query: web::Query<ActivityQuery>,
+) -> Result<impl Responder> {
+    PlexClient::user_from_request(&req)
+        .ok_or_else(|| http_error::Error::Unauthorized("Not signed in".to_string()))?;
+    let limit = query.limit.unwrap_or(50).min(ACTIVITY_CAPACITY);
+    Ok(HttpResponse::Ok().json(events.recent(limit)))
+}
+
+/// Live notification stream: every published event is pushed as a JSON text frame.
+#[get("/events/ws")]
+async fn events_websocket(
+    req: HttpRequest,
+    stream: web::Payload,
+    events: web::Data<EventBus>,
+) -> std::result::Result<HttpResponse, actix_web::Error> {
+    PlexClient::user_from_request(&req)
+        .ok_or_else(|| actix_web::error::ErrorUnauthorized("Not signed in"))?;
+
+    let (resp, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+    let mut rx = events.subscribe();
+
+    actix_web::rt::spawn(async move {
+        loop {
+            tokio::select! {
+                event = rx.recv() => match event {
+                    Ok(event) => {
+                        let Ok(json) = serde_json::to_string(&event) else { continue };
+                        if session.text(json).await.is_err() {
+                            break;
+                        }
+                    }
+                    // Slow consumer: skip what was missed and keep going.
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                msg = msg_stream.next() => match msg {
+                    Some(Ok(Message::Ping(bytes))) if session.pong(&bytes).await.is_err() => break,
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                    _ => {}
+                },
+            }
+        }
+        let _ = session.close(None).await;
+    });
+
+    Ok(resp)
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(activity_feed).service(events_websocket);
+}
@@ -0,0 +1,416 @@
+//! `/api/images`: one proxy for Plex, TMDB and *arr artwork that resizes,
+//! converts to AVIF/WebP/JPEG, caches the result on disk and computes
+//! blurhash placeholders, so poster grids load small images from one origin.
+
+mod store;
+pub mod transform;
+
+use std::path::PathBuf;
+
+use actix_web::http::header;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::require_admin;
+use crate::config::SharedConfig;
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+use crate::radarr::client::RadarrClient;
+use crate::sonarr::client::SonarrClient;
+use store::ImageStore;
+use transform::{Format, MAX_DIMENSION};
+
+pub const TMDB_IMAGE_BASE: &str = "https://image.tmdb.org/t/p";
+/// Browsers may keep content-addressed images for a year.
+const IMMUTABLE_SECS: u64 = 365 * 24 * 3600;
+/// Hosts `source=remote` may fetch from: where *arr `remoteUrl`s point.
+const REMOTE_HOSTS: &[&str] = &["image.tmdb.org", "artworks.thetvdb.com", "thetvdb.com", "assets.fanart.tv"];
+
+/// Size, quality and format of the image to serve.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Resize {
+    #[serde(default, alias = "w")]
+    pub width: Option<u32>,
+    #[serde(default, alias = "h")]
+    pub height: Option<u32>,
+    #[serde(default, alias = "q")]
+    pub quality: Option<u8>,
+    #[serde(default = "auto_format")]
+    pub format: Format,
+}
+
+fn auto_format() -> Format {
+    Format::Auto
+}
+
+impl Resize {
+    fn validate(&self) -> Result<()> {
+        if [self.width, self.height].iter().flatten().any(|&d| d == 0 || d > MAX_DIMENSION) {
+            return Err(Error::BadRequest(format!("width and height must be between 1 and {}", MAX_DIMENSION)));
+        }
+        if self.quality.is_some_and(|q| q == 0 || q > 100) {
+            return Err(Error::BadRequest("quality must be between 1 and 100".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+enum Source {
+    Plex,
+    Tmdb,
+    Sonarr,
+    Radarr,
+    Remote,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceQuery {
+    source: Source,
+    /// Plex path, TMDB file path, *arr `MediaCover` path or remote URL.
+    path: String,
+    /// *arr instance; the default one when left out.
+    instance: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Blurhash {
+    blurhash: String,
+    width: u32,
+    height: u32,
+}
+
+pub struct ImageService {
+    http: reqwest::Client,
+    config: SharedConfig,
+    store: ImageStore,
+    tmdb_base: String,
+}
+
+impl ImageService {
+    pub fn new(config: SharedConfig) -> Self {
+        Self::with_tmdb_base(config, TMDB_IMAGE_BASE)
+    }
+
+    pub fn with_tmdb_base(config: SharedConfig, tmdb_base: &str) -> Self {
+        let (configured, cache_mb) = config
+            .read()
+            .map(|c| (c.images.path.clone(), c.images.cache_mb))
+            .unwrap_or_default();
+        let dir = if cfg!(test) {
+            std::env::temp_dir().join(format!("playarr-images-{}", uuid::Uuid::new_v4()))
+        } else if configured.is_empty() {
+            crate::config::data_dir().join("images")
+        } else {
+            PathBuf::from(configured)
+        };
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("Failed to create HTTP client");
+        Self {
+            http,
+            config,
+            store: ImageStore::new(dir, cache_mb.saturating_mul(1024 * 1024)),
+            tmdb_base: tmdb_base.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn default_quality(&self) -> u8 {
+        self.config.read().map(|c| c.images.quality).unwrap_or(75).clamp(1, 100)
+    }
+
+    /// Serve `upstream` resized and re-encoded, from the disk cache when
+    /// possible. `id` names the upstream image for the cache key;
+    /// `max_age_secs` is for sources whose URL doesn't change with the
+    /// artwork, and makes cached copies expire.
+    pub async fn serve(
+        &self,
+        req: &HttpRequest,
+        id: &str,
+        resize: &Resize,
+        max_age_secs: Option<u64>,
+        upstream: reqwest::RequestBuilder,
+    ) -> Result<HttpResponse> {
+        resize.validate()?;
+        let accept = req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        let format = resize.format.negotiate(accept);
+        let quality = resize.quality.unwrap_or_else(|| self.default_quality());
+        let dimension = |d: Option<u32>| d.map_or(String::new(), |d| d.to_string());
+        let stem = cache_stem(&format!(
+            "{}|{}|{}|{}|{}",
+            id,
+            dimension(resize.width),
+            dimension(resize.height),
+            quality,
+            format.extension()
+        ));
+        let etag = format!("\"{}\"", stem);
+        let cache_control = match max_age_secs {
+            Some(secs) => format!("public, max-age={}", secs),
+            None => format!("public, max-age={}, immutable", IMMUTABLE_SECS),
+        };
+        let respond = |body: Vec<u8>, format: Format| {
+            let mut resp = HttpResponse::Ok();
+            resp.content_type(format.mime())
+                .insert_header((header::CACHE_CONTROL, cache_control.clone()))
+                .insert_header((header::ETAG, etag.clone()));
+            if resize.format == Format::Auto {
+                resp.insert_header((header::VARY, "Accept"));
+            }
+            resp.body(body)
+        };
+
+        if req
+            .headers()
+            .get(header::IF_NONE_MATCH)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v == etag)
+            && self.store.get(&format!("{}.{}", stem, format.extension()), max_age_secs).is_some()
+        {
+            return Ok(HttpResponse::NotModified()
+                .insert_header((header::CACHE_CONTROL, cache_control.clone()))
+                .insert_header((header::ETAG, etag.clone()))
+                .finish());
+        }
+        // JPEG requests for transparent images are stored as PNG
+        for stored in [format, Format::Png] {
+            if let Some(cached) = self.store.get(&format!("{}.{}", stem, stored.extension()), max_age_secs) {
+                return Ok(respond(cached, stored));
+            }
+        }
+
+        let original = fetch(upstream, id).await?;
+        let (width, height) = (resize.width, resize.height);
+        let (body, format) = web::block(move || transform::transform(&original, width, height, quality, format))
+            .await
+            .map_err(|e| anyhow::anyhow!("Image conversion was cancelled: {}", e))?
+            .map_err(|e| Error::UpstreamError(format!("{} is not a readable image: {}", id, e)))?;
+        self.store.put(&format!("{}.{}", stem, format.extension()), &body);
+        Ok(respond(body, format))
+    }
+
+    /// Blurhash placeholder for `upstream`, cached like the images.
+    async fn blurhash(&self, id: &str, max_age_secs: Option<u64>, upstream: reqwest::RequestBuilder) -> Result<Blurhash> {
+        let key = format!("{}.blurhash", cache_stem(&format!("{}|blurhash", id)));
+        if let Some(cached) = self.store.get(&key, max_age_secs)
+            && let Ok(hash) = serde_json::from_slice(&cached)
+        {
+            return Ok(hash);
+        }
+        let original = fetch(upstream, id).await?;
+        let (blurhash, width, height) = web::block(move || transform::blurhash(&original))
+            .await
+            .map_err(|e| anyhow::anyhow!("Blurhash was cancelled: {}", e))?
+            .map_err(|e| Error::UpstreamError(format!("{} is not a readable image: {}", id, e)))?;
+        let hash = Blurhash { blurhash, width, height };
+        self.store.put(&key, &serde_json::to_vec(&hash).map_err(anyhow::Error::from)?);
+        Ok(hash)
+    }
+}
+
+/// 64-bit FNV-1a of `key`. Cached files outlive the binary, so the name
+/// can't come from `DefaultHasher`, which may change between Rust releases.
+fn cache_stem(key: &str) -> String {
+    let hash = key
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// The upstream bytes.
+async fn fetch(upstream: reqwest::RequestBuilder, id: &str) -> Result<Vec<u8>> {
+    let resp = upstream
+        .send()
+        .await
+        .map_err(|e| Error::UpstreamError(format!("Image request for {} failed: {}", id, e)))?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(Error::NotFound(format!("No image at {}", id)));
+    }
+    if !resp.status().is_success() {
+        return Err(Error::UpstreamError(format!("Image request for {} returned {}", id, resp.status().as_u16())));
+    }
+    let bytes = resp
+        .bytes()
+        .await
+        .map_err(|e| Error::UpstreamError(format!("Failed to read image {}: {}", id, e)))?;
+    Ok(bytes.to_vec())
+}
+
+/// Artwork kinds under `/library/metadata/{id}/`, `/library/collections/{id}/`
+/// and `/playlists/{id}/`.
+const PLEX_ARTWORK: &[&str] = &["thumb", "art", "banner", "clearLogo", "composite"];
+
+/// Whether `path` names a piece of Plex artwork, optionally followed by its
+/// update timestamp. Everything else on the server (accounts, sessions,
+/// library listings) is off limits, since requests carry the admin token.
+fn is_plex_artwork(path: &str) -> bool {
+    if path.contains(['?', '#', '%', '\\']) || path.contains("..") {
+        return false;
+    }
+    let numeric = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let segments: Vec<&str> = path.strip_prefix('/').unwrap_or_default().split('/').collect();
+    let (id, kind, rest) = match segments.as_slice() {
+        ["library", "metadata" | "collections", id, kind, rest @ ..] => (*id, *kind, rest),
+        ["playlists", id, kind, rest @ ..] => (*id, *kind, rest),
+        _ => return false,
+    };
+    numeric(id) && PLEX_ARTWORK.contains(&kind) && rest.len() <= 1 && rest.iter().all(|s| numeric(s))
+}
+
+/// A request for Plex artwork. Absolute URLs (agent artwork) go through
+/// Plex's photo transcoder, which fetches them for us and only ever returns
+/// images; server paths must be artwork paths.
+pub fn plex_request(plex: &PlexClient, path: &str) -> Result<reqwest::RequestBuilder> {
+    if path.starts_with("https://") || path.starts_with("http://") {
+        return Ok(plex.get_image("/photo/:/transcode")?.query(&[("url", path), ("minSize", "1")]));
+    }
+    if !is_plex_artwork(path) {
+        return Err(Error::BadRequest(format!("{} is not a Plex artwork path", path)));
+    }
+    plex.get_image(path)
+}
+
+const DAY_SECS: u64 = 24 * 3600;
+
+/// Plex thumbnail paths end in an update timestamp, so those stay cached
+/// until evicted; bare `/thumb` paths and remote artwork are rechecked daily.
+pub fn plex_max_age(path: &str) -> Option<u64> {
+    let versioned = !path.starts_with("http") && path.rsplit('/').next().is_some_and(|s| s.parse::<u64>().is_ok());
+    (!versioned).then_some(DAY_SECS)
+}
+
+/// TMDB paths and *arr `lastWrite` URLs change with the file.
+fn max_age(source: Source, path: &str) -> Option<u64> {
+    match source {
+        Source::Plex => plex_max_age(path),
+        Source::Tmdb => None,
+        Source::Sonarr | Source::Radarr if path.contains("lastWrite=") => None,
+        _ => Some(DAY_SECS),
+    }
+}
+
+/// Widths TMDB serves besides `original`.
+const TMDB_WIDTHS: &[u32] = &[92, 154, 185, 342, 500, 780];
+
+/// The smallest TMDB size at least `width` wide, so a thumbnail isn't cut
+/// from the full-size original.
+fn tmdb_size(width: Option<u32>) -> String {
+    width
+        .and_then(|w| TMDB_WIDTHS.iter().find(|&&size| size >= w))
+        .map_or_else(|| "original".to_string(), |size| format!("w{}", size))
+}
+
+/// `width` picks the TMDB size; leave it out to get the original.
+fn upstream_request(
+    query: &SourceQuery,
+    width: Option<u32>,
+    images: &ImageService,
+    plex: &PlexClient,
+    sonarr: &SonarrClient,
+    radarr: &RadarrClient,
+) -> Result<reqwest::RequestBuilder> {
+    let path = query.path.as_str();
+    match query.source {
+        Source::Plex => plex_request(plex, path),
+        Source::Tmdb => {
+            if !path.starts_with('/') || path.contains("..") {
+                return Err(Error::BadRequest("TMDB image paths look like /abc123.jpg".to_string()));
+            }
+            Ok(images.http.get(format!("{}/{}{}", images.tmdb_base, tmdb_size(width), path)))
+        }
+        Source::Sonarr | Source::Radarr => {
+            if !path.starts_with("/MediaCover/") || path.contains("..") {
+                return Err(Error::BadRequest("*arr image paths start with /MediaCover/".to_string()));
+            }
+            let (http, instance) = match query.source {
+                Source::Sonarr => (&sonarr.http, sonarr.instance(query.instance.as_deref())?),
+                _ => (&radarr.http, radarr.instance(query.instance.as_deref())?),
+            };
+            Ok(http
+                .get(format!("{}{}", instance.url.trim_end_matches('/'), path))
+                .header("X-Api-Key", &instance.api_key))
+        }
+        Source::Remote => {
+            let url = reqwest::Url::parse(path).map_err(|_| Error::BadRequest(format!("{} is not a URL", path)))?;
+            let allowed = url.scheme() == "https" && url.host_str().is_some_and(|h| REMOTE_HOSTS.contains(&h));
+            if !allowed {
+                return Err(Error::Forbidden(format!("Images from {} are not proxied", url.host_str().unwrap_or(path))));
+            }
+            Ok(images.http.get(url))
+        }
+    }
+}
+
+/// Cache identity of the upstream image. Plex paths are used bare so
+/// `/api/media/{id}/thumb` and `/api/images?source=plex` share entries.
+fn image_id(query: &SourceQuery) -> String {
+    if query.source == Source::Plex {
+        return query.path.clone();
+    }
+    format!("{:?}:{}:{}", query.source, query.instance.as_deref().unwrap_or_default(), query.path)
+}
+
+/// `?source=plex|tmdb|sonarr|radarr|remote&path=...&width=&height=&quality=&format=auto|avif|webp|jpeg`
+#[get("")]
+async fn get_image(
+    req: HttpRequest,
+    images: web::Data<ImageService>,
+    plex: web::Data<PlexClient>,
+    sonarr: web::Data<SonarrClient>,
+    radarr: web::Data<RadarrClient>,
+    query: web::Query<SourceQuery>,
+    resize: web::Query<Resize>,
+) -> Result<HttpResponse> {
+    let upstream = upstream_request(&query, resize.width, &images, &plex, &sonarr, &radarr)?;
+    let max_age = max_age(query.source, &query.path);
+    images.serve(&req, &image_id(&query), &resize, max_age, upstream).await
+}
+
+/// `{blurhash, width, height}` for the image at `?source=&path=`.
+#[get("/blurhash")]
+async fn get_blurhash(
+    images: web::Data<ImageService>,
+    plex: web::Data<PlexClient>,
+    sonarr: web::Data<SonarrClient>,
+    radarr: web::Data<RadarrClient>,
+    query: web::Query<SourceQuery>,
+) -> Result<impl Responder> {
+    // The blurhash reports the original's dimensions
+    let upstream = upstream_request(&query, None, &images, &plex, &sonarr, &radarr)?;
+    let hash = images.blurhash(&image_id(&query), max_age(query.source, &query.path), upstream).await?;
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "public, max-age=86400"))
+        .json(hash))
+}
+
+/// Size of the disk cache (admin).
+#[get("/cache")]
+async fn cache_stats(
+    req: HttpRequest,
+    config: web::Data<SharedConfig>,
+    images: web::Data<ImageService>,
+) -> Result<impl Responder> {
+    require_admin(&req, &config)?;
+    let (files, bytes, max_bytes) = images.store.stats();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "files": files,
+        "bytes": bytes,
+        "maxBytes": max_bytes,
+    })))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/images")
+            .service(get_blurhash)
+            .service(cache_stats)
+            .service(get_image),
+    );
+}
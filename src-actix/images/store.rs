@@ -0,0 +1,131 @@
+//! Size-capped disk cache for encoded images. Recency lives in memory and is
+//! seeded from file modification times on startup.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use log::{debug, warn};
+
+struct Entry {
+    size: u64,
+    /// Higher is more recent.
+    used: u64,
+}
+
+#[derive(Default)]
+struct Index {
+    entries: HashMap<String, Entry>,
+    total: u64,
+    clock: u64,
+}
+
+pub struct ImageStore {
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<Index>,
+}
+
+impl ImageStore {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        let mut files: Vec<(String, u64, std::time::SystemTime)> = std::fs::read_dir(&dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|e| {
+                        let meta = e.metadata().ok().filter(|m| m.is_file())?;
+                        let name = e.file_name().into_string().ok().filter(|n| !n.ends_with(".tmp"))?;
+                        Some((name, meta.len(), meta.modified().unwrap_or(std::time::UNIX_EPOCH)))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort_by_key(|(_, _, modified)| *modified);
+
+        let mut index = Index::default();
+        for (name, size, _) in files {
+            index.clock += 1;
+            index.total += size;
+            index.entries.insert(name, Entry { size, used: index.clock });
+        }
+        debug!("Image cache at {} holds {} files, {} bytes", dir.display(), index.entries.len(), index.total);
+        let store = Self { dir, max_bytes, index: Mutex::new(index) };
+        store.evict();
+        store
+    }
+
+    /// The cached bytes, unless they are older than `max_age_secs`.
+    pub fn get(&self, key: &str, max_age_secs: Option<u64>) -> Option<Vec<u8>> {
+        let path = self.dir.join(key);
+        if let Some(max_age) = max_age_secs {
+            let age = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?.elapsed().unwrap_or_default();
+            if age.as_secs() > max_age {
+                return None;
+            }
+        }
+        {
+            let mut index = self.index.lock().ok()?;
+            index.clock += 1;
+            let clock = index.clock;
+            index.entries.get_mut(key)?.used = clock;
+        }
+        match std::fs::read(&path) {
+            Ok(data) => Some(data),
+            Err(_) => {
+                // Deleted behind our back
+                self.forget(key);
+                None
+            }
+        }
+    }
+
+    pub fn put(&self, key: &str, data: &[u8]) {
+        let tmp = self.dir.join(format!("{}.{}.tmp", key, uuid::Uuid::new_v4()));
+        let written = std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(&tmp, data))
+            .and_then(|_| std::fs::rename(&tmp, self.dir.join(key)));
+        if let Err(e) = written {
+            warn!("Failed to cache image {}: {}", key, e);
+            let _ = std::fs::remove_file(&tmp);
+            return;
+        }
+        if let Ok(mut index) = self.index.lock() {
+            index.clock += 1;
+            let entry = Entry { size: data.len() as u64, used: index.clock };
+            if let Some(old) = index.entries.insert(key.to_string(), entry) {
+                index.total -= old.size;
+            }
+            index.total += data.len() as u64;
+        }
+        self.evict();
+    }
+
+    fn forget(&self, key: &str) {
+        if let Ok(mut index) = self.index.lock()
+            && let Some(old) = index.entries.remove(key)
+        {
+            index.total -= old.size;
+        }
+    }
+
+    /// Delete least recently used files until the cache fits its cap.
+    fn evict(&self) {
+        let Ok(mut index) = self.index.lock() else { return };
+        while index.total > self.max_bytes {
+            let Some(oldest) = index.entries.iter().min_by_key(|(_, e)| e.used).map(|(k, _)| k.clone()) else {
+                break;
+            };
+            if let Some(entry) = index.entries.remove(&oldest) {
+                index.total -= entry.size;
+            }
+            let _ = std::fs::remove_file(self.dir.join(&oldest));
+        }
+    }
+
+    pub fn stats(&self) -> (usize, u64, u64) {
+        self.index
+            .lock()
+            .map(|i| (i.entries.len(), i.total, self.max_bytes))
+            .unwrap_or((0, 0, self.max_bytes))
+    }
+}
@@ -0,0 +1,117 @@
+//! Decode, resize and re-encode images, and compute blurhash placeholders.
+
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageEncoder};
+use serde::Deserialize;
+
+/// Resizing past this is refused rather than upscaled into huge files.
+pub const MAX_DIMENSION: u32 = 3840;
+/// rav1e's fastest preset; slower ones cost seconds per poster.
+const AVIF_SPEED: u8 = 10;
+const BLURHASH_WIDTH: u32 = 32;
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// The best the browser's `Accept` header allows.
+    Auto,
+    Avif,
+    Webp,
+    Jpeg,
+    /// Chosen instead of JPEG for images with transparency.
+    Png,
+}
+
+impl Format {
+    /// Resolve `Auto` against the request's `Accept` header.
+    pub fn negotiate(self, accept: &str) -> Self {
+        match self {
+            Self::Auto if accept.contains("image/avif") => Self::Avif,
+            Self::Auto if accept.contains("image/webp") => Self::Webp,
+            Self::Auto => Self::Jpeg,
+            other => other,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Avif => "avif",
+            Self::Webp => "webp",
+            Self::Png => "png",
+            Self::Jpeg | Self::Auto => "jpg",
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            Self::Avif => "image/avif",
+            Self::Webp => "image/webp",
+            Self::Png => "image/png",
+            Self::Jpeg | Self::Auto => "image/jpeg",
+        }
+    }
+}
+
+/// Fit within `width` x `height` keeping the aspect ratio, never upscaling.
+fn resize(img: DynamicImage, width: Option<u32>, height: Option<u32>) -> DynamicImage {
+    let (w, h) = img.dimensions();
+    let width = width.unwrap_or(w).min(w);
+    let height = height.unwrap_or(h).min(h);
+    if (width, height) == (w, h) {
+        return img;
+    }
+    img.resize(width, height, FilterType::Lanczos3)
+}
+
+/// The re-encoded image and the format it ended up in.
+pub fn transform(
+    data: &[u8],
+    width: Option<u32>,
+    height: Option<u32>,
+    quality: u8,
+    format: Format,
+) -> anyhow::Result<(Vec<u8>, Format)> {
+    let img = resize(image::load_from_memory(data)?, width, height);
+    let format = match format {
+        Format::Jpeg if img.color().has_alpha() => Format::Png,
+        other => other,
+    };
+    let (w, h) = img.dimensions();
+    let mut out = Vec::new();
+    match format {
+        Format::Avif => {
+            let rgba = img.to_rgba8();
+            AvifEncoder::new_with_speed_quality(&mut out, AVIF_SPEED, quality)
+                .write_image(&rgba, w, h, image::ExtendedColorType::Rgba8)?;
+        }
+        Format::Webp => {
+            let rgba = img.to_rgba8();
+            out = webp::Encoder::from_rgba(&rgba, w, h).encode(quality as f32).to_vec();
+        }
+        Format::Png => {
+            let rgba = img.to_rgba8();
+            PngEncoder::new(&mut out).write_image(&rgba, w, h, image::ExtendedColorType::Rgba8)?;
+        }
+        Format::Jpeg | Format::Auto => {
+            let rgb = img.to_rgb8();
+            JpegEncoder::new_with_quality(&mut out, quality).encode_image(&rgb)?;
+        }
+    }
+    Ok((out, format))
+}
+
+/// Blurhash of a downscaled copy, with the original's dimensions so the
+/// placeholder can reserve the right space.
+pub fn blurhash(data: &[u8]) -> anyhow::Result<(String, u32, u32)> {
+    let img = image::load_from_memory(data)?;
+    let (w, h) = img.dimensions();
+    let small = img.thumbnail(BLURHASH_WIDTH, BLURHASH_WIDTH * h / w.max(1)).to_rgba8();
+    // More components along the longer side
+    let (x, y) = if w >= h { (4, 3) } else { (3, 4) };
+    let hash = ::blurhash::encode(x, y, small.width(), small.height(), small.as_raw())
+        .map_err(|e| anyhow::anyhow!("Blurhash failed: {}", e))?;
+    Ok((hash, w, h))
+}
@@ -8,16 +8,27 @@ use vite_actix::start_vite_server;
 
 use watch_party::websocket::WsMessage;
 
+mod arr;
 mod asset_endpoint;
 mod auth;
+mod cache;
+mod calendar;
+mod commands;
 pub mod config;
 mod discover;
 mod downloads;
+mod events;
 mod http_error;
+mod images;
+mod offline;
 mod plex;
 mod radarr;
+mod search;
 mod settings;
 mod sonarr;
+mod stats;
+mod watchlist;
+mod webhooks;
 
 mod watch_party;
 
@@ -49,9 +60,27 @@ pub async fn run() -> Result<()> {
     let plex_client = web::Data::new(plex::client::PlexClient::new(shared_config.clone()));
     let sonarr_client = web::Data::new(sonarr::client::SonarrClient::new(shared_config.clone()));
     let radarr_client = web::Data::new(radarr::client::RadarrClient::new(shared_config.clone()));
+    let tmdb_client = web::Data::new(discover::client::TmdbClient::new(shared_config.clone()));
     let room_manager = web::Data::new(watch_party::room::RoomManager::new());
     let config_data = web::Data::new(shared_config.clone());
     let health_state = web::Data::new(status_endpoints::new_health_state());
+    let event_bus = web::Data::new(events::EventBus::new());
+    let response_cache = web::Data::new(cache::ResponseCache::new(shared_config.clone()));
+    let command_tracker = web::Data::new(commands::CommandTracker::new());
+    let watchlist_store = web::Data::new(watchlist::WatchlistStore::new());
+    let playback_log = web::Data::new(stats::playback::PlaybackLog::new());
+    let offline_manager = web::Data::new(offline::OfflineManager::new(shared_config.clone()));
+    let image_service = web::Data::new(images::ImageService::new(shared_config.clone()));
+
+    // Resume offline downloads interrupted by the last shutdown, and expire
+    // finished ones on a timer
+    offline::OfflineManager::start(offline_manager.clone(), plex_client.clone());
+    {
+        let manager = offline_manager.clone();
+        actix_web::rt::spawn(async move {
+            offline::run_expiry(manager).await;
+        });
+    }
 
     // Spawn health check task: every 30s, ping all configured services
     {
@@ -60,6 +89,7 @@ pub async fn run() -> Result<()> {
         let plex = plex_client.clone();
         let sonarr = sonarr_client.clone();
         let radarr = radarr_client.clone();
+        let tmdb = tmdb_client.clone();
         actix_web::rt::spawn(async move {
             status_endpoints::run_health_checks(
                 state.get_ref().clone(),
@@ -67,11 +97,36 @@ pub async fn run() -> Result<()> {
                 plex,
                 sonarr,
                 radarr,
+                tmdb,
             )
             .await;
         });
     }
 
+    // Spawn cache tasks: webhook-driven invalidation and periodic expiry/snapshot
+    {
+        let cache = response_cache.clone();
+        let events = event_bus.clone();
+        actix_web::rt::spawn(async move {
+            cache::run_invalidation(cache, events).await;
+        });
+        let cache = response_cache.clone();
+        actix_web::rt::spawn(async move {
+            cache::run_maintenance(cache).await;
+        });
+    }
+
+    // Spawn command polling task: follow *arr commands started from Playarr
+    {
+        let tracker = command_tracker.clone();
+        let sonarr = sonarr_client.clone();
+        let radarr = radarr_client.clone();
+        let events = event_bus.clone();
+        actix_web::rt::spawn(async move {
+            commands::run_polling(tracker, sonarr, radarr, events).await;
+        });
+    }
+
     // Spawn heartbeat task: every 500ms, broadcast server time + media_id to all playing rooms
     let hb_rooms = room_manager.clone();
     actix_web::rt::spawn(async move {
@@ -109,8 +164,16 @@ pub async fn run() -> Result<()> {
             .app_data(plex_client.clone())
             .app_data(sonarr_client.clone())
             .app_data(radarr_client.clone())
+            .app_data(tmdb_client.clone())
             .app_data(room_manager.clone())
             .app_data(health_state.clone())
+            .app_data(event_bus.clone())
+            .app_data(response_cache.clone())
+            .app_data(command_tracker.clone())
+            .app_data(watchlist_store.clone())
+            .app_data(playback_log.clone())
+            .app_data(offline_manager.clone())
+            .app_data(image_service.clone())
             .service(
                 web::scope("api")
                     .configure(status_endpoints::configure)
@@ -120,6 +183,7 @@ pub async fn run() -> Result<()> {
                     .configure(plex::libraries::configure)
                     .configure(plex::media::configure)
                     .configure(plex::hub::configure)
+                    .configure(search::configure)
                     .configure(plex::search::configure)
                     .configure(plex::timeline::configure)
                     .configure(|cfg: &mut web::ServiceConfig| {
@@ -127,29 +191,39 @@ pub async fn run() -> Result<()> {
                             web::scope("/sonarr")
                                 .configure(sonarr::series::configure)
                                 .configure(sonarr::episodes::configure)
+                                .configure(sonarr::files::configure)
                                 .configure(sonarr::calendar::configure)
-                                .configure(sonarr::queue::configure)
-                                .configure(sonarr::profiles::configure)
-                                .configure(sonarr::commands::configure)
-                                .configure(sonarr::releases::configure),
+                                .configure(sonarr::releases::configure)
+                                .configure(arr::handlers::configure::<arr::Sonarr>),
                         );
                     })
                     .configure(|cfg: &mut web::ServiceConfig| {
                         cfg.service(
                             web::scope("/radarr")
                                 .configure(radarr::movies::configure)
+                                .configure(radarr::files::configure)
+                                .configure(radarr::collections::configure)
+                                .configure(radarr::exclusions::configure)
                                 .configure(radarr::calendar::configure)
-                                .configure(radarr::queue::configure)
-                                .configure(radarr::profiles::configure)
-                                .configure(radarr::commands::configure)
-                                .configure(radarr::releases::configure),
+                                .configure(radarr::releases::configure)
+                                .configure(arr::handlers::configure::<arr::Radarr>),
                         );
                     })
                     .configure(discover::tmdb::configure)
                     .configure(downloads::configure)
+                    .configure(webhooks::configure)
+                    .configure(events::configure)
+                    .configure(cache::configure)
+                    .configure(calendar::configure)
+                    .configure(commands::configure)
+                    .configure(watchlist::configure)
+                    .configure(stats::configure)
+                    .configure(offline::configure)
+                    .configure(images::configure)
 
                     .configure(watch_party::configure)
-                    .configure(plex::users::configure),
+                    .configure(plex::users::configure)
+                    .configure(plex::sessions::configure),
             )
             .configure_frontend_routes()
     })
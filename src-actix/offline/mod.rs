@@ -0,0 +1,433 @@
+//! Offline downloads: a user queues a movie or episode, the server fetches
+//! the original file or a Plex transcode of it into a managed directory, and
+//! the browser downloads it from there with resume support. Finished files
+//! expire after `offline.expiry_days`; all files together stay under
+//! `offline.quota_gb`.
+
+mod worker;
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use actix_files::NamedFile;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::require_admin;
+use crate::config::json_store::JsonFileStore;
+use crate::config::SharedConfig;
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+
+/// Jobs one user may have queued or running at a time.
+const MAX_ACTIVE_PER_USER: usize = 5;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Downloading,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadJob {
+    pub id: Uuid,
+    pub user_id: i64,
+    pub rating_key: String,
+    pub title: String,
+    /// Transcode profile name; `None` keeps the original file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub status: JobStatus,
+    pub bytes_done: u64,
+    /// Exact for originals, an estimate while a transcode runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_total: Option<u64>,
+    /// 0 to 1.
+    pub progress: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Name the browser saves the file as.
+    pub file_name: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The owner's Plex server token, so the worker fetches only what they
+    /// can see. Kept in `offline.json` and blanked in API responses.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    plex_token: String,
+}
+
+impl DownloadJob {
+    fn is_active(&self) -> bool {
+        matches!(self.status, JobStatus::Queued | JobStatus::Downloading)
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+
+    /// The job as shown to clients, without the owner's token.
+    fn redacted(mut self) -> Self {
+        self.plex_token.clear();
+        self
+    }
+}
+
+/// Jobs and their files, written to `offline.json` in the data directory
+/// whenever a job changes state.
+pub struct OfflineManager {
+    config: SharedConfig,
+    jobs: JsonFileStore<Vec<DownloadJob>>,
+    dir: PathBuf,
+    running: AtomicBool,
+}
+
+impl OfflineManager {
+    /// Loads the job list from disk; downloads cut off by a restart go back
+    /// in the queue and pick up from their partial file.
+    pub fn new(config: SharedConfig) -> Self {
+        let configured = config.read().map(|c| c.offline.path.clone()).unwrap_or_default();
+        let dir = if configured.is_empty() {
+            crate::config::data_dir().join("offline")
+        } else {
+            PathBuf::from(configured)
+        };
+        let jobs = JsonFileStore::<Vec<DownloadJob>>::open("offline.json");
+        if let Ok(mut jobs) = jobs.write() {
+            for job in jobs.iter_mut().filter(|j| j.status == JobStatus::Downloading) {
+                job.status = JobStatus::Queued;
+            }
+        }
+        Self {
+            config,
+            jobs,
+            dir,
+            running: AtomicBool::new(false),
+        }
+    }
+
+    /// No saved jobs, and files go to a throwaway directory.
+    #[cfg(test)]
+    pub fn in_memory(config: SharedConfig) -> Self {
+        Self {
+            config,
+            jobs: JsonFileStore::in_memory(),
+            dir: std::env::temp_dir().join(format!("playarr-offline-{}", Uuid::new_v4())),
+            running: AtomicBool::new(false),
+        }
+    }
+
+    fn file_path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(id.to_string())
+    }
+
+    /// Where a download is written until it finishes.
+    fn partial_path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.part", id))
+    }
+
+    fn quota_bytes(&self) -> u64 {
+        let gb = self.config.read().map(|c| c.offline.quota_gb).unwrap_or(0);
+        gb.saturating_mul(1024 * 1024 * 1024)
+    }
+
+    fn expiry(&self) -> Duration {
+        let days = self.config.read().map(|c| c.offline.expiry_days).unwrap_or(0);
+        Duration::days(days as i64)
+    }
+
+    /// Bytes on disk or promised to running downloads, leaving out `except`.
+    fn used_bytes(&self, except: Uuid) -> u64 {
+        self.jobs
+            .read()
+            .map(|jobs| {
+                jobs.iter()
+                    .filter(|j| j.id != except)
+                    .map(|j| match j.status {
+                        JobStatus::Completed => j.bytes_done,
+                        JobStatus::Downloading => j.bytes_total.unwrap_or(0).max(j.bytes_done),
+                        _ => 0,
+                    })
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+
+    pub fn list(&self, user: Option<i64>) -> Vec<DownloadJob> {
+        self.prune_expired();
+        self.jobs
+            .read()
+            .map(|jobs| {
+                jobs.iter()
+                    .rev()
+                    .filter(|j| user.is_none_or(|u| j.user_id == u))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<DownloadJob> {
+        self.jobs.read().ok()?.iter().find(|j| j.id == id).cloned()
+    }
+
+    fn add(&self, job: DownloadJob) -> Result<()> {
+        let mut jobs = self.jobs.write().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+        let active = jobs.iter().filter(|j| j.user_id == job.user_id && j.is_active()).count();
+        if active >= MAX_ACTIVE_PER_USER {
+            return Err(Error::BadRequest(format!(
+                "You already have {} downloads queued; wait for one to finish",
+                MAX_ACTIVE_PER_USER
+            )));
+        }
+        jobs.push(job);
+        drop(jobs);
+        self.jobs.save();
+        Ok(())
+    }
+
+    fn update(&self, id: Uuid, change: impl FnOnce(&mut DownloadJob)) {
+        if let Ok(mut jobs) = self.jobs.write()
+            && let Some(job) = jobs.iter_mut().find(|j| j.id == id)
+        {
+            change(job);
+        }
+    }
+
+    /// Drop a job and its file. A running download notices and stops.
+    pub fn remove(&self, id: Uuid) -> bool {
+        let removed = self
+            .jobs
+            .write()
+            .map(|mut jobs| {
+                let before = jobs.len();
+                jobs.retain(|j| j.id != id);
+                jobs.len() != before
+            })
+            .unwrap_or(false);
+        if removed {
+            let _ = std::fs::remove_file(self.file_path(id));
+            let _ = std::fs::remove_file(self.partial_path(id));
+            self.jobs.save();
+        }
+        removed
+    }
+
+    fn prune_expired(&self) {
+        let now = Utc::now();
+        let expired: Vec<Uuid> = self
+            .jobs
+            .read()
+            .map(|jobs| {
+                jobs.iter()
+                    .filter(|j| j.is_expired(now))
+                    .map(|j| j.id)
+                    .collect()
+            })
+            .unwrap_or_default();
+        for id in expired {
+            info!("Offline download {} expired", id);
+            self.remove(id);
+        }
+    }
+
+    /// Work through queued jobs one at a time, unless that is already happening.
+    pub fn start(manager: web::Data<OfflineManager>, plex: web::Data<PlexClient>) {
+        if manager.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        actix_web::rt::spawn(async move {
+            loop {
+                worker::run_queue(&manager, &plex).await;
+                manager.running.store(false, Ordering::SeqCst);
+                // A job queued while the worker was on its way out
+                let queued = manager.list(None).iter().any(|j| j.status == JobStatus::Queued);
+                if !queued || manager.running.swap(true, Ordering::SeqCst) {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// Every ten minutes delete finished downloads past their expiry.
+pub async fn run_expiry(manager: web::Data<OfflineManager>) {
+    let mut interval = actix_web::rt::time::interval(std::time::Duration::from_secs(600));
+    loop {
+        interval.tick().await;
+        manager.prune_expired();
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateJobRequest {
+    rating_key: String,
+    /// Transcode profile name; left out (or `original`) keeps the original file.
+    profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    /// Admin only: everyone's downloads.
+    #[serde(default)]
+    all: bool,
+}
+
+fn signed_in(req: &HttpRequest) -> Result<i64> {
+    PlexClient::user_from_request(req)
+        .map(|(id, _)| id)
+        .ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))
+}
+
+/// The job, if it belongs to the caller or the caller is the admin and it
+/// hasn't expired. Expired jobs are removed on the spot rather than waiting
+/// for `run_expiry`.
+fn owned_job(req: &HttpRequest, plex: &PlexClient, manager: &OfflineManager, id: Uuid) -> Result<DownloadJob> {
+    let user = signed_in(req)?;
+    let job = manager
+        .get(id)
+        .filter(|j| j.user_id == user || require_admin(req, &plex.config).is_ok())
+        .ok_or_else(|| Error::NotFound(format!("No download {}", id)))?;
+    if job.is_expired(Utc::now()) {
+        info!("Offline download {} expired", id);
+        manager.remove(id);
+        return Err(Error::NotFound(format!("The download of {} has expired", job.title)));
+    }
+    Ok(job)
+}
+
+#[get("/jobs")]
+async fn list_jobs(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    manager: web::Data<OfflineManager>,
+    query: web::Query<ListQuery>,
+) -> Result<impl Responder> {
+    let user = signed_in(&req)?;
+    let user = if query.all {
+        require_admin(&req, &plex.config)?;
+        None
+    } else {
+        Some(user)
+    };
+    let jobs: Vec<DownloadJob> = manager.list(user).into_iter().map(DownloadJob::redacted).collect();
+    Ok(HttpResponse::Ok().json(jobs))
+}
+
+/// Queue a movie or episode the caller can see in Plex. The job starts
+/// right away if nothing else is downloading.
+#[post("/jobs")]
+async fn create_job(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    manager: web::Data<OfflineManager>,
+    body: web::Json<CreateJobRequest>,
+) -> Result<impl Responder> {
+    let user = signed_in(&req)?;
+    let plex_token = PlexClient::user_token_from_request(&req)
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))?;
+    let request = body.into_inner();
+    let profile = match request.profile.as_deref().map(str::trim) {
+        None | Some("") | Some("original") => None,
+        Some(name) => {
+            let cfg = plex.config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+            let profiles = cfg.effective_transcode_profiles();
+            let profile = profiles.iter().find(|p| p.name.eq_ignore_ascii_case(name)).ok_or_else(|| {
+                let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+                Error::BadRequest(format!("Unknown profile '{}'; use original or one of {}", name, names.join(", ")))
+            })?;
+            Some(profile.name.clone())
+        }
+    };
+
+    let item = worker::fetch_item(&plex, &request.rating_key, &plex_token).await?;
+    let (title, file_name) = worker::describe(&item, profile.as_deref())?;
+    let job = DownloadJob {
+        id: Uuid::new_v4(),
+        user_id: user,
+        rating_key: request.rating_key,
+        title,
+        profile,
+        status: JobStatus::Queued,
+        bytes_done: 0,
+        bytes_total: None,
+        progress: 0.0,
+        error: None,
+        file_name,
+        created_at: Utc::now(),
+        completed_at: None,
+        expires_at: None,
+        plex_token,
+    };
+    manager.add(job.clone())?;
+    OfflineManager::start(manager, plex);
+    Ok(HttpResponse::Created().json(job.redacted()))
+}
+
+#[get("/jobs/{id}")]
+async fn get_job(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    manager: web::Data<OfflineManager>,
+    path: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    Ok(HttpResponse::Ok().json(owned_job(&req, &plex, &manager, path.into_inner())?.redacted()))
+}
+
+/// Cancel a download or delete a finished one.
+#[delete("/jobs/{id}")]
+async fn delete_job(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    manager: web::Data<OfflineManager>,
+    path: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let job = owned_job(&req, &plex, &manager, path.into_inner())?;
+    manager.remove(job.id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// The finished file, with range support so an interrupted download can
+/// pick up where it stopped.
+#[get("/jobs/{id}/file")]
+async fn download_file(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    manager: web::Data<OfflineManager>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse> {
+    let job = owned_job(&req, &plex, &manager, path.into_inner())?;
+    if job.status != JobStatus::Completed {
+        return Err(Error::BadRequest(format!("{} is not finished downloading yet", job.title)));
+    }
+    let file = NamedFile::open(manager.file_path(job.id))
+        .map_err(|_| Error::NotFound(format!("The file for {} is gone", job.title)))?
+        .set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(job.file_name.clone())],
+        });
+    Ok(file.into_response(&req))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/offline")
+            .service(list_jobs)
+            .service(create_job)
+            .service(get_job)
+            .service(delete_job)
+            .service(download_file),
+    );
+}
@@ -0,0 +1,250 @@
+//! The download loop behind `OfflineManager::start`: one job at a time,
+//! streamed to a `.part` file and renamed once complete.
+
+use chrono::Utc;
+use log::{info, warn};
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+
+use super::{DownloadJob, JobStatus, OfflineManager};
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+use crate::plex::transcode;
+
+/// Progress is written to `offline.json` at most this often.
+const SAVE_EVERY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// The library item as `plex_token`'s owner sees it. Unlike
+/// `get_json_as_user` this never falls back to the admin token, so an empty
+/// token is refused rather than passed on.
+pub(super) async fn fetch_item(plex: &PlexClient, rating_key: &str, plex_token: &str) -> Result<Value> {
+    if plex_token.is_empty() {
+        return Err(Error::Unauthorized("No Plex token for this download".to_string()));
+    }
+    let req = plex.get_as_user(&format!("/library/metadata/{}", rating_key), plex_token)?;
+    let body = plex.send_json(req).await?;
+    body["MediaContainer"]["Metadata"]
+        .get(0)
+        .cloned()
+        .ok_or_else(|| Error::NotFound(format!("No library item {}", rating_key)))
+}
+
+/// Display title and download file name for a movie or episode.
+pub(super) fn describe(item: &Value, profile: Option<&str>) -> Result<(String, String)> {
+    let title = item["title"].as_str().unwrap_or("Untitled");
+    let title = match item["type"].as_str() {
+        Some("movie") => match item["year"].as_u64() {
+            Some(year) => format!("{} ({})", title, year),
+            None => title.to_string(),
+        },
+        Some("episode") => format!(
+            "{} - S{:02}E{:02} - {}",
+            item["grandparentTitle"].as_str().unwrap_or("Unknown show"),
+            item["parentIndex"].as_u64().unwrap_or(0),
+            item["index"].as_u64().unwrap_or(0),
+            title
+        ),
+        other => {
+            return Err(Error::BadRequest(format!(
+                "Only movies and episodes can be downloaded, not {}",
+                other.unwrap_or("this item")
+            )));
+        }
+    };
+    let extension = match profile {
+        Some(_) => "mkv",
+        None => item["Media"][0]["container"].as_str().unwrap_or("mkv"),
+    };
+    let safe: String = title
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect();
+    Ok((title, format!("{}.{}", safe, extension)))
+}
+
+/// Run queued jobs until none are left.
+pub(super) async fn run_queue(manager: &OfflineManager, plex: &PlexClient) {
+    manager.prune_expired();
+    while let Some(id) = next_queued(manager) {
+        let Some(job) = manager.get(id) else { continue };
+        info!("Offline download of {} starting", job.title);
+        let result = download(manager, plex, &job).await;
+        let expires_at = Utc::now() + manager.expiry();
+        match result {
+            Ok(true) => {
+                manager.update(id, |j| {
+                    j.status = JobStatus::Completed;
+                    j.bytes_total = Some(j.bytes_done);
+                    j.progress = 1.0;
+                    j.completed_at = Some(Utc::now());
+                    j.expires_at = Some(expires_at);
+                });
+                info!("Offline download of {} finished", job.title);
+            }
+            // Deleted while downloading
+            Ok(false) => {
+                let _ = tokio::fs::remove_file(manager.partial_path(id)).await;
+            }
+            Err(e) => {
+                warn!("Offline download of {} failed: {}", job.title, e);
+                let _ = tokio::fs::remove_file(manager.partial_path(id)).await;
+                manager.update(id, |j| {
+                    j.status = JobStatus::Failed;
+                    j.error = Some(e.to_string());
+                });
+            }
+        }
+        manager.jobs.save();
+    }
+}
+
+fn next_queued(manager: &OfflineManager) -> Option<uuid::Uuid> {
+    let mut jobs = manager.jobs.write().ok()?;
+    let job = jobs.iter_mut().find(|j| j.status == JobStatus::Queued)?;
+    job.status = JobStatus::Downloading;
+    job.error = None;
+    Some(job.id)
+}
+
+/// Fetch the job's file; `Ok(false)` if the job was deleted part way.
+async fn download(manager: &OfflineManager, plex: &PlexClient, job: &DownloadJob) -> Result<bool> {
+    // Jobs saved before tokens were kept; the admin token must never stand in
+    if job.plex_token.is_empty() {
+        return Err(Error::Unauthorized(format!("Queue {} again to download it", job.title)));
+    }
+    let item = fetch_item(plex, &job.rating_key, &job.plex_token).await?;
+    let media = &item["Media"][0];
+    let partial = manager.partial_path(job.id);
+    tokio::fs::create_dir_all(&manager.dir)
+        .await
+        .map_err(|e| anyhow::anyhow!("Can't create {}: {}", manager.dir.display(), e))?;
+
+    // Originals resume from whatever a previous run left behind; a transcode
+    // is a new encode every time, so it starts over.
+    let (request, expected, resume_from) = match job.profile.as_deref() {
+        None => {
+            let part = &media["Part"][0];
+            let part_key = part["key"]
+                .as_str()
+                .ok_or_else(|| Error::NotFound(format!("{} has no file", job.title)))?;
+            let existing = tokio::fs::metadata(&partial).await.map(|m| m.len()).unwrap_or(0);
+            let mut request = plex.get_as_user(part_key, &job.plex_token)?;
+            if existing > 0 {
+                request = request.header("Range", format!("bytes={}-", existing));
+            }
+            (request, part["size"].as_u64(), existing)
+        }
+        Some(name) => {
+            let profile = {
+                let cfg = plex.config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+                transcode::find_profile(&cfg.effective_transcode_profiles(), Some(name))
+            };
+            let duration_secs = item["duration"].as_u64().unwrap_or(0) / 1000;
+            let estimate = duration_secs * profile.max_bitrate_kbps as u64 * 1000 / 8;
+            let media_path = format!("/library/metadata/{}", job.rating_key);
+            let bitrate = profile.max_bitrate_kbps.to_string();
+            let profile_extra = transcode::client_profile_extra(&profile, "http");
+            let session = job.id.to_string();
+            let params: Vec<(&str, &str)> = vec![
+                ("path", &media_path),
+                ("mediaIndex", "0"),
+                ("partIndex", "0"),
+                ("protocol", "http"),
+                ("container", "mkv"),
+                ("directPlay", "0"),
+                ("directStream", "1"),
+                ("directStreamAudio", "1"),
+                ("videoResolution", &profile.max_resolution),
+                ("videoQuality", "100"),
+                ("maxVideoBitrate", &bitrate),
+                ("autoAdjustQuality", "0"),
+                ("subtitles", transcode::subtitle_param(profile.subtitle_mode, None)),
+                ("location", "lan"),
+                ("session", &session),
+                ("X-Plex-Client-Profile-Extra", &profile_extra),
+                ("X-Plex-Platform", "Chrome"),
+            ];
+            let request = plex
+                .get_as_user("/video/:/transcode/universal/start.mkv", &job.plex_token)?
+                .query(&params);
+            let _ = tokio::fs::remove_file(&partial).await;
+            (request, Some(estimate).filter(|&e| e > 0), 0)
+        }
+    };
+
+    let quota = manager.quota_bytes();
+    let check_quota = |size: u64| {
+        let used = manager.used_bytes(job.id);
+        if used + size > quota {
+            return Err(Error::BadRequest(format!(
+                "Not enough offline storage: {} needs {} MB but only {} MB of the quota is free",
+                job.title,
+                size / (1024 * 1024),
+                quota.saturating_sub(used) / (1024 * 1024)
+            )));
+        }
+        Ok(())
+    };
+    check_quota(expected.unwrap_or(0))?;
+
+    let mut resp = request
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("Download request failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(Error::UpstreamError(format!("Plex returned {} for {}", resp.status().as_u16(), job.title)));
+    }
+    // A server that ignores the range sends the whole file again
+    let resumed = resume_from > 0 && resp.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&partial)
+        .await
+        .map_err(|e| anyhow::anyhow!("Can't write {}: {}", partial.display(), e))?;
+
+    let mut done = if resumed { resume_from } else { 0 };
+    let mut saved_at = done;
+    manager.update(job.id, |j| {
+        j.bytes_done = done;
+        j.bytes_total = expected;
+    });
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| anyhow::anyhow!("Download of {} interrupted: {}", job.title, e))?
+    {
+        if manager.get(job.id).is_none() {
+            return Ok(false);
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| anyhow::anyhow!("Can't write {}: {}", partial.display(), e))?;
+        done += chunk.len() as u64;
+        // Transcodes can come out larger than estimated
+        if expected.is_none_or(|e| done > e) {
+            check_quota(done)?;
+        }
+        manager.update(job.id, |j| {
+            j.bytes_done = done;
+            j.progress = expected.map_or(0.0, |e| (done as f64 / e as f64).min(0.99));
+        });
+        if done - saved_at >= SAVE_EVERY_BYTES {
+            manager.jobs.save();
+            saved_at = done;
+        }
+    }
+    file.flush()
+        .await
+        .map_err(|e| anyhow::anyhow!("Can't write {}: {}", partial.display(), e))?;
+    drop(file);
+    if manager.get(job.id).is_none() {
+        return Ok(false);
+    }
+    tokio::fs::rename(&partial, manager.file_path(job.id))
+        .await
+        .map_err(|e| anyhow::anyhow!("Can't finish {}: {}", partial.display(), e))?;
+    Ok(true)
+}
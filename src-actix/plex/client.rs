@@ -9,6 +9,8 @@ use crate::config::SharedConfig;
 use crate::http_error;
 
 const PLEX_PRODUCT: &str = "Playarr";
+/// Plex Discover, home of the account-wide watchlist.
+const PLEX_DISCOVER: &str = "https://discover.provider.plex.tv";
 
 #[derive(Debug, Clone, Serialize)]
 pub struct PlexUserInfo {
@@ -94,6 +96,12 @@ impl PlexClient {
         }
     }
 
+    /// The playback session ID behind one of our per-tab client identifiers,
+    /// or `None` for any other Plex client.
+    pub fn client_id_to_session(client_id: &str) -> Option<&str> {
+        client_id.strip_prefix("playarr-").filter(|s| !s.is_empty())
+    }
+
     /// Extract the playback session client ID from the X-Playarr-Session request header.
     /// Each browser tab sends a unique session ID so that Plex treats each viewer
     /// as a separate player (preventing session conflicts).
@@ -125,19 +133,37 @@ impl PlexClient {
     /// Build a PUT request with standard Plex headers.
     /// Token is sent as a query parameter for local Plex Media Server compatibility.
     pub fn put(&self, path: &str) -> http_error::Result<reqwest::RequestBuilder> {
-        let token = self.token();
-        if token.is_empty() {
-            return Err(http_error::Error::Unauthorized(
-                "Not authenticated with Plex. Please sign in first.".to_string(),
-            ));
+        self.request_as_user(reqwest::Method::PUT, path, "")
+    }
+
+    /// Build a POST request with the admin token.
+    pub fn post(&self, path: &str) -> http_error::Result<reqwest::RequestBuilder> {
+        self.request_as_user(reqwest::Method::POST, path, "")
+    }
+
+    /// Build a DELETE request with the admin token.
+    pub fn delete(&self, path: &str) -> http_error::Result<reqwest::RequestBuilder> {
+        self.request_as_user(reqwest::Method::DELETE, path, "")
+    }
+
+    /// `server://{machineId}/com.plexapp.plugins.library{path}`, the URI form
+    /// Plex wants for items added to playlists and collections.
+    pub async fn library_uri(&self, path: &str) -> http_error::Result<reqwest::Url> {
+        let machine_id = self
+            .get_server_machine_id()
+            .await
+            .ok_or_else(|| http_error::Error::ServiceUnavailable("Could not reach the Plex server".to_string()))?;
+        reqwest::Url::parse(&format!("server://{}/com.plexapp.plugins.library{}", machine_id, path))
+            .map_err(|e| anyhow::anyhow!("Invalid library URI: {}", e).into())
+    }
+
+    /// Library URI for a list of items, as used by playlist and collection edits.
+    pub async fn items_uri(&self, rating_keys: &[u64]) -> http_error::Result<String> {
+        if rating_keys.is_empty() {
+            return Err(http_error::Error::BadRequest("ratingKeys must not be empty".to_string()));
         }
-        let url = format!("{}{}", self.base_url()?, path);
-        Ok(self.http
-            .put(&url)
-            .query(&[("X-Plex-Token", &token)])
-            .header("X-Plex-Product", PLEX_PRODUCT)
-            .header("X-Plex-Client-Identifier", self.client_id())
-            .header("Accept", "application/json"))
+        let keys: Vec<String> = rating_keys.iter().map(|k| k.to_string()).collect();
+        Ok(self.library_uri(&format!("/library/metadata/{}", keys.join(","))).await?.to_string())
     }
 
     /// Send a Plex API request and parse the JSON response.
@@ -158,7 +184,7 @@ impl PlexClient {
             return Err(anyhow::anyhow!(
                 "Plex returned HTTP {}: {}",
                 status.as_u16(),
-                &body[..body.len().min(200)]
+                &body[..body.floor_char_boundary(200)]
             ).into());
         }
 
@@ -167,6 +193,33 @@ impl PlexClient {
         Ok(body)
     }
 
+    /// Send a Plex API request whose response body doesn't matter (PUT/DELETE
+    /// often answer with an empty 200). Same status handling as `send_json`.
+    pub async fn send(&self, req: reqwest::RequestBuilder) -> http_error::Result<()> {
+        let resp = req.send().await
+            .map_err(|e| anyhow::anyhow!("Plex request failed: {}", e))?;
+
+        let status = resp.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(http_error::Error::Unauthorized(
+                "Plex rejected the auth token. Please sign in again.".to_string(),
+            ));
+        }
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(http_error::Error::NotFound("Plex item not found".to_string()));
+        }
+
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Plex returned HTTP {}: {}",
+                status.as_u16(),
+                &body[..body.floor_char_boundary(200)]
+            ).into());
+        }
+        Ok(())
+    }
+
     /// Build a GET request for binary content (images, etc.) — no Accept: application/json.
     pub fn get_image(&self, path: &str) -> http_error::Result<reqwest::RequestBuilder> {
         let token = self.token();
@@ -295,7 +348,7 @@ impl PlexClient {
             return Err(anyhow::anyhow!(
                 "Plex returned HTTP {}: {}",
                 status.as_u16(),
-                &body[..body.len().min(200)]
+                &body[..body.floor_char_boundary(200)]
             ).into());
         }
 
@@ -392,6 +445,25 @@ impl PlexClient {
 
     /// Build a PUT request using a per-user token (falls back to server token if empty).
     pub fn put_as_user(&self, path: &str, user_token: &str) -> http_error::Result<reqwest::RequestBuilder> {
+        self.request_as_user(reqwest::Method::PUT, path, user_token)
+    }
+
+    /// Build a POST request using a per-user token (falls back to server token if empty).
+    pub fn post_as_user(&self, path: &str, user_token: &str) -> http_error::Result<reqwest::RequestBuilder> {
+        self.request_as_user(reqwest::Method::POST, path, user_token)
+    }
+
+    /// Build a DELETE request using a per-user token (falls back to server token if empty).
+    pub fn delete_as_user(&self, path: &str, user_token: &str) -> http_error::Result<reqwest::RequestBuilder> {
+        self.request_as_user(reqwest::Method::DELETE, path, user_token)
+    }
+
+    fn request_as_user(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        user_token: &str,
+    ) -> http_error::Result<reqwest::RequestBuilder> {
         let token = if user_token.is_empty() { self.token() } else { user_token.to_string() };
         if token.is_empty() {
             return Err(http_error::Error::Unauthorized(
@@ -400,7 +472,7 @@ impl PlexClient {
         }
         let url = format!("{}{}", self.base_url()?, path);
         Ok(self.http
-            .put(&url)
+            .request(method, &url)
             .query(&[("X-Plex-Token", &token)])
             .header("X-Plex-Product", PLEX_PRODUCT)
             .header("X-Plex-Client-Identifier", self.client_id())
@@ -427,6 +499,26 @@ impl PlexClient {
             .header("Accept", "application/json")
     }
 
+    /// Build a request to Plex Discover (watchlist) with a user's plex.tv token.
+    pub fn discover_get(&self, path: &str, plex_tv_token: &str) -> reqwest::RequestBuilder {
+        self.discover(reqwest::Method::GET, path, plex_tv_token)
+    }
+
+    /// Build a PUT request to Plex Discover with a user's plex.tv token.
+    pub fn discover_put(&self, path: &str, plex_tv_token: &str) -> reqwest::RequestBuilder {
+        self.discover(reqwest::Method::PUT, path, plex_tv_token)
+    }
+
+    fn discover(&self, method: reqwest::Method, path: &str, plex_tv_token: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}{}", PLEX_DISCOVER, path);
+        self.http
+            .request(method, &url)
+            .query(&[("X-Plex-Token", plex_tv_token)])
+            .header("X-Plex-Product", PLEX_PRODUCT)
+            .header("X-Plex-Client-Identifier", self.client_id())
+            .header("Accept", "application/json")
+    }
+
     /// Fetch user info from plex.tv using their auth token.
     pub async fn fetch_user_info(&self, user_token: &str) -> http_error::Result<PlexUserInfo> {
         let resp = self.plex_tv_get("/api/v2/user")
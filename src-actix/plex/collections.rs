@@ -0,0 +1,474 @@
+//! Library collections under `/api/libraries/{key}/collections`. Collections
+//! are shared by the whole server, so every change is admin only and made
+//! with the server token.
+
+use std::collections::HashMap;
+
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::auth::require_admin;
+use crate::cache::{self, ResponseCache};
+use crate::discover::client::TmdbClient;
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+use crate::plex::guid::{guid_id, item_guids};
+use crate::plex::metadata::{list_artwork, select_artwork, validate_title, Artwork};
+
+const MAX_POSTER_BYTES: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateCollectionRequest {
+    title: String,
+    rating_keys: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddItemsRequest {
+    rating_keys: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum CollectionSort {
+    Release,
+    Alpha,
+    Custom,
+}
+
+impl CollectionSort {
+    fn plex_value(self) -> &'static str {
+        match self {
+            CollectionSort::Release => "0",
+            CollectionSort::Alpha => "1",
+            CollectionSort::Custom => "2",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SortRequest {
+    sort: CollectionSort,
+}
+
+/// Where the collection is promoted as a hub. Omitted flags are turned off.
+#[derive(Debug, Default, Deserialize)]
+struct VisibilityRequest {
+    /// The library's own recommended tab.
+    #[serde(default)]
+    library: bool,
+    /// The server owner's home screen.
+    #[serde(default)]
+    home: bool,
+    /// Home screens of users the library is shared with.
+    #[serde(default)]
+    shared: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SelectPosterRequest {
+    /// One of the `key`s from `GET .../posters`, or any image URL.
+    url: String,
+}
+
+/// Source for an auto-built collection: exactly one of `tmdbCollectionId`
+/// (e.g. 10 for Star Wars) or `tmdbListId`. Matches are added to
+/// `collection` when given, otherwise a new collection is created.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TmdbBuildRequest {
+    tmdb_collection_id: Option<u64>,
+    tmdb_list_id: Option<String>,
+    /// Defaults to the TMDB collection or list name.
+    title: Option<String>,
+    collection: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TmdbEntry {
+    tmdb_id: u64,
+    /// `movie` or `tv`; TMDB numbers the two separately.
+    media_type: String,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rating_key: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TmdbBuildResult {
+    /// The collection's ratingKey, absent when nothing matched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<String>,
+    title: String,
+    matched: Vec<TmdbEntry>,
+    missing: Vec<TmdbEntry>,
+}
+
+/// Plex's metadata type for a section, needed when creating a collection:
+/// `1` for movie libraries, `2` for shows.
+async fn section_type(plex: &PlexClient, key: &str) -> Result<&'static str> {
+    let body = plex.send_json(plex.get("/library/sections")?).await?;
+    let section = body["MediaContainer"]["Directory"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|s| s["key"].as_str() == Some(key))
+        .ok_or_else(|| Error::NotFound(format!("Library '{}' not found", key)))?;
+    match section["type"].as_str() {
+        Some("movie") => Ok("1"),
+        Some("show") => Ok("2"),
+        other => Err(Error::BadRequest(format!(
+            "Collections can't be built in a '{}' library",
+            other.unwrap_or("unknown")
+        ))),
+    }
+}
+
+async fn create(plex: &PlexClient, key: &str, kind: &str, title: &str, rating_keys: &[u64]) -> Result<Value> {
+    let uri = plex.items_uri(rating_keys).await?;
+    let req = plex.post("/library/collections")?.query(&[
+        ("type", kind),
+        ("title", title),
+        ("smart", "0"),
+        ("sectionId", key),
+        ("uri", uri.as_str()),
+    ]);
+    let body = plex.send_json(req).await?;
+    let metadata = &body["MediaContainer"]["Metadata"];
+    Ok(metadata.get(0).unwrap_or(metadata).clone())
+}
+
+async fn add(plex: &PlexClient, id: u64, rating_keys: &[u64]) -> Result<()> {
+    let uri = plex.items_uri(rating_keys).await?;
+    let req = plex
+        .put(&format!("/library/collections/{}/items", id))?
+        .query(&[("uri", uri.as_str())]);
+    plex.send(req).await
+}
+
+/// `(title, [(tmdbId, mediaType, title)])` of a TMDB collection or list.
+async fn tmdb_source(
+    tmdb: &TmdbClient,
+    request: &TmdbBuildRequest,
+) -> Result<(String, Vec<(u64, String, String)>)> {
+    let (path, entries_field) = match (request.tmdb_collection_id, request.tmdb_list_id.as_deref()) {
+        (Some(id), None) => (format!("/collection/{}", id), "parts"),
+        (None, Some(id)) if !id.trim().is_empty() => (format!("/list/{}", id.trim()), "items"),
+        _ => {
+            return Err(Error::BadRequest(
+                "Give exactly one of tmdbCollectionId or tmdbListId".to_string(),
+            ))
+        }
+    };
+
+    let resp = tmdb
+        .get(&path)?
+        .send()
+        .await
+        .map_err(|e| Error::UpstreamError(format!("TMDB request failed: {}", e)))?;
+    match resp.status() {
+        s if s.is_success() => {}
+        reqwest::StatusCode::NOT_FOUND => return Err(Error::NotFound(format!("TMDB has nothing at {}", path))),
+        reqwest::StatusCode::UNAUTHORIZED => {
+            return Err(Error::Unauthorized("TMDB rejected the configured credentials".to_string()))
+        }
+        s => return Err(Error::UpstreamError(format!("TMDB returned HTTP {} for {}", s.as_u16(), path))),
+    }
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+
+    let entries = body[entries_field]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let title = item["title"].as_str().or(item["name"].as_str()).unwrap_or_default();
+            // Collection parts are always movies
+            let media_type = item["media_type"].as_str().unwrap_or("movie");
+            Some((item["id"].as_u64()?, media_type.to_string(), title.to_string()))
+        })
+        .collect();
+    Ok((body["name"].as_str().unwrap_or_default().to_string(), entries))
+}
+
+/// TMDB id → ratingKey for every item in a library.
+async fn tmdb_index(plex: &PlexClient, key: &str) -> Result<HashMap<u64, String>> {
+    let req = plex
+        .get(&format!("/library/sections/{}/all", key))?
+        .query(&[("includeGuids", "1")]);
+    let body = plex.send_json(req).await?;
+    let mut index = HashMap::new();
+    for item in body["MediaContainer"]["Metadata"].as_array().into_iter().flatten() {
+        let Some(rating_key) = item["ratingKey"].as_str() else { continue };
+        for id in item_guids(item).iter().filter_map(|g| guid_id(g, "tmdb")) {
+            index.insert(id, rating_key.to_string());
+        }
+    }
+    Ok(index)
+}
+
+#[get("/{key}/collections")]
+async fn library_collections(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let key = path.into_inner();
+    let cache_key = ResponseCache::key(cache::PLEX_LIBRARY, &format!("/library/sections/{}/collections", key), &req);
+    let entry = cache.get_or_fetch(cache_key, cache::PLEX_LIBRARY, || async {
+        let req = plex.get(&format!("/library/sections/{}/collections", key))?;
+        let body = plex.send_json(req).await?;
+        let items = &body["MediaContainer"]["Metadata"];
+        if items.is_null() {
+            Ok(serde_json::json!([]))
+        } else {
+            Ok(items.clone())
+        }
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::PLEX_LIBRARY))
+}
+
+#[post("/{key}/collections")]
+async fn create_collection(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<String>,
+    body: web::Json<CreateCollectionRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    validate_title(&body.title)?;
+    let key = path.into_inner();
+    let kind = section_type(&plex, &key).await?;
+    let created = create(&plex, &key, kind, body.title.trim(), &body.rating_keys).await?;
+    cache.invalidate_prefix(cache::PLEX_LIBRARY.name);
+    Ok(HttpResponse::Created().json(created))
+}
+
+/// Build (or extend) a collection from a TMDB collection or list, matching
+/// library items by their TMDB GUID. Titles not in the library come back
+/// under `missing` so they can be requested through Radarr or Sonarr.
+#[post("/{key}/collections/tmdb")]
+async fn build_from_tmdb(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    tmdb: web::Data<TmdbClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<String>,
+    body: web::Json<TmdbBuildRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let key = path.into_inner();
+    let request = body.into_inner();
+    let (name, entries) = tmdb_source(&tmdb, &request).await?;
+    let title = request.title.clone().filter(|t| !t.trim().is_empty()).unwrap_or(name);
+    validate_title(&title)?;
+
+    let kind = section_type(&plex, &key).await?;
+    let library_media = if kind == "2" { "tv" } else { "movie" };
+    let index = tmdb_index(&plex, &key).await?;
+    let (matched, missing): (Vec<TmdbEntry>, Vec<TmdbEntry>) = entries
+        .into_iter()
+        .map(|(tmdb_id, media_type, title)| TmdbEntry {
+            rating_key: index.get(&tmdb_id).filter(|_| media_type == library_media).cloned(),
+            tmdb_id,
+            media_type,
+            title,
+        })
+        .partition(|e| e.rating_key.is_some());
+    let rating_keys: Vec<u64> = matched
+        .iter()
+        .filter_map(|e| e.rating_key.as_deref()?.parse().ok())
+        .collect();
+
+    let collection = if rating_keys.is_empty() {
+        None
+    } else if let Some(id) = request.collection {
+        add(&plex, id, &rating_keys).await?;
+        Some(id.to_string())
+    } else {
+        let created = create(&plex, &key, kind, title.trim(), &rating_keys).await?;
+        created["ratingKey"].as_str().map(String::from)
+    };
+
+    cache.invalidate_prefix(cache::PLEX_LIBRARY.name);
+    Ok(HttpResponse::Ok().json(TmdbBuildResult { collection, title, matched, missing }))
+}
+
+#[delete("/{key}/collections/{id}")]
+async fn delete_collection(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<(String, u64)>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let (_, id) = path.into_inner();
+    plex.send(plex.delete(&format!("/library/collections/{}", id))?).await?;
+    cache.invalidate_prefix(cache::PLEX_LIBRARY.name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/{key}/collections/{id}/items")]
+async fn add_items(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<(String, u64)>,
+    body: web::Json<AddItemsRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let (_, id) = path.into_inner();
+    add(&plex, id, &body.rating_keys).await?;
+    cache.invalidate_prefix(cache::PLEX_LIBRARY.name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[delete("/{key}/collections/{id}/items/{item}")]
+async fn remove_item(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<(String, u64, u64)>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let (_, id, item) = path.into_inner();
+    plex.send(plex.delete(&format!("/library/collections/{}/items/{}", id, item))?).await?;
+    cache.invalidate_prefix(cache::PLEX_LIBRARY.name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// `{"sort": "release" | "alpha" | "custom"}`.
+#[put("/{key}/collections/{id}/sort")]
+async fn set_sort(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<(String, u64)>,
+    body: web::Json<SortRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let (_, id) = path.into_inner();
+    let upstream = plex
+        .put(&format!("/library/metadata/{}/prefs", id))?
+        .query(&[("collectionSort", body.sort.plex_value())]);
+    plex.send(upstream).await?;
+    cache.invalidate_prefix(cache::PLEX_LIBRARY.name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[put("/{key}/collections/{id}/visibility")]
+async fn set_visibility(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<(String, u64)>,
+    body: web::Json<VisibilityRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let (key, id) = path.into_inner();
+    let flag = |on: bool| if on { "1" } else { "0" };
+    let upstream = plex
+        .post(&format!("/hubs/sections/{}/manage", key))?
+        .query(&[
+            ("metadataItemId", id.to_string().as_str()),
+            ("promotedToRecommended", flag(body.library)),
+            ("promotedToOwnHome", flag(body.home)),
+            ("promotedToSharedHome", flag(body.shared)),
+        ]);
+    plex.send(upstream).await?;
+    cache.invalidate_prefix(cache::PLEX_HUBS.name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Posters Plex knows for the collection, including uploaded ones.
+#[get("/{key}/collections/{id}/posters")]
+async fn list_posters(
+    plex: web::Data<PlexClient>,
+    path: web::Path<(String, u64)>,
+) -> Result<impl Responder> {
+    let (_, id) = path.into_inner();
+    Ok(HttpResponse::Ok().json(list_artwork(&plex, id, Artwork::Poster).await?))
+}
+
+#[put("/{key}/collections/{id}/poster")]
+async fn select_poster(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<(String, u64)>,
+    body: web::Json<SelectPosterRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let (_, id) = path.into_inner();
+    select_artwork(&plex, id, Artwork::Poster, &body.url).await?;
+    cache.invalidate_prefix(cache::PLEX_LIBRARY.name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Upload a poster as the raw request body (`Content-Type: image/jpeg` or
+/// `image/png`); Plex selects it straight away.
+#[post("/{key}/collections/{id}/poster")]
+async fn upload_poster(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<(String, u64)>,
+    mut payload: web::Payload,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let (_, id) = path.into_inner();
+    let content_type = req
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    if !content_type.starts_with("image/") {
+        return Err(Error::BadRequest("Send the poster as an image/* body".to_string()));
+    }
+
+    let mut image = Vec::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| Error::BadRequest(e.to_string()))?;
+        if image.len() + chunk.len() > MAX_POSTER_BYTES {
+            return Err(Error::BadRequest("Poster is larger than 10 MB".to_string()));
+        }
+        image.extend_from_slice(&chunk);
+    }
+    if image.is_empty() {
+        return Err(Error::BadRequest("Poster body is empty".to_string()));
+    }
+
+    let upstream = plex
+        .post(&format!("/library/metadata/{}/posters", id))?
+        .header("Content-Type", content_type)
+        .body(image);
+    plex.send(upstream).await?;
+    cache.invalidate_prefix(cache::PLEX_LIBRARY.name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(library_collections)
+        .service(create_collection)
+        .service(build_from_tmdb)
+        .service(delete_collection)
+        .service(add_items)
+        .service(remove_item)
+        .service(set_sort)
+        .service(set_visibility)
+        .service(list_posters)
+        .service(select_poster)
+        .service(upload_poster);
+}
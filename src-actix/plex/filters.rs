@@ -0,0 +1,137 @@
+//! Typed library filters, translated to Plex's `/library/sections/{key}/all`
+//! filter syntax (`field=value`, `field>>=value` for "after", `field<<=value`
+//! for "before"). Values that Plex would silently ignore are rejected here so
+//! a typo shows up as a 400 instead of an unfiltered library.
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::http_error::{Error, Result};
+
+const RESOLUTIONS: &[&str] = &["4k", "1080", "720", "480", "sd"];
+const MAX_YEAR: u32 = 9999;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryFilter {
+    /// Only items the signed-in user has not watched.
+    pub unwatched: Option<bool>,
+    /// Genre name or tag id; comma separated values match any of them.
+    pub genre: Option<String>,
+    /// Inclusive year range.
+    pub year_from: Option<u32>,
+    pub year_to: Option<u32>,
+    /// First year of the decade, e.g. `1990`.
+    pub decade: Option<u32>,
+    pub content_rating: Option<String>,
+    /// `4k`, `1080`, `720`, `480` or `sd`.
+    pub resolution: Option<String>,
+    pub hdr: Option<bool>,
+    /// ISO 639 code as Plex reports it on audio streams, e.g. `eng`.
+    pub audio_language: Option<String>,
+    /// Plex tag ids, as found on `Role[].id` / `Director[].id`.
+    pub actor: Option<u64>,
+    pub director: Option<u64>,
+    /// `YYYY-MM-DD`, or a relative window like `30d`, `6w`, `3m` or `1y`.
+    pub added_since: Option<String>,
+}
+
+impl LibraryFilter {
+    /// Results depend on who is asking: watch state is per user.
+    pub fn per_user(&self) -> bool {
+        self.unwatched == Some(true)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(year) = [self.year_from, self.year_to, self.decade].into_iter().flatten().find(|y| *y > MAX_YEAR) {
+            return Err(Error::BadRequest(format!("years must be at most {}, got {}", MAX_YEAR, year)));
+        }
+        if self.year_from.zip(self.year_to).is_some_and(|(from, to)| from > to) {
+            return Err(Error::BadRequest("yearFrom must not be after yearTo".to_string()));
+        }
+        if let Some(decade) = self.decade.filter(|d| d % 10 != 0) {
+            return Err(Error::BadRequest(format!("decade must be the first year of a decade, got {}", decade)));
+        }
+        if let Some(resolution) = self.resolution.as_deref().filter(|r| !RESOLUTIONS.contains(&r.to_lowercase().as_str())) {
+            return Err(Error::BadRequest(format!(
+                "resolution must be one of {}, got '{}'",
+                RESOLUTIONS.join(", "),
+                resolution
+            )));
+        }
+        if let Some(ref added) = self.added_since {
+            added_since_value(added)?;
+        }
+        Ok(())
+    }
+
+    /// Plex query parameters for this filter, in a stable order so they can
+    /// double as part of a cache key.
+    pub fn params(&self) -> Result<Vec<(String, String)>> {
+        self.validate()?;
+        let mut params: Vec<(String, String)> = Vec::new();
+        let mut push = |key: &str, value: String| params.push((key.to_string(), value));
+
+        if self.unwatched == Some(true) {
+            push("unwatched", "1".to_string());
+        }
+        if let Some(ref genre) = self.genre {
+            push("genre", genre.clone());
+        }
+        // Plex's year operators are exclusive
+        if let Some(from) = self.year_from {
+            push("year>>", from.saturating_sub(1).to_string());
+        }
+        if let Some(to) = self.year_to {
+            push("year<<", (to + 1).to_string());
+        }
+        if let Some(decade) = self.decade {
+            push("decade", decade.to_string());
+        }
+        if let Some(ref rating) = self.content_rating {
+            push("contentRating", rating.clone());
+        }
+        if let Some(ref resolution) = self.resolution {
+            push("resolution", resolution.to_lowercase());
+        }
+        if self.hdr == Some(true) {
+            push("hdr", "1".to_string());
+        }
+        if let Some(ref language) = self.audio_language {
+            push("audioLanguage", language.to_lowercase());
+        }
+        if let Some(actor) = self.actor {
+            push("actor", actor.to_string());
+        }
+        if let Some(director) = self.director {
+            push("director", director.to_string());
+        }
+        if let Some(ref added) = self.added_since {
+            push("addedAt>>", added_since_value(added)?);
+        }
+        Ok(params)
+    }
+}
+
+/// A date becomes a unix timestamp; a relative window becomes Plex's own
+/// `-30d` form so cached results don't pin a moving cutoff.
+fn added_since_value(value: &str) -> Result<String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        return Ok(midnight.and_utc().timestamp().to_string());
+    }
+    let unit = value.chars().last().filter(|c| matches!(c, 'd' | 'w' | 'm' | 'y'));
+    let amount = unit.and_then(|u| value.strip_suffix(u)).map(str::parse::<u32>);
+    match (amount, unit) {
+        (Some(Ok(n)), Some(unit)) if n > 0 => Ok(format!("-{}{}", n, unit)),
+        _ => Err(Error::BadRequest(format!(
+            "addedSince must be YYYY-MM-DD or a window like 30d, 6w, 3m or 1y, got '{}'",
+            value
+        ))),
+    }
+}
+
+/// Query string form of `params`, for cache keys and logging.
+pub fn query_string(params: &[(String, String)]) -> String {
+    params.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("&")
+}
@@ -0,0 +1,40 @@
+//! Parsing of Plex `Guid` entries into external ids.
+
+use serde_json::Value;
+
+/// Extract the numeric ID for `scheme` ("tmdb" or "tvdb") from a Plex GUID,
+/// covering both the new (`tmdb://603`) and legacy agent formats.
+pub fn guid_id(guid: &str, scheme: &str) -> Option<u64> {
+    let legacy = match scheme {
+        "tmdb" => "com.plexapp.agents.themoviedb://",
+        "tvdb" => "com.plexapp.agents.thetvdb://",
+        _ => return None,
+    };
+    let rest = guid
+        .strip_prefix(&format!("{}://", scheme))
+        .or_else(|| guid.strip_prefix(legacy))?;
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// `tt0133093` from `imdb://tt0133093` or the legacy IMDb agent.
+pub fn imdb_id(guid: &str) -> Option<String> {
+    let rest = guid
+        .strip_prefix("imdb://")
+        .or_else(|| guid.strip_prefix("com.plexapp.agents.imdb://"))?;
+    let id: String = rest.chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+    id.starts_with("tt").then_some(id)
+}
+
+/// All GUIDs of an item: the `Guid` array (needs `includeGuids=1`) plus the
+/// primary `guid`.
+pub fn item_guids(item: &Value) -> Vec<&str> {
+    let mut guids: Vec<&str> = item["Guid"]
+        .as_array()
+        .map(|g| g.iter().filter_map(|g| g["id"].as_str()).collect())
+        .unwrap_or_default();
+    if let Some(guid) = item["guid"].as_str() {
+        guids.push(guid);
+    }
+    guids
+}
@@ -1,16 +1,41 @@
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
 use rand::seq::SliceRandom;
 use serde::Deserialize;
-use crate::http_error::Result;
+use crate::cache::{self, CacheRoute, ResponseCache};
+use crate::http_error::{Error, Result};
 use crate::plex::client::PlexClient;
+use crate::plex::filters::{self, LibraryFilter};
 
 #[get("")]
 async fn list_libraries(
+    req: HttpRequest,
     plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<impl Responder> {
-    let req = plex.get("/library/sections")?;
-    let body = plex.send_json(req).await?;
-    Ok(HttpResponse::Ok().json(&body["MediaContainer"]["Directory"]))
+    let key = ResponseCache::key(cache::PLEX_LIBRARY, "/library/sections", &req);
+    let entry = cache.get_or_fetch(key, cache::PLEX_LIBRARY, || async {
+        let req = plex.get("/library/sections")?;
+        let body = plex.send_json(req).await?;
+        Ok(body["MediaContainer"]["Directory"].clone())
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::PLEX_LIBRARY))
+}
+
+/// A GET for a filtered listing and the cache route it belongs in. Watch
+/// state is per user, so `unwatched` listings go out with the user's own
+/// token and are cached per user.
+fn filtered_get(
+    plex: &PlexClient,
+    req: &HttpRequest,
+    path: &str,
+    filter: &LibraryFilter,
+) -> Result<(reqwest::RequestBuilder, CacheRoute)> {
+    if filter.per_user() {
+        let user_token = PlexClient::user_token_from_request(req).unwrap_or_default();
+        return Ok((plex.get_as_user(path, &user_token)?, cache::PLEX_LIBRARY_UNWATCHED));
+    }
+    Ok((plex.get(path)?, cache::PLEX_LIBRARY))
 }
 
 #[derive(Deserialize)]
@@ -20,70 +45,176 @@ struct LibraryItemsQuery {
     sort: Option<String>,
 }
 
+/// Items in a library. Filters from [`LibraryFilter`] can be combined, e.g.
+/// `?unwatched=true&genre=Comedy&yearFrom=1990&yearTo=1999&resolution=4k`.
 #[get("/{key}/items")]
 async fn library_items(
+    req: HttpRequest,
     plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
     path: web::Path<String>,
     query: web::Query<LibraryItemsQuery>,
+    filter: web::Query<LibraryFilter>,
 ) -> Result<impl Responder> {
     let key = path.into_inner();
     let start = query.start.unwrap_or(0);
     let size = query.size.unwrap_or(50);
+    let params = filter.params()?;
 
-    let mut req = plex.get(&format!("/library/sections/{}/all", key))?;
-    req = req.query(&[
-        ("X-Plex-Container-Start", start.to_string()),
-        ("X-Plex-Container-Size", size.to_string()),
-    ]);
+    let upstream = format!(
+        "/library/sections/{}/all?start={}&size={}&sort={}&{}",
+        key, start, size, query.sort.as_deref().unwrap_or(""), filters::query_string(&params)
+    );
+    let (upstream_req, route) = filtered_get(&plex, &req, &format!("/library/sections/{}/all", key), &filter)?;
+    let cache_key = ResponseCache::key(route, &upstream, &req);
+    let entry = cache.get_or_fetch(cache_key, route, || async {
+        let mut req = upstream_req.query(&[
+            ("X-Plex-Container-Start", start.to_string()),
+            ("X-Plex-Container-Size", size.to_string()),
+        ]);
+        req = req.query(&params);
 
-    if let Some(ref sort) = query.sort {
-        req = req.query(&[("sort", sort.as_str())]);
-    }
+        if let Some(ref sort) = query.sort {
+            req = req.query(&[("sort", sort.as_str())]);
+        }
+
+        let body = plex.send_json(req).await?;
+
+        let container = &body["MediaContainer"];
+        Ok(serde_json::json!({
+            "items": container["Metadata"],
+            "totalSize": container["totalSize"],
+            "offset": container["offset"],
+            "size": container["size"]
+        }))
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, route))
+}
+
+/// A–Z jump index: one entry per first character with its item count and the
+/// `start` offset of its first item when sorted by `titleSort:asc`. Takes the
+/// same filters as `/items` so offsets line up with a filtered listing.
+#[get("/{key}/firstCharacter")]
+async fn library_first_character(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<String>,
+    filter: web::Query<LibraryFilter>,
+) -> Result<impl Responder> {
+    let key = path.into_inner();
+    let params = filter.params()?;
+
+    let upstream = format!("/library/sections/{}/firstCharacter?{}", key, filters::query_string(&params));
+    let (upstream_req, route) = filtered_get(&plex, &req, &format!("/library/sections/{}/firstCharacter", key), &filter)?;
+    let cache_key = ResponseCache::key(route, &upstream, &req);
+    let entry = cache.get_or_fetch(cache_key, route, || async {
+        let req = upstream_req.query(&params);
+        let body = plex.send_json(req).await?;
+
+        let mut offset = 0;
+        let index: Vec<serde_json::Value> = body["MediaContainer"]["Directory"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|dir| {
+                let size = dir["size"].as_u64().unwrap_or(0);
+                let entry = serde_json::json!({
+                    "character": dir["title"],
+                    "key": dir["key"],
+                    "size": size,
+                    "offset": offset,
+                });
+                offset += size;
+                entry
+            })
+            .collect();
+        Ok(serde_json::json!(index))
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, route))
+}
+
+/// One random item, optionally narrowed by the same filters as `/items`.
+/// Never cached, every call is a new pick.
+#[get("/{key}/random")]
+async fn library_random(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<String>,
+    filter: web::Query<LibraryFilter>,
+) -> Result<impl Responder> {
+    let key = path.into_inner();
+    let params = filter.params()?;
 
+    let (upstream_req, _) = filtered_get(&plex, &req, &format!("/library/sections/{}/all", key), &filter)?;
+    let req = upstream_req
+        .query(&[("sort", "random"), ("X-Plex-Container-Start", "0"), ("X-Plex-Container-Size", "1")])
+        .query(&params);
     let body = plex.send_json(req).await?;
 
-    let container = &body["MediaContainer"];
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "items": container["Metadata"],
-        "totalSize": container["totalSize"],
-        "offset": container["offset"],
-        "size": container["size"]
-    })))
+    let item = body["MediaContainer"]["Metadata"]
+        .get(0)
+        .cloned()
+        .ok_or_else(|| Error::NotFound("No items match these filters".to_string()))?;
+    Ok(HttpResponse::Ok().json(item))
 }
 
 #[get("/{key}/recent")]
 async fn library_recent(
+    req: HttpRequest,
     plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
     path: web::Path<String>,
 ) -> Result<impl Responder> {
     let key = path.into_inner();
-    let req = plex
-        .get(&format!("/library/sections/{}/recentlyAdded", key))?
-        .query(&[("X-Plex-Container-Size", "20")]);
-    let body = plex.send_json(req).await?;
-    Ok(HttpResponse::Ok().json(&body["MediaContainer"]["Metadata"]))
+    let cache_key = ResponseCache::key(cache::PLEX_LIBRARY, &format!("/library/sections/{}/recentlyAdded", key), &req);
+    let entry = cache.get_or_fetch(cache_key, cache::PLEX_LIBRARY, || async {
+        let req = plex
+            .get(&format!("/library/sections/{}/recentlyAdded", key))?
+            .query(&[("X-Plex-Container-Size", "20")]);
+        let body = plex.send_json(req).await?;
+        Ok(body["MediaContainer"]["Metadata"].clone())
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::PLEX_LIBRARY))
 }
 
 #[get("/{key}/folders")]
 async fn library_folders(
+    req: HttpRequest,
     plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
     path: web::Path<String>,
 ) -> Result<impl Responder> {
     let key = path.into_inner();
-    let req = plex.get(&format!("/library/sections/{}/folder", key))?;
-    let body = plex.send_json(req).await?;
-    Ok(HttpResponse::Ok().json(&body["MediaContainer"]["Metadata"]))
+    let cache_key = ResponseCache::key(cache::PLEX_LIBRARY, &format!("/library/sections/{}/folder", key), &req);
+    let entry = cache.get_or_fetch(cache_key, cache::PLEX_LIBRARY, || async {
+        let req = plex.get(&format!("/library/sections/{}/folder", key))?;
+        let body = plex.send_json(req).await?;
+        Ok(body["MediaContainer"]["Metadata"].clone())
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::PLEX_LIBRARY))
 }
 
 #[get("/{key}/genres")]
 async fn library_genres(
+    req: HttpRequest,
     plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
     path: web::Path<String>,
 ) -> Result<impl Responder> {
     let key = path.into_inner();
-    let req = plex.get(&format!("/library/sections/{}/genre", key))?;
-    let body = plex.send_json(req).await?;
-    Ok(HttpResponse::Ok().json(&body["MediaContainer"]["Directory"]))
+    let cache_key = ResponseCache::key(cache::PLEX_LIBRARY, &format!("/library/sections/{}/genre", key), &req);
+    let entry = cache.get_or_fetch(cache_key, cache::PLEX_LIBRARY, || async {
+        let req = plex.get(&format!("/library/sections/{}/genre", key))?;
+        let body = plex.send_json(req).await?;
+        Ok(body["MediaContainer"]["Directory"].clone())
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::PLEX_LIBRARY))
 }
 
 #[derive(Deserialize)]
@@ -147,31 +278,17 @@ async fn library_by_genre(
     Ok(HttpResponse::Ok().json(groups))
 }
 
-#[get("/{key}/collections")]
-async fn library_collections(
-    plex: web::Data<PlexClient>,
-    path: web::Path<String>,
-) -> Result<impl Responder> {
-    let key = path.into_inner();
-    let req = plex.get(&format!("/library/sections/{}/collections", key))?;
-    let body = plex.send_json(req).await?;
-    let items = &body["MediaContainer"]["Metadata"];
-    if items.is_null() {
-        Ok(HttpResponse::Ok().json(serde_json::json!([])))
-    } else {
-        Ok(HttpResponse::Ok().json(items))
-    }
-}
-
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/libraries")
             .service(list_libraries)
             .service(library_items)
+            .service(library_first_character)
+            .service(library_random)
             .service(library_recent)
             .service(library_genres)
             .service(library_by_genre)
             .service(library_folders)
-            .service(library_collections),
+            .configure(crate::plex::collections::configure),
     );
 }
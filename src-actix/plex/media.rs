@@ -2,7 +2,10 @@ use actix_web::{get, put, web, HttpRequest, HttpResponse, Responder};
 use serde::Deserialize;
 use uuid::Uuid;
 use crate::http_error::Result;
+use crate::images::{self, ImageService, Resize};
 use crate::plex::client::PlexClient;
+use crate::plex::{metadata, play_intent, subtitles, trickplay};
+use crate::plex::transcode::{self, ClientCapabilities, PlaybackMode};
 
 #[get("/{id}")]
 async fn get_metadata(
@@ -117,9 +120,88 @@ async fn get_related(
 
 #[derive(Deserialize)]
 struct StreamQuery {
+    /// Legacy alias for `profile`.
     quality: Option<String>,
+    /// Transcode profile name, or `auto` to choose from the client's capabilities.
+    profile: Option<String>,
     direct_play: Option<bool>,
     direct_stream: Option<bool>,
+    /// Client-measured bandwidth in kbps (auto mode).
+    bandwidth: Option<u32>,
+    /// Comma-separated codecs/containers the client plays natively (auto mode).
+    video_codecs: Option<String>,
+    audio_codecs: Option<String>,
+    containers: Option<String>,
+    max_audio_channels: Option<u32>,
+}
+
+/// Ask Plex for its transcode decision and return a summary of it.
+///
+/// Plex caches transcode decisions per client identifier; calling this
+/// before `start.m3u8` also makes Plex pick up changed quality settings.
+async fn fetch_decision(
+    plex: &PlexClient,
+    base_url: &str,
+    params: &[(&str, &str)],
+) -> Option<serde_json::Value> {
+    let decision_url = format!("{}/video/:/transcode/universal/decision", base_url);
+    let resp = plex.http
+        .get(&decision_url)
+        .query(params)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .ok()?;
+    let body: serde_json::Value = resp.json().await.ok()?;
+    Some(transcode::summarize_decision(&body))
+}
+
+/// Start an HLS session and return the proxied URL of its playlist.
+async fn start_hls_session(
+    plex: &PlexClient,
+    base_url: &str,
+    params: &[(&str, &str)],
+    label: &str,
+) -> Result<String> {
+    let start_url = format!(
+        "{}/video/:/transcode/universal/start.m3u8",
+        base_url
+    );
+    let resp = plex.http
+        .get(&start_url)
+        .query(params)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("{} request failed: {}", label, e))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "Plex {} returned {}: {}",
+            label.to_lowercase(),
+            status.as_u16(),
+            &body[..body.floor_char_boundary(200)]
+        ).into());
+    }
+
+    let m3u8_body = resp.text().await
+        .map_err(|e| anyhow::anyhow!("Failed to read m3u8: {}", e))?;
+
+    // Extract session URL from master m3u8 (first non-comment, non-empty line)
+    let session_path = m3u8_body.lines()
+        .find(|line| !line.starts_with('#') && !line.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("No session URL in m3u8 response"))?;
+
+    // Proxy through backend so local Plex IP is never exposed to clients
+    Ok(if session_path.starts_with("http") {
+        session_path.replace(
+            &format!("{}/video/:/transcode/universal/", base_url),
+            "/api/media/transcode/",
+        )
+    } else {
+        format!("/api/media/transcode/{}", session_path)
+    })
 }
 
 #[get("/{id}/stream")]
@@ -133,6 +215,7 @@ async fn get_stream_url(
     let cfg = plex.config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
     let base_url = cfg.plex.url.trim_end_matches('/').to_string();
     let token = cfg.plex.token.clone();
+    let profiles = cfg.effective_transcode_profiles();
     drop(cfg);
     // Use per-session client identifier so each browser tab gets its own
     // Plex transcode session, preventing multi-user conflicts.
@@ -147,226 +230,168 @@ async fn get_stream_url(
     let part = &media["Part"][0];
     let part_key = part["key"].as_str().unwrap_or("");
 
-    let direct_play = query.direct_play.unwrap_or(true);
-    let direct_stream = query.direct_stream.unwrap_or(false);
-
-    if direct_play && !direct_stream {
-        // Proxy through backend so local Plex IP is never exposed to clients
-        let stream_url = format!("/api/media/stream-proxy{}", part_key);
-        Ok(HttpResponse::Ok().json(serde_json::json!({
-            "url": stream_url,
-            "type": "direct",
-            "media": media,
-            "part": part
-        })))
-    } else if direct_stream {
-        // DirectStream: video passes through untouched, only audio is transcoded
-        let media_path = format!("/library/metadata/{}", id);
-        let ds_params: Vec<(&str, &str)> = vec![
-            ("path", &media_path),
-            ("mediaIndex", "0"),
-            ("partIndex", "0"),
-            ("protocol", "hls"),
-            ("directPlay", "0"),
-            ("directStream", "1"),
-            ("directStreamAudio", "0"),
-            ("videoBitrate", "200000"),
-            ("autoAdjustQuality", "0"),
-
-            ("subtitleSize", "100"),
-            ("audioBoost", "100"),
-            // "burn" forces Plex to burn subtitles into the video stream.
-            // This works for both bitmap (PGS/VOBSUB) and text (SRT/ASS) subs.
-            ("subtitles", "burn"),
-            ("location", "lan"),
-            ("session", &session),
-            ("X-Plex-Token", &token),
-            ("X-Plex-Client-Identifier", &client_id),
-            ("X-Plex-Product", "Playarr"),
-            ("X-Plex-Platform", "Chrome"),
-        ];
-
-        // Call the decision endpoint first to bust Plex's cached transcode decision.
-        // Without this, switching from a lower-quality transcode (e.g. 480p) to
-        // directstream causes Plex to reuse the old quality settings.
-        let decision_url = format!(
-            "{}/video/:/transcode/universal/decision",
-            base_url
-        );
-        let _ = plex.http
-            .get(&decision_url)
-            .query(&ds_params)
-            .header("Accept", "application/json")
-            .send()
-            .await;
-
-        let start_url = format!(
-            "{}/video/:/transcode/universal/start.m3u8",
-            base_url
+    // Text subtitles are delivered as WebVTT sidecars; bitmap ones must be burned.
+    let subtitle_streams: Vec<&serde_json::Value> = part["Stream"]
+        .as_array()
+        .map(|streams| streams.iter().filter(|s| s["streamType"].as_u64() == Some(3)).collect())
+        .unwrap_or_default();
+    let selected_subtitle_codec = subtitle_streams
+        .iter()
+        .find(|s| s["selected"].as_bool() == Some(true))
+        .and_then(|s| s["codec"].as_str());
+    let sidecars: Vec<serde_json::Value> = subtitle_streams
+        .iter()
+        .filter(|s| !s["codec"].as_str().is_some_and(subtitles::is_bitmap_codec))
+        .map(|s| serde_json::json!({
+            "id": s["id"],
+            "codec": s["codec"],
+            "language": s["languageCode"],
+            "title": s["displayTitle"],
+            "selected": s["selected"].as_bool().unwrap_or(false),
+            "url": subtitles::sidecar_url(&id, &part["id"], &s["id"]),
+        }))
+        .collect();
+
+    let requested = query.profile.as_deref().or(query.quality.as_deref());
+    let (mode, profile, selection) = if requested == Some("auto") {
+        let caps = ClientCapabilities::from_lists(
+            query.video_codecs.as_deref(),
+            query.audio_codecs.as_deref(),
+            query.containers.as_deref(),
+            query.bandwidth,
+            query.max_audio_channels,
         );
-        let resp = plex.http
-            .get(&start_url)
-            .query(&ds_params)
-            .send()
-            .await
-            .map_err(|e| anyhow::anyhow!("DirectStream request failed: {}", e))?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Plex directstream returned {}: {}",
-                status.as_u16(),
-                &body[..body.len().min(200)]
-            ).into());
-        }
-
-        let m3u8_body = resp.text().await
-            .map_err(|e| anyhow::anyhow!("Failed to read m3u8: {}", e))?;
-
-        let session_path = m3u8_body.lines()
-            .find(|line| !line.starts_with('#') && !line.is_empty())
-            .ok_or_else(|| anyhow::anyhow!("No session URL in m3u8 response"))?;
-
-        // Proxy through backend so local Plex IP is never exposed to clients
-        let stream_url = if session_path.starts_with("http") {
-            session_path.replace(
-                &format!("{}/video/:/transcode/universal/", base_url),
-                "/api/media/transcode/",
-            )
-        } else {
-            format!("/api/media/transcode/{}", session_path)
-        };
-
-        Ok(HttpResponse::Ok().json(serde_json::json!({
-            "url": stream_url,
-            "type": "directstream",
-            "session": session,
-            "media": media,
-            "part": part
-        })))
+        let (selection, profile) = transcode::select_auto(media, &caps, &profiles);
+        (selection.mode, profile, Some(selection))
     } else {
-        // Quality presets: (bitrate in kbps, resolution WxH)
-        let (bitrate, resolution) = match query.quality.as_deref() {
-            Some("4k") => ("20000", "3840x2160"),
-            Some("1080p-high") => ("20000", "1920x1080"),
-            Some("1080p-medium") => ("12000", "1920x1080"),
-            Some("1080p") | Some("1080") => ("10000", "1920x1080"),
-            Some("1080p-low") => ("8000", "1920x1080"),
-            Some("720p-high") => ("4000", "1280x720"),
-            Some("720p-medium") => ("3000", "1280x720"),
-            Some("720p") | Some("720") => ("2000", "1280x720"),
-            Some("480p") | Some("480") => ("1500", "720x480"),
-            Some("360p") => ("700", "480x360"),
-            Some("original") => ("200000", "1920x1080"),
-            _ => ("10000", "1920x1080"),
-        };
-
-        let height = resolution.split('x').nth(1).unwrap_or("1080");
-        let profile_extra = format!(
-            "add-limitation(scope=videoCodec&scopeName=*&type=upperBound\
-            &name=video.bitrate&value={bitrate}&replace=true)\
-            +add-limitation(scope=videoCodec&scopeName=*&type=upperBound\
-            &name=video.height&value={height}&replace=true)\
-            +append-transcode-target-codec(type=videoProfile&context=streaming\
-            &videoCodec=h264&audioCodec=aac&protocol=hls)"
-        );
-
-        let media_path = format!("/library/metadata/{}", id);
-        let transcode_params: Vec<(&str, &str)> = vec![
-            ("hasMDE", "1"),
-            ("path", &media_path),
-            ("mediaIndex", "0"),
-            ("partIndex", "0"),
-            ("protocol", "hls"),
-            ("fastSeek", "1"),
-            ("directPlay", "0"),
-            ("directStream", "0"),
-            ("directStreamAudio", "0"),
-            ("videoResolution", resolution),
-            ("videoQuality", "100"),
-            ("maxVideoBitrate", bitrate),
-            ("autoAdjustQuality", "0"),
-
-            ("subtitleSize", "100"),
-            ("audioBoost", "100"),
-            // "burn" forces Plex to burn subtitles into the video stream.
-            // This works for both bitmap (PGS/VOBSUB) and text (SRT/ASS) subs.
-            ("subtitles", "burn"),
-            ("mediaBufferSize", "102400"),
-            ("location", "lan"),
-            ("session", &session),
-            ("X-Plex-Client-Profile-Extra", &profile_extra),
-            ("X-Plex-Token", &token),
-            ("X-Plex-Client-Identifier", &client_id),
-            ("X-Plex-Product", "Playarr"),
-            ("X-Plex-Platform", "Chrome"),
-        ];
-
-        // Step 1: Call the decision endpoint to update the transcode decision.
-        // Plex caches transcode decisions per client identifier; without this
-        // call, start.m3u8 reuses the old quality settings.
-        let decision_url = format!(
-            "{}/video/:/transcode/universal/decision",
-            base_url
-        );
-        let _ = plex.http
-            .get(&decision_url)
-            .query(&transcode_params)
-            .header("Accept", "application/json")
-            .send()
-            .await;
-
-        // Step 2: Request the HLS manifest — Plex now uses the updated decision
-        let start_url = format!(
-            "{}/video/:/transcode/universal/start.m3u8",
-            base_url
-        );
-        let resp = plex.http
-            .get(&start_url)
-            .query(&transcode_params)
-            .send()
-            .await
-            .map_err(|e| anyhow::anyhow!("Transcode request failed: {}", e))?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Plex transcode returned {}: {}",
-                status.as_u16(),
-                &body[..body.len().min(200)]
-            ).into());
-        }
-
-        let m3u8_body = resp.text().await
-            .map_err(|e| anyhow::anyhow!("Failed to read m3u8: {}", e))?;
-
-        // Extract session URL from master m3u8 (first non-comment, non-empty line)
-        let session_path = m3u8_body.lines()
-            .find(|line| !line.starts_with('#') && !line.is_empty())
-            .ok_or_else(|| anyhow::anyhow!("No session URL in m3u8 response"))?;
-
-        // Proxy through backend so local Plex IP is never exposed to clients
-        let stream_url = if session_path.starts_with("http") {
-            session_path.replace(
-                &format!("{}/video/:/transcode/universal/", base_url),
-                "/api/media/transcode/",
-            )
+        let direct_play = query.direct_play.unwrap_or(true);
+        let direct_stream = query.direct_stream.unwrap_or(false);
+        let mode = if direct_play && !direct_stream {
+            PlaybackMode::Direct
+        } else if direct_stream {
+            PlaybackMode::DirectStream
         } else {
-            format!("/api/media/transcode/{}", session_path)
+            PlaybackMode::Transcode
         };
+        (mode, transcode::find_profile(&profiles, requested), None)
+    };
 
-        Ok(HttpResponse::Ok().json(serde_json::json!({
-            "url": stream_url,
-            "type": "hls",
-            "session": session,
-            "media": media,
-            "part": part
-        })))
+    match mode {
+        PlaybackMode::Direct => {
+            // Proxy through backend so local Plex IP is never exposed to clients
+            let stream_url = format!("/api/media/stream-proxy{}", part_key);
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "url": stream_url,
+                "type": "direct",
+                "media": media,
+                "part": part,
+                "subtitles": sidecars,
+                "selection": selection
+            })))
+        }
+        PlaybackMode::DirectStream => {
+            // DirectStream: video passes through untouched, only audio is transcoded
+            let media_path = format!("/library/metadata/{}", id);
+            let ds_params: Vec<(&str, &str)> = vec![
+                ("path", &media_path),
+                ("mediaIndex", "0"),
+                ("partIndex", "0"),
+                ("protocol", "hls"),
+                ("directPlay", "0"),
+                ("directStream", "1"),
+                ("directStreamAudio", "0"),
+                ("videoBitrate", "200000"),
+                ("autoAdjustQuality", "0"),
+
+                ("subtitleSize", "100"),
+                ("audioBoost", "100"),
+                ("subtitles", transcode::subtitle_param(profile.subtitle_mode, selected_subtitle_codec)),
+                ("location", "lan"),
+                ("session", &session),
+                ("X-Plex-Token", &token),
+                ("X-Plex-Client-Identifier", &client_id),
+                ("X-Plex-Product", "Playarr"),
+                ("X-Plex-Platform", "Chrome"),
+            ];
+
+            // Without the decision call, switching from a lower-quality transcode
+            // (e.g. 480p) to directstream causes Plex to reuse the old quality settings.
+            let decision = fetch_decision(&plex, &base_url, &ds_params).await;
+            let stream_url = start_hls_session(&plex, &base_url, &ds_params, "DirectStream").await?;
+
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "url": stream_url,
+                "type": "directstream",
+                "session": session,
+                "media": media,
+                "part": part,
+                "subtitles": sidecars,
+                "decision": decision,
+                "selection": selection
+            })))
+        }
+        PlaybackMode::Transcode => {
+            let bitrate = profile.max_bitrate_kbps.to_string();
+            let profile_extra = transcode::client_profile_extra(&profile, "hls");
+
+            let media_path = format!("/library/metadata/{}", id);
+            let transcode_params: Vec<(&str, &str)> = vec![
+                ("hasMDE", "1"),
+                ("path", &media_path),
+                ("mediaIndex", "0"),
+                ("partIndex", "0"),
+                ("protocol", "hls"),
+                ("fastSeek", "1"),
+                ("directPlay", "0"),
+                ("directStream", "0"),
+                ("directStreamAudio", "0"),
+                ("videoResolution", &profile.max_resolution),
+                ("videoQuality", "100"),
+                ("maxVideoBitrate", &bitrate),
+                ("autoAdjustQuality", "0"),
+
+                ("subtitleSize", "100"),
+                ("audioBoost", "100"),
+                ("subtitles", transcode::subtitle_param(profile.subtitle_mode, selected_subtitle_codec)),
+                ("mediaBufferSize", "102400"),
+                ("location", "lan"),
+                ("session", &session),
+                ("X-Plex-Client-Profile-Extra", &profile_extra),
+                ("X-Plex-Token", &token),
+                ("X-Plex-Client-Identifier", &client_id),
+                ("X-Plex-Product", "Playarr"),
+                ("X-Plex-Platform", "Chrome"),
+            ];
+
+            // Step 1: update the transcode decision; Step 2: request the HLS
+            // manifest, which now uses the updated decision.
+            let decision = fetch_decision(&plex, &base_url, &transcode_params).await;
+            let stream_url = start_hls_session(&plex, &base_url, &transcode_params, "Transcode").await?;
+
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "url": stream_url,
+                "type": "hls",
+                "session": session,
+                "profile": profile.name,
+                "media": media,
+                "part": part,
+                "subtitles": sidecars,
+                "decision": decision,
+                "selection": selection
+            })))
+        }
     }
 }
 
+/// Transcode profile names for the player's quality menu.
+#[get("/transcode-profiles")]
+async fn list_transcode_profiles(
+    plex: web::Data<PlexClient>,
+) -> Result<impl Responder> {
+    let cfg = plex.config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+    Ok(HttpResponse::Ok().json(cfg.effective_transcode_profiles()))
+}
+
 #[get("/{id}/bif")]
 async fn get_bif(
     plex: web::Data<PlexClient>,
@@ -401,69 +426,46 @@ async fn get_bif(
 
 #[get("/{id}/thumb")]
 async fn get_thumb(
+    req: HttpRequest,
     plex: web::Data<PlexClient>,
+    images: web::Data<ImageService>,
     path: web::Path<String>,
-) -> Result<impl Responder> {
-    let id = path.into_inner();
-    proxy_image(&plex, &format!("/library/metadata/{}/thumb", id)).await
+    resize: web::Query<Resize>,
+) -> Result<HttpResponse> {
+    let thumb = format!("/library/metadata/{}/thumb", path.into_inner());
+    let upstream = images::plex_request(&plex, &thumb)?;
+    images.serve(&req, &thumb, &resize, images::plex_max_age(&thumb), upstream).await
 }
 
 #[get("/{id}/art")]
 async fn get_art(
+    req: HttpRequest,
     plex: web::Data<PlexClient>,
+    images: web::Data<ImageService>,
     path: web::Path<String>,
-) -> Result<impl Responder> {
-    let id = path.into_inner();
-    proxy_image(&plex, &format!("/library/metadata/{}/art", id)).await
+    resize: web::Query<Resize>,
+) -> Result<HttpResponse> {
+    let art = format!("/library/metadata/{}/art", path.into_inner());
+    let upstream = images::plex_request(&plex, &art)?;
+    images.serve(&req, &art, &resize, images::plex_max_age(&art), upstream).await
 }
 
 #[derive(Deserialize)]
 struct ImageQuery {
     path: String,
-    width: Option<u32>,
-    height: Option<u32>,
 }
 
+/// Plex artwork by path; the same as `/api/images?source=plex`.
 #[get("/image")]
 async fn get_image(
+    req: HttpRequest,
     plex: web::Data<PlexClient>,
+    images: web::Data<ImageService>,
     query: web::Query<ImageQuery>,
-) -> Result<impl Responder> {
-    let encoded_url = query.path.replace('%', "%25").replace('&', "%26").replace('?', "%3F").replace('=', "%3D").replace(' ', "%20").replace('#', "%23");
-    let mut plex_path = format!("/photo/:/transcode?url={}", encoded_url);
-    if let Some(w) = query.width {
-        plex_path.push_str(&format!("&width={}", w));
-    }
-    if let Some(h) = query.height {
-        plex_path.push_str(&format!("&height={}", h));
-    }
-    plex_path.push_str("&minSize=1&upscale=1");
-    proxy_image(&plex, &plex_path).await
-}
-
-async fn proxy_image(plex: &PlexClient, path: &str) -> Result<HttpResponse> {
-    let req = plex.get_image(path)?;
-    let resp = req.send().await
-        .map_err(|e| anyhow::anyhow!("Plex image request failed: {}", e))?;
-
-    if !resp.status().is_success() {
-        return Ok(HttpResponse::NotFound().finish());
-    }
-
-    let content_type = resp
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("image/jpeg")
-        .to_string();
-
-    let bytes = resp.bytes().await
-        .map_err(|e| anyhow::anyhow!("Failed to read image bytes: {}", e))?;
-
-    Ok(HttpResponse::Ok()
-        .content_type(content_type)
-        .append_header(("Cache-Control", "public, max-age=86400"))
-        .body(bytes))
+    resize: web::Query<Resize>,
+) -> Result<HttpResponse> {
+    let upstream = images::plex_request(&plex, &query.path)?;
+    images.serve(&req, &query.path, &resize, images::plex_max_age(&query.path), upstream).await
 }
 
 /// Proxy direct-play streams from Plex, with HTTP range request support.
@@ -659,8 +661,10 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .service(stream_proxy)
             .service(transcode_ping)
             .service(transcode_proxy)
+            .service(list_transcode_profiles)
             .service(get_stream_url)
             .service(get_bif)
+            .configure(trickplay::configure)
             .service(get_thumb)
             .service(get_art)
             .service(get_children)
@@ -668,6 +672,9 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .service(get_on_deck)
             .service(get_related)
             .service(set_part_streams)
+            .configure(subtitles::configure)
+            .configure(metadata::configure)
+            .configure(play_intent::configure)
             .service(get_metadata),
     );
 }
@@ -0,0 +1,347 @@
+//! Admin tools for fixing library items: edit and lock fields, fix a wrong
+//! match, refresh or analyze, and pick posters and background art. Mounted
+//! under `/api/media/{id}` next to the read-only metadata routes.
+
+use std::collections::BTreeMap;
+
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::auth::require_admin;
+use crate::cache::ResponseCache;
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+
+/// Fields Plex lets us lock so agent refreshes leave them alone.
+const LOCKABLE_FIELDS: &[&str] = &[
+    "title",
+    "titleSort",
+    "originalTitle",
+    "summary",
+    "tagline",
+    "contentRating",
+    "studio",
+    "year",
+    "originallyAvailableAt",
+    "thumb",
+    "art",
+    "genre",
+    "label",
+    "collection",
+];
+
+/// Titles of items, collections and playlists can't be blank.
+pub fn validate_title(title: &str) -> Result<()> {
+    if title.trim().is_empty() {
+        return Err(Error::BadRequest("title must not be empty".to_string()));
+    }
+    Ok(())
+}
+
+/// Poster or background art of an item or collection.
+#[derive(Debug, Clone, Copy)]
+pub enum Artwork {
+    Poster,
+    Art,
+}
+
+impl Artwork {
+    fn list_path(self) -> &'static str {
+        match self {
+            Artwork::Poster => "posters",
+            Artwork::Art => "arts",
+        }
+    }
+
+    fn select_path(self) -> &'static str {
+        match self {
+            Artwork::Poster => "poster",
+            Artwork::Art => "art",
+        }
+    }
+}
+
+/// Everything Plex offers for `kind`: agent images, uploads and local files.
+/// The `key` of an entry is what `select_artwork` takes.
+pub async fn list_artwork(plex: &PlexClient, id: u64, kind: Artwork) -> Result<Value> {
+    let req = plex.get(&format!("/library/metadata/{}/{}", id, kind.list_path()))?;
+    let body = plex.send_json(req).await?;
+    let items = &body["MediaContainer"]["Metadata"];
+    Ok(if items.is_null() { serde_json::json!([]) } else { items.clone() })
+}
+
+pub async fn select_artwork(plex: &PlexClient, id: u64, kind: Artwork, url: &str) -> Result<()> {
+    if url.trim().is_empty() {
+        return Err(Error::BadRequest("url must not be empty".to_string()));
+    }
+    let req = plex
+        .put(&format!("/library/metadata/{}/{}", id, kind.select_path()))?
+        .query(&[("url", url.trim())]);
+    plex.send(req).await
+}
+
+/// Any field left out is untouched. Edited fields are locked unless `locks`
+/// says otherwise; `locks` can also (un)lock fields without editing them,
+/// e.g. `{"locks": {"thumb": false}}` to let the agent pick a poster again.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EditMetadataRequest {
+    title: Option<String>,
+    title_sort: Option<String>,
+    original_title: Option<String>,
+    summary: Option<String>,
+    tagline: Option<String>,
+    #[serde(default)]
+    locks: BTreeMap<String, bool>,
+}
+
+impl EditMetadataRequest {
+    fn params(&self) -> Result<Vec<(String, String)>> {
+        if let Some(field) = self.locks.keys().find(|f| !LOCKABLE_FIELDS.contains(&f.as_str())) {
+            return Err(Error::BadRequest(format!(
+                "'{}' can't be locked; lockable fields are {}",
+                field,
+                LOCKABLE_FIELDS.join(", ")
+            )));
+        }
+        if let Some(title) = &self.title {
+            validate_title(title)?;
+        }
+
+        let mut locks = self.locks.clone();
+        let mut params = Vec::new();
+        let edits = [
+            ("title", &self.title),
+            ("titleSort", &self.title_sort),
+            ("originalTitle", &self.original_title),
+            ("summary", &self.summary),
+            ("tagline", &self.tagline),
+        ];
+        for (field, value) in edits {
+            if let Some(value) = value {
+                params.push((format!("{}.value", field), value.trim().to_string()));
+                locks.entry(field.to_string()).or_insert(true);
+            }
+        }
+        for (field, locked) in locks {
+            params.push((format!("{}.locked", field), if locked { "1" } else { "0" }.to_string()));
+        }
+
+        if params.is_empty() {
+            return Err(Error::BadRequest("Nothing to change".to_string()));
+        }
+        Ok(params)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MatchSearchQuery {
+    /// Defaults to the item's current title.
+    title: Option<String>,
+    year: Option<u32>,
+    /// e.g. `tv.plex.agents.movie`; defaults to the library's agent.
+    agent: Option<String>,
+    language: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApplyMatchRequest {
+    /// `guid` of one of the candidates from `GET .../matches`.
+    guid: String,
+    name: String,
+    year: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SelectArtworkRequest {
+    url: String,
+}
+
+async fn fetch_item(plex: &PlexClient, id: u64) -> Result<Value> {
+    let body = plex.send_json(plex.get(&format!("/library/metadata/{}", id))?).await?;
+    let metadata = &body["MediaContainer"]["Metadata"];
+    Ok(metadata.get(0).unwrap_or(metadata).clone())
+}
+
+/// Edits show up everywhere the item is listed.
+fn invalidate(cache: &ResponseCache) {
+    cache.invalidate_prefix("plex.");
+}
+
+/// Edit title, sort title, summary and friends. Returns the updated item.
+#[put("/{id}/metadata")]
+async fn edit_metadata(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<u64>,
+    body: web::Json<EditMetadataRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let id = path.into_inner();
+    let params = body.params()?;
+    plex.send(plex.put(&format!("/library/metadata/{}", id))?.query(&params)).await?;
+    invalidate(&cache);
+    Ok(HttpResponse::Ok().json(fetch_item(&plex, id).await?))
+}
+
+/// Match candidates from the agent, best first.
+#[get("/{id}/matches")]
+async fn list_matches(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<u64>,
+    query: web::Query<MatchSearchQuery>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let id = path.into_inner();
+    let mut upstream = plex
+        .get(&format!("/library/metadata/{}/matches", id))?
+        .query(&[("manual", "1")]);
+    if let Some(ref title) = query.title {
+        upstream = upstream.query(&[("title", title.as_str())]);
+    }
+    if let Some(year) = query.year {
+        upstream = upstream.query(&[("year", year)]);
+    }
+    if let Some(ref agent) = query.agent {
+        upstream = upstream.query(&[("agent", agent.as_str())]);
+    }
+    if let Some(ref language) = query.language {
+        upstream = upstream.query(&[("language", language.as_str())]);
+    }
+
+    let body = plex.send_json(upstream).await?;
+    let mut candidates = body["MediaContainer"]["SearchResult"].as_array().cloned().unwrap_or_default();
+    candidates.sort_by_key(|c| std::cmp::Reverse(c["score"].as_u64().unwrap_or(0)));
+    Ok(HttpResponse::Ok().json(candidates))
+}
+
+/// Re-match the item to a candidate. Plex refreshes it in the background.
+#[put("/{id}/match")]
+async fn apply_match(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<u64>,
+    body: web::Json<ApplyMatchRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let id = path.into_inner();
+    if body.guid.trim().is_empty() {
+        return Err(Error::BadRequest("guid must not be empty".to_string()));
+    }
+    let mut upstream = plex
+        .put(&format!("/library/metadata/{}/match", id))?
+        .query(&[("guid", body.guid.trim()), ("name", body.name.trim())]);
+    if let Some(year) = body.year {
+        upstream = upstream.query(&[("year", year)]);
+    }
+    plex.send(upstream).await?;
+    invalidate(&cache);
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[put("/{id}/unmatch")]
+async fn unmatch(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<u64>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    plex.send(plex.put(&format!("/library/metadata/{}/unmatch", path.into_inner()))?).await?;
+    invalidate(&cache);
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Fetch fresh metadata from the agent (runs in the background on Plex).
+#[post("/{id}/refresh")]
+async fn refresh(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<u64>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let upstream = plex
+        .put(&format!("/library/metadata/{}/refresh", path.into_inner()))?
+        .query(&[("force", "1")]);
+    plex.send(upstream).await?;
+    invalidate(&cache);
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Re-read codecs, duration and loudness from the files.
+#[post("/{id}/analyze")]
+async fn analyze(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<u64>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    plex.send(plex.put(&format!("/library/metadata/{}/analyze", path.into_inner()))?).await?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[get("/{id}/posters")]
+async fn list_posters(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<u64>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    Ok(HttpResponse::Ok().json(list_artwork(&plex, path.into_inner(), Artwork::Poster).await?))
+}
+
+#[put("/{id}/poster")]
+async fn select_poster(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<u64>,
+    body: web::Json<SelectArtworkRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    select_artwork(&plex, path.into_inner(), Artwork::Poster, &body.url).await?;
+    invalidate(&cache);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[get("/{id}/arts")]
+async fn list_arts(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<u64>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    Ok(HttpResponse::Ok().json(list_artwork(&plex, path.into_inner(), Artwork::Art).await?))
+}
+
+#[put("/{id}/art")]
+async fn select_art(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<u64>,
+    body: web::Json<SelectArtworkRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    select_artwork(&plex, path.into_inner(), Artwork::Art, &body.url).await?;
+    invalidate(&cache);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(edit_metadata)
+        .service(list_matches)
+        .service(apply_match)
+        .service(unmatch)
+        .service(refresh)
+        .service(analyze)
+        .service(list_posters)
+        .service(select_poster)
+        .service(list_arts)
+        .service(select_art);
+}
@@ -1,7 +1,17 @@
 pub mod client;
+pub mod collections;
+pub mod filters;
+pub mod guid;
 pub mod libraries;
 pub mod media;
+pub mod metadata;
+pub mod play_intent;
+pub mod playlists;
 pub mod hub;
 pub mod search;
+pub mod sessions;
+pub mod subtitles;
 pub mod timeline;
+pub mod transcode;
+pub mod trickplay;
 pub mod users;
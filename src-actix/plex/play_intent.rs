@@ -0,0 +1,222 @@
+//! `GET /api/media/{id}/play-intent`: everything the player needs before it
+//! starts, decided once on the server so the player and watch parties agree.
+//! Where to resume and whether to ask, where the intro and credits are, when
+//! to offer the next episode, and which episode that is.
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+
+/// Closer to the start than this just starts over without asking.
+const MIN_RESUME_MS: u64 = 60_000;
+/// Past this share of the runtime counts as finished, like the player's
+/// scrobble threshold.
+const WATCHED_FRACTION: f64 = 0.9;
+/// Without a credits marker the next episode is offered this close to the end.
+const NEXT_UP_FALLBACK_MS: u64 = 30_000;
+/// Plex files specials under season 0.
+const SPECIALS_SEASON: u64 = 0;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Marker {
+    start_ms: u64,
+    end_ms: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Markers {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    intro: Option<Marker>,
+    /// The final credits; mid-credits scenes are left to play.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    credits: Option<Marker>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Resume {
+    /// Where to start; 0 for the beginning.
+    offset_ms: u64,
+    /// Ask "resume or start over" instead of resuming silently.
+    prompt: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayIntent {
+    /// The item to play. For a show or season this is the episode to pick up
+    /// at, not the show itself.
+    item: Value,
+    resume: Resume,
+    markers: Markers,
+    /// When to show "up next", if there is a next episode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_up_at_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<Value>,
+}
+
+fn ms(value: &Value) -> u64 {
+    value.as_u64().unwrap_or(0)
+}
+
+fn season(item: &Value) -> u64 {
+    ms(&item["parentIndex"])
+}
+
+fn is_watched(item: &Value) -> bool {
+    ms(&item["viewCount"]) > 0 && ms(&item["viewOffset"]) == 0
+}
+
+async fn fetch_item(plex: &PlexClient, id: &str, user_token: &str) -> Result<Value> {
+    let body = plex
+        .get_json_as_user(&format!("/library/metadata/{}", id), user_token, &[("includeMarkers", "1")])
+        .await?;
+    let metadata = &body["MediaContainer"]["Metadata"];
+    metadata
+        .get(0)
+        .cloned()
+        .ok_or_else(|| Error::NotFound(format!("No library item {}", id)))
+}
+
+/// Episodes of a show or season in airing order, specials first.
+async fn episodes(plex: &PlexClient, parent: &str, user_token: &str) -> Result<Vec<Value>> {
+    let body = plex
+        .get_json_as_user(&format!("/library/metadata/{}/allLeaves", parent), user_token, &[])
+        .await?;
+    let mut episodes = body["MediaContainer"]["Metadata"].as_array().cloned().unwrap_or_default();
+    episodes.sort_by_key(|e| (season(e), ms(&e["index"])));
+    Ok(episodes)
+}
+
+/// Where to pick a show up: the episode in progress, else the first unwatched
+/// one after the furthest watched, else the first. Specials only if there is
+/// nothing else.
+fn pick_up_at(episodes: &[Value]) -> Option<&Value> {
+    let regular: Vec<&Value> = episodes.iter().filter(|e| season(e) != SPECIALS_SEASON).collect();
+    let candidates = if regular.is_empty() { episodes.iter().collect() } else { regular };
+
+    if let Some(in_progress) = candidates
+        .iter()
+        .filter(|e| ms(&e["viewOffset"]) > 0)
+        .max_by_key(|e| ms(&e["lastViewedAt"]))
+    {
+        return Some(in_progress);
+    }
+    let after_watched = candidates.iter().rposition(|e| is_watched(e)).map_or(0, |i| i + 1);
+    candidates[after_watched..]
+        .iter()
+        .chain(candidates[..after_watched].iter())
+        .find(|e| !is_watched(e))
+        .or(candidates.first())
+        .copied()
+}
+
+/// The episode after `current`, across season boundaries. Specials lead to
+/// the next special; regular episodes skip over specials.
+fn next_after<'a>(episodes: &'a [Value], current: &Value) -> Option<&'a Value> {
+    let key = current["ratingKey"].as_str()?;
+    let position = episodes.iter().position(|e| e["ratingKey"].as_str() == Some(key))?;
+    let special = season(current) == SPECIALS_SEASON;
+    episodes[position + 1..]
+        .iter()
+        .find(|e| (season(e) == SPECIALS_SEASON) == special)
+}
+
+fn markers(item: &Value) -> Markers {
+    let all = item["Marker"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let of_type = |kind: &'static str| all.iter().filter(move |m| m["type"].as_str() == Some(kind));
+    let marker = |m: &Value| Marker {
+        start_ms: ms(&m["startTimeOffset"]),
+        end_ms: ms(&m["endTimeOffset"]),
+    };
+    Markers {
+        intro: of_type("intro").next().map(marker),
+        credits: of_type("credits")
+            .find(|m| m["final"].as_bool() == Some(true))
+            .or_else(|| of_type("credits").max_by_key(|m| ms(&m["startTimeOffset"])))
+            .map(marker),
+    }
+}
+
+fn resume(item: &Value, markers: &Markers) -> Resume {
+    let offset = ms(&item["viewOffset"]);
+    let duration = ms(&item["duration"]);
+    let finished_at = markers
+        .credits
+        .as_ref()
+        .map(|c| c.start_ms)
+        .unwrap_or((duration as f64 * WATCHED_FRACTION) as u64);
+    if offset < MIN_RESUME_MS || (duration > 0 && offset >= finished_at) {
+        return Resume { offset_ms: 0, prompt: false };
+    }
+    Resume { offset_ms: offset, prompt: true }
+}
+
+#[get("/{id}/play-intent")]
+async fn play_intent(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let id = path.into_inner();
+    let user_token = PlexClient::user_token_from_request(&req).unwrap_or_default();
+    let mut item = fetch_item(&plex, &id, &user_token).await?;
+
+    let mut show_episodes = None;
+    match item["type"].as_str() {
+        Some(kind @ ("show" | "season")) => {
+            let is_show = kind == "show";
+            let list = episodes(&plex, &id, &user_token).await?;
+            let key = pick_up_at(&list)
+                .and_then(|e| e["ratingKey"].as_str())
+                .ok_or_else(|| Error::NotFound(format!("{} has no episodes", id)))?
+                .to_string();
+            item = fetch_item(&plex, &key, &user_token).await?;
+            // A season's list ends at the season; next-up needs the whole show
+            if is_show {
+                show_episodes = Some(list);
+            }
+        }
+        Some("episode" | "movie" | "clip") => {}
+        other => {
+            return Err(Error::BadRequest(format!("{} can't be played", other.unwrap_or("This item"))));
+        }
+    }
+
+    let next = match (item["type"].as_str(), item["grandparentRatingKey"].as_str()) {
+        (Some("episode"), Some(show)) => {
+            let list = match show_episodes {
+                Some(list) => list,
+                None => episodes(&plex, show, &user_token).await?,
+            };
+            next_after(&list, &item).cloned()
+        }
+        _ => None,
+    };
+
+    let markers = markers(&item);
+    let duration = ms(&item["duration"]);
+    let next_up_at_ms = next.as_ref().map(|_| {
+        markers
+            .credits
+            .as_ref()
+            .map(|c| c.start_ms)
+            .unwrap_or(duration.saturating_sub(NEXT_UP_FALLBACK_MS))
+    });
+    Ok(HttpResponse::Ok().json(PlayIntent {
+        resume: resume(&item, &markers),
+        item,
+        markers,
+        next_up_at_ms,
+        next,
+    }))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(play_intent);
+}
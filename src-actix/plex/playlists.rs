@@ -0,0 +1,300 @@
+//! Playlists under `/api/hubs/playlists`. Everything goes through the signed-in
+//! user's own server token so playlists belong to that user; writes never
+//! fall back to the admin token.
+
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+use crate::plex::filters::LibraryFilter;
+use crate::plex::metadata::validate_title;
+
+const MAX_SMART_LIMIT: u32 = 1000;
+
+/// Plex item type a smart playlist collects.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SmartItemType {
+    Movie,
+    Episode,
+}
+
+impl SmartItemType {
+    fn plex_type(self) -> u8 {
+        match self {
+            SmartItemType::Movie => 1,
+            SmartItemType::Episode => 4,
+        }
+    }
+}
+
+/// Rules for a smart playlist: a library section plus the same filters the
+/// library browser takes, e.g.
+/// `{"section": 1, "itemType": "movie", "unwatched": true, "genre": "Horror", "limit": 50}`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SmartRules {
+    section: u64,
+    item_type: SmartItemType,
+    #[serde(flatten)]
+    filter: LibraryFilter,
+    sort: Option<String>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreatePlaylistRequest {
+    title: String,
+    /// Items for a regular playlist.
+    #[serde(default)]
+    rating_keys: Vec<u64>,
+    /// Makes it a smart playlist; `ratingKeys` must then be empty.
+    smart: Option<SmartRules>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RenamePlaylistRequest {
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddItemsRequest {
+    rating_keys: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoveItemRequest {
+    /// `playlistItemID` to place the item after; omitted moves it to the top.
+    after: Option<u64>,
+}
+
+fn user_token(req: &HttpRequest) -> Result<String> {
+    PlexClient::user_token_from_request(req)
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))
+}
+
+async fn smart_uri(plex: &PlexClient, rules: &SmartRules) -> Result<String> {
+    if rules.limit.is_some_and(|l| l == 0 || l > MAX_SMART_LIMIT) {
+        return Err(Error::BadRequest(format!("limit must be between 1 and {}", MAX_SMART_LIMIT)));
+    }
+    let params = rules.filter.params()?;
+    let mut uri = plex.library_uri(&format!("/library/sections/{}/all", rules.section)).await?;
+    {
+        let mut query = uri.query_pairs_mut();
+        query.append_pair("type", &rules.item_type.plex_type().to_string());
+        query.extend_pairs(params);
+        if let Some(ref sort) = rules.sort {
+            query.append_pair("sort", sort);
+        }
+        if let Some(limit) = rules.limit {
+            query.append_pair("limit", &limit.to_string());
+        }
+    }
+    Ok(uri.to_string())
+}
+
+async fn fetch_items(plex: &PlexClient, id: &str, user_token: &str) -> Result<serde_json::Value> {
+    let req = plex.get_as_user(&format!("/playlists/{}/items", id), user_token)?;
+    let body = plex.send_json(req).await?;
+    let items = &body["MediaContainer"]["Metadata"];
+    Ok(if items.is_null() { serde_json::json!([]) } else { items.clone() })
+}
+
+#[get("/playlists")]
+async fn playlists(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+) -> Result<impl Responder> {
+    let user_token = PlexClient::user_token_from_request(&req).unwrap_or_default();
+    let body = plex
+        .get_json_as_user("/playlists", &user_token, &[
+            ("playlistType", "video"),
+            ("X-Plex-Container-Size", "50"),
+        ])
+        .await?;
+    let items = &body["MediaContainer"]["Metadata"];
+    if items.is_null() {
+        Ok(HttpResponse::Ok().json(serde_json::json!([])))
+    } else {
+        Ok(HttpResponse::Ok().json(items))
+    }
+}
+
+#[get("/playlists/{id}")]
+async fn playlist_metadata(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let id = path.into_inner();
+    let user_token = PlexClient::user_token_from_request(&req).unwrap_or_default();
+    let body = plex
+        .get_json_as_user(&format!("/playlists/{}", id), &user_token, &[])
+        .await?;
+    let metadata = &body["MediaContainer"]["Metadata"];
+    let item = metadata.get(0).unwrap_or(metadata);
+    Ok(HttpResponse::Ok().json(item))
+}
+
+#[get("/playlists/{id}/items")]
+async fn playlist_items(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let id = path.into_inner();
+    let user_token = PlexClient::user_token_from_request(&req).unwrap_or_default();
+    let body = plex
+        .get_json_as_user(&format!("/playlists/{}/items", id), &user_token, &[])
+        .await?;
+    let items = &body["MediaContainer"]["Metadata"];
+    if items.is_null() {
+        Ok(HttpResponse::Ok().json(serde_json::json!([])))
+    } else {
+        Ok(HttpResponse::Ok().json(items))
+    }
+}
+
+/// Create a regular playlist from `ratingKeys`, or a smart one from `smart`.
+#[post("/playlists")]
+async fn create_playlist(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    body: web::Json<CreatePlaylistRequest>,
+) -> Result<impl Responder> {
+    let user_token = user_token(&req)?;
+    let request = body.into_inner();
+    validate_title(&request.title)?;
+
+    if request.smart.is_some() && !request.rating_keys.is_empty() {
+        return Err(Error::BadRequest("A smart playlist takes rules, not ratingKeys".to_string()));
+    }
+
+    let (uri, smart) = match request.smart {
+        Some(ref rules) => (smart_uri(&plex, rules).await?, "1"),
+        None => (plex.items_uri(&request.rating_keys).await?, "0"),
+    };
+
+    let upstream = plex.post_as_user("/playlists", &user_token)?.query(&[
+        ("type", "video"),
+        ("title", request.title.trim()),
+        ("smart", smart),
+        ("uri", uri.as_str()),
+    ]);
+    let body = plex.send_json(upstream).await?;
+    let metadata = &body["MediaContainer"]["Metadata"];
+    let created = metadata.get(0).unwrap_or(metadata);
+    Ok(HttpResponse::Created().json(created))
+}
+
+#[put("/playlists/{id}")]
+async fn rename_playlist(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<u64>,
+    body: web::Json<RenamePlaylistRequest>,
+) -> Result<impl Responder> {
+    let user_token = user_token(&req)?;
+    validate_title(&body.title)?;
+    let upstream = plex
+        .put_as_user(&format!("/playlists/{}", path.into_inner()), &user_token)?
+        .query(&[("title", body.title.trim())]);
+    plex.send(upstream).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[delete("/playlists/{id}")]
+async fn delete_playlist(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<u64>,
+) -> Result<impl Responder> {
+    let user_token = user_token(&req)?;
+    plex.send(plex.delete_as_user(&format!("/playlists/{}", path.into_inner()), &user_token)?).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Replace a smart playlist's rules.
+#[put("/playlists/{id}/rules")]
+async fn update_rules(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<u64>,
+    body: web::Json<SmartRules>,
+) -> Result<impl Responder> {
+    let user_token = user_token(&req)?;
+    let uri = smart_uri(&plex, &body).await?;
+    let upstream = plex
+        .put_as_user(&format!("/playlists/{}/items", path.into_inner()), &user_token)?
+        .query(&[("uri", uri.as_str())]);
+    plex.send(upstream).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Append items. Returns the playlist's items afterwards.
+#[post("/playlists/{id}/items")]
+async fn add_items(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<u64>,
+    body: web::Json<AddItemsRequest>,
+) -> Result<impl Responder> {
+    let user_token = user_token(&req)?;
+    let id = path.into_inner().to_string();
+    let uri = plex.items_uri(&body.rating_keys).await?;
+    let upstream = plex
+        .put_as_user(&format!("/playlists/{}/items", id), &user_token)?
+        .query(&[("uri", uri.as_str())]);
+    plex.send(upstream).await?;
+    Ok(HttpResponse::Ok().json(fetch_items(&plex, &id, &user_token).await?))
+}
+
+/// Remove one entry by its `playlistItemID` (not the item's ratingKey).
+#[delete("/playlists/{id}/items/{item}")]
+async fn remove_item(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<(u64, u64)>,
+) -> Result<impl Responder> {
+    let user_token = user_token(&req)?;
+    let (id, item) = path.into_inner();
+    plex.send(plex.delete_as_user(&format!("/playlists/{}/items/{}", id, item), &user_token)?).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Reorder one entry. Returns the playlist's items in their new order.
+#[put("/playlists/{id}/items/{item}/move")]
+async fn move_item(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<(u64, u64)>,
+    body: web::Json<MoveItemRequest>,
+) -> Result<impl Responder> {
+    let user_token = user_token(&req)?;
+    let (id, item) = path.into_inner();
+    if body.after == Some(item) {
+        return Err(Error::BadRequest("An item can't be moved after itself".to_string()));
+    }
+    let mut upstream = plex.put_as_user(&format!("/playlists/{}/items/{}/move", id, item), &user_token)?;
+    if let Some(after) = body.after {
+        upstream = upstream.query(&[("after", after)]);
+    }
+    plex.send(upstream).await?;
+    Ok(HttpResponse::Ok().json(fetch_items(&plex, &id.to_string(), &user_token).await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(playlist_metadata)
+        .service(playlist_items)
+        .service(playlists)
+        .service(create_playlist)
+        .service(rename_playlist)
+        .service(delete_playlist)
+        .service(update_rules)
+        .service(add_items)
+        .service(remove_item)
+        .service(move_item);
+}
@@ -0,0 +1,326 @@
+//! "Now playing" for the admin: every stream on the Plex server right now,
+//! from `/status/sessions` with the live numbers from `/transcode/sessions`
+//! merged in. `/api/sessions/ws` pushes a fresh snapshot whenever it changes.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use actix_ws::Message;
+use futures_util::StreamExt;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::auth::require_admin;
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+use crate::plex::users::user_id_for_account;
+
+const POLL_INTERVAL_SECS: u64 = 5;
+const DEFAULT_TERMINATE_MESSAGE: &str = "Your stream was ended by the server admin.";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionUser {
+    id: Option<i64>,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumb: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionPlayer {
+    /// Device name, or the browser tab for Playarr's own player.
+    title: String,
+    product: String,
+    platform: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    local: bool,
+    machine_identifier: String,
+    /// Playback session id of the browser tab, for Playarr's player.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    playarr_tab: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamDecision {
+    /// `directplay`, `copy` or `transcode`.
+    decision: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    codec: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscodeProgress {
+    /// Transcoder speed relative to playback; below 1 means buffering.
+    speed: Option<f64>,
+    progress: Option<f64>,
+    throttled: bool,
+    hardware: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NowPlaying {
+    /// What `POST /api/sessions/{id}/terminate` takes.
+    session_id: String,
+    user: SessionUser,
+    rating_key: String,
+    title: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grandparent_title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumb: Option<String>,
+    /// `playing`, `paused` or `buffering`.
+    state: String,
+    view_offset_ms: u64,
+    duration_ms: u64,
+    progress_percent: f64,
+    player: SessionPlayer,
+    /// `lan` or `wan`.
+    location: String,
+    bandwidth_kbps: u64,
+    /// Overall decision: `directplay`, `copy` (direct stream) or `transcode`.
+    decision: String,
+    video: StreamDecision,
+    audio: StreamDecision,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transcode: Option<TranscodeProgress>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionsSnapshot {
+    sessions: Vec<NowPlaying>,
+    stream_count: usize,
+    transcode_count: usize,
+    bandwidth_kbps: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TerminateRequest {
+    /// Shown to the viewer by their Plex app.
+    message: Option<String>,
+}
+
+fn text(value: &Value, field: &str) -> Option<String> {
+    value[field].as_str().filter(|s| !s.is_empty()).map(String::from)
+}
+
+/// Plex sends numbers as strings in some places and not in others.
+fn number(value: &Value) -> Option<f64> {
+    value.as_f64().or_else(|| value.as_str()?.parse().ok())
+}
+
+fn stream_decision(item: &Value, stream_type: u64, decision_field: &str, transcode: &Value) -> StreamDecision {
+    let stream = item["Media"][0]["Part"][0]["Stream"]
+        .as_array()
+        .and_then(|streams| streams.iter().find(|s| s["streamType"].as_u64() == Some(stream_type)));
+    let detail = match stream_type {
+        1 => stream.and_then(|s| text(s, "displayTitle")).or_else(|| text(&item["Media"][0], "videoResolution")),
+        _ => stream.and_then(|s| text(s, "displayTitle")),
+    };
+    StreamDecision {
+        decision: text(transcode, decision_field)
+            .or_else(|| stream.and_then(|s| text(s, "decision")))
+            .unwrap_or_else(|| "directplay".to_string()),
+        codec: stream.and_then(|s| text(s, "codec")),
+        detail,
+    }
+}
+
+fn now_playing(item: &Value, live: &HashMap<String, Value>, admin_user_id: i64) -> Option<NowPlaying> {
+    let session_id = text(&item["Session"], "id")?;
+    let player = &item["Player"];
+    let machine_identifier = text(player, "machineIdentifier").unwrap_or_default();
+    let playarr_tab = PlexClient::client_id_to_session(&machine_identifier).map(String::from);
+    let title = match playarr_tab {
+        Some(ref tab) => format!("Browser tab {}", tab.get(..8).unwrap_or(tab)),
+        None => text(player, "title").or_else(|| text(player, "device")).unwrap_or_default(),
+    };
+
+    // The session list only refreshes its embedded transcode info now and then
+    let embedded = &item["TranscodeSession"];
+    let transcode = text(embedded, "key").and_then(|key| live.get(&key)).unwrap_or(embedded);
+    let transcode_progress = (!transcode.is_null()).then(|| TranscodeProgress {
+        speed: number(&transcode["speed"]),
+        progress: number(&transcode["progress"]),
+        throttled: transcode["throttled"].as_bool().unwrap_or(false),
+        hardware: transcode["transcodeHwEncoding"].is_string() || transcode["transcodeHwFullPipeline"].as_bool().unwrap_or(false),
+    });
+
+    let view_offset_ms = number(&item["viewOffset"]).unwrap_or(0.0) as u64;
+    let duration_ms = number(&item["duration"]).unwrap_or(0.0) as u64;
+    let user = &item["User"];
+    let video = stream_decision(item, 1, "videoDecision", transcode);
+    let audio = stream_decision(item, 2, "audioDecision", transcode);
+    let decision = text(&item["Media"][0]["Part"][0], "decision").unwrap_or_else(|| {
+        if video.decision == "transcode" || audio.decision == "transcode" {
+            "transcode".to_string()
+        } else if video.decision == "copy" || audio.decision == "copy" {
+            "copy".to_string()
+        } else {
+            "directplay".to_string()
+        }
+    });
+
+    Some(NowPlaying {
+        session_id,
+        user: SessionUser {
+            id: number(&user["id"]).map(|id| user_id_for_account(id as i64, admin_user_id)),
+            name: text(user, "title").unwrap_or_default(),
+            thumb: text(user, "thumb"),
+        },
+        rating_key: text(item, "ratingKey").unwrap_or_default(),
+        title: text(item, "title").unwrap_or_default(),
+        kind: text(item, "type").unwrap_or_default(),
+        grandparent_title: text(item, "grandparentTitle"),
+        thumb: text(item, "grandparentThumb").or_else(|| text(item, "thumb")),
+        state: text(player, "state").unwrap_or_else(|| "playing".to_string()),
+        view_offset_ms,
+        duration_ms,
+        progress_percent: if duration_ms == 0 {
+            0.0
+        } else {
+            (view_offset_ms as f64 / duration_ms as f64 * 1000.0).round() / 10.0
+        },
+        player: SessionPlayer {
+            title,
+            product: text(player, "product").unwrap_or_default(),
+            platform: text(player, "platform").unwrap_or_default(),
+            address: text(player, "address"),
+            local: player["local"].as_bool().unwrap_or(false),
+            machine_identifier,
+            playarr_tab,
+        },
+        location: text(&item["Session"], "location").unwrap_or_default(),
+        bandwidth_kbps: number(&item["Session"]["bandwidth"]).unwrap_or(0.0) as u64,
+        decision,
+        video,
+        audio,
+        transcode: transcode_progress,
+    })
+}
+
+async fn snapshot(plex: &PlexClient) -> Result<SessionsSnapshot> {
+    let admin_user_id = {
+        let cfg = plex.config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+        cfg.plex.admin_user_id
+    };
+    let transcodes = async { plex.send_json(plex.get("/transcode/sessions")?).await };
+    let (sessions, transcodes) = tokio::join!(plex.send_json(plex.get("/status/sessions")?), transcodes);
+    let sessions = sessions?;
+    // Only adds detail, the session list stands on its own
+    let live: HashMap<String, Value> = transcodes
+        .inspect_err(|e| debug!("Could not read Plex transcode sessions: {}", e))
+        .ok()
+        .and_then(|body| body["MediaContainer"]["TranscodeSession"].as_array().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|t| Some((text(&t, "key")?, t)))
+        .collect();
+
+    let sessions: Vec<NowPlaying> = sessions["MediaContainer"]["Metadata"]
+        .as_array()
+        .map(|items| items.iter().filter_map(|i| now_playing(i, &live, admin_user_id)).collect())
+        .unwrap_or_default();
+    Ok(SessionsSnapshot {
+        stream_count: sessions.len(),
+        transcode_count: sessions.iter().filter(|s| s.decision == "transcode").count(),
+        bandwidth_kbps: sessions.iter().map(|s| s.bandwidth_kbps).sum(),
+        sessions,
+    })
+}
+
+#[get("")]
+async fn list_sessions(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    Ok(HttpResponse::Ok().json(snapshot(&plex).await?))
+}
+
+/// Stop someone's stream. Plex shows them `message`.
+#[post("/{id}/terminate")]
+async fn terminate_session(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<String>,
+    body: Option<web::Json<TerminateRequest>>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let id = path.into_inner();
+    let message = body
+        .and_then(|b| b.into_inner().message)
+        .filter(|m| !m.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_TERMINATE_MESSAGE.to_string());
+    let upstream = plex
+        .get("/status/sessions/terminate")?
+        .query(&[("sessionId", id.as_str()), ("reason", message.trim())]);
+    plex.send(upstream).await.map_err(|e| match e {
+        Error::NotFound(_) => Error::NotFound(format!("No session {} is playing", id)),
+        e => e,
+    })?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Pushes a snapshot right away, then again whenever it changes. Upstream
+/// errors are sent as `{"error": "..."}` frames and polling carries on.
+#[get("/ws")]
+async fn sessions_websocket(
+    req: HttpRequest,
+    stream: web::Payload,
+    plex: web::Data<PlexClient>,
+) -> std::result::Result<HttpResponse, actix_web::Error> {
+    require_admin(&req, &plex.config)?;
+
+    let (resp, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(POLL_INTERVAL_SECS));
+        let mut last_sent = String::new();
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let json = match snapshot(&plex).await {
+                        Ok(snapshot) => serde_json::to_string(&snapshot).unwrap_or_default(),
+                        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+                    };
+                    if json == last_sent {
+                        continue;
+                    }
+                    if session.text(json.clone()).await.is_err() {
+                        break;
+                    }
+                    last_sent = json;
+                }
+                msg = msg_stream.next() => match msg {
+                    Some(Ok(Message::Ping(bytes))) if session.pong(&bytes).await.is_err() => break,
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                    _ => {}
+                },
+            }
+        }
+        let _ = session.close(None).await;
+    });
+
+    Ok(resp)
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/sessions")
+            .service(sessions_websocket)
+            .service(list_sessions)
+            .service(terminate_session),
+    );
+}
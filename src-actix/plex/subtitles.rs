@@ -0,0 +1,251 @@
+//! Conversion of text subtitle streams (SRT, ASS/SSA, WebVTT) to WebVTT so
+//! browsers can render them as a `<track>` instead of Plex burning them in.
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+
+/// Bitmap subtitle codecs as reported by Plex. These cannot be converted to
+/// text and have to be burned into the video.
+const BITMAP_CODECS: &[&str] = &["pgs", "hdmv_pgs_subtitle", "vobsub", "dvd_subtitle", "dvb_subtitle", "dvbsub"];
+
+pub fn is_bitmap_codec(codec: &str) -> bool {
+    BITMAP_CODECS.contains(&codec.to_lowercase().as_str())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Cue {
+    start_ms: i64,
+    end_ms: i64,
+    text: String,
+}
+
+/// Parse `HH:MM:SS,mmm`, `HH:MM:SS.mmm`, `MM:SS.mmm` or ASS `H:MM:SS.cc`.
+fn parse_timestamp(value: &str) -> Option<i64> {
+    let value = value.trim().replace(',', ".");
+    let (clock, fraction) = value.split_once('.').unwrap_or((&value, "0"));
+    let parts: Vec<i64> = clock.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let (h, m, s) = match parts.as_slice() {
+        [h, m, s] => (*h, *m, *s),
+        [m, s] => (0, *m, *s),
+        _ => return None,
+    };
+    // Normalise the fraction to milliseconds ("5" = 500ms, "05" = 50ms, "005" = 5ms)
+    let digits: String = fraction.chars().take(3).collect();
+    let millis = format!("{:0<3}", digits).parse::<i64>().ok()?;
+    Some(((h * 60 + m) * 60 + s) * 1000 + millis)
+}
+
+fn format_timestamp(ms: i64) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1000) % 60,
+        ms % 1000
+    )
+}
+
+/// Parse SRT or WebVTT cue blocks.
+fn parse_srt(input: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut lines = input.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some((start, rest)) = line.split_once("-->") else { continue };
+        // WebVTT cue settings may follow the end time
+        let end = rest.split_whitespace().next().unwrap_or("");
+        let (Some(start_ms), Some(end_ms)) = (parse_timestamp(start), parse_timestamp(end)) else {
+            continue;
+        };
+
+        let mut text = Vec::new();
+        while let Some(next) = lines.peek() {
+            if next.trim().is_empty() {
+                break;
+            }
+            text.push(clean_srt_text(next));
+            lines.next();
+        }
+        cues.push(Cue { start_ms, end_ms, text: text.join("\n") });
+    }
+    cues
+}
+
+/// Drop the `<font>` tags SRT files often carry; WebVTT only knows `<b>`, `<i>` and `<u>`.
+fn clean_srt_text(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let tag = &rest[start..start + end + 1];
+        if !tag.to_lowercase().starts_with("<font") && !tag.eq_ignore_ascii_case("</font>") {
+            out.push_str(tag);
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out.replace("-->", "->")
+}
+
+/// Parse the `[Events]` section of an ASS/SSA file.
+fn parse_ass(input: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut in_events = false;
+    let mut format: Vec<String> = Vec::new();
+
+    for line in input.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+        if let Some(fields) = line.strip_prefix("Format:") {
+            format = fields.split(',').map(|f| f.trim().to_lowercase()).collect();
+            continue;
+        }
+        let Some(values) = line.strip_prefix("Dialogue:") else { continue };
+        if format.is_empty() {
+            continue;
+        }
+
+        // The last field (Text) may itself contain commas.
+        let values: Vec<&str> = values.splitn(format.len(), ',').map(|v| v.trim()).collect();
+        let field = |name: &str| format.iter().position(|f| f == name).and_then(|i| values.get(i).copied());
+        let (Some(start), Some(end), Some(text)) = (field("start"), field("end"), field("text")) else {
+            continue;
+        };
+        let (Some(start_ms), Some(end_ms)) = (parse_timestamp(start), parse_timestamp(end)) else {
+            continue;
+        };
+        let text = clean_ass_text(text);
+        if !text.is_empty() {
+            cues.push(Cue { start_ms, end_ms, text });
+        }
+    }
+
+    cues.sort_by_key(|c| c.start_ms);
+    cues
+}
+
+/// Strip `{\override}` blocks and translate ASS line breaks.
+fn clean_ass_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0;
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("\\N", "\n")
+        .replace("\\n", "\n")
+        .replace("\\h", " ")
+        .replace("-->", "->")
+        .trim()
+        .to_string()
+}
+
+/// Convert SRT, ASS/SSA or WebVTT text to WebVTT, shifting every cue by
+/// `offset_ms`. Cues that end before zero after shifting are dropped.
+pub fn to_webvtt(input: &str, offset_ms: i64) -> String {
+    let input = input.trim_start_matches('\u{feff}');
+    let cues = if input.contains("[Script Info]") || input.contains("[Events]") {
+        parse_ass(input)
+    } else {
+        parse_srt(input)
+    };
+
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        let end = cue.end_ms + offset_ms;
+        if end <= 0 {
+            continue;
+        }
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            format_timestamp(cue.start_ms + offset_ms),
+            format_timestamp(end),
+            cue.text
+        ));
+    }
+    out
+}
+
+/// URL of the WebVTT sidecar for a subtitle stream of one of `id`'s parts.
+pub fn sidecar_url(id: &str, part_id: &serde_json::Value, stream_id: &serde_json::Value) -> String {
+    format!("/api/media/{}/parts/{}/subtitles/{}", id, part_id, stream_id)
+}
+
+#[derive(Deserialize)]
+struct SubtitleQuery {
+    /// Shift cues by this many milliseconds (negative moves them earlier).
+    offset: Option<i64>,
+}
+
+/// Fetch a text subtitle stream from Plex and serve it as WebVTT. The item
+/// is looked up as the user, and the stream must be one of its part's.
+#[get("/{id}/parts/{part_id}/subtitles/{stream_id}")]
+async fn get_subtitle_vtt(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<(String, u64, u64)>,
+    query: web::Query<SubtitleQuery>,
+) -> Result<impl Responder> {
+    let (id, part_id, stream_id) = path.into_inner();
+    let user_token = PlexClient::user_token_from_request(&req).unwrap_or_default();
+    let item = plex
+        .send_json(plex.get_as_user(&format!("/library/metadata/{}", id), &user_token)?)
+        .await?;
+    let owned = item["MediaContainer"]["Metadata"][0]["Media"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|media| media["Part"].as_array().into_iter().flatten())
+        .filter(|part| part["id"].as_u64() == Some(part_id))
+        .flat_map(|part| part["Stream"].as_array().into_iter().flatten())
+        .any(|stream| stream["id"].as_u64() == Some(stream_id) && stream["streamType"].as_u64() == Some(3));
+    if !owned {
+        return Err(Error::NotFound(format!("Subtitle stream {} not found", stream_id)));
+    }
+
+    let req = plex
+        .get(&format!("/library/streams/{}", stream_id))?
+        .query(&[("encoding", "utf-8")]);
+    let resp = req
+        .send()
+        .await
+        .map_err(|e| Error::UpstreamError(format!("Plex request failed: {}", e)))?;
+
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(Error::NotFound(format!("Subtitle stream {} not found", stream_id)));
+    }
+    if !resp.status().is_success() {
+        return Err(Error::UpstreamError(format!("Plex returned {}", resp.status())));
+    }
+
+    let bytes = resp
+        .bytes()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read subtitle stream: {}", e))?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/vtt; charset=utf-8")
+        .body(to_webvtt(&text, query.offset.unwrap_or(0))))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_subtitle_vtt);
+}
@@ -1,7 +1,8 @@
 use actix_web::{post, put, web, HttpRequest, HttpResponse, Responder};
 use serde::Deserialize;
-use crate::http_error::Result;
+use crate::http_error::{Error, Result};
 use crate::plex::client::PlexClient;
+use crate::stats::playback::{PlayMethod, PlaybackLog, TimelineReport};
 
 #[derive(Deserialize)]
 struct TimelineUpdate {
@@ -11,16 +12,27 @@ struct TimelineUpdate {
     state: String,
     time: u64,
     duration: u64,
+    /// Stream type the player is on, for the transcode statistics.
+    #[serde(rename = "playMethod")]
+    play_method: Option<PlayMethod>,
 }
 
 #[put("/timeline")]
 async fn update_timeline(
     req: HttpRequest,
     plex: web::Data<PlexClient>,
+    log: web::Data<PlaybackLog>,
     body: web::Json<TimelineUpdate>,
 ) -> Result<impl Responder> {
     let user_token = PlexClient::user_token_from_request(&req).unwrap_or_default();
     let session_client_id = plex.playback_client_id(&req);
+    log.record(TimelineReport {
+        client_id: &session_client_id,
+        user_id: PlexClient::user_from_request(&req).map(|(id, _)| id),
+        rating_key: &body.rating_key,
+        state: &body.state,
+        method: body.play_method,
+    });
     let time_str = body.time.to_string();
     let duration_str = body.duration.to_string();
     let resp = plex
@@ -61,10 +73,18 @@ struct StopRequest {
 async fn stop_playback(
     req: HttpRequest,
     plex: web::Data<PlexClient>,
+    log: web::Data<PlaybackLog>,
     body: web::Json<StopRequest>,
 ) -> Result<impl Responder> {
     let user_token = PlexClient::user_token_from_request(&req).unwrap_or_default();
     let session_client_id = plex.session_to_client_id(body.session_id.as_deref());
+    log.record(TimelineReport {
+        client_id: &session_client_id,
+        user_id: PlexClient::user_from_request(&req).map(|(id, _)| id),
+        rating_key: &body.rating_key,
+        state: "stopped",
+        method: None,
+    });
     let time_str = body.time.to_string();
     let duration_str = body.duration.to_string();
     let _ = plex
@@ -133,11 +153,44 @@ async fn unscrobble(
     }
 }
 
+#[derive(Deserialize)]
+struct RateRequest {
+    /// 0.5 to 5 in half-star steps; null clears the rating.
+    stars: Option<f32>,
+}
+
+/// Star-rate an item as the signed-in user. Plex stores ratings out of 10.
+#[put("/rate/{id}")]
+async fn rate(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<String>,
+    body: web::Json<RateRequest>,
+) -> Result<impl Responder> {
+    let id = path.into_inner();
+    let rating = match body.stars {
+        Some(stars) if (0.5..=5.0).contains(&stars) && (stars * 2.0).fract() == 0.0 => (stars * 2.0) as i32,
+        Some(stars) => {
+            return Err(Error::BadRequest(format!("stars must be 0.5 to 5 in half steps, got {}", stars)));
+        }
+        None => -1,
+    };
+    let user_token = PlexClient::user_token_from_request(&req).unwrap_or_default();
+    let upstream = plex.put_as_user("/:/rate", &user_token)?.query(&[
+        ("identifier", "com.plexapp.plugins.library"),
+        ("key", id.as_str()),
+        ("rating", rating.to_string().as_str()),
+    ]);
+    plex.send(upstream).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "success": true, "userRating": rating.max(0) })))
+}
+
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/player")
             .service(update_timeline)
             .service(stop_playback)
+            .service(rate)
             .service(scrobble)
             .service(unscrobble),
     );
@@ -0,0 +1,264 @@
+//! Transcode profile selection and Plex decision reporting for `/media/{id}/stream`.
+
+use serde::Serialize;
+
+use crate::config::models::{SubtitleMode, TranscodeProfile};
+use crate::plex::subtitles;
+
+/// Leave this share of the client's bandwidth unused to absorb fluctuations.
+const BANDWIDTH_HEADROOM: f64 = 0.8;
+
+/// Profile used when a request names no profile (matches the old default quality).
+const DEFAULT_PROFILE: &str = "1080p";
+
+// Mirrors the browser support tables in `src/lib/codec-support.ts`.
+const DEFAULT_VIDEO_CODECS: &[&str] = &["h264", "avc1", "vp8", "vp9", "av1"];
+const DEFAULT_AUDIO_CODECS: &[&str] = &["aac", "mp3", "opus", "vorbis", "flac"];
+const DEFAULT_CONTAINERS: &[&str] = &["mp4", "m4v", "webm", "ogg", "mov"];
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum PlaybackMode {
+    #[serde(rename = "direct")]
+    Direct,
+    #[serde(rename = "directstream")]
+    DirectStream,
+    #[serde(rename = "hls")]
+    Transcode,
+}
+
+/// What the client reported it can play, used by `profile=auto`.
+#[derive(Debug, Clone)]
+pub struct ClientCapabilities {
+    pub video_codecs: Vec<String>,
+    pub audio_codecs: Vec<String>,
+    pub containers: Vec<String>,
+    pub bandwidth_kbps: Option<u32>,
+    pub max_audio_channels: Option<u32>,
+}
+
+fn parse_list(value: Option<&str>, default: &[&str]) -> Vec<String> {
+    match value.filter(|v| !v.trim().is_empty()) {
+        Some(v) => v.split(',').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()).collect(),
+        None => default.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+impl ClientCapabilities {
+    /// Build from comma-separated lists; missing lists fall back to what
+    /// every modern browser supports.
+    pub fn from_lists(
+        video_codecs: Option<&str>,
+        audio_codecs: Option<&str>,
+        containers: Option<&str>,
+        bandwidth_kbps: Option<u32>,
+        max_audio_channels: Option<u32>,
+    ) -> Self {
+        Self {
+            video_codecs: parse_list(video_codecs, DEFAULT_VIDEO_CODECS),
+            audio_codecs: parse_list(audio_codecs, DEFAULT_AUDIO_CODECS),
+            containers: parse_list(containers, DEFAULT_CONTAINERS),
+            bandwidth_kbps: bandwidth_kbps.filter(|b| *b > 0),
+            max_audio_channels,
+        }
+    }
+
+    fn budget_kbps(&self) -> Option<u32> {
+        self.bandwidth_kbps.map(|b| (b as f64 * BANDWIDTH_HEADROOM) as u32)
+    }
+}
+
+/// Result of automatic playback selection.
+#[derive(Debug, Clone, Serialize)]
+pub struct Selection {
+    pub mode: PlaybackMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub reason: String,
+}
+
+/// Values the old `quality` parameter took, and the profile each became.
+const LEGACY_QUALITIES: &[(&str, &str)] = &[("1080", "1080p"), ("720", "720p"), ("480", "480p")];
+
+/// Look up a profile by name (or legacy quality value), falling back to the
+/// default and then the first profile.
+pub fn find_profile(profiles: &[TranscodeProfile], name: Option<&str>) -> TranscodeProfile {
+    let name = name.map(|n| LEGACY_QUALITIES.iter().find(|(old, _)| *old == n).map_or(n, |(_, profile)| *profile));
+    name.and_then(|n| profiles.iter().find(|p| p.name.eq_ignore_ascii_case(n)))
+        .or_else(|| profiles.iter().find(|p| p.name == DEFAULT_PROFILE))
+        .or_else(|| profiles.first())
+        .cloned()
+        .unwrap_or_else(|| TranscodeProfile::new(DEFAULT_PROFILE, 10000, "1920x1080"))
+}
+
+/// Pick direct play, direct stream or a transcode profile for `media`
+/// (a Plex `Media` object) given what the client can handle.
+pub fn select_auto(
+    media: &serde_json::Value,
+    caps: &ClientCapabilities,
+    profiles: &[TranscodeProfile],
+) -> (Selection, TranscodeProfile) {
+    let video_codec = media["videoCodec"].as_str().unwrap_or("").to_lowercase();
+    let audio_codec = media["audioCodec"].as_str().unwrap_or("").to_lowercase();
+    let container = media["container"].as_str().unwrap_or("").to_lowercase();
+    let source_bitrate = media["bitrate"].as_u64().unwrap_or(0) as u32;
+    let source_height = media["height"].as_u64().map(|h| h as u32);
+    let channels = media["audioChannels"].as_u64().unwrap_or(2) as u32;
+
+    let can_video = caps.video_codecs.contains(&video_codec);
+    let can_audio = caps.audio_codecs.contains(&audio_codec)
+        && caps.max_audio_channels.is_none_or(|max| channels <= max);
+    let can_container = caps.containers.contains(&container);
+    let fits_bandwidth = caps.budget_kbps().is_none_or(|budget| source_bitrate <= budget);
+
+    if can_video && can_audio && can_container && fits_bandwidth {
+        let selection = Selection {
+            mode: PlaybackMode::Direct,
+            profile: None,
+            reason: format!("Client plays {}/{} in {} natively", video_codec, audio_codec, container),
+        };
+        return (selection, find_profile(profiles, None));
+    }
+
+    if can_video && fits_bandwidth {
+        let reason = if !can_audio {
+            format!("Audio codec \"{}\" ({} channels) not supported by client", audio_codec, channels)
+        } else {
+            format!("Container \"{}\" not supported for direct play", container)
+        };
+        let selection = Selection { mode: PlaybackMode::DirectStream, profile: None, reason };
+        return (selection, find_profile(profiles, None));
+    }
+
+    let profile = pick_profile(profiles, caps, source_bitrate, source_height);
+    let why = if !can_video {
+        format!("Video codec \"{}\" not supported by client", video_codec)
+    } else {
+        format!(
+            "Source bitrate {} kbps exceeds available bandwidth {} kbps",
+            source_bitrate,
+            caps.bandwidth_kbps.unwrap_or(0)
+        )
+    };
+    let selection = Selection {
+        mode: PlaybackMode::Transcode,
+        profile: Some(profile.name.clone()),
+        reason: format!("{}; using profile \"{}\"", why, profile.name),
+    };
+    (selection, profile)
+}
+
+/// The highest-bitrate profile the client can decode that fits the bandwidth
+/// budget and does not upscale; otherwise the cheapest compatible profile.
+fn pick_profile(
+    profiles: &[TranscodeProfile],
+    caps: &ClientCapabilities,
+    source_bitrate: u32,
+    source_height: Option<u32>,
+) -> TranscodeProfile {
+    let compatible: Vec<&TranscodeProfile> = profiles
+        .iter()
+        .filter(|p| p.video_codecs.iter().any(|c| caps.video_codecs.contains(&c.to_lowercase())))
+        .collect();
+    let candidates = if compatible.is_empty() {
+        profiles.iter().collect()
+    } else {
+        compatible
+    };
+
+    let budget = caps.budget_kbps().unwrap_or(if source_bitrate > 0 { source_bitrate } else { u32::MAX });
+    let no_upscale = |p: &&&TranscodeProfile| match (p.max_height(), source_height) {
+        (Some(h), Some(src)) => h <= src.max(480),
+        _ => true,
+    };
+
+    candidates
+        .iter()
+        .filter(|p| p.max_bitrate_kbps <= budget)
+        .filter(no_upscale)
+        .max_by_key(|p| (p.max_bitrate_kbps, p.max_height().unwrap_or(0)))
+        .or_else(|| candidates.iter().min_by_key(|p| p.max_bitrate_kbps))
+        .map(|p| (*p).clone())
+        .unwrap_or_else(|| find_profile(profiles, None))
+}
+
+/// Value for Plex's `subtitles` transcode parameter. In sidecar mode only
+/// bitmap subtitles (which browsers cannot render) are burned in.
+pub fn subtitle_param(mode: SubtitleMode, selected_codec: Option<&str>) -> &'static str {
+    match mode {
+        SubtitleMode::Burn => "burn",
+        SubtitleMode::Sidecar if selected_codec.is_some_and(subtitles::is_bitmap_codec) => "burn",
+        SubtitleMode::Sidecar | SubtitleMode::None => "none",
+    }
+}
+
+/// `X-Plex-Client-Profile-Extra` limiting bitrate, height and audio channels
+/// and declaring the target codecs for a profile. `protocol` is `hls` for
+/// streaming or `http` for a file download.
+pub fn client_profile_extra(profile: &TranscodeProfile, protocol: &str) -> String {
+    let height = profile.max_height().unwrap_or(1080);
+    let video_codecs = if profile.video_codecs.is_empty() {
+        "h264".to_string()
+    } else {
+        profile.video_codecs.join(",")
+    };
+    let audio_codecs = if profile.audio_codecs.is_empty() {
+        "aac".to_string()
+    } else {
+        profile.audio_codecs.join(",")
+    };
+    format!(
+        "add-limitation(scope=videoCodec&scopeName=*&type=upperBound\
+        &name=video.bitrate&value={bitrate}&replace=true)\
+        +add-limitation(scope=videoCodec&scopeName=*&type=upperBound\
+        &name=video.height&value={height}&replace=true)\
+        +add-limitation(scope=audioCodec&scopeName=*&type=upperBound\
+        &name=audio.channels&value={channels}&replace=true)\
+        +append-transcode-target-codec(type=videoProfile&context=streaming\
+        &videoCodec={video_codecs}&audioCodec={audio_codecs}&protocol={protocol})",
+        bitrate = profile.max_bitrate_kbps,
+        channels = profile.max_audio_channels,
+    )
+}
+
+/// Condense Plex's `/video/:/transcode/universal/decision` response into
+/// the reasons behind direct play, direct stream or transcode.
+pub fn summarize_decision(body: &serde_json::Value) -> serde_json::Value {
+    let container = &body["MediaContainer"];
+    let decision = |prefix: &str| {
+        serde_json::json!({
+            "code": container[format!("{}DecisionCode", prefix)],
+            "text": container[format!("{}DecisionText", prefix)],
+        })
+    };
+
+    let part = &container["Metadata"][0]["Media"][0]["Part"][0];
+    let streams: Vec<serde_json::Value> = part["Stream"]
+        .as_array()
+        .map(|streams| {
+            streams
+                .iter()
+                .map(|s| {
+                    let kind = match s["streamType"].as_u64() {
+                        Some(1) => "video",
+                        Some(2) => "audio",
+                        Some(3) => "subtitle",
+                        _ => "other",
+                    };
+                    serde_json::json!({
+                        "type": kind,
+                        "codec": s["codec"],
+                        "decision": s["decision"],
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    serde_json::json!({
+        "general": decision("general"),
+        "direct_play": decision("directPlay"),
+        "transcode": decision("transcode"),
+        "part_decision": part["decision"],
+        "streams": streams,
+    })
+}
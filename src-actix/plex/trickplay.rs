@@ -0,0 +1,308 @@
+//! Seek-bar previews as WebVTT + sprite sheets, cached on disk per part.
+//!
+//! Plex only has preview frames when its "video preview thumbnails" task has
+//! built the part's BIF index, which is off by default. When the index is
+//! there its frames are packed into sheets; otherwise the chapter images Plex
+//! makes for files with chapters are fetched through `/photo/:/transcode` and
+//! used instead, one tile per chapter.
+//!
+//! There is no fallback to frames sampled at fixed offsets: Plex has no
+//! endpoint that extracts a frame from a video at an arbitrary time, and
+//! `/photo/:/transcode` only rescales images that already exist. Parts with
+//! neither an index nor chapter images get a 404 that says so.
+
+use std::path::{Path, PathBuf};
+
+use actix_files::NamedFile;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use futures_util::future::join_all;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{GenericImage, RgbImage};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+
+const TILE_WIDTH: u32 = 240;
+const COLUMNS: u32 = 10;
+const ROWS: u32 = 10;
+const JPEG_QUALITY: u8 = 75;
+const BIF_MAGIC: [u8; 8] = [0x89, b'B', b'I', b'F', 0x0d, 0x0a, 0x1a, 0x0a];
+/// The last BIF index entry carries this timestamp and the end of the data.
+const BIF_END: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Source {
+    Bif,
+    Chapters,
+}
+
+impl Source {
+    /// Part of the sheet URLs, so browsers don't keep chapter sheets cached
+    /// once they have been rebuilt from a BIF.
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Bif => "bif",
+            Self::Chapters => "chapters",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Cue {
+    start_ms: u64,
+    end_ms: u64,
+}
+
+/// `manifest.json` next to a part's sheets; its presence marks the cache as
+/// complete.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    source: Source,
+    tile_width: u32,
+    tile_height: u32,
+    /// Cue `i` is tile `i`, filling sheets row by row.
+    cues: Vec<Cue>,
+}
+
+impl Manifest {
+    fn to_vtt(&self, part_id: i64) -> String {
+        let per_sheet = (COLUMNS * ROWS) as usize;
+        let mut vtt = String::from("WEBVTT\n");
+        for (i, cue) in self.cues.iter().enumerate() {
+            let tile = (i % per_sheet) as u32;
+            vtt.push_str(&format!(
+                "\n{} --> {}\n/api/media/trickplay/{}/{}.jpg?v={}#xywh={},{},{},{}\n",
+                timestamp(cue.start_ms),
+                timestamp(cue.end_ms),
+                part_id,
+                i / per_sheet,
+                self.source.as_str(),
+                (tile % COLUMNS) * self.tile_width,
+                (tile / COLUMNS) * self.tile_height,
+                self.tile_width,
+                self.tile_height
+            ));
+        }
+        vtt
+    }
+}
+
+fn timestamp(ms: u64) -> String {
+    format!("{:02}:{:02}:{:02}.{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+fn cache_root() -> PathBuf {
+    if cfg!(test) {
+        return std::env::temp_dir().join(format!("playarr-trickplay-{}", std::process::id()));
+    }
+    crate::config::data_dir().join("trickplay")
+}
+
+/// Timestamps and JPEG bytes of every frame in a BIF file.
+fn parse_bif(data: &[u8]) -> Option<Vec<(u64, &[u8])>> {
+    let u32_at = |at: usize| data.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    if data.get(..8)? != BIF_MAGIC {
+        return None;
+    }
+    let count = u32_at(12)? as usize;
+    let multiplier = match u32_at(16)? {
+        0 => 1000,
+        m => m,
+    } as u64;
+    // The index needs 8 bytes per frame, which bounds a corrupt count
+    let mut frames = Vec::with_capacity(count.min(data.len().saturating_sub(64) / 8));
+    for i in 0..count {
+        let entry = 64 + i * 8;
+        let (time, start) = (u32_at(entry)?, u32_at(entry + 4)? as usize);
+        if time == BIF_END {
+            break;
+        }
+        let end = u32_at(entry + 12)? as usize;
+        frames.push((time as u64 * multiplier, data.get(start..end)?));
+    }
+    Some(frames)
+}
+
+/// Pack `frames` into sheets in `dir` and return the tile size. Frames are
+/// decoded one sheet at a time so a long BIF never sits decoded in memory.
+fn write_sheets(dir: &Path, frames: &[Vec<u8>]) -> anyhow::Result<(u32, u32)> {
+    let mut tile_height = None;
+    for (sheet_index, chunk) in frames.chunks((COLUMNS * ROWS) as usize).enumerate() {
+        let decoded: Vec<RgbImage> = chunk
+            .iter()
+            .map(|f| image::load_from_memory(f).map(|img| img.to_rgb8()))
+            .collect::<std::result::Result<_, _>>()?;
+        // Every tile takes the first frame's aspect ratio
+        let height = *tile_height.get_or_insert_with(|| {
+            decoded.first().map_or(1, |first| (TILE_WIDTH * first.height() / first.width().max(1)).max(1))
+        });
+        let rows = (decoded.len() as u32).div_ceil(COLUMNS);
+        let columns = (decoded.len() as u32).min(COLUMNS);
+        let mut sheet = RgbImage::new(columns * TILE_WIDTH, rows * height);
+        for (i, frame) in decoded.iter().enumerate() {
+            let tile = image::imageops::resize(frame, TILE_WIDTH, height, FilterType::Triangle);
+            let i = i as u32;
+            sheet.copy_from(&tile, (i % COLUMNS) * TILE_WIDTH, (i / COLUMNS) * height)?;
+        }
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY).encode_image(&sheet)?;
+        std::fs::write(dir.join(format!("{}.jpg", sheet_index)), jpeg)?;
+    }
+    let tile_height = tile_height.ok_or_else(|| anyhow::anyhow!("No frames to pack"))?;
+    Ok((TILE_WIDTH, tile_height))
+}
+
+async fn fetch_bytes(plex: &PlexClient, path: &str) -> Option<Vec<u8>> {
+    let resp = plex.get_image(path).ok()?.send().await.ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    resp.bytes().await.ok().map(|b| b.to_vec())
+}
+
+/// Frames from the BIF index, or failing that one per chapter image.
+async fn collect_frames(plex: &PlexClient, item: &serde_json::Value, part_id: i64) -> Option<(Source, Vec<Cue>, Vec<Vec<u8>>)> {
+    let duration = item["duration"].as_u64().unwrap_or(0);
+
+    if let Some(bif) = fetch_bytes(plex, &format!("/library/parts/{}/indexes/sd", part_id)).await
+        && let Some(frames) = parse_bif(&bif).filter(|f| !f.is_empty())
+    {
+        let cues = frames
+            .iter()
+            .enumerate()
+            .map(|(i, (start, _))| Cue {
+                start_ms: *start,
+                end_ms: frames.get(i + 1).map_or(duration.max(*start), |(next, _)| *next),
+            })
+            .collect();
+        let images = frames.into_iter().map(|(_, jpeg)| jpeg.to_vec()).collect();
+        return Some((Source::Bif, cues, images));
+    }
+
+    let chapters: Vec<&serde_json::Value> = item["Chapter"]
+        .as_array()
+        .map(|c| c.iter().filter(|c| c["thumb"].is_string()).collect())
+        .unwrap_or_default();
+    let images = join_all(chapters.iter().map(|chapter| {
+        let thumb = chapter["thumb"].as_str().unwrap_or_default();
+        let path = format!(
+            "/photo/:/transcode?url={}&width={}&minSize=1&upscale=1",
+            thumb.replace('%', "%25").replace('&', "%26").replace('?', "%3F").replace('=', "%3D"),
+            TILE_WIDTH
+        );
+        async move { fetch_bytes(plex, &path).await }
+    }))
+    .await;
+    let (cues, images): (Vec<Cue>, Vec<Vec<u8>>) = chapters
+        .iter()
+        .zip(images)
+        .filter_map(|(chapter, image)| {
+            let cue = Cue {
+                start_ms: chapter["startTimeOffset"].as_u64().unwrap_or(0),
+                end_ms: chapter["endTimeOffset"].as_u64().unwrap_or(duration),
+            };
+            Some((cue, image?))
+        })
+        .unzip();
+    (!images.is_empty()).then_some((Source::Chapters, cues, images))
+}
+
+/// The part's manifest, building and caching its sheets on first use.
+async fn ensure_sprites(plex: &PlexClient, id: &str) -> Result<(i64, Manifest)> {
+    let req = plex.get(&format!("/library/metadata/{}", id))?.query(&[("includeChapters", "1")]);
+    let body = plex.send_json(req).await?;
+    let item = &body["MediaContainer"]["Metadata"][0];
+    let part_id = item["Media"][0]["Part"][0]["id"]
+        .as_i64()
+        .ok_or_else(|| Error::NotFound(format!("No part found for media {}", id)))?;
+
+    // Plex lists `indexes="sd"` on the part once its BIF exists; sheets built
+    // from chapter images until then are rebuilt from it.
+    let has_index = item["Media"][0]["Part"][0]["indexes"].as_str() == Some("sd");
+    let dir = cache_root().join(part_id.to_string());
+    let cached: Option<Manifest> = std::fs::read(dir.join("manifest.json"))
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok());
+    let stale = cached.as_ref().is_some_and(|m| m.source == Source::Chapters && has_index);
+    if !stale && let Some(manifest) = cached {
+        return Ok((part_id, manifest));
+    }
+
+    let collected = collect_frames(plex, item, part_id).await;
+    // The index is listed but unreadable; the chapter sheets still stand
+    if let Some(manifest) = cached.filter(|_| collected.as_ref().is_none_or(|(source, ..)| *source == Source::Chapters)) {
+        return Ok((part_id, manifest));
+    }
+    let (source, cues, frames) = collected.ok_or_else(|| {
+        Error::NotFound(format!(
+            "Plex has no preview thumbnails or chapter images for media {}; enable \"Generate video preview thumbnails\" for its library",
+            id
+        ))
+    })?;
+
+    // Built beside the final directory and moved in, so a half-written cache
+    // is never served and concurrent builds don't collide.
+    let building = cache_root().join(format!("{}.{}", part_id, Uuid::new_v4()));
+    let target = dir.clone();
+    let manifest = web::block(move || -> anyhow::Result<Manifest> {
+        std::fs::create_dir_all(&building)?;
+        let built = write_sheets(&building, &frames).and_then(|(tile_width, tile_height)| {
+            let manifest = Manifest { source, tile_width, tile_height, cues };
+            std::fs::write(building.join("manifest.json"), serde_json::to_vec(&manifest)?)?;
+            Ok(manifest)
+        });
+        if built.is_ok() && target.exists() {
+            let _ = std::fs::remove_dir_all(&target);
+        }
+        if built.is_err() || std::fs::rename(&building, &target).is_err() {
+            let _ = std::fs::remove_dir_all(&building);
+        }
+        built
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Trickplay build was cancelled: {}", e))?
+    .inspect_err(|e| warn!("Failed to build trickplay sprites for part {}: {}", part_id, e))?;
+    info!("Built {} trickplay tiles for part {} from {:?}", manifest.cues.len(), part_id, source);
+    Ok((part_id, manifest))
+}
+
+/// WebVTT whose cues point at tiles in the sprite sheets.
+#[get("/{id}/trickplay.vtt")]
+async fn get_trickplay(
+    plex: web::Data<PlexClient>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let (part_id, manifest) = ensure_sprites(&plex, &path.into_inner()).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/vtt; charset=utf-8")
+        .append_header(("Cache-Control", "public, max-age=86400"))
+        .body(manifest.to_vtt(part_id)))
+}
+
+#[get("/trickplay/{part_id}/{sheet}.jpg")]
+async fn get_sprite_sheet(
+    req: HttpRequest,
+    path: web::Path<(i64, u32)>,
+) -> Result<HttpResponse> {
+    let (part_id, sheet) = path.into_inner();
+    let file = NamedFile::open(cache_root().join(part_id.to_string()).join(format!("{}.jpg", sheet)))
+        .map_err(|_| Error::NotFound(format!("No sprite sheet {} for part {}", sheet, part_id)))?
+        .set_content_type(actix_web::mime::IMAGE_JPEG);
+    let mut resp = file.into_response(&req);
+    resp.headers_mut().insert(
+        actix_web::http::header::CACHE_CONTROL,
+        actix_web::http::header::HeaderValue::from_static("public, max-age=86400"),
+    );
+    Ok(resp)
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_trickplay).service(get_sprite_sheet);
+}
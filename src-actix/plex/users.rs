@@ -3,6 +3,20 @@ use crate::config::SharedConfig;
 use crate::http_error::{self, Result};
 use super::client::PlexClient;
 
+/// Plex servers number the owner's account 1 in their history and session
+/// lists; everyone else keeps their plex.tv id.
+const OWNER_ACCOUNT_ID: i64 = 1;
+
+/// Playarr (plex.tv) user id for a server account id.
+pub fn user_id_for_account(account: i64, admin_user_id: i64) -> i64 {
+    if account == OWNER_ACCOUNT_ID { admin_user_id } else { account }
+}
+
+/// Server account id for a Playarr (plex.tv) user id.
+pub fn account_for_user_id(user_id: i64, admin_user_id: i64) -> i64 {
+    if user_id == admin_user_id { OWNER_ACCOUNT_ID } else { user_id }
+}
+
 #[get("")]
 async fn list_plex_users(
     req: HttpRequest,
@@ -1,7 +1,10 @@
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, web, HttpRequest, Responder};
 use serde::Deserialize;
+use crate::cache::{self, ResponseCache};
+use crate::config::models::RadarrConfig;
 use crate::http_error::Result;
-use crate::radarr::client::RadarrClient;
+use crate::radarr::client::{tag_instance, InstanceQuery, RadarrClient};
+use crate::radarr::models::Movie;
 
 #[derive(Deserialize)]
 struct CalendarQuery {
@@ -9,30 +12,50 @@ struct CalendarQuery {
     end: Option<String>,
 }
 
+/// Calendar of the selected instance, or of all instances merged when none is given.
 #[get("/calendar")]
 async fn calendar(
+    req: HttpRequest,
     radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
     query: web::Query<CalendarQuery>,
 ) -> Result<impl Responder> {
+    let targets = radarr.targets(inst.instance.as_deref())?;
     let now = chrono::Utc::now();
     let start = query.start.clone()
         .unwrap_or_else(|| now.format("%Y-%m-%d").to_string());
     let end = query.end.clone()
         .unwrap_or_else(|| (now + chrono::Duration::days(30)).format("%Y-%m-%d").to_string());
 
-    let resp = radarr
-        .get("/calendar")
-        .query(&[("start", start.as_str()), ("end", end.as_str())])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to fetch calendar: {}", e))?;
+    let labels: Vec<&str> = targets.iter().map(|i| i.label()).collect();
+    let key = ResponseCache::key(cache::RADARR, &format!("{}:/calendar?start={}&end={}", labels.join(","), start, end), &req);
+    let entry = cache.get_or_fetch(key, cache::RADARR, || async {
+        let results = radarr
+            .get_each::<Vec<Movie>>(&targets, "/calendar", &[("start", start.as_str()), ("end", end.as_str())])
+            .await?;
+        Ok(serde_json::Value::Array(merge(results)))
+    }).await?;
 
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse calendar: {}", e))?;
+    Ok(cache::respond(&req, &entry, cache::RADARR))
+}
 
-    Ok(HttpResponse::Ok().json(body))
+fn merge(results: Vec<(RadarrConfig, Vec<Movie>)>) -> Vec<serde_json::Value> {
+    let mut items: Vec<serde_json::Value> = results
+        .into_iter()
+        .flat_map(|(instance, body)| tag_instance(&body, &instance))
+        .collect();
+    // Interleave instances by the earliest upcoming release date
+    let date = |m: &serde_json::Value| {
+        ["inCinemas", "digitalRelease", "physicalRelease"]
+            .iter()
+            .filter_map(|f| m[*f].as_str())
+            .min()
+            .unwrap_or("")
+            .to_string()
+    };
+    items.sort_by_key(date);
+    items
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
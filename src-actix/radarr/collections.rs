@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use serde_json::json;
+use crate::auth::require_admin;
+use crate::cache::{self, ResponseCache};
+use crate::http_error::Result;
+use crate::radarr::client::{InstanceQuery, RadarrClient};
+use crate::radarr::models::{AddCollectionRequest, AddMovieRequest, Collection, Movie};
+
+#[get("/collection")]
+async fn list_collections(
+    req: HttpRequest,
+    radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+) -> Result<impl Responder> {
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    let key = ResponseCache::key(cache::RADARR, &format!("{}:/collection", instance.label()), &req);
+    let entry = cache.get_or_fetch(key, cache::RADARR, || async {
+        let collections: Vec<Collection> = radarr
+            .send_json(radarr.get(&instance, "/collection"), "collections")
+            .await?;
+        Ok(serde_json::to_value(collections).map_err(anyhow::Error::from)?)
+    }).await?;
+
+    Ok(cache::respond(&req, &entry, cache::RADARR))
+}
+
+#[get("/collection/{id}")]
+async fn get_collection(
+    radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    path: web::Path<u64>,
+) -> Result<impl Responder> {
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    let collection: Collection = radarr
+        .send_json(radarr.get(&instance, &format!("/collection/{}", path.into_inner())), "collection")
+        .await?;
+
+    Ok(HttpResponse::Ok().json(collection))
+}
+
+/// Add every movie of the collection that isn't in the library yet, in one
+/// bulk import. Movies already present are skipped.
+#[post("/collection/{id}/add")]
+async fn add_collection(
+    req: HttpRequest,
+    radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<u64>,
+    body: web::Json<AddCollectionRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &radarr.config)?;
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    let id = path.into_inner();
+    let request = body.into_inner();
+    request.validate()?;
+
+    let raw: serde_json::Value = radarr
+        .send_json(radarr.get(&instance, &format!("/collection/{}", id)), "collection")
+        .await?;
+    let collection: Collection = serde_json::from_value(raw.clone())
+        .map_err(|e| anyhow::anyhow!("Failed to parse collection: {}", e))?;
+    let library: Vec<Movie> = radarr.send_json(radarr.get(&instance, "/movie"), "movies").await?;
+    let owned: HashSet<u64> = library.iter().map(|m| m.tmdb_id).collect();
+
+    let missing: Vec<AddMovieRequest> = collection
+        .movies
+        .iter()
+        .filter(|m| m.tmdb_id > 0 && !owned.contains(&m.tmdb_id))
+        .map(|m| request.movie(m))
+        .collect();
+    let skipped = collection.movies.len() - missing.len();
+
+    let added: Vec<Movie> = if missing.is_empty() {
+        Vec::new()
+    } else {
+        radarr
+            .send_json(radarr.post(&instance, "/movie/import").json(&missing), "collection movies")
+            .await?
+    };
+
+    if request.monitor_collection {
+        let mut updated = raw;
+        if let Some(obj) = updated.as_object_mut() {
+            obj.insert("monitored".to_string(), json!(true));
+            obj.insert("qualityProfileId".to_string(), json!(request.quality_profile_id));
+            obj.insert("rootFolderPath".to_string(), json!(request.root_folder_path));
+            obj.insert("minimumAvailability".to_string(), json!(request.minimum_availability));
+            obj.insert("searchOnAdd".to_string(), json!(request.search_for_movie));
+        }
+        radarr
+            .send(radarr.put(&instance, &format!("/collection/{}", id)).json(&updated), "collection")
+            .await?;
+    }
+
+    cache.invalidate_prefix(cache::RADARR.name);
+    Ok(HttpResponse::Ok().json(json!({
+        "added": added,
+        "skipped": skipped,
+    })))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_collections)
+        .service(get_collection)
+        .service(add_collection);
+}
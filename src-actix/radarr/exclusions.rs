@@ -0,0 +1,64 @@
+//! Import list exclusions: movies Radarr's lists must never add again.
+
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use crate::auth::require_admin;
+use crate::http_error::Result;
+use crate::radarr::client::{InstanceQuery, RadarrClient};
+use crate::radarr::models::ImportExclusion;
+
+#[get("/exclusions")]
+async fn list_exclusions(
+    req: HttpRequest,
+    radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+) -> Result<impl Responder> {
+    require_admin(&req, &radarr.config)?;
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    let exclusions: Vec<ImportExclusion> = radarr
+        .send_json(radarr.get(&instance, "/exclusions"), "exclusions")
+        .await?;
+
+    Ok(HttpResponse::Ok().json(exclusions))
+}
+
+#[post("/exclusions")]
+async fn add_exclusion(
+    req: HttpRequest,
+    radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    body: web::Json<ImportExclusion>,
+) -> Result<impl Responder> {
+    require_admin(&req, &radarr.config)?;
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    let mut exclusion = body.into_inner();
+    exclusion.validate()?;
+    exclusion.id = None;
+
+    let created: ImportExclusion = radarr
+        .send_json(radarr.post(&instance, "/exclusions").json(&exclusion), "exclusion")
+        .await?;
+
+    Ok(HttpResponse::Ok().json(created))
+}
+
+#[delete("/exclusions/{id}")]
+async fn delete_exclusion(
+    req: HttpRequest,
+    radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    path: web::Path<u64>,
+) -> Result<impl Responder> {
+    require_admin(&req, &radarr.config)?;
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    radarr
+        .send(radarr.delete(&instance, &format!("/exclusions/{}", path.into_inner())), "exclusion")
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_exclusions)
+        .service(add_exclusion)
+        .service(delete_exclusion);
+}
@@ -0,0 +1,53 @@
+use actix_web::{delete, get, web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use crate::arr::models::require_id;
+use crate::auth::require_admin;
+use crate::cache::{self, ResponseCache};
+use crate::http_error::Result;
+use crate::radarr::client::{InstanceQuery, RadarrClient};
+use crate::radarr::models::MovieFile;
+
+#[derive(Deserialize)]
+struct MovieFilesQuery {
+    #[serde(rename = "movieId", default)]
+    movie_id: u64,
+}
+
+#[get("/moviefile")]
+async fn list_movie_files(
+    req: HttpRequest,
+    radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    query: web::Query<MovieFilesQuery>,
+) -> Result<impl Responder> {
+    require_admin(&req, &radarr.config)?;
+    require_id("movieId", query.movie_id)?;
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    let files: Vec<MovieFile> = radarr
+        .send_json(radarr.get(&instance, "/moviefile").query(&[("movieId", query.movie_id)]), "movie files")
+        .await?;
+
+    Ok(HttpResponse::Ok().json(files))
+}
+
+#[delete("/moviefile/{id}")]
+async fn delete_movie_file(
+    req: HttpRequest,
+    radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<u64>,
+) -> Result<impl Responder> {
+    require_admin(&req, &radarr.config)?;
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    radarr
+        .send(radarr.delete(&instance, &format!("/moviefile/{}", path.into_inner())), "movie file")
+        .await?;
+
+    cache.invalidate_prefix(cache::RADARR.name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_movie_files).service(delete_movie_file);
+}
@@ -1,7 +1,8 @@
 pub mod client;
+pub mod models;
 pub mod movies;
+pub mod files;
+pub mod collections;
+pub mod exclusions;
 pub mod calendar;
-pub mod queue;
-pub mod profiles;
-pub mod commands;
 pub mod releases;
@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+
+use crate::arr::models::{require_absolute_path, require_id, require_text, Image, Language, QualityModel};
+use crate::http_error::{Error, Result};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Movie {
+    /// Absent for lookup results that are not added yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub title: String,
+    pub original_title: String,
+    pub sort_title: String,
+    pub title_slug: String,
+    pub tmdb_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imdb_id: Option<String>,
+    pub year: u32,
+    pub overview: String,
+    pub status: String,
+    pub studio: String,
+    pub runtime: u32,
+    pub certification: String,
+    pub genres: Vec<String>,
+    pub monitored: bool,
+    pub has_file: bool,
+    pub is_available: bool,
+    pub quality_profile_id: u64,
+    pub root_folder_path: String,
+    pub path: String,
+    pub added: String,
+    pub minimum_availability: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_cinemas: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digital_release: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub physical_release: Option<String>,
+    pub images: Vec<Image>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_poster: Option<String>,
+    pub size_on_disk: u64,
+    pub tags: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MinimumAvailability {
+    Tba,
+    Announced,
+    InCinemas,
+    #[default]
+    Released,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MovieMonitor {
+    #[default]
+    MovieOnly,
+    MovieAndCollection,
+    None,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AddMovieOptions {
+    pub search_for_movie: bool,
+    pub monitor: MovieMonitor,
+}
+
+/// Body of `POST /movie`. Fields copied from a lookup result are forwarded as-is.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddMovieRequest {
+    #[serde(default)]
+    pub tmdb_id: u64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub quality_profile_id: u64,
+    #[serde(default)]
+    pub root_folder_path: String,
+    #[serde(default = "default_true")]
+    pub monitored: bool,
+    #[serde(default)]
+    pub minimum_availability: MinimumAvailability,
+    #[serde(default)]
+    pub tags: Vec<u64>,
+    #[serde(default)]
+    pub add_options: AddMovieOptions,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl AddMovieRequest {
+    pub fn validate(&self) -> Result<()> {
+        require_id("tmdbId", self.tmdb_id)?;
+        require_text("title", &self.title)?;
+        require_id("qualityProfileId", self.quality_profile_id)?;
+        require_absolute_path("rootFolderPath", &self.root_folder_path)
+    }
+}
+
+/// Body of `PUT /movie/{id}`. Only the given fields change; everything else
+/// is kept from the current movie.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MovieUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitored: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality_profile_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum_availability: Option<MinimumAvailability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root_folder_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<u64>>,
+}
+
+impl MovieUpdate {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(id) = self.quality_profile_id {
+            require_id("qualityProfileId", id)?;
+        }
+        if let Some(path) = &self.path {
+            require_absolute_path("path", path)?;
+        }
+        if let Some(path) = &self.root_folder_path {
+            require_absolute_path("rootFolderPath", path)?;
+        }
+        Ok(())
+    }
+
+    /// Overlay these fields on the movie as returned by Radarr.
+    pub fn apply(&self, current: &mut serde_json::Value) -> Result<()> {
+        let updates = serde_json::to_value(self).map_err(anyhow::Error::from)?;
+        let (Some(target), Some(updates)) = (current.as_object_mut(), updates.as_object()) else {
+            return Err(Error::UpstreamError("Radarr returned an invalid movie".to_string()));
+        };
+        for (key, value) in updates {
+            target.insert(key.clone(), value.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Query of `DELETE /movie/{id}`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DeleteMovieQuery {
+    pub delete_files: bool,
+    pub add_import_exclusion: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MovieFile {
+    pub id: u64,
+    pub movie_id: u64,
+    pub relative_path: String,
+    pub path: String,
+    pub size: u64,
+    pub date_added: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edition: Option<String>,
+    pub quality: QualityModel,
+    pub languages: Vec<Language>,
+    pub quality_cutoff_not_met: bool,
+}
+
+/// A movie inside a collection, whether or not it is in the library.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CollectionMovie {
+    pub tmdb_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imdb_id: Option<String>,
+    pub title: String,
+    pub clean_title: String,
+    pub year: u32,
+    pub overview: String,
+    pub runtime: u32,
+    pub images: Vec<Image>,
+    pub genres: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Collection {
+    pub id: u64,
+    pub title: String,
+    pub tmdb_id: u64,
+    pub overview: String,
+    pub monitored: bool,
+    pub quality_profile_id: u64,
+    pub root_folder_path: String,
+    pub minimum_availability: String,
+    pub search_on_add: bool,
+    pub images: Vec<Image>,
+    pub movies: Vec<CollectionMovie>,
+    pub missing_movies: u64,
+}
+
+/// Body of `POST /collection/{id}/add`: add every collection movie that is
+/// not in the library yet.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddCollectionRequest {
+    #[serde(default)]
+    pub quality_profile_id: u64,
+    #[serde(default)]
+    pub root_folder_path: String,
+    #[serde(default)]
+    pub minimum_availability: MinimumAvailability,
+    #[serde(default = "default_true")]
+    pub monitored: bool,
+    #[serde(default)]
+    pub search_for_movie: bool,
+    /// Also monitor the collection so future movies are added automatically.
+    #[serde(default)]
+    pub monitor_collection: bool,
+    #[serde(default)]
+    pub tags: Vec<u64>,
+}
+
+impl AddCollectionRequest {
+    pub fn validate(&self) -> Result<()> {
+        require_id("qualityProfileId", self.quality_profile_id)?;
+        require_absolute_path("rootFolderPath", &self.root_folder_path)
+    }
+
+    pub fn movie(&self, movie: &CollectionMovie) -> AddMovieRequest {
+        AddMovieRequest {
+            tmdb_id: movie.tmdb_id,
+            title: movie.title.clone(),
+            quality_profile_id: self.quality_profile_id,
+            root_folder_path: self.root_folder_path.clone(),
+            monitored: self.monitored,
+            minimum_availability: self.minimum_availability,
+            tags: self.tags.clone(),
+            add_options: AddMovieOptions {
+                search_for_movie: self.search_for_movie,
+                monitor: MovieMonitor::MovieOnly,
+            },
+            extra: serde_json::Map::from_iter([
+                ("year".to_string(), serde_json::json!(movie.year)),
+                ("images".to_string(), serde_json::to_value(&movie.images).unwrap_or_default()),
+            ]),
+        }
+    }
+}
+
+/// A movie Radarr's import lists will never add again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ImportExclusion {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub tmdb_id: u64,
+    pub movie_title: String,
+    pub movie_year: u32,
+}
+
+impl ImportExclusion {
+    pub fn validate(&self) -> Result<()> {
+        require_id("tmdbId", self.tmdb_id)?;
+        require_text("movieTitle", &self.movie_title)
+    }
+}
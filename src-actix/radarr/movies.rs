@@ -1,121 +1,181 @@
-use actix_web::{get, post, put, web, HttpResponse, Responder};
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
 use serde::Deserialize;
+use crate::arr::models::FieldsQuery;
+use crate::auth::require_admin;
+use crate::cache::{self, ResponseCache};
 use crate::http_error::Result;
-use crate::radarr::client::RadarrClient;
+use crate::radarr::client::{tag_instance, InstanceQuery, RadarrClient};
+use crate::radarr::models::{AddMovieRequest, DeleteMovieQuery, Movie, MovieUpdate};
 
 #[derive(Deserialize)]
 struct LookupQuery {
     term: String,
 }
 
+/// Search the selected instance, or every instance with duplicates merged.
+/// Each result lists the instances it was found on under `instances`; an
+/// `id` from an instance means it is already added there.
 #[get("/lookup")]
 async fn lookup(
     radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
     query: web::Query<LookupQuery>,
 ) -> Result<impl Responder> {
-    let resp = radarr
-        .get("/movie/lookup")
-        .query(&[("term", query.term.as_str())])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to lookup movie: {}", e))?;
-
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse lookup: {}", e))?;
-
-    Ok(HttpResponse::Ok().json(body))
+    let targets = radarr.targets(inst.instance.as_deref())?;
+    let results = radarr
+        .get_each::<Vec<Movie>>(&targets, "/movie/lookup", &[("term", query.term.as_str())])
+        .await?;
+
+    let mut merged: Vec<serde_json::Value> = Vec::new();
+    for (instance, movies) in results {
+        for item in tag_instance(&movies, &instance) {
+            let existing = merged
+                .iter_mut()
+                .find(|m| item["tmdbId"].as_u64().is_some_and(|id| id > 0 && m["tmdbId"].as_u64() == Some(id)));
+            match existing {
+                Some(m) => {
+                    if let Some(list) = m["instances"].as_array_mut() {
+                        list.push(serde_json::json!(instance.label()));
+                    }
+                    // Prefer the copy from an instance that already has it
+                    if m["id"].as_u64().unwrap_or(0) == 0 && item["id"].as_u64().unwrap_or(0) > 0 {
+                        let instances = m["instances"].take();
+                        *m = item;
+                        m["instances"] = instances;
+                    }
+                }
+                None => {
+                    let mut item = item;
+                    item["instances"] = serde_json::json!([instance.label()]);
+                    merged.push(item);
+                }
+            }
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(merged))
 }
 
+/// All movies. `?fields=id,title,tmdbId` trims each entry to those fields.
 #[get("/movie")]
 async fn list_movies(
+    req: HttpRequest,
     radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    fields: web::Query<FieldsQuery>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<impl Responder> {
-    let resp = radarr
-        .get("/movie")
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to fetch movies: {}", e))?;
-
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse movies: {}", e))?;
-
-    Ok(HttpResponse::Ok().json(body))
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    let key = ResponseCache::key(cache::RADARR, &format!("{}:/movie", instance.label()), &req);
+    let entry = cache.get_or_fetch(key, cache::RADARR, || async {
+        let movies: Vec<Movie> = radarr.send_json(radarr.get(&instance, "/movie"), "movies").await?;
+        Ok(serde_json::to_value(movies).map_err(anyhow::Error::from)?)
+    }).await?;
+
+    if fields.fields.is_some() {
+        return Ok(HttpResponse::Ok().json(fields.apply(entry.body)));
+    }
+    Ok(cache::respond(&req, &entry, cache::RADARR))
 }
 
 #[get("/movie/{id}")]
 async fn get_movie(
+    req: HttpRequest,
     radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    fields: web::Query<FieldsQuery>,
+    cache: web::Data<ResponseCache>,
     path: web::Path<u64>,
 ) -> Result<impl Responder> {
+    let instance = radarr.instance(inst.instance.as_deref())?;
     let id = path.into_inner();
-    let resp = radarr
-        .get(&format!("/movie/{}", id))
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to fetch movie: {}", e))?;
-
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse movie: {}", e))?;
-
-    Ok(HttpResponse::Ok().json(body))
+    let key = ResponseCache::key(cache::RADARR, &format!("{}:/movie/{}", instance.label(), id), &req);
+    let entry = cache.get_or_fetch(key, cache::RADARR, || async {
+        let movie: Movie = radarr
+            .send_json(radarr.get(&instance, &format!("/movie/{}", id)), "movie")
+            .await?;
+        Ok(serde_json::to_value(movie).map_err(anyhow::Error::from)?)
+    }).await?;
+
+    if fields.fields.is_some() {
+        return Ok(HttpResponse::Ok().json(fields.apply(entry.body)));
+    }
+    Ok(cache::respond(&req, &entry, cache::RADARR))
 }
 
 #[post("/movie")]
 async fn add_movie(
     radarr: web::Data<RadarrClient>,
-    body: web::Json<serde_json::Value>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+    body: web::Json<AddMovieRequest>,
 ) -> Result<impl Responder> {
-    let resp = radarr
-        .post("/movie")
-        .json(&body.into_inner())
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to add movie: {}", e))?;
-
-    let status = resp.status();
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse add response: {}", e))?;
-
-    if status.is_success() {
-        Ok(HttpResponse::Ok().json(body))
-    } else {
-        Ok(HttpResponse::BadRequest().json(body))
-    }
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    let request = body.into_inner();
+    request.validate()?;
+
+    let added: Movie = radarr
+        .send_json(radarr.post(&instance, "/movie").json(&request), "movie")
+        .await?;
+
+    cache.invalidate_prefix(cache::RADARR.name);
+    Ok(HttpResponse::Ok().json(added))
 }
 
+/// Partial update: fetch the movie, overlay the given fields and save it back.
 #[put("/movie/{id}")]
 async fn update_movie(
     radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
     path: web::Path<u64>,
-    body: web::Json<serde_json::Value>,
+    body: web::Json<MovieUpdate>,
 ) -> Result<impl Responder> {
+    let instance = radarr.instance(inst.instance.as_deref())?;
     let id = path.into_inner();
-    let resp = radarr
-        .put(&format!("/movie/{}", id))
-        .json(&body.into_inner())
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to update movie: {}", e))?;
-
-    let status = resp.status();
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse update response: {}", e))?;
-
-    if status.is_success() {
-        Ok(HttpResponse::Ok().json(body))
-    } else {
-        Ok(HttpResponse::BadRequest().json(body))
-    }
+    let update = body.into_inner();
+    update.validate()?;
+
+    let mut current: serde_json::Value = radarr
+        .send_json(radarr.get(&instance, &format!("/movie/{}", id)), "movie")
+        .await?;
+    update.apply(&mut current)?;
+
+    let updated: Movie = radarr
+        .send_json(radarr.put(&instance, &format!("/movie/{}", id)).json(&current), "movie")
+        .await?;
+
+    cache.invalidate_prefix(cache::RADARR.name);
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+/// Remove a movie. `?deleteFiles=true` also deletes it from disk and
+/// `?addImportExclusion=true` keeps import lists from adding it back.
+#[delete("/movie/{id}")]
+async fn delete_movie(
+    req: HttpRequest,
+    radarr: web::Data<RadarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<u64>,
+    query: web::Query<DeleteMovieQuery>,
+) -> Result<impl Responder> {
+    require_admin(&req, &radarr.config)?;
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    let id = path.into_inner();
+
+    radarr
+        .send(
+            radarr.delete(&instance, &format!("/movie/{}", id)).query(&[
+                ("deleteFiles", query.delete_files),
+                ("addImportExclusion", query.add_import_exclusion),
+            ]),
+            "movie",
+        )
+        .await?;
+
+    cache.invalidate_prefix(cache::RADARR.name);
+    Ok(HttpResponse::NoContent().finish())
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
@@ -123,5 +183,6 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .service(list_movies)
         .service(get_movie)
         .service(add_movie)
-        .service(update_movie);
+        .service(update_movie)
+        .service(delete_movie);
 }
@@ -1,63 +1,49 @@
-use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use crate::arr::models::{require_id, Release};
+use crate::cache::{self, ResponseCache};
+use crate::arr::releases::ReleaseFilter;
 use crate::http_error::Result;
-use crate::radarr::client::RadarrClient;
+use crate::radarr::client::{InstanceQuery, RadarrClient};
 
 #[derive(serde::Deserialize)]
 struct ReleaseQuery {
-    #[serde(rename = "movieId")]
-    movie_id: Option<u64>,
+    #[serde(rename = "movieId", default)]
+    movie_id: u64,
 }
 
+/// One page of a manual search. The search itself is cached briefly so
+/// paging, sorting and filtering don't hit the indexers again.
 #[get("/release")]
 async fn get_releases(
+    req: HttpRequest,
     radarr: web::Data<RadarrClient>,
+    cache: web::Data<ResponseCache>,
+    inst: web::Query<InstanceQuery>,
     query: web::Query<ReleaseQuery>,
+    filter: web::Query<ReleaseFilter>,
 ) -> Result<impl Responder> {
-    let mut params: Vec<(&str, String)> = Vec::new();
-    if let Some(id) = query.movie_id {
-        params.push(("movieId", id.to_string()));
-    }
-
-    let resp = radarr
-        .get("/release")
-        .query(&params)
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to fetch releases: {}", e))?;
-
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse releases: {}", e))?;
-
-    Ok(HttpResponse::Ok().json(body))
-}
-
-#[post("/release")]
-async fn grab_release(
-    radarr: web::Data<RadarrClient>,
-    body: web::Json<serde_json::Value>,
-) -> Result<impl Responder> {
-    let resp = radarr
-        .post("/release")
-        .json(&body.into_inner())
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to grab release: {}", e))?;
-
-    let status = resp.status();
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse grab response: {}", e))?;
-
-    if status.is_success() {
-        Ok(HttpResponse::Ok().json(body))
-    } else {
-        Ok(HttpResponse::BadRequest().json(body))
-    }
+    filter.validate()?;
+    require_id("movieId", query.movie_id)?;
+    let instance = radarr.instance(inst.instance.as_deref())?;
+    let key = ResponseCache::key(
+        cache::RADARR_RELEASES,
+        &format!("{}:/release?movieId={}", instance.label(), query.movie_id),
+        &req,
+    );
+    let entry = cache.get_or_fetch(key, cache::RADARR_RELEASES, || async {
+        let releases: Vec<Release> = radarr
+            .send_json(
+                radarr.get(&instance, "/release").query(&[("movieId", query.movie_id.to_string())]),
+                "releases",
+            )
+            .await?;
+        Ok(serde_json::to_value(releases).map_err(anyhow::Error::from)?)
+    }).await?;
+    let releases: Vec<Release> = serde_json::from_value(entry.body).map_err(anyhow::Error::from)?;
+
+    Ok(HttpResponse::Ok().json(filter.apply(releases)))
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_releases).service(grab_release);
+    cfg.service(get_releases);
 }
@@ -0,0 +1,399 @@
+//! `/api/search/all`: one query fanned out to Plex, TMDB, Sonarr and Radarr.
+//! Results describing the same title are merged by TMDB, TVDB and IMDb ids,
+//! tagged with where the title stands (in Plex, tracked by an *arr, or
+//! neither) and ranked by how well the title matches the query.
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::discover::client::TmdbClient;
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+use crate::plex::guid::{guid_id, imdb_id, item_guids};
+use crate::radarr::client::RadarrClient;
+use crate::radarr::models::Movie;
+use crate::sonarr::client::SonarrClient;
+use crate::sonarr::models::Series;
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 50;
+/// TMDB posters go through the image proxy; TMDB file paths need no escaping.
+const TMDB_POSTER_BASE: &str = "/api/images?source=tmdb&width=342&path=";
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Movie,
+    Show,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Availability {
+    /// Unknown to the *arrs, or added but unmonitored.
+    NotTracked,
+    /// Monitored by Sonarr/Radarr but not (yet) in Plex.
+    Monitored,
+    InLibrary,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSource {
+    Plex,
+    Tmdb,
+    Sonarr,
+    Radarr,
+}
+
+/// Where an *arr already has the title.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArrEntry {
+    pub instance: String,
+    pub id: u64,
+    pub monitored: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub kind: SearchKind,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overview: Option<String>,
+    /// Remote poster URL (TMDB or the *arr's metadata source).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poster: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tmdb_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tvdb_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imdb_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plex_rating_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plex_thumb: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub arr: Vec<ArrEntry>,
+    pub availability: Availability,
+    pub sources: Vec<SearchSource>,
+    pub score: f64,
+    #[serde(skip)]
+    popularity: f64,
+}
+
+impl SearchResult {
+    fn new(kind: SearchKind, title: &str, source: SearchSource) -> Self {
+        Self {
+            kind,
+            title: title.to_string(),
+            year: None,
+            overview: None,
+            poster: None,
+            tmdb_id: None,
+            tvdb_id: None,
+            imdb_id: None,
+            plex_rating_key: None,
+            plex_thumb: None,
+            arr: Vec::new(),
+            availability: Availability::NotTracked,
+            sources: vec![source],
+            score: 0.0,
+            popularity: 0.0,
+        }
+    }
+
+    fn same_title(&self, other: &SearchResult) -> bool {
+        self.kind == other.kind
+            && ((self.tmdb_id.is_some() && self.tmdb_id == other.tmdb_id)
+                || (self.tvdb_id.is_some() && self.tvdb_id == other.tvdb_id)
+                || (self.imdb_id.is_some() && self.imdb_id == other.imdb_id))
+    }
+
+    /// Fill gaps from another result for the same title. Earlier sources win.
+    fn merge(&mut self, other: SearchResult) {
+        self.year = self.year.or(other.year);
+        self.overview = self.overview.take().or(other.overview);
+        self.poster = self.poster.take().or(other.poster);
+        self.tmdb_id = self.tmdb_id.or(other.tmdb_id);
+        self.tvdb_id = self.tvdb_id.or(other.tvdb_id);
+        self.imdb_id = self.imdb_id.take().or(other.imdb_id);
+        self.plex_rating_key = self.plex_rating_key.take().or(other.plex_rating_key);
+        self.plex_thumb = self.plex_thumb.take().or(other.plex_thumb);
+        self.arr.extend(other.arr);
+        self.availability = self.availability.max(other.availability);
+        self.popularity = self.popularity.max(other.popularity);
+        for source in other.sources {
+            if !self.sources.contains(&source) {
+                self.sources.push(source);
+            }
+        }
+    }
+}
+
+fn from_plex(item: &Value) -> Option<SearchResult> {
+    let kind = match item["type"].as_str()? {
+        "movie" => SearchKind::Movie,
+        "show" => SearchKind::Show,
+        _ => return None,
+    };
+    let mut result = SearchResult::new(kind, item["title"].as_str()?, SearchSource::Plex);
+    let guids = item_guids(item);
+    result.tmdb_id = guids.iter().find_map(|g| guid_id(g, "tmdb"));
+    result.tvdb_id = guids.iter().find_map(|g| guid_id(g, "tvdb"));
+    result.imdb_id = guids.iter().find_map(|g| imdb_id(g));
+    result.year = item["year"].as_u64().map(|y| y as u32);
+    result.overview = item["summary"].as_str().filter(|s| !s.is_empty()).map(str::to_string);
+    result.plex_rating_key = item["ratingKey"].as_str().map(str::to_string);
+    result.plex_thumb = item["thumb"].as_str().map(str::to_string);
+    result.availability = Availability::InLibrary;
+    Some(result)
+}
+
+fn from_tmdb(item: &Value) -> Option<SearchResult> {
+    let (kind, title, date) = match item["media_type"].as_str()? {
+        "movie" => (SearchKind::Movie, item["title"].as_str()?, item["release_date"].as_str()),
+        "tv" => (SearchKind::Show, item["name"].as_str()?, item["first_air_date"].as_str()),
+        _ => return None,
+    };
+    let mut result = SearchResult::new(kind, title, SearchSource::Tmdb);
+    result.tmdb_id = item["id"].as_u64();
+    result.year = date.and_then(|d| d.get(..4)).and_then(|y| y.parse().ok());
+    result.overview = item["overview"].as_str().filter(|s| !s.is_empty()).map(str::to_string);
+    result.poster = item["poster_path"].as_str().map(|p| format!("{}{}", TMDB_POSTER_BASE, p));
+    result.popularity = item["popularity"].as_f64().unwrap_or(0.0);
+    Some(result)
+}
+
+fn remote_poster(images: &[crate::arr::models::Image], remote: &Option<String>) -> Option<String> {
+    remote.clone().or_else(|| {
+        images
+            .iter()
+            .find(|i| i.cover_type == "poster")
+            .and_then(|i| i.remote_url.clone())
+    })
+}
+
+fn from_series(series: &Series, instance: &str) -> SearchResult {
+    let mut result = SearchResult::new(SearchKind::Show, &series.title, SearchSource::Sonarr);
+    result.tvdb_id = Some(series.tvdb_id).filter(|id| *id > 0);
+    result.tmdb_id = Some(series.tmdb_id).filter(|id| *id > 0);
+    result.imdb_id = series.imdb_id.clone().filter(|id| !id.is_empty());
+    result.year = Some(series.year).filter(|y| *y > 0);
+    result.overview = Some(series.overview.clone()).filter(|s| !s.is_empty());
+    result.poster = remote_poster(&series.images, &series.remote_poster);
+    if let Some(id) = series.id.filter(|id| *id > 0) {
+        result.arr.push(ArrEntry { instance: instance.to_string(), id, monitored: series.monitored });
+        if series.monitored {
+            result.availability = Availability::Monitored;
+        }
+    }
+    result
+}
+
+fn from_movie(movie: &Movie, instance: &str) -> SearchResult {
+    let mut result = SearchResult::new(SearchKind::Movie, &movie.title, SearchSource::Radarr);
+    result.tmdb_id = Some(movie.tmdb_id).filter(|id| *id > 0);
+    result.imdb_id = movie.imdb_id.clone().filter(|id| !id.is_empty());
+    result.year = Some(movie.year).filter(|y| *y > 0);
+    result.overview = Some(movie.overview.clone()).filter(|s| !s.is_empty());
+    result.poster = remote_poster(&movie.images, &movie.remote_poster);
+    if let Some(id) = movie.id.filter(|id| *id > 0) {
+        result.arr.push(ArrEntry { instance: instance.to_string(), id, monitored: movie.monitored });
+        if movie.monitored {
+            result.availability = Availability::Monitored;
+        }
+    }
+    result
+}
+
+fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 0..1 for how well `title` matches `query`: exact, prefix, substring,
+/// then the share of query words present.
+fn title_score(title: &str, query: &str) -> f64 {
+    let (title, query) = (normalize(title), normalize(query));
+    if query.is_empty() {
+        return 0.0;
+    }
+    if title == query {
+        return 1.0;
+    }
+    if title.starts_with(&query) {
+        return 0.8;
+    }
+    if title.contains(&query) {
+        return 0.6;
+    }
+    let words: Vec<&str> = query.split(' ').collect();
+    let found = words.iter().filter(|w| title.split(' ').any(|t| t == **w)).count();
+    0.5 * found as f64 / words.len() as f64
+}
+
+fn rank(result: &mut SearchResult, query: &str) {
+    let status = match result.availability {
+        Availability::InLibrary => 0.3,
+        Availability::Monitored => 0.15,
+        Availability::NotTracked => 0.0,
+    };
+    // Breaks ties between equally named titles ("Dune" 1984 vs 2021)
+    let popularity = ((1.0 + result.popularity).ln() / 25.0).min(0.2);
+    result.score = ((title_score(&result.title, query) + status + popularity) * 1000.0).round() / 1000.0;
+}
+
+/// Merge `incoming` into `results`, folding duplicates together. An item
+/// can link results that share no id with each other (a Plex copy known by
+/// TMDB id, another by IMDb id), so every match folds into the first.
+fn add_all(results: &mut Vec<SearchResult>, incoming: impl IntoIterator<Item = SearchResult>) {
+    for item in incoming {
+        let matches: Vec<usize> = (0..results.len()).filter(|&i| results[i].same_title(&item)).collect();
+        let Some((&first, rest)) = matches.split_first() else {
+            results.push(item);
+            continue;
+        };
+        let mut dupes: Vec<SearchResult> = rest.iter().rev().map(|&i| results.remove(i)).collect();
+        dupes.reverse();
+        let existing = &mut results[first];
+        for dupe in dupes {
+            existing.merge(dupe);
+        }
+        existing.merge(item);
+    }
+}
+
+async fn search_plex(plex: &PlexClient, query: &str) -> Result<Vec<SearchResult>> {
+    let req = plex.get("/hubs/search")?.query(&[
+        ("query", query),
+        ("limit", "20"),
+        ("includeGuids", "1"),
+    ]);
+    let body = plex.send_json(req).await?;
+    Ok(body["MediaContainer"]["Hub"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|hub| hub["Metadata"].as_array().into_iter().flatten())
+        .filter_map(from_plex)
+        .collect())
+}
+
+async fn search_tmdb(tmdb: &TmdbClient, query: &str) -> Result<Vec<SearchResult>> {
+    tmdb.ensure_configured()?;
+    let resp = tmdb
+        .get("/search/multi")?
+        .query(&[("query", query), ("include_adult", "false")])
+        .send()
+        .await
+        .map_err(|e| Error::UpstreamError(format!("TMDB request failed: {}", e)))?;
+    if !resp.status().is_success() {
+        return Err(Error::UpstreamError(format!("TMDB search returned HTTP {}", resp.status().as_u16())));
+    }
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+    Ok(body["results"].as_array().into_iter().flatten().filter_map(from_tmdb).collect())
+}
+
+async fn search_sonarr(sonarr: &SonarrClient, query: &str) -> Result<Vec<SearchResult>> {
+    let targets = sonarr.targets(None)?;
+    let results = sonarr
+        .get_each::<Vec<Series>>(&targets, "/series/lookup", &[("term", query)])
+        .await?;
+    Ok(results
+        .iter()
+        .flat_map(|(instance, series)| series.iter().map(|s| from_series(s, instance.label())))
+        .collect())
+}
+
+async fn search_radarr(radarr: &RadarrClient, query: &str) -> Result<Vec<SearchResult>> {
+    let targets = radarr.targets(None)?;
+    let results = radarr
+        .get_each::<Vec<Movie>>(&targets, "/movie/lookup", &[("term", query)])
+        .await?;
+    Ok(results
+        .iter()
+        .flat_map(|(instance, movies)| movies.iter().map(|m| from_movie(m, instance.label())))
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct SearchAllQuery {
+    q: String,
+    limit: Option<usize>,
+}
+
+/// Search everything at once. A source that is unconfigured or failing is
+/// left out and listed under `skipped` rather than failing the search.
+#[get("/search/all")]
+async fn search_all(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    tmdb: web::Data<TmdbClient>,
+    sonarr: web::Data<SonarrClient>,
+    radarr: web::Data<RadarrClient>,
+    query: web::Query<SearchAllQuery>,
+) -> Result<impl Responder> {
+    PlexClient::user_from_request(&req)
+        .ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))?;
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err(Error::BadRequest("q must not be empty".to_string()));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let (plex_results, sonarr_results, radarr_results, tmdb_results) = tokio::join!(
+        search_plex(&plex, q),
+        search_sonarr(&sonarr, q),
+        search_radarr(&radarr, q),
+        search_tmdb(&tmdb, q),
+    );
+
+    // Plex first so library copies win, then the *arrs whose lookups link
+    // TVDB, TMDB and IMDb ids, then TMDB.
+    let mut results: Vec<SearchResult> = Vec::new();
+    let mut skipped: Vec<SearchSource> = Vec::new();
+    for (source, outcome) in [
+        (SearchSource::Plex, plex_results),
+        (SearchSource::Sonarr, sonarr_results),
+        (SearchSource::Radarr, radarr_results),
+        (SearchSource::Tmdb, tmdb_results),
+    ] {
+        match outcome {
+            Ok(items) => add_all(&mut results, items),
+            Err(e) => {
+                debug!("Search skipped {:?}: {}", source, e);
+                skipped.push(source);
+            }
+        }
+    }
+
+    for result in &mut results {
+        rank(result, q);
+    }
+    results.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| b.year.cmp(&a.year)));
+    results.truncate(limit);
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "results": results,
+        "skipped": skipped,
+    })))
+}
+
+/// Registered ahead of the `/search` scope from `plex::search`, which would
+/// otherwise swallow `/search/all`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(search_all);
+}
@@ -2,21 +2,13 @@ use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
 use serde::Deserialize;
 use crate::config::{save_config, SharedConfig};
 use crate::config::models::*;
-use crate::http_error::Result;
+use crate::auth::require_admin;
+use crate::cache::{self, ResponseCache};
+use crate::discover::client::TmdbClient;
+use crate::http_error::{Error, Result};
 use crate::plex::client::PlexClient;
 use serde_json::json;
 
-/// Verify the requesting user is the admin. Returns Err(Unauthorized) if not.
-fn require_admin(req: &HttpRequest, config: &SharedConfig) -> Result<()> {
-    let (user_id, _) = PlexClient::user_from_request(req)
-        .ok_or_else(|| crate::http_error::Error::Unauthorized("Not signed in".to_string()))?;
-    let cfg = config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
-    if user_id != cfg.plex.admin_user_id {
-        return Err(crate::http_error::Error::Unauthorized("Admin access required".to_string()));
-    }
-    Ok(())
-}
-
 #[get("")]
 async fn get_settings(
     req: HttpRequest,
@@ -46,29 +38,104 @@ async fn update_plex(
     Ok(HttpResponse::Ok().json(cfg.redacted()))
 }
 
+/// Accepts a single instance (added, or replacing the one with the same name)
+/// or the full list of instances.
 #[put("/sonarr")]
 async fn update_sonarr(
     req: HttpRequest,
     config: web::Data<SharedConfig>,
-    body: web::Json<SonarrConfig>,
+    body: web::Json<OneOrMany<SonarrConfig>>,
 ) -> Result<impl Responder> {
     require_admin(&req, &config)?;
     let mut cfg = config.write().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
-    cfg.sonarr = body.into_inner();
+    apply_instances(&mut cfg.sonarr, body.into_inner())?;
     save_config(&cfg)?;
     Ok(HttpResponse::Ok().json(cfg.redacted()))
 }
 
+/// Accepts a single instance (added, or replacing the one with the same name)
+/// or the full list of instances.
 #[put("/radarr")]
 async fn update_radarr(
     req: HttpRequest,
     config: web::Data<SharedConfig>,
-    body: web::Json<RadarrConfig>,
+    body: web::Json<OneOrMany<RadarrConfig>>,
+) -> Result<impl Responder> {
+    require_admin(&req, &config)?;
+    let mut cfg = config.write().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+    apply_instances(&mut cfg.radarr, body.into_inner())?;
+    save_config(&cfg)?;
+    Ok(HttpResponse::Ok().json(cfg.redacted()))
+}
+
+/// Merge an instance update into the saved list. An empty API key keeps the
+/// key saved for the instance with the same name.
+fn apply_instances(saved: &mut Vec<ArrInstanceConfig>, update: OneOrMany<ArrInstanceConfig>) -> Result<()> {
+    let keep_key = |saved: &[ArrInstanceConfig], mut instance: ArrInstanceConfig| {
+        if let Some(prev) = saved
+            .iter()
+            .find(|s| instance.api_key.is_empty() && s.label().eq_ignore_ascii_case(instance.label()))
+        {
+            instance.api_key = prev.api_key.clone();
+        }
+        instance
+    };
+
+    let mut next = saved.clone();
+    match update {
+        OneOrMany::One(instance) => {
+            let instance = keep_key(saved, instance);
+            match next.iter_mut().find(|s| s.label().eq_ignore_ascii_case(instance.label())) {
+                Some(existing) => {
+                    let is_default = existing.is_default || instance.is_default;
+                    *existing = ArrInstanceConfig { is_default, ..instance };
+                }
+                None => next.push(instance),
+            }
+        }
+        OneOrMany::Many(instances) => {
+            next = instances.into_iter().map(|i| keep_key(saved, i)).collect();
+        }
+    }
+
+    let mut labels: Vec<String> = next.iter().map(|i| i.label().to_lowercase()).collect();
+    labels.sort();
+    if labels.windows(2).any(|w| w[0] == w[1]) {
+        return Err(Error::BadRequest("Instance names must be unique".to_string()));
+    }
+    if next.iter().filter(|i| i.is_default).count() > 1 {
+        return Err(Error::BadRequest("Only one instance can be the default".to_string()));
+    }
+    if let [only] = next.as_mut_slice() {
+        only.is_default = true;
+    }
+    *saved = next;
+    Ok(())
+}
+
+/// Partial update: omitted fields keep their saved value, an empty string clears it.
+/// Cached TMDB responses are dropped so nothing fetched with the old
+/// credentials outlives them.
+#[put("/tmdb")]
+async fn update_tmdb(
+    req: HttpRequest,
+    config: web::Data<SharedConfig>,
+    cache: web::Data<ResponseCache>,
+    body: web::Json<serde_json::Value>,
 ) -> Result<impl Responder> {
     require_admin(&req, &config)?;
     let mut cfg = config.write().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
-    cfg.radarr = body.into_inner();
+    let updates = body.into_inner();
+    if let Some(api_key) = updates["api_key"].as_str() {
+        cfg.tmdb.api_key = api_key.trim().to_string();
+    }
+    if let Some(token) = updates["read_access_token"].as_str() {
+        cfg.tmdb.read_access_token = token.trim().to_string();
+    }
     save_config(&cfg)?;
+    for route in [cache::TMDB_LISTS, cache::TMDB_DETAIL, cache::TMDB_SEARCH] {
+        cache.invalidate_prefix(route.name);
+    }
     Ok(HttpResponse::Ok().json(cfg.redacted()))
 }
 
@@ -85,6 +152,84 @@ async fn update_download_clients(
     Ok(HttpResponse::Ok().json(cfg.redacted()))
 }
 
+/// Replace the transcode profiles. An empty list restores the built-in presets.
+#[put("/transcode-profiles")]
+async fn update_transcode_profiles(
+    req: HttpRequest,
+    config: web::Data<SharedConfig>,
+    body: web::Json<Vec<TranscodeProfile>>,
+) -> Result<impl Responder> {
+    require_admin(&req, &config)?;
+    let profiles = body.into_inner();
+    let mut names = std::collections::HashSet::new();
+    for profile in &profiles {
+        let name = profile.name.trim();
+        if name.is_empty() || name.eq_ignore_ascii_case("auto") {
+            return Err(Error::BadRequest(format!("Invalid profile name '{}'", profile.name)));
+        }
+        if !names.insert(name.to_lowercase()) {
+            return Err(Error::BadRequest(format!("Duplicate profile name '{}'", name)));
+        }
+        if profile.max_bitrate_kbps == 0 {
+            return Err(Error::BadRequest(format!("Profile '{}' needs a max bitrate", name)));
+        }
+        if profile.max_height().is_none() {
+            return Err(Error::BadRequest(format!(
+                "Profile '{}' has invalid resolution '{}', expected WIDTHxHEIGHT",
+                name, profile.max_resolution
+            )));
+        }
+    }
+
+    let mut cfg = config.write().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+    cfg.transcode_profiles = profiles;
+    save_config(&cfg)?;
+    Ok(HttpResponse::Ok().json(cfg.redacted()))
+}
+
+#[put("/webhooks")]
+async fn update_webhooks(
+    req: HttpRequest,
+    config: web::Data<SharedConfig>,
+    body: web::Json<WebhookConfig>,
+) -> Result<impl Responder> {
+    require_admin(&req, &config)?;
+    let mut cfg = config.write().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+    cfg.webhooks = body.into_inner();
+    save_config(&cfg)?;
+    Ok(HttpResponse::Ok().json(cfg.redacted()))
+}
+
+/// Generate a fresh random secret for one webhook source and return it once,
+/// together with the receiver path to paste into Sonarr/Radarr/Plex.
+#[post("/webhooks/{source}/secret")]
+async fn regenerate_webhook_secret(
+    req: HttpRequest,
+    config: web::Data<SharedConfig>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    require_admin(&req, &config)?;
+    let source = path.into_inner();
+    let secret = uuid::Uuid::new_v4().simple().to_string();
+    let mut cfg = config.write().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+    match source.as_str() {
+        "sonarr" => cfg.webhooks.sonarr_secret = secret.clone(),
+        "radarr" => cfg.webhooks.radarr_secret = secret.clone(),
+        "plex" => cfg.webhooks.plex_secret = secret.clone(),
+        _ => {
+            return Err(crate::http_error::Error::BadRequest(format!(
+                "Unknown webhook source: {}",
+                source
+            )));
+        }
+    }
+    save_config(&cfg)?;
+    Ok(HttpResponse::Ok().json(json!({
+        "secret": secret,
+        "path": format!("/api/webhooks/{}?secret={}", source, secret),
+    })))
+}
+
 #[derive(Deserialize)]
 struct TestServicePath {
     service: String,
@@ -104,6 +249,17 @@ struct TestConnectionBody {
     username: Option<String>,
     #[serde(default)]
     password: Option<String>,
+    /// Sonarr/Radarr instance whose saved settings fill in missing fields.
+    #[serde(default)]
+    instance: Option<String>,
+}
+
+/// The saved instance named `name`, or the default one.
+fn saved_instance<'a>(instances: &'a [ArrInstanceConfig], name: Option<&str>) -> Option<&'a ArrInstanceConfig> {
+    match name.filter(|n| !n.is_empty()) {
+        Some(name) => instances.iter().find(|i| i.label().eq_ignore_ascii_case(name)),
+        None => default_instance(instances),
+    }
 }
 
 /// Return `override_val` if non-empty, otherwise `saved_val`.
@@ -199,6 +355,7 @@ pub async fn test_download_client_connection(
 async fn test_connection(
     req: HttpRequest,
     config: web::Data<SharedConfig>,
+    tmdb: web::Data<TmdbClient>,
     path: web::Path<TestServicePath>,
     body: web::Json<TestConnectionBody>,
 ) -> Result<impl Responder> {
@@ -229,8 +386,9 @@ async fn test_connection(
             req.send().await
         }
         "sonarr" => {
-            let sonarr_url = pick(&body.url, &cfg.sonarr.url);
-            let sonarr_key = pick(&body.api_key, &cfg.sonarr.api_key);
+            let saved = saved_instance(&cfg.sonarr, body.instance.as_deref()).cloned().unwrap_or_default();
+            let sonarr_url = pick(&body.url, &saved.url);
+            let sonarr_key = pick(&body.api_key, &saved.api_key);
             if sonarr_url.is_empty() {
                 return Ok(HttpResponse::BadRequest().json(serde_json::json!({
                     "success": false,
@@ -245,8 +403,9 @@ async fn test_connection(
                 .await
         }
         "radarr" => {
-            let radarr_url = pick(&body.url, &cfg.radarr.url);
-            let radarr_key = pick(&body.api_key, &cfg.radarr.api_key);
+            let saved = saved_instance(&cfg.radarr, body.instance.as_deref()).cloned().unwrap_or_default();
+            let radarr_url = pick(&body.url, &saved.url);
+            let radarr_key = pick(&body.api_key, &saved.api_key);
             if radarr_url.is_empty() {
                 return Ok(HttpResponse::BadRequest().json(serde_json::json!({
                     "success": false,
@@ -260,6 +419,18 @@ async fn test_connection(
                 .send()
                 .await
         }
+        "tmdb" => {
+            let api_key = pick(&body.api_key, &cfg.tmdb.api_key);
+            let token = pick(&body.token, &cfg.tmdb.read_access_token);
+            let Some(auth) = tmdb.resolve_auth(&api_key, &token) else {
+                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "message": "TMDB API key or read access token is required"
+                })));
+            };
+            let url = format!("{}/configuration", tmdb.base_url());
+            auth.apply(client.get(&url)).send().await
+        }
         "download-client" => {
             let dc_url = body.url.unwrap_or_default();
             let dc_type = body.client_type.unwrap_or_default();
@@ -319,8 +490,8 @@ async fn get_service_urls(
 ) -> Result<impl Responder> {
     let cfg = config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
     let plex_url = cfg.plex.url.trim_end_matches('/').to_string();
-    let sonarr_url = cfg.sonarr.url.trim_end_matches('/').to_string();
-    let radarr_url = cfg.radarr.url.trim_end_matches('/').to_string();
+    let sonarr_url = default_instance(&cfg.sonarr).map(|i| i.url.trim_end_matches('/').to_string()).unwrap_or_default();
+    let radarr_url = default_instance(&cfg.radarr).map(|i| i.url.trim_end_matches('/').to_string()).unwrap_or_default();
     drop(cfg);
 
     let machine_id = plex.get_server_machine_id().await;
@@ -341,7 +512,11 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .service(update_plex)
             .service(update_sonarr)
             .service(update_radarr)
+            .service(update_tmdb)
             .service(update_download_clients)
+            .service(update_transcode_profiles)
+            .service(update_webhooks)
+            .service(regenerate_webhook_secret)
 
             .service(test_connection),
     );
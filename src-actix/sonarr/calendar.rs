@@ -1,7 +1,10 @@
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, web, HttpRequest, Responder};
 use serde::Deserialize;
+use crate::cache::{self, ResponseCache};
+use crate::config::models::SonarrConfig;
 use crate::http_error::Result;
-use crate::sonarr::client::SonarrClient;
+use crate::sonarr::client::{tag_instance, InstanceQuery, SonarrClient};
+use crate::sonarr::models::Episode;
 
 #[derive(Deserialize)]
 struct CalendarQuery {
@@ -9,30 +12,42 @@ struct CalendarQuery {
     end: Option<String>,
 }
 
+/// Calendar of the selected instance, or of all instances merged when none is given.
 #[get("/calendar")]
 async fn calendar(
+    req: HttpRequest,
     sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
     query: web::Query<CalendarQuery>,
 ) -> Result<impl Responder> {
+    let targets = sonarr.targets(inst.instance.as_deref())?;
     let now = chrono::Utc::now();
     let start = query.start.clone()
         .unwrap_or_else(|| now.format("%Y-%m-%d").to_string());
     let end = query.end.clone()
         .unwrap_or_else(|| (now + chrono::Duration::days(14)).format("%Y-%m-%d").to_string());
 
-    let resp = sonarr
-        .get("/calendar")
-        .query(&[("start", start.as_str()), ("end", end.as_str()), ("includeSeries", "true")])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to fetch calendar: {}", e))?;
+    let labels: Vec<&str> = targets.iter().map(|i| i.label()).collect();
+    let key = ResponseCache::key(cache::SONARR, &format!("{}:/calendar?start={}&end={}", labels.join(","), start, end), &req);
+    let entry = cache.get_or_fetch(key, cache::SONARR, || async {
+        let results = sonarr
+            .get_each::<Vec<Episode>>(&targets, "/calendar", &[("start", start.as_str()), ("end", end.as_str()), ("includeSeries", "true")])
+            .await?;
+        Ok(serde_json::Value::Array(merge(results)))
+    }).await?;
 
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse calendar: {}", e))?;
+    Ok(cache::respond(&req, &entry, cache::SONARR))
+}
 
-    Ok(HttpResponse::Ok().json(body))
+fn merge(results: Vec<(SonarrConfig, Vec<Episode>)>) -> Vec<serde_json::Value> {
+    let mut items: Vec<serde_json::Value> = results
+        .into_iter()
+        .flat_map(|(instance, body)| tag_instance(&body, &instance))
+        .collect();
+    // Interleave instances by air time
+    items.sort_by(|a, b| a["airDateUtc"].as_str().unwrap_or("").cmp(b["airDateUtc"].as_str().unwrap_or("")));
+    items
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
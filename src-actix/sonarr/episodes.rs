@@ -1,57 +1,49 @@
 use actix_web::{get, put, web, HttpResponse, Responder};
 use serde::Deserialize;
+use crate::arr::models::require_id;
 use crate::http_error::Result;
-use crate::sonarr::client::SonarrClient;
+use crate::sonarr::client::{InstanceQuery, SonarrClient};
+use crate::sonarr::models::{Episode, EpisodeMonitorRequest};
 
 #[derive(Deserialize)]
 struct EpisodesQuery {
-    #[serde(rename = "seriesId")]
+    #[serde(rename = "seriesId", default)]
     series_id: u64,
 }
 
 #[get("/episodes")]
 async fn list_episodes(
     sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
     query: web::Query<EpisodesQuery>,
 ) -> Result<impl Responder> {
-    let resp = sonarr
-        .get("/episode")
-        .query(&[("seriesId", query.series_id.to_string())])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to fetch episodes: {}", e))?;
-
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse episodes: {}", e))?;
-
-    Ok(HttpResponse::Ok().json(body))
+    require_id("seriesId", query.series_id)?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let episodes: Vec<Episode> = sonarr
+        .send_json(
+            sonarr.get(&instance, "/episode").query(&[("seriesId", query.series_id.to_string())]),
+            "episodes",
+        )
+        .await?;
+
+    Ok(HttpResponse::Ok().json(episodes))
 }
 
 #[put("/episode/monitor")]
 async fn monitor_episodes(
     sonarr: web::Data<SonarrClient>,
-    body: web::Json<serde_json::Value>,
+    inst: web::Query<InstanceQuery>,
+    body: web::Json<EpisodeMonitorRequest>,
 ) -> Result<impl Responder> {
-    let resp = sonarr
-        .put("/episode/monitor")
-        .json(&body.into_inner())
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to update episode monitoring: {}", e))?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let request = body.into_inner();
+    request.validate()?;
 
-    let status = resp.status();
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse monitor response: {}", e))?;
+    let episodes: Vec<Episode> = sonarr
+        .send_json(sonarr.put(&instance, "/episode/monitor").json(&request), "episodes")
+        .await?;
 
-    if status.is_success() {
-        Ok(HttpResponse::Ok().json(body))
-    } else {
-        Ok(HttpResponse::BadRequest().json(body))
-    }
+    Ok(HttpResponse::Ok().json(episodes))
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
@@ -0,0 +1,209 @@
+//! Episode files on disk: listing and deleting them, importing completed
+//! downloads by hand and renaming to the naming format. All admin only.
+
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::json;
+use crate::arr::models::{require_id, Command};
+use crate::auth::require_admin;
+use crate::cache::{self, ResponseCache};
+use crate::commands::CommandTracker;
+use crate::events::EventSource;
+use crate::http_error::Result;
+use crate::plex::client::PlexClient;
+use crate::sonarr::client::{InstanceQuery, SonarrClient};
+use crate::sonarr::models::{
+    DeleteEpisodeFilesRequest, EpisodeFile, ManualImportItem, ManualImportQuery, ManualImportRequest, RenamePreview,
+    RenameRequest,
+};
+
+#[derive(Deserialize)]
+struct SeriesQuery {
+    #[serde(rename = "seriesId", default)]
+    series_id: u64,
+    #[serde(rename = "seasonNumber")]
+    season_number: Option<u64>,
+}
+
+impl SeriesQuery {
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("seriesId", self.series_id.to_string())];
+        if let Some(season) = self.season_number {
+            params.push(("seasonNumber", season.to_string()));
+        }
+        params
+    }
+}
+
+#[get("/episodefile")]
+async fn list_episode_files(
+    req: HttpRequest,
+    sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
+    query: web::Query<SeriesQuery>,
+) -> Result<impl Responder> {
+    require_admin(&req, &sonarr.config)?;
+    require_id("seriesId", query.series_id)?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let mut files: Vec<EpisodeFile> = sonarr
+        .send_json(sonarr.get(&instance, "/episodefile").query(&[("seriesId", query.series_id)]), "episode files")
+        .await?;
+    // Sonarr's /episodefile only filters by series
+    if let Some(season) = query.season_number {
+        files.retain(|f| f.season_number == season);
+    }
+
+    Ok(HttpResponse::Ok().json(files))
+}
+
+#[delete("/episodefile/{id}")]
+async fn delete_episode_file(
+    req: HttpRequest,
+    sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<u64>,
+) -> Result<impl Responder> {
+    require_admin(&req, &sonarr.config)?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    sonarr
+        .send(sonarr.delete(&instance, &format!("/episodefile/{}", path.into_inner())), "episode file")
+        .await?;
+
+    cache.invalidate_prefix(cache::SONARR.name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[delete("/episodefile")]
+async fn delete_episode_files(
+    req: HttpRequest,
+    sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+    body: web::Json<DeleteEpisodeFilesRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &sonarr.config)?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let request = body.into_inner();
+    request.validate()?;
+
+    sonarr
+        .send(sonarr.delete(&instance, "/episodefile/bulk").json(&request), "episode files")
+        .await?;
+
+    cache.invalidate_prefix(cache::SONARR.name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Files in a download (or folder) that Sonarr could import, with its guess
+/// at series, episodes and quality for each.
+#[get("/manualimport")]
+async fn manual_import_candidates(
+    req: HttpRequest,
+    sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
+    query: web::Query<ManualImportQuery>,
+) -> Result<impl Responder> {
+    require_admin(&req, &sonarr.config)?;
+    query.validate()?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let items: Vec<ManualImportItem> = sonarr
+        .send_json(sonarr.get(&instance, "/manualimport").query(&*query), "import candidates")
+        .await?;
+
+    Ok(HttpResponse::Ok().json(items))
+}
+
+/// Import the confirmed files. Runs as a Sonarr command, which is returned
+/// and followed in `/api/commands`.
+#[post("/manualimport")]
+async fn manual_import(
+    req: HttpRequest,
+    sonarr: web::Data<SonarrClient>,
+    tracker: web::Data<CommandTracker>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+    body: web::Json<ManualImportRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &sonarr.config)?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let request = body.into_inner();
+    request.validate()?;
+
+    let command: Command = sonarr
+        .send_json(
+            sonarr.post(&instance, "/command").json(&json!({
+                "name": "ManualImport",
+                "files": request.files,
+                "importMode": request.import_mode,
+            })),
+            "manual import",
+        )
+        .await?;
+
+    let user = PlexClient::user_from_request(&req).map(|(id, _)| id);
+    tracker.track(EventSource::Sonarr, instance.label(), &command, user);
+
+    cache.invalidate_prefix(cache::SONARR.name);
+    Ok(HttpResponse::Ok().json(command))
+}
+
+/// Files whose name differs from the naming format, with old and new paths.
+#[get("/rename")]
+async fn rename_preview(
+    req: HttpRequest,
+    sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
+    query: web::Query<SeriesQuery>,
+) -> Result<impl Responder> {
+    require_admin(&req, &sonarr.config)?;
+    require_id("seriesId", query.series_id)?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let previews: Vec<RenamePreview> = sonarr
+        .send_json(sonarr.get(&instance, "/rename").query(&query.params()), "rename preview")
+        .await?;
+
+    Ok(HttpResponse::Ok().json(previews))
+}
+
+#[post("/rename")]
+async fn rename_files(
+    req: HttpRequest,
+    sonarr: web::Data<SonarrClient>,
+    tracker: web::Data<CommandTracker>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+    body: web::Json<RenameRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &sonarr.config)?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let request = body.into_inner();
+    request.validate()?;
+
+    let command: Command = sonarr
+        .send_json(
+            sonarr.post(&instance, "/command").json(&json!({
+                "name": "RenameFiles",
+                "seriesId": request.series_id,
+                "files": request.files,
+            })),
+            "rename",
+        )
+        .await?;
+
+    let user = PlexClient::user_from_request(&req).map(|(id, _)| id);
+    tracker.track(EventSource::Sonarr, instance.label(), &command, user);
+
+    cache.invalidate_prefix(cache::SONARR.name);
+    Ok(HttpResponse::Ok().json(command))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_episode_files)
+        .service(delete_episode_file)
+        .service(delete_episode_files)
+        .service(manual_import_candidates)
+        .service(manual_import)
+        .service(rename_preview)
+        .service(rename_files);
+}
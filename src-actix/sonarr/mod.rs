@@ -1,8 +1,7 @@
 pub mod client;
+pub mod models;
 pub mod series;
 pub mod episodes;
+pub mod files;
 pub mod calendar;
-pub mod queue;
-pub mod profiles;
-pub mod commands;
 pub mod releases;
@@ -0,0 +1,427 @@
+use serde::{Deserialize, Serialize};
+
+use crate::arr::models::{require_absolute_path, require_id, require_text, Image, Language, QualityModel};
+use crate::http_error::{Error, Result};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SeasonStatistics {
+    pub episode_count: u64,
+    pub episode_file_count: u64,
+    pub total_episode_count: u64,
+    pub size_on_disk: u64,
+    pub percent_of_episodes: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Season {
+    pub season_number: u64,
+    pub monitored: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statistics: Option<SeasonStatistics>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Series {
+    /// Absent for lookup results that are not added yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub title: String,
+    pub sort_title: String,
+    pub title_slug: String,
+    pub tvdb_id: u64,
+    pub tmdb_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imdb_id: Option<String>,
+    pub overview: String,
+    pub year: u32,
+    pub status: String,
+    pub network: String,
+    pub runtime: u32,
+    pub monitored: bool,
+    pub seasons: Vec<Season>,
+    pub quality_profile_id: u64,
+    pub root_folder_path: String,
+    pub series_type: String,
+    pub season_folder: bool,
+    pub path: String,
+    pub added: String,
+    pub images: Vec<Image>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_poster: Option<String>,
+    pub genres: Vec<String>,
+    pub tags: Vec<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statistics: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Episode {
+    pub id: u64,
+    pub series_id: u64,
+    pub season_number: u64,
+    pub episode_number: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub absolute_episode_number: Option<u64>,
+    pub title: String,
+    pub overview: String,
+    pub monitored: bool,
+    pub has_file: bool,
+    pub episode_file_id: u64,
+    pub air_date: Option<String>,
+    pub air_date_utc: Option<String>,
+    pub runtime: u32,
+    /// Included by `/calendar?includeSeries=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<Box<Series>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SeriesType {
+    #[default]
+    Standard,
+    Daily,
+    Anime,
+}
+
+/// Which episodes Sonarr monitors when a series is added.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MonitorType {
+    #[default]
+    All,
+    Future,
+    Missing,
+    Existing,
+    Recent,
+    Pilot,
+    FirstSeason,
+    LastSeason,
+    LatestSeason,
+    MonitorSpecials,
+    UnmonitorSpecials,
+    None,
+    Skip,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AddSeriesOptions {
+    pub monitor: MonitorType,
+    pub search_for_missing_episodes: bool,
+    pub search_for_cutoff_unmet_episodes: bool,
+}
+
+/// Body of `POST /series`. Fields copied from a lookup result (images,
+/// seasons, ...) are forwarded as-is.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddSeriesRequest {
+    #[serde(default)]
+    pub tvdb_id: u64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub quality_profile_id: u64,
+    #[serde(default)]
+    pub root_folder_path: String,
+    #[serde(default = "default_true")]
+    pub monitored: bool,
+    #[serde(default)]
+    pub series_type: SeriesType,
+    #[serde(default = "default_true")]
+    pub season_folder: bool,
+    #[serde(default)]
+    pub tags: Vec<u64>,
+    #[serde(default)]
+    pub add_options: AddSeriesOptions,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl AddSeriesRequest {
+    pub fn validate(&self) -> Result<()> {
+        require_id("tvdbId", self.tvdb_id)?;
+        require_text("title", &self.title)?;
+        require_id("qualityProfileId", self.quality_profile_id)?;
+        require_absolute_path("rootFolderPath", &self.root_folder_path)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SeasonUpdate {
+    pub season_number: u64,
+    pub monitored: bool,
+}
+
+/// Body of `PUT /series/{id}`. Only the given fields change; everything else
+/// is kept from the current series.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SeriesUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitored: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality_profile_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series_type: Option<SeriesType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub season_folder: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root_folder_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seasons: Option<Vec<SeasonUpdate>>,
+}
+
+impl SeriesUpdate {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(id) = self.quality_profile_id {
+            require_id("qualityProfileId", id)?;
+        }
+        if let Some(path) = &self.path {
+            require_absolute_path("path", path)?;
+        }
+        if let Some(path) = &self.root_folder_path {
+            require_absolute_path("rootFolderPath", path)?;
+        }
+        Ok(())
+    }
+
+    /// Overlay these fields on the series as returned by Sonarr. Seasons are
+    /// matched by number so their statistics are kept.
+    pub fn apply(&self, current: &mut serde_json::Value) -> Result<()> {
+        let updates = serde_json::to_value(self).map_err(anyhow::Error::from)?;
+        let (Some(target), Some(updates)) = (current.as_object_mut(), updates.as_object()) else {
+            return Err(Error::UpstreamError("Sonarr returned an invalid series".to_string()));
+        };
+        for (key, value) in updates {
+            if key == "seasons" {
+                continue;
+            }
+            target.insert(key.clone(), value.clone());
+        }
+        for season in self.seasons.iter().flatten() {
+            let existing = target
+                .get_mut("seasons")
+                .and_then(|s| s.as_array_mut())
+                .and_then(|s| s.iter_mut().find(|s| s["seasonNumber"].as_u64() == Some(season.season_number)));
+            match existing {
+                Some(s) => s["monitored"] = serde_json::json!(season.monitored),
+                None => {
+                    return Err(Error::BadRequest(format!("Season {} does not exist", season.season_number)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Body of `PUT /episode/monitor`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EpisodeMonitorRequest {
+    pub episode_ids: Vec<u64>,
+    pub monitored: bool,
+}
+
+impl EpisodeMonitorRequest {
+    pub fn validate(&self) -> Result<()> {
+        if self.episode_ids.is_empty() {
+            return Err(Error::BadRequest("episodeIds must not be empty".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Body of `PUT /series/{id}/season/{season}/monitor`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeasonMonitorRequest {
+    pub monitored: bool,
+    /// Also (un)monitor every episode of the season, like Sonarr's season pass.
+    #[serde(default = "default_true")]
+    pub episodes: bool,
+}
+
+/// Query of `DELETE /series/{id}`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DeleteSeriesQuery {
+    pub delete_files: bool,
+    pub add_import_list_exclusion: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EpisodeFile {
+    pub id: u64,
+    pub series_id: u64,
+    pub season_number: u64,
+    pub relative_path: String,
+    pub path: String,
+    pub size: u64,
+    pub date_added: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_group: Option<String>,
+    pub quality: QualityModel,
+    pub languages: Vec<Language>,
+    pub quality_cutoff_not_met: bool,
+}
+
+/// Body of `DELETE /episodefile`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DeleteEpisodeFilesRequest {
+    pub episode_file_ids: Vec<u64>,
+}
+
+impl DeleteEpisodeFilesRequest {
+    pub fn validate(&self) -> Result<()> {
+        if self.episode_file_ids.is_empty() {
+            return Err(Error::BadRequest("episodeFileIds must not be empty".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Query of `GET /manualimport`: either a download client id or a folder.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ManualImportQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub season_number: Option<u64>,
+    pub filter_existing_files: bool,
+}
+
+impl ManualImportQuery {
+    pub fn validate(&self) -> Result<()> {
+        match (&self.download_id, &self.folder) {
+            (Some(id), None) => require_text("downloadId", id),
+            (None, Some(folder)) => require_absolute_path("folder", folder),
+            _ => Err(Error::BadRequest("Exactly one of downloadId or folder is required".to_string())),
+        }
+    }
+}
+
+/// A file Sonarr found in a download, with its best guess at what it is.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ManualImportItem {
+    pub id: u64,
+    pub path: String,
+    pub relative_path: String,
+    pub name: String,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<Box<Series>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub season_number: Option<u64>,
+    pub episodes: Vec<Episode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_group: Option<String>,
+    pub quality: QualityModel,
+    pub languages: Vec<Language>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_id: Option<String>,
+    pub rejections: Vec<serde_json::Value>,
+}
+
+/// One file to import, as confirmed by the user.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ManualImportFile {
+    pub path: String,
+    pub series_id: u64,
+    pub episode_ids: Vec<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode_file_id: Option<u64>,
+    pub quality: QualityModel,
+    pub languages: Vec<Language>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportMode {
+    #[default]
+    Auto,
+    Move,
+    Copy,
+}
+
+/// Body of `POST /manualimport`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ManualImportRequest {
+    pub files: Vec<ManualImportFile>,
+    pub import_mode: ImportMode,
+}
+
+impl ManualImportRequest {
+    pub fn validate(&self) -> Result<()> {
+        if self.files.is_empty() {
+            return Err(Error::BadRequest("files must not be empty".to_string()));
+        }
+        for file in &self.files {
+            require_absolute_path("path", &file.path)?;
+            require_id("seriesId", file.series_id)?;
+            if file.episode_ids.is_empty() {
+                return Err(Error::BadRequest(format!("No episodes chosen for {}", file.path)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A pending rename, from `GET /rename`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RenamePreview {
+    pub series_id: u64,
+    pub season_number: u64,
+    pub episode_numbers: Vec<u64>,
+    pub episode_file_id: u64,
+    pub existing_path: String,
+    pub new_path: String,
+}
+
+/// Body of `POST /rename`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RenameRequest {
+    pub series_id: u64,
+    /// Episode file ids from the preview.
+    pub files: Vec<u64>,
+}
+
+impl RenameRequest {
+    pub fn validate(&self) -> Result<()> {
+        require_id("seriesId", self.series_id)?;
+        if self.files.is_empty() {
+            return Err(Error::BadRequest("files must not be empty".to_string()));
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
-use actix_web::{get, post, web, HttpResponse, Responder};
-use crate::http_error::Result;
-use crate::sonarr::client::SonarrClient;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use crate::arr::models::Release;
+use crate::cache::{self, ResponseCache};
+use crate::arr::releases::ReleaseFilter;
+use crate::http_error::{Error, Result};
+use crate::sonarr::client::{InstanceQuery, SonarrClient};
 
 #[derive(serde::Deserialize)]
 struct ReleaseQuery {
@@ -12,11 +15,19 @@ struct ReleaseQuery {
     season_number: Option<u32>,
 }
 
+/// One page of a manual search. The search itself is cached briefly so
+/// paging, sorting and filtering don't hit the indexers again.
 #[get("/release")]
 async fn get_releases(
+    req: HttpRequest,
     sonarr: web::Data<SonarrClient>,
+    cache: web::Data<ResponseCache>,
+    inst: web::Query<InstanceQuery>,
     query: web::Query<ReleaseQuery>,
+    filter: web::Query<ReleaseFilter>,
 ) -> Result<impl Responder> {
+    filter.validate()?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
     let mut params: Vec<(&str, String)> = Vec::new();
     if let Some(id) = query.episode_id {
         params.push(("episodeId", id.to_string()));
@@ -25,49 +36,28 @@ async fn get_releases(
         params.push(("seriesId", id.to_string()));
     }
     if let Some(num) = query.season_number {
+        if query.series_id.is_none() {
+            return Err(Error::BadRequest("seasonNumber requires seriesId".to_string()));
+        }
         params.push(("seasonNumber", num.to_string()));
     }
-
-    let resp = sonarr
-        .get("/release")
-        .query(&params)
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to fetch releases: {}", e))?;
-
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse releases: {}", e))?;
-
-    Ok(HttpResponse::Ok().json(body))
-}
-
-#[post("/release")]
-async fn grab_release(
-    sonarr: web::Data<SonarrClient>,
-    body: web::Json<serde_json::Value>,
-) -> Result<impl Responder> {
-    let resp = sonarr
-        .post("/release")
-        .json(&body.into_inner())
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to grab release: {}", e))?;
-
-    let status = resp.status();
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse grab response: {}", e))?;
-
-    if status.is_success() {
-        Ok(HttpResponse::Ok().json(body))
-    } else {
-        Ok(HttpResponse::BadRequest().json(body))
+    if params.is_empty() {
+        return Err(Error::BadRequest("episodeId or seriesId is required".to_string()));
     }
+
+    let search: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    let key = ResponseCache::key(cache::SONARR_RELEASES, &format!("{}:/release?{}", instance.label(), search.join("&")), &req);
+    let entry = cache.get_or_fetch(key, cache::SONARR_RELEASES, || async {
+        let releases: Vec<Release> = sonarr
+            .send_json(sonarr.get(&instance, "/release").query(&params), "releases")
+            .await?;
+        Ok(serde_json::to_value(releases).map_err(anyhow::Error::from)?)
+    }).await?;
+    let releases: Vec<Release> = serde_json::from_value(entry.body).map_err(anyhow::Error::from)?;
+
+    Ok(HttpResponse::Ok().json(filter.apply(releases)))
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_releases).service(grab_release);
+    cfg.service(get_releases);
 }
@@ -1,121 +1,236 @@
-use actix_web::{get, post, put, web, HttpResponse, Responder};
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
 use serde::Deserialize;
+use crate::arr::models::FieldsQuery;
+use crate::auth::require_admin;
+use crate::cache::{self, ResponseCache};
 use crate::http_error::Result;
-use crate::sonarr::client::SonarrClient;
+use crate::sonarr::client::{tag_instance, InstanceQuery, SonarrClient};
+use crate::sonarr::models::{
+    AddSeriesRequest, DeleteSeriesQuery, Episode, EpisodeMonitorRequest, SeasonMonitorRequest, SeasonUpdate, Series,
+    SeriesUpdate,
+};
 
 #[derive(Deserialize)]
 struct LookupQuery {
     term: String,
 }
 
+/// Search the selected instance, or every instance with duplicates merged.
+/// Each result lists the instances it was found on under `instances`; an
+/// `id` from an instance means it is already added there.
 #[get("/lookup")]
 async fn lookup(
     sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
     query: web::Query<LookupQuery>,
 ) -> Result<impl Responder> {
-    let resp = sonarr
-        .get("/series/lookup")
-        .query(&[("term", query.term.as_str())])
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to lookup series: {}", e))?;
-
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse lookup: {}", e))?;
-
-    Ok(HttpResponse::Ok().json(body))
+    let targets = sonarr.targets(inst.instance.as_deref())?;
+    let results = sonarr
+        .get_each::<Vec<Series>>(&targets, "/series/lookup", &[("term", query.term.as_str())])
+        .await?;
+
+    let mut merged: Vec<serde_json::Value> = Vec::new();
+    for (instance, series) in results {
+        for item in tag_instance(&series, &instance) {
+            let existing = merged
+                .iter_mut()
+                .find(|m| item["tvdbId"].as_u64().is_some_and(|id| id > 0 && m["tvdbId"].as_u64() == Some(id)));
+            match existing {
+                Some(m) => {
+                    if let Some(list) = m["instances"].as_array_mut() {
+                        list.push(serde_json::json!(instance.label()));
+                    }
+                    // Prefer the copy from an instance that already has it
+                    if m["id"].as_u64().unwrap_or(0) == 0 && item["id"].as_u64().unwrap_or(0) > 0 {
+                        let instances = m["instances"].take();
+                        *m = item;
+                        m["instances"] = instances;
+                    }
+                }
+                None => {
+                    let mut item = item;
+                    item["instances"] = serde_json::json!([instance.label()]);
+                    merged.push(item);
+                }
+            }
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(merged))
 }
 
+/// All series. `?fields=id,title,tvdbId` trims each entry to those fields.
 #[get("/series")]
 async fn list_series(
+    req: HttpRequest,
     sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
+    fields: web::Query<FieldsQuery>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<impl Responder> {
-    let resp = sonarr
-        .get("/series")
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to fetch series: {}", e))?;
-
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse series: {}", e))?;
-
-    Ok(HttpResponse::Ok().json(body))
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let key = ResponseCache::key(cache::SONARR, &format!("{}:/series", instance.label()), &req);
+    let entry = cache.get_or_fetch(key, cache::SONARR, || async {
+        let series: Vec<Series> = sonarr.send_json(sonarr.get(&instance, "/series"), "series").await?;
+        Ok(serde_json::to_value(series).map_err(anyhow::Error::from)?)
+    }).await?;
+
+    if fields.fields.is_some() {
+        return Ok(HttpResponse::Ok().json(fields.apply(entry.body)));
+    }
+    Ok(cache::respond(&req, &entry, cache::SONARR))
 }
 
 #[get("/series/{id}")]
 async fn get_series(
+    req: HttpRequest,
     sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
+    fields: web::Query<FieldsQuery>,
+    cache: web::Data<ResponseCache>,
     path: web::Path<u64>,
 ) -> Result<impl Responder> {
+    let instance = sonarr.instance(inst.instance.as_deref())?;
     let id = path.into_inner();
-    let resp = sonarr
-        .get(&format!("/series/{}", id))
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to fetch series: {}", e))?;
-
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse series: {}", e))?;
-
-    Ok(HttpResponse::Ok().json(body))
+    let key = ResponseCache::key(cache::SONARR, &format!("{}:/series/{}", instance.label(), id), &req);
+    let entry = cache.get_or_fetch(key, cache::SONARR, || async {
+        let series: Series = sonarr
+            .send_json(sonarr.get(&instance, &format!("/series/{}", id)), "series")
+            .await?;
+        Ok(serde_json::to_value(series).map_err(anyhow::Error::from)?)
+    }).await?;
+
+    if fields.fields.is_some() {
+        return Ok(HttpResponse::Ok().json(fields.apply(entry.body)));
+    }
+    Ok(cache::respond(&req, &entry, cache::SONARR))
 }
 
 #[post("/series")]
 async fn add_series(
     sonarr: web::Data<SonarrClient>,
-    body: web::Json<serde_json::Value>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+    body: web::Json<AddSeriesRequest>,
 ) -> Result<impl Responder> {
-    let resp = sonarr
-        .post("/series")
-        .json(&body.into_inner())
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to add series: {}", e))?;
-
-    let status = resp.status();
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse add response: {}", e))?;
-
-    if status.is_success() {
-        Ok(HttpResponse::Ok().json(body))
-    } else {
-        Ok(HttpResponse::BadRequest().json(body))
-    }
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let request = body.into_inner();
+    request.validate()?;
+
+    let added: Series = sonarr
+        .send_json(sonarr.post(&instance, "/series").json(&request), "series")
+        .await?;
+
+    cache.invalidate_prefix(cache::SONARR.name);
+    Ok(HttpResponse::Ok().json(added))
 }
 
+/// Partial update: fetch the series, overlay the given fields and save it back.
 #[put("/series/{id}")]
 async fn update_series(
     sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
     path: web::Path<u64>,
-    body: web::Json<serde_json::Value>,
+    body: web::Json<SeriesUpdate>,
 ) -> Result<impl Responder> {
+    let instance = sonarr.instance(inst.instance.as_deref())?;
     let id = path.into_inner();
-    let resp = sonarr
-        .put(&format!("/series/{}", id))
-        .json(&body.into_inner())
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to update series: {}", e))?;
-
-    let status = resp.status();
-    let body: serde_json::Value = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse update response: {}", e))?;
-
-    if status.is_success() {
-        Ok(HttpResponse::Ok().json(body))
-    } else {
-        Ok(HttpResponse::BadRequest().json(body))
+    let update = body.into_inner();
+    update.validate()?;
+
+    let mut current: serde_json::Value = sonarr
+        .send_json(sonarr.get(&instance, &format!("/series/{}", id)), "series")
+        .await?;
+    update.apply(&mut current)?;
+
+    let updated: Series = sonarr
+        .send_json(sonarr.put(&instance, &format!("/series/{}", id)).json(&current), "series")
+        .await?;
+
+    cache.invalidate_prefix(cache::SONARR.name);
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+/// Season pass: (un)monitor a season and, unless `episodes` is false, all of
+/// its episodes.
+#[put("/series/{id}/season/{season}/monitor")]
+async fn monitor_season(
+    req: HttpRequest,
+    sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<(u64, u64)>,
+    body: web::Json<SeasonMonitorRequest>,
+) -> Result<impl Responder> {
+    require_admin(&req, &sonarr.config)?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let (id, season_number) = path.into_inner();
+    let request = body.into_inner();
+
+    let mut current: serde_json::Value = sonarr
+        .send_json(sonarr.get(&instance, &format!("/series/{}", id)), "series")
+        .await?;
+    let update = SeriesUpdate {
+        seasons: Some(vec![SeasonUpdate { season_number, monitored: request.monitored }]),
+        ..Default::default()
+    };
+    update.apply(&mut current)?;
+    let updated: Series = sonarr
+        .send_json(sonarr.put(&instance, &format!("/series/{}", id)).json(&current), "series")
+        .await?;
+
+    if request.episodes {
+        let episodes: Vec<Episode> = sonarr
+            .send_json(
+                sonarr.get(&instance, "/episode").query(&[
+                    ("seriesId", id.to_string()),
+                    ("seasonNumber", season_number.to_string()),
+                ]),
+                "episodes",
+            )
+            .await?;
+        let monitor = EpisodeMonitorRequest {
+            episode_ids: episodes.iter().map(|e| e.id).collect(),
+            monitored: request.monitored,
+        };
+        if !monitor.episode_ids.is_empty() {
+            sonarr
+                .send(sonarr.put(&instance, "/episode/monitor").json(&monitor), "episodes")
+                .await?;
+        }
     }
+
+    cache.invalidate_prefix(cache::SONARR.name);
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+/// Remove a series. `?deleteFiles=true` also deletes it from disk.
+#[delete("/series/{id}")]
+async fn delete_series(
+    req: HttpRequest,
+    sonarr: web::Data<SonarrClient>,
+    inst: web::Query<InstanceQuery>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<u64>,
+    query: web::Query<DeleteSeriesQuery>,
+) -> Result<impl Responder> {
+    require_admin(&req, &sonarr.config)?;
+    let instance = sonarr.instance(inst.instance.as_deref())?;
+    let id = path.into_inner();
+
+    sonarr
+        .send(
+            sonarr.delete(&instance, &format!("/series/{}", id)).query(&[
+                ("deleteFiles", query.delete_files),
+                ("addImportListExclusion", query.add_import_list_exclusion),
+            ]),
+            "series",
+        )
+        .await?;
+
+    cache.invalidate_prefix(cache::SONARR.name);
+    Ok(HttpResponse::NoContent().finish())
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
@@ -123,5 +238,7 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .service(list_series)
         .service(get_series)
         .service(add_series)
-        .service(update_series);
+        .service(update_series)
+        .service(monitor_season)
+        .service(delete_series);
 }
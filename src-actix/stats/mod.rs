@@ -0,0 +1,404 @@
+//! Watch history and viewing statistics for the admin dashboard.
+//! What was watched comes from Plex's own history, so plays from any Plex
+//! app count; how it was played (direct or transcoded, concurrent streams)
+//! is only known for Playarr's player, from its timeline reports.
+
+pub mod playback;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use futures_util::future::join_all;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::auth::require_admin;
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+use crate::plex::users::{account_for_user_id, user_id_for_account};
+use playback::{concurrency_peaks, ConcurrencyPeaks, PlayMethod, PlaybackLog};
+
+const DEFAULT_DAYS: u32 = 30;
+const MAX_DAYS: u32 = 365;
+const MAX_HISTORY: u32 = 10000;
+const TOP_COUNT: usize = 10;
+/// Keys per `/library/metadata/{a,b,c}` lookup.
+const METADATA_CHUNK: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct StatsQuery {
+    /// How far back to look, 1 to 365 days.
+    days: Option<u32>,
+    /// Plex user id to narrow everything to.
+    user: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryEntry {
+    user_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_name: Option<String>,
+    rating_key: String,
+    title: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grandparent_title: Option<String>,
+    /// ratingKey of the show, for episodes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    show_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumb: Option<String>,
+    viewed_at: DateTime<Utc>,
+}
+
+impl HistoryEntry {
+    fn from_plex(item: &Value, admin_user_id: i64) -> Option<Self> {
+        let account = item["accountID"].as_i64()?;
+        let viewed_at = DateTime::from_timestamp(item["viewedAt"].as_i64()?, 0)?;
+        let text = |field: &str| item[field].as_str().filter(|s| !s.is_empty()).map(String::from);
+        Some(Self {
+            user_id: user_id_for_account(account, admin_user_id),
+            user_name: None,
+            rating_key: text("ratingKey")?,
+            title: text("title").unwrap_or_default(),
+            kind: text("type").unwrap_or_default(),
+            grandparent_title: text("grandparentTitle"),
+            show_key: text("grandparentKey").and_then(|k| k.rsplit('/').next().map(String::from)),
+            thumb: text("grandparentThumb").or_else(|| text("thumb")),
+            viewed_at,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct ItemInfo {
+    duration_ms: u64,
+    genres: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UserStats {
+    user_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_name: Option<String>,
+    plays: usize,
+    hours: f64,
+    last_watched: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct HoursBucket {
+    date: NaiveDate,
+    hours: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TopShow {
+    rating_key: String,
+    title: String,
+    plays: usize,
+    hours: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct TopGenre {
+    genre: String,
+    plays: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayMethods {
+    direct: usize,
+    direct_stream: usize,
+    transcode: usize,
+    /// Share of Playarr streams that were transcoded, when there were any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transcode_ratio: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Stats {
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    plays: usize,
+    hours: f64,
+    users: Vec<UserStats>,
+    /// Every UTC day in the range, oldest first.
+    hours_by_day: Vec<HoursBucket>,
+    /// Weeks starting on Monday.
+    hours_by_week: Vec<HoursBucket>,
+    top_shows: Vec<TopShow>,
+    top_genres: Vec<TopGenre>,
+    play_methods: PlayMethods,
+    concurrent_streams: ConcurrencyPeaks,
+}
+
+fn hours(ms: u64) -> f64 {
+    (ms as f64 / 3_600_000.0 * 100.0).round() / 100.0
+}
+
+fn admin_user_id(plex: &PlexClient) -> Result<i64> {
+    let cfg = plex.config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+    Ok(cfg.plex.admin_user_id)
+}
+
+fn range(days: Option<u32>) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let days = days.unwrap_or(DEFAULT_DAYS);
+    if days == 0 || days > MAX_DAYS {
+        return Err(Error::BadRequest(format!("days must be between 1 and {}", MAX_DAYS)));
+    }
+    let to = Utc::now();
+    Ok((to - Duration::days(days as i64), to))
+}
+
+/// Plex history since `from`, newest first.
+async fn fetch_history(
+    plex: &PlexClient,
+    from: DateTime<Utc>,
+    user: Option<i64>,
+    limit: u32,
+) -> Result<Vec<HistoryEntry>> {
+    let admin = admin_user_id(plex)?;
+    let mut upstream = plex.get("/status/sessions/history/all")?.query(&[
+        ("sort", "viewedAt:desc".to_string()),
+        ("viewedAt>", from.timestamp().to_string()),
+        ("X-Plex-Container-Start", "0".to_string()),
+        ("X-Plex-Container-Size", limit.to_string()),
+    ]);
+    if let Some(user) = user {
+        upstream = upstream.query(&[("accountID", account_for_user_id(user, admin))]);
+    }
+    let body = plex.send_json(upstream).await?;
+    let entries = body["MediaContainer"]["Metadata"]
+        .as_array()
+        .map(|items| items.iter().filter_map(|i| HistoryEntry::from_plex(i, admin)).collect())
+        .unwrap_or_default();
+    Ok(entries)
+}
+
+/// Account names from the server, by Plex user id. Best effort.
+async fn account_names(plex: &PlexClient) -> HashMap<i64, String> {
+    let Ok(admin) = admin_user_id(plex) else { return HashMap::new() };
+    let body = async { plex.send_json(plex.get("/accounts")?).await };
+    let body = match body.await {
+        Ok(body) => body,
+        Err(e) => {
+            debug!("Stats could not read Plex accounts: {}", e);
+            return HashMap::new();
+        }
+    };
+    body["MediaContainer"]["Account"]
+        .as_array()
+        .map(|accounts| {
+            accounts
+                .iter()
+                .filter_map(|a| {
+                    let id = a["id"].as_i64()?;
+                    let name = a["name"].as_str().filter(|n| !n.is_empty())?;
+                    Some((user_id_for_account(id, admin), name.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Duration and genres for the watched items and their shows. Items since
+/// removed from the library are simply missing.
+async fn item_info(plex: &PlexClient, keys: BTreeSet<&str>) -> HashMap<String, ItemInfo> {
+    let keys: Vec<&str> = keys.into_iter().collect();
+    let requests = keys.chunks(METADATA_CHUNK).map(|chunk| async move {
+        let req = plex.get(&format!("/library/metadata/{}", chunk.join(",")))?;
+        plex.send_json(req).await
+    });
+    let mut info = HashMap::new();
+    for body in join_all(requests).await {
+        let body = match body {
+            Ok(body) => body,
+            Err(e) => {
+                debug!("Stats could not read item metadata: {}", e);
+                continue;
+            }
+        };
+        for item in body["MediaContainer"]["Metadata"].as_array().into_iter().flatten() {
+            let Some(key) = item["ratingKey"].as_str() else { continue };
+            let genres = item["Genre"]
+                .as_array()
+                .map(|g| g.iter().filter_map(|g| g["tag"].as_str().map(String::from)).collect())
+                .unwrap_or_default();
+            info.insert(key.to_string(), ItemInfo {
+                duration_ms: item["duration"].as_u64().unwrap_or(0),
+                genres,
+            });
+        }
+    }
+    info
+}
+
+fn top<K: Ord + Clone>(counts: BTreeMap<K, (usize, u64)>) -> Vec<(K, usize, u64)> {
+    let mut sorted: Vec<_> = counts.into_iter().map(|(k, (plays, ms))| (k, plays, ms)).collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)));
+    sorted.truncate(TOP_COUNT);
+    sorted
+}
+
+fn summarize(
+    history: &[HistoryEntry],
+    info: &HashMap<String, ItemInfo>,
+    names: &HashMap<i64, String>,
+    log: &PlaybackLog,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    user: Option<i64>,
+) -> Stats {
+    let duration = |e: &HistoryEntry| info.get(&e.rating_key).map_or(0, |i| i.duration_ms);
+    let days: Vec<NaiveDate> = from.date_naive().iter_days().take_while(|d| *d <= to.date_naive()).collect();
+
+    let mut users: BTreeMap<i64, (usize, u64, DateTime<Utc>)> = BTreeMap::new();
+    let mut by_day: BTreeMap<NaiveDate, u64> = days.iter().map(|d| (*d, 0)).collect();
+    let mut shows: BTreeMap<(String, String), (usize, u64)> = BTreeMap::new();
+    let mut genres: BTreeMap<String, (usize, u64)> = BTreeMap::new();
+    let mut total_ms = 0;
+    for entry in history {
+        let ms = duration(entry);
+        total_ms += ms;
+        let user = users.entry(entry.user_id).or_insert((0, 0, entry.viewed_at));
+        user.0 += 1;
+        user.1 += ms;
+        user.2 = user.2.max(entry.viewed_at);
+        *by_day.entry(entry.viewed_at.date_naive()).or_default() += ms;
+
+        if let (Some(key), Some(title)) = (&entry.show_key, &entry.grandparent_title) {
+            let show = shows.entry((key.clone(), title.clone())).or_default();
+            show.0 += 1;
+            show.1 += ms;
+        }
+        // Episodes rarely carry genres; their show does
+        let genre_source = entry.show_key.as_ref().unwrap_or(&entry.rating_key);
+        for genre in info.get(genre_source).map(|i| i.genres.as_slice()).unwrap_or_default() {
+            let count = genres.entry(genre.clone()).or_default();
+            count.0 += 1;
+            count.1 += ms;
+        }
+    }
+
+    let mut by_week: BTreeMap<NaiveDate, u64> = BTreeMap::new();
+    for (day, ms) in &by_day {
+        let monday = *day - Duration::days(day.weekday().num_days_from_monday() as i64);
+        *by_week.entry(monday).or_default() += ms;
+    }
+
+    let sessions = log.sessions_between(from, to, user);
+    let mut methods = PlayMethods::default();
+    for session in &sessions {
+        match session.method {
+            Some(PlayMethod::Direct) => methods.direct += 1,
+            Some(PlayMethod::DirectStream) => methods.direct_stream += 1,
+            Some(PlayMethod::Transcode) => methods.transcode += 1,
+            None => {}
+        }
+    }
+    let known = methods.direct + methods.direct_stream + methods.transcode;
+    if known > 0 {
+        methods.transcode_ratio = Some((methods.transcode as f64 / known as f64 * 1000.0).round() / 1000.0);
+    }
+
+    let mut users: Vec<UserStats> = users
+        .into_iter()
+        .map(|(user_id, (plays, ms, last_watched))| UserStats {
+            user_id,
+            user_name: names.get(&user_id).cloned(),
+            plays,
+            hours: hours(ms),
+            last_watched,
+        })
+        .collect();
+    users.sort_by(|a, b| b.hours.total_cmp(&a.hours).then(b.plays.cmp(&a.plays)));
+
+    Stats {
+        from,
+        to,
+        plays: history.len(),
+        hours: hours(total_ms),
+        users,
+        hours_by_day: by_day.into_iter().map(|(date, ms)| HoursBucket { date, hours: hours(ms) }).collect(),
+        hours_by_week: by_week.into_iter().map(|(date, ms)| HoursBucket { date, hours: hours(ms) }).collect(),
+        top_shows: top(shows)
+            .into_iter()
+            .map(|((rating_key, title), plays, ms)| TopShow { rating_key, title, plays, hours: hours(ms) })
+            .collect(),
+        top_genres: top(genres).into_iter().map(|(genre, plays, _)| TopGenre { genre, plays }).collect(),
+        play_methods: methods,
+        concurrent_streams: concurrency_peaks(&sessions, &days),
+    }
+}
+
+/// Dashboard numbers for the last `days` days, for everyone or one `user`.
+#[get("")]
+async fn stats(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    log: web::Data<PlaybackLog>,
+    query: web::Query<StatsQuery>,
+) -> Result<impl Responder> {
+    require_admin(&req, &plex.config)?;
+    let (from, to) = range(query.days)?;
+    let (history, names) = tokio::join!(fetch_history(&plex, from, query.user, MAX_HISTORY), account_names(&plex));
+    let history = history?;
+
+    let keys: BTreeSet<&str> = history
+        .iter()
+        .flat_map(|e| [Some(e.rating_key.as_str()), e.show_key.as_deref()])
+        .flatten()
+        .collect();
+    let info = item_info(&plex, keys).await;
+    Ok(HttpResponse::Ok().json(summarize(&history, &info, &names, &log, from, to, query.user)))
+}
+
+/// Recently watched, newest first. Users see their own history; the admin
+/// can look at anyone's with `?user=`, or everyone's by leaving it out.
+#[get("/history")]
+async fn watch_history(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    query: web::Query<StatsQuery>,
+    page: web::Query<HistoryQuery>,
+) -> Result<impl Responder> {
+    let (user_id, _) = PlexClient::user_from_request(&req)
+        .ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))?;
+    let user = if require_admin(&req, &plex.config).is_ok() {
+        query.user
+    } else if query.user.is_none_or(|u| u == user_id) {
+        Some(user_id)
+    } else {
+        return Err(Error::Forbidden("Only the admin can see other users' history".to_string()));
+    };
+    let limit = page.limit.unwrap_or(50);
+    if limit == 0 || limit > 500 {
+        return Err(Error::BadRequest("limit must be between 1 and 500".to_string()));
+    }
+    let (from, _) = range(query.days)?;
+
+    let (entries, names) = tokio::join!(fetch_history(&plex, from, user, limit as u32), account_names(&plex));
+    let mut entries = entries?;
+    for entry in &mut entries {
+        entry.user_name = names.get(&entry.user_id).cloned();
+    }
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/stats").service(stats).service(watch_history));
+}
@@ -0,0 +1,209 @@
+//! Playback sessions as reported to `/api/player/timeline` by Playarr's own
+//! player. Plex's history only says *what* was watched; this log says *how*:
+//! direct play or transcode, how long, and which streams overlapped.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::json_store::JsonFileStore;
+
+/// Sessions kept, oldest dropped first.
+const LOG_CAPACITY: usize = 5000;
+/// The player reports every few seconds while playing; a longer gap is a
+/// closed tab or a suspended laptop, not time spent watching.
+const MAX_REPORT_GAP_SECS: i64 = 60;
+/// Open sessions with no report for this long count as ended.
+const STALE_AFTER_SECS: i64 = 300;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlayMethod {
+    Direct,
+    #[serde(rename = "directstream")]
+    DirectStream,
+    /// The player calls these `hls` streams.
+    #[serde(alias = "hls")]
+    Transcode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackSession {
+    /// Plex client identifier of the browser tab.
+    pub client_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i64>,
+    pub rating_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<PlayMethod>,
+    pub state: String,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<DateTime<Utc>>,
+    pub watched_secs: i64,
+}
+
+impl PlaybackSession {
+    fn is_open(&self, now: DateTime<Utc>) -> bool {
+        self.ended_at.is_none() && now - self.updated_at < Duration::seconds(STALE_AFTER_SECS)
+    }
+
+    /// When the session stopped or went quiet; now if it is still playing.
+    pub fn end(&self) -> DateTime<Utc> {
+        let now = Utc::now();
+        match self.ended_at {
+            Some(ended) => ended,
+            None if self.is_open(now) => now,
+            None => self.updated_at,
+        }
+    }
+}
+
+/// One `/api/player/timeline` call.
+pub struct TimelineReport<'a> {
+    pub client_id: &'a str,
+    pub user_id: Option<i64>,
+    pub rating_key: &'a str,
+    pub state: &'a str,
+    pub method: Option<PlayMethod>,
+}
+
+/// Written to `playback.json` in the data directory whenever a session ends.
+pub struct PlaybackLog {
+    sessions: JsonFileStore<VecDeque<PlaybackSession>>,
+}
+
+impl PlaybackLog {
+    pub fn new() -> Self {
+        Self { sessions: JsonFileStore::open("playback.json") }
+    }
+
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self { sessions: JsonFileStore::in_memory() }
+    }
+
+    pub fn record(&self, report: TimelineReport) {
+        let now = Utc::now();
+        let Ok(mut sessions) = self.sessions.write() else { return };
+        let stopped = report.state == "stopped";
+        let mut closed = false;
+
+        let current = sessions
+            .iter_mut()
+            .rev()
+            .find(|s| s.client_id == report.client_id && s.ended_at.is_none());
+        if let Some(session) = current {
+            if session.rating_key == report.rating_key && session.is_open(now) {
+                if session.state == "playing" {
+                    session.watched_secs += (now - session.updated_at).num_seconds().clamp(0, MAX_REPORT_GAP_SECS);
+                }
+                session.state = report.state.to_string();
+                session.updated_at = now;
+                session.method = report.method.or(session.method);
+                if stopped {
+                    session.ended_at = Some(now);
+                }
+                drop(sessions);
+                if stopped {
+                    self.sessions.save();
+                }
+                return;
+            }
+            // The tab moved on to another item, or came back after going quiet
+            session.ended_at = Some(session.updated_at);
+            closed = true;
+        }
+
+        if !stopped {
+            if sessions.len() >= LOG_CAPACITY {
+                sessions.pop_front();
+            }
+            sessions.push_back(PlaybackSession {
+                client_id: report.client_id.to_string(),
+                user_id: report.user_id,
+                rating_key: report.rating_key.to_string(),
+                method: report.method,
+                state: report.state.to_string(),
+                started_at: now,
+                updated_at: now,
+                ended_at: None,
+                watched_secs: 0,
+            });
+        }
+        drop(sessions);
+        if closed {
+            self.sessions.save();
+        }
+    }
+
+    /// Sessions that overlap `from..to`, optionally for one user.
+    pub fn sessions_between(&self, from: DateTime<Utc>, to: DateTime<Utc>, user: Option<i64>) -> Vec<PlaybackSession> {
+        self.sessions
+            .read()
+            .map(|sessions| {
+                sessions
+                    .iter()
+                    .filter(|s| s.started_at < to && s.end() >= from)
+                    .filter(|s| user.is_none_or(|u| s.user_id == Some(u)))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcurrencyPeaks {
+    pub peak: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_at: Option<DateTime<Utc>>,
+    pub by_day: Vec<DayPeak>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DayPeak {
+    pub date: NaiveDate,
+    pub peak: usize,
+}
+
+/// Most streams running at once, overall and per UTC day of `days`.
+pub fn concurrency_peaks(sessions: &[PlaybackSession], days: &[NaiveDate]) -> ConcurrencyPeaks {
+    // Ends sort before starts at the same instant, so back-to-back
+    // episodes on one tab don't count as two streams.
+    let mut events: Vec<(DateTime<Utc>, i32)> = sessions
+        .iter()
+        .flat_map(|s| [(s.started_at, 1), (s.end(), -1)])
+        .collect();
+    events.sort();
+
+    let mut by_day: BTreeMap<NaiveDate, usize> = days.iter().map(|d| (*d, 0)).collect();
+    let mut peaks = ConcurrencyPeaks::default();
+    let mut running: usize = 0;
+    let mut last_day: Option<NaiveDate> = None;
+    for (at, delta) in events {
+        let day = at.date_naive();
+        // Streams still running at midnight count towards the next day too
+        if let Some(previous) = last_day.filter(|d| *d < day) {
+            for (_, peak) in by_day.range_mut(previous.succ_opt().unwrap_or(previous)..=day) {
+                *peak = (*peak).max(running);
+            }
+        }
+        last_day = Some(day);
+
+        running = running.saturating_add_signed(delta as isize);
+        if let Some(peak) = by_day.get_mut(&day) {
+            *peak = (*peak).max(running);
+        }
+        if running > peaks.peak {
+            peaks.peak = running;
+            peaks.peak_at = Some(at);
+        }
+    }
+    peaks.by_day = by_day.into_iter().map(|(date, peak)| DayPeak { date, peak }).collect();
+    peaks
+}
@@ -5,8 +5,9 @@ use log::warn;
 use serde::Serialize;
 use serde_json::json;
 
-use crate::config::models::DownloadClientType;
+use crate::config::models::{ArrInstanceConfig, DownloadClientType};
 use crate::config::SharedConfig;
+use crate::discover::client::TmdbClient;
 use crate::http_error::Result;
 use crate::plex::client::PlexClient;
 use crate::radarr::client::RadarrClient;
@@ -38,6 +39,9 @@ pub struct ServiceStatuses {
     plex: ServiceHealth,
     sonarr: ServiceHealth,
     radarr: ServiceHealth,
+    sonarr_instances: Vec<ArrInstanceHealth>,
+    radarr_instances: Vec<ArrInstanceHealth>,
+    tmdb: ServiceHealth,
     download_clients: Vec<DownloadClientHealth>,
 }
 
@@ -50,6 +54,14 @@ struct ServiceHealth {
     last_checked: Option<u64>,
 }
 
+#[derive(Serialize, Clone)]
+struct ArrInstanceHealth {
+    name: String,
+    is_default: bool,
+    #[serde(flatten)]
+    health: ServiceHealth,
+}
+
 #[derive(Serialize, Clone)]
 struct DownloadClientHealth {
     name: String,
@@ -80,11 +92,12 @@ pub async fn run_health_checks(
     plex: web::Data<PlexClient>,
     sonarr: web::Data<SonarrClient>,
     radarr: web::Data<RadarrClient>,
+    tmdb: web::Data<TmdbClient>,
 ) {
     let mut interval = actix_web::rt::time::interval(std::time::Duration::from_secs(30));
     loop {
         interval.tick().await;
-        let statuses = check_all(&config, &plex, &sonarr, &radarr).await;
+        let statuses = check_all(&config, &plex, &sonarr, &radarr, &tmdb).await;
         if let Ok(mut guard) = state.write() {
             *guard = statuses;
         }
@@ -96,6 +109,7 @@ async fn check_all(
     plex: &PlexClient,
     sonarr: &SonarrClient,
     radarr: &RadarrClient,
+    tmdb: &TmdbClient,
 ) -> ServiceStatuses {
     // Read config snapshot once
     let cfg = match config.read() {
@@ -106,10 +120,11 @@ async fn check_all(
     let now = chrono::Utc::now().timestamp_millis() as u64;
 
     // Run all checks concurrently
-    let (plex_health, sonarr_health, radarr_health, dl_healths) = tokio::join!(
+    let (plex_health, (sonarr_health, sonarr_instances), (radarr_health, radarr_instances), tmdb_health, dl_healths) = tokio::join!(
         check_plex(plex, &cfg.plex, now),
-        check_arr(sonarr, &cfg.sonarr.url, &cfg.sonarr.api_key, now),
-        check_arr(radarr, &cfg.radarr.url, &cfg.radarr.api_key, now),
+        check_arr_instances(sonarr, &cfg.sonarr, now),
+        check_arr_instances(radarr, &cfg.radarr, now),
+        check_tmdb(tmdb, now),
         check_download_clients(&cfg.download_clients, now),
     );
 
@@ -117,6 +132,9 @@ async fn check_all(
         plex: plex_health,
         sonarr: sonarr_health,
         radarr: radarr_health,
+        sonarr_instances,
+        radarr_instances,
+        tmdb: tmdb_health,
         download_clients: dl_healths,
     }
 }
@@ -184,6 +202,35 @@ async fn check_plex(
     }
 }
 
+/// Check every Sonarr or Radarr instance. The first value is the default
+/// instance's health, kept for clients that only know a single instance.
+async fn check_arr_instances<C: HasHttp>(
+    client: &C,
+    instances: &[ArrInstanceConfig],
+    now: u64,
+) -> (ServiceHealth, Vec<ArrInstanceHealth>) {
+    let checks = instances.iter().map(|i| check_arr(client, &i.url, &i.api_key, now));
+    let healths = futures_util::future::join_all(checks).await;
+
+    let default = crate::config::models::default_instance(instances);
+    let summary = instances
+        .iter()
+        .zip(&healths)
+        .find(|(i, _)| Some(*i) == default)
+        .map(|(_, h)| h.clone())
+        .unwrap_or_default();
+    let per_instance = instances
+        .iter()
+        .zip(healths)
+        .map(|(i, health)| ArrInstanceHealth {
+            name: i.label().to_string(),
+            is_default: Some(i) == default,
+            health,
+        })
+        .collect();
+    (summary, per_instance)
+}
+
 /// Check Sonarr or Radarr — both expose `/api/v3/system/status`.
 async fn check_arr<C: HasHttp>(client: &C, url: &str, api_key: &str, now: u64) -> ServiceHealth {
     let configured = !url.is_empty() && !api_key.is_empty();
@@ -241,6 +288,38 @@ async fn check_arr<C: HasHttp>(client: &C, url: &str, api_key: &str, now: u64) -
     }
 }
 
+/// Check TMDB via `/configuration`, which is cheap and requires valid credentials.
+async fn check_tmdb(client: &TmdbClient, now: u64) -> ServiceHealth {
+    let req = match client.get("/configuration") {
+        Ok(req) => req,
+        Err(_) => {
+            return ServiceHealth {
+                configured: false,
+                ..Default::default()
+            };
+        }
+    };
+
+    let reachable = match req.timeout(std::time::Duration::from_secs(5)).send().await {
+        Ok(r) if r.status().is_success() => true,
+        Ok(r) => {
+            warn!("TMDB health check returned HTTP {}", r.status());
+            false
+        }
+        Err(e) => {
+            warn!("TMDB health check failed: {}", e);
+            false
+        }
+    };
+
+    ServiceHealth {
+        configured: true,
+        reachable: Some(reachable),
+        version: None,
+        last_checked: Some(now),
+    }
+}
+
 /// Minimal trait so we can reuse `check_arr` for both SonarrClient and RadarrClient.
 trait HasHttp {
     fn http_client(&self) -> &reqwest::Client;
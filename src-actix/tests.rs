@@ -9,7 +9,10 @@ use crate::config::SharedConfig;
 use crate::plex::client::PlexClient;
 use crate::sonarr::client::SonarrClient;
 use crate::radarr::client::RadarrClient;
+use crate::discover::client::TmdbClient;
 use crate::watch_party::room::RoomManager;
+use crate::events::EventBus;
+use crate::cache::ResponseCache;
 
 fn mock_config(plex_url: &str, plex_token: &str) -> SharedConfig {
     Arc::new(RwLock::new(AppConfig {
@@ -19,10 +22,10 @@ fn mock_config(plex_url: &str, plex_token: &str) -> SharedConfig {
             client_id: "test-client-id".to_string(),
             admin_user_id: 0,
         },
-        sonarr: SonarrConfig::default(),
-        radarr: RadarrConfig::default(),
+        sonarr: vec![],
+        radarr: vec![],
         download_clients: vec![],
-        opensubtitles: Default::default(),
+        ..Default::default()
     }))
 }
 
@@ -34,27 +37,42 @@ fn full_mock_config(plex_url: &str, sonarr_url: &str, radarr_url: &str) -> Share
             client_id: "test-client-id".to_string(),
             admin_user_id: 0,
         },
-        sonarr: SonarrConfig {
+        sonarr: vec![SonarrConfig {
             url: sonarr_url.to_string(),
             api_key: "sonarr-key".to_string(),
-        },
-        radarr: RadarrConfig {
+            is_default: true,
+            ..Default::default()
+        }],
+        radarr: vec![RadarrConfig {
             url: radarr_url.to_string(),
             api_key: "radarr-key".to_string(),
-        },
+            is_default: true,
+            ..Default::default()
+        }],
         download_clients: vec![],
-        opensubtitles: Default::default(),
+        ..Default::default()
     }))
 }
 
 /// Macro to build the test app inline so the compiler can infer all types.
 macro_rules! test_app {
-    ($config:expr) => {{
+    ($config:expr) => {
+        test_app!($config, crate::discover::client::TMDB_BASE)
+    };
+    ($config:expr, $tmdb_url:expr) => {{
         let sc: SharedConfig = $config;
+        let tmdb_client = web::Data::new(TmdbClient::with_base_url(sc.clone(), $tmdb_url));
         let plex_client = web::Data::new(PlexClient::new(sc.clone()));
         let sonarr_client = web::Data::new(SonarrClient::new(sc.clone()));
         let radarr_client = web::Data::new(RadarrClient::new(sc.clone()));
         let room_manager = web::Data::new(RoomManager::new());
+        let event_bus = web::Data::new(EventBus::new());
+        let response_cache = web::Data::new(ResponseCache::new(sc.clone()));
+        let command_tracker = web::Data::new(crate::commands::CommandTracker::new());
+        let watchlist_store = web::Data::new(crate::watchlist::WatchlistStore::in_memory());
+        let playback_log = web::Data::new(crate::stats::playback::PlaybackLog::in_memory());
+        let offline_manager = web::Data::new(crate::offline::OfflineManager::in_memory(sc.clone()));
+        let image_service = web::Data::new(crate::images::ImageService::with_tmdb_base(sc.clone(), $tmdb_url));
         let config_data = web::Data::new(sc);
         test::init_service(
             App::new()
@@ -62,7 +80,15 @@ macro_rules! test_app {
                 .app_data(plex_client)
                 .app_data(sonarr_client)
                 .app_data(radarr_client)
+                .app_data(tmdb_client)
                 .app_data(room_manager)
+                .app_data(event_bus)
+                .app_data(response_cache)
+                .app_data(command_tracker)
+                .app_data(watchlist_store)
+                .app_data(playback_log)
+                .app_data(offline_manager)
+                .app_data(image_service)
                 .service(
                     web::scope("/api")
                         .configure(crate::settings::endpoints::configure)
@@ -70,6 +96,7 @@ macro_rules! test_app {
                         .configure(crate::plex::libraries::configure)
                         .configure(crate::plex::media::configure)
                         .configure(crate::plex::hub::configure)
+                        .configure(crate::search::configure)
                         .configure(crate::plex::search::configure)
                         .configure(crate::plex::timeline::configure)
                         .configure(|cfg: &mut web::ServiceConfig| {
@@ -77,21 +104,37 @@ macro_rules! test_app {
                                 web::scope("/sonarr")
                                     .configure(crate::sonarr::series::configure)
                                     .configure(crate::sonarr::episodes::configure)
+                                    .configure(crate::sonarr::files::configure)
                                     .configure(crate::sonarr::calendar::configure)
-                                    .configure(crate::sonarr::queue::configure),
+                                    .configure(crate::sonarr::releases::configure)
+                                    .configure(crate::arr::handlers::configure::<crate::arr::Sonarr>),
                             );
                         })
                         .configure(|cfg: &mut web::ServiceConfig| {
                             cfg.service(
                                 web::scope("/radarr")
                                     .configure(crate::radarr::movies::configure)
+                                    .configure(crate::radarr::files::configure)
+                                    .configure(crate::radarr::collections::configure)
+                                    .configure(crate::radarr::exclusions::configure)
                                     .configure(crate::radarr::calendar::configure)
-                                    .configure(crate::radarr::queue::configure),
+                                    .configure(crate::radarr::releases::configure)
+                                    .configure(crate::arr::handlers::configure::<crate::arr::Radarr>),
                             );
                         })
                         .configure(crate::discover::tmdb::configure)
                         .configure(crate::downloads::configure)
-                        .configure(crate::watch_party::configure),
+                        .configure(crate::watch_party::configure)
+                        .configure(crate::webhooks::configure)
+                        .configure(crate::events::configure)
+                        .configure(crate::cache::configure)
+                        .configure(crate::calendar::configure)
+                        .configure(crate::commands::configure)
+                        .configure(crate::watchlist::configure)
+                        .configure(crate::stats::configure)
+                        .configure(crate::offline::configure)
+                        .configure(crate::images::configure)
+                        .configure(crate::plex::sessions::configure),
                 ),
         )
         .await
@@ -279,8 +322,63 @@ async fn settings_update_sonarr() {
     assert_eq!(resp.status(), 200);
 
     let cfg = config.read().unwrap();
-    assert_eq!(cfg.sonarr.url, "http://sonarr:8989");
-    assert_eq!(cfg.sonarr.api_key, "test-key");
+    assert_eq!(cfg.sonarr.len(), 1);
+    assert_eq!(cfg.sonarr[0].url, "http://sonarr:8989");
+    assert_eq!(cfg.sonarr[0].api_key, "test-key");
+    assert!(cfg.sonarr[0].is_default);
+}
+
+#[actix_rt::test]
+async fn settings_update_sonarr_instances() {
+    let config = full_mock_config("", "http://sonarr:8989", "");
+    let app = test_app!(config.clone());
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+
+    // Empty api_key keeps the saved one for the same name
+    let req = test::TestRequest::put()
+        .uri("/api/settings/sonarr")
+        .cookie(admin.clone())
+        .set_json(json!([
+            { "url": "http://sonarr:8989", "api_key": "", "is_default": true },
+            { "name": "anime", "url": "http://sonarr-anime:8989", "api_key": "anime-key", "tags": ["anime"] }
+        ]))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["sonarr_instances"][1]["name"], "anime");
+    assert_eq!(body["sonarr_instances"][1]["has_api_key"], true);
+
+    {
+        let cfg = config.read().unwrap();
+        assert_eq!(cfg.sonarr.len(), 2);
+        assert_eq!(cfg.sonarr[0].api_key, "sonarr-key");
+        assert_eq!(cfg.sonarr[1].tags, vec!["anime".to_string()]);
+    }
+
+    let req = test::TestRequest::put()
+        .uri("/api/settings/sonarr")
+        .cookie(admin.clone())
+        .set_json(json!([
+            { "name": "a", "url": "http://a", "api_key": "k", "is_default": true },
+            { "name": "b", "url": "http://b", "api_key": "k", "is_default": true }
+        ]))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    assert_eq!(config.read().unwrap().sonarr.len(), 2);
+}
+
+#[actix_rt::test]
+async fn legacy_single_arr_table_is_read_as_default_instance() {
+    let cfg: AppConfig = toml::from_str(
+        "[sonarr]\nurl = \"http://sonarr:8989\"\napi_key = \"k\"\n\n[radarr]\nurl = \"\"\napi_key = \"\"\n",
+    )
+    .unwrap();
+    assert_eq!(cfg.sonarr.len(), 1);
+    assert_eq!(cfg.sonarr[0].label(), "default");
+    assert!(cfg.sonarr[0].is_default);
+    assert!(cfg.radarr.is_empty());
 }
 
 #[actix_rt::test]
@@ -296,7 +394,7 @@ async fn settings_update_radarr() {
     assert_eq!(resp.status(), 200);
 
     let cfg = config.read().unwrap();
-    assert_eq!(cfg.radarr.url, "http://radarr:7878");
+    assert_eq!(cfg.radarr[0].url, "http://radarr:7878");
 }
 
 #[actix_rt::test]
@@ -449,6 +547,227 @@ async fn library_items_returns_paginated() {
     assert_eq!(body["items"][0]["title"], "Movie A");
 }
 
+#[actix_rt::test]
+async fn library_items_translates_filters() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/library/sections/1/all"))
+        .and(query_param("unwatched", "1"))
+        .and(query_param("genre", "Comedy"))
+        .and(query_param("year>>", "1989"))
+        .and(query_param("year<<", "2000"))
+        .and(query_param("resolution", "4k"))
+        .and(query_param("hdr", "1"))
+        .and(query_param("actor", "42"))
+        .and(query_param("addedAt>>", "-30d"))
+        // Unwatched is the user's own watch state
+        .and(query_param("X-Plex-Token", "server-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "totalSize": 1, "offset": 0, "size": 1, "Metadata": [{"ratingKey": "10", "title": "Movie A"}] }
+        })))
+        // Once per user; the second call for user 1 is cached
+        .expect(2)
+        .mount(&mock_server).await;
+
+    let app = test_app!(mock_config(&mock_server.uri(), "valid-token"));
+
+    let uri = "/api/libraries/1/items?unwatched=true&genre=Comedy&yearFrom=1990&yearTo=1999&resolution=4K&hdr=true&actor=42&addedSince=30d";
+    let other = actix_web::cookie::Cookie::new("plex_user_token", "2:tv-token:server-token");
+    for cookie in [user_cookie(), user_cookie(), other] {
+        let req = test::TestRequest::get().uri(uri).cookie(cookie).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["items"][0]["title"], "Movie A");
+    }
+
+    for bad in ["yearFrom=2000&yearTo=1990", "decade=1995", "resolution=8k", "addedSince=soon", "addedSince=3%C3%A9", "yearTo=4294967295"] {
+        let req = test::TestRequest::get().uri(&format!("/api/libraries/1/items?{}", bad)).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400, "{} should be rejected", bad);
+    }
+}
+
+#[actix_rt::test]
+async fn library_first_character_index_and_random_pick() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/library/sections/1/firstCharacter"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Directory": [
+                {"key": "%23", "title": "#", "size": 3},
+                {"key": "A", "title": "A", "size": 10},
+                {"key": "B", "title": "B", "size": 4}
+            ]}
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/library/sections/1/all"))
+        .and(query_param("sort", "random"))
+        .and(query_param("X-Plex-Container-Size", "1"))
+        .and(query_param("unwatched", "1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{"ratingKey": "77", "title": "Lucky Pick"}] }
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/library/sections/2/all"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "MediaContainer": { "size": 0 } })))
+        .mount(&mock_server).await;
+
+    let app = test_app!(mock_config(&mock_server.uri(), "valid-token"));
+
+    let req = test::TestRequest::get().uri("/api/libraries/1/firstCharacter").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body[1]["character"], "A");
+    assert_eq!(body[1]["offset"], 3);
+    assert_eq!(body[2]["offset"], 13);
+
+    let req = test::TestRequest::get().uri("/api/libraries/1/random?unwatched=true").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["title"], "Lucky Pick");
+
+    let req = test::TestRequest::get().uri("/api/libraries/2/random").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+}
+
+#[actix_rt::test]
+async fn collection_built_from_tmdb_matches_by_guid() {
+    let plex = MockServer::start().await;
+    let tmdb = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "machineIdentifier": "abc123" }
+        })))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/sections"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Directory": [{"key": "1", "type": "movie", "title": "Movies"}] }
+        })))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/sections/1/all"))
+        .and(query_param("includeGuids", "1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [
+                {"ratingKey": "20", "title": "A New Hope", "Guid": [{"id": "tmdb://11"}]},
+                {"ratingKey": "21", "title": "Empire", "guid": "com.plexapp.agents.themoviedb://1891?lang=en"},
+                {"ratingKey": "22", "title": "Unrelated", "Guid": [{"id": "tmdb://999"}]}
+            ]}
+        })))
+        .mount(&plex).await;
+    Mock::given(method("POST"))
+        .and(path("/library/collections"))
+        .and(query_param("type", "1"))
+        .and(query_param("sectionId", "1"))
+        .and(query_param("title", "Star Wars Collection"))
+        .and(query_param("uri", "server://abc123/com.plexapp.plugins.library/library/metadata/20,21"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{"ratingKey": "300", "title": "Star Wars Collection"}] }
+        })))
+        .expect(1)
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/collection/10"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 10, "name": "Star Wars Collection",
+            "parts": [
+                {"id": 11, "title": "Star Wars"},
+                {"id": 1891, "title": "The Empire Strikes Back"},
+                {"id": 1892, "title": "Return of the Jedi"}
+            ]
+        })))
+        .mount(&tmdb).await;
+
+    let config = mock_config(&plex.uri(), "admin-token");
+    config.write().unwrap().tmdb = TmdbConfig { api_key: "tmdb-key".to_string(), read_access_token: String::new() };
+    let app = test_app!(config, &tmdb.uri());
+
+    let req = test::TestRequest::post()
+        .uri("/api/libraries/1/collections/tmdb")
+        .cookie(user_cookie())
+        .set_json(json!({"tmdbCollectionId": 10}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401, "collection changes are admin only");
+
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+    let req = test::TestRequest::post()
+        .uri("/api/libraries/1/collections/tmdb")
+        .cookie(admin.clone())
+        .set_json(json!({"tmdbCollectionId": 10, "tmdbListId": "42"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+
+    let req = test::TestRequest::post()
+        .uri("/api/libraries/1/collections/tmdb")
+        .cookie(admin)
+        .set_json(json!({"tmdbCollectionId": 10}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["collection"], "300");
+    assert_eq!(body["matched"].as_array().unwrap().len(), 2);
+    assert_eq!(body["missing"][0]["tmdbId"], 1892);
+    assert_eq!(body["missing"][0]["mediaType"], "movie");
+
+    // A list mixes movies and shows, whose TMDB ids overlap: show 1891 is not Empire
+    Mock::given(method("GET"))
+        .and(path("/list/42"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "Favourites",
+            "items": [
+                {"media_type": "tv", "id": 1891, "name": "Some Show"},
+                {"media_type": "movie", "id": 11, "title": "Star Wars"}
+            ]
+        })))
+        .mount(&tmdb).await;
+    Mock::given(method("PUT"))
+        .and(path("/library/collections/300/items"))
+        .and(query_param("uri", "server://abc123/com.plexapp.plugins.library/library/metadata/20"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&plex).await;
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+    let req = test::TestRequest::post()
+        .uri("/api/libraries/1/collections/tmdb")
+        .cookie(admin.clone())
+        .set_json(json!({"tmdbListId": "42", "collection": 300}))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["matched"].as_array().unwrap().len(), 1);
+    assert_eq!(body["missing"][0]["tmdbId"], 1891);
+    assert_eq!(body["missing"][0]["mediaType"], "tv");
+
+    // TMDB errors other than 404 are not swallowed as an empty source
+    Mock::given(method("GET"))
+        .and(path("/collection/77"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&tmdb).await;
+    Mock::given(method("GET"))
+        .and(path("/collection/78"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&tmdb).await;
+    for (id, status) in [(77, 401), (78, 502)] {
+        let req = test::TestRequest::post()
+            .uri("/api/libraries/1/collections/tmdb")
+            .cookie(admin.clone())
+            .set_json(json!({"tmdbCollectionId": id}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), status);
+    }
+}
+
 #[actix_rt::test]
 async fn library_recent_returns_metadata() {
     let mock_server = MockServer::start().await;
@@ -538,6 +857,332 @@ async fn media_get_children() {
     assert_eq!(body[0]["title"], "Season 1");
 }
 
+#[actix_rt::test]
+async fn media_metadata_edit_and_match_are_admin_only() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("PUT"))
+        .and(path("/library/metadata/50"))
+        .and(query_param("title.value", "The Thing"))
+        .and(query_param("title.locked", "1"))
+        .and(query_param("thumb.locked", "0"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/50"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{"ratingKey": "50", "title": "The Thing"}] }
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/50/matches"))
+        .and(query_param("manual", "1"))
+        .and(query_param("year", "1982"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "SearchResult": [
+                {"guid": "plex://movie/b", "name": "The Thing", "year": 2011, "score": 80},
+                {"guid": "plex://movie/a", "name": "The Thing", "year": 1982, "score": 100}
+            ]}
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("PUT"))
+        .and(path("/library/metadata/50/match"))
+        .and(query_param("guid", "plex://movie/a"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(mock_config(&mock_server.uri(), "admin-token"));
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+
+    let req = test::TestRequest::put()
+        .uri("/api/media/50/metadata")
+        .cookie(user_cookie())
+        .set_json(json!({"title": "The Thing"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+
+    let req = test::TestRequest::put()
+        .uri("/api/media/50/metadata")
+        .cookie(admin.clone())
+        .set_json(json!({"locks": {"rating": true}}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+
+    let req = test::TestRequest::put()
+        .uri("/api/media/50/metadata")
+        .cookie(admin.clone())
+        .set_json(json!({"title": "The Thing", "locks": {"thumb": false}}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["title"], "The Thing");
+
+    let req = test::TestRequest::get().uri("/api/media/50/matches?year=1982").cookie(admin.clone()).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body[0]["guid"], "plex://movie/a", "best score first");
+
+    let req = test::TestRequest::put()
+        .uri("/api/media/50/match")
+        .cookie(admin)
+        .set_json(json!({"guid": "plex://movie/a", "name": "The Thing", "year": 1982}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 202);
+}
+
+#[actix_rt::test]
+async fn play_intent_resumes_and_crosses_season_boundary() {
+    let plex = MockServer::start().await;
+    let episode = json!({
+        "ratingKey": "110", "type": "episode", "title": "Finale", "grandparentRatingKey": "100",
+        "parentIndex": 1, "index": 10, "viewOffset": 600000, "duration": 2400000,
+        "Marker": [
+            {"type": "intro", "startTimeOffset": 30000, "endTimeOffset": 90000},
+            {"type": "credits", "startTimeOffset": 2000000, "endTimeOffset": 2050000},
+            {"type": "credits", "startTimeOffset": 2200000, "endTimeOffset": 2400000, "final": true}
+        ]
+    });
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/110"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"MediaContainer": {"Metadata": [episode]}})))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/100"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": {"Metadata": [{"ratingKey": "100", "type": "show", "title": "The Show"}]}
+        })))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/100/allLeaves"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": {"Metadata": [
+                {"ratingKey": "201", "parentIndex": 2, "index": 1, "title": "Premiere"},
+                {"ratingKey": "110", "parentIndex": 1, "index": 10, "viewOffset": 600000, "lastViewedAt": 5},
+                {"ratingKey": "50", "parentIndex": 0, "index": 1, "title": "Behind the Scenes"},
+                {"ratingKey": "109", "parentIndex": 1, "index": 9, "viewCount": 1}
+            ]}
+        })))
+        .mount(&plex).await;
+
+    let app = test_app!(mock_config(&plex.uri(), "valid-token"));
+
+    let req = test::TestRequest::get().uri("/api/media/110/play-intent").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["resume"]["offsetMs"], 600000);
+    assert_eq!(body["resume"]["prompt"], true);
+    assert_eq!(body["markers"]["intro"]["endMs"], 90000);
+    assert_eq!(body["markers"]["credits"]["startMs"], 2200000, "final credits, not the mid-credits scene");
+    assert_eq!(body["nextUpAtMs"], 2200000);
+    assert_eq!(body["next"]["ratingKey"], "201", "skips the special and moves to season 2");
+
+    let req = test::TestRequest::get().uri("/api/media/100/play-intent").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["item"]["ratingKey"], "110", "a show resolves to the episode in progress");
+    assert_eq!(body["next"]["ratingKey"], "201");
+}
+
+fn test_jpeg(width: u32, height: u32) -> Vec<u8> {
+    let mut jpeg = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new(&mut jpeg)
+        .encode_image(&image::RgbImage::new(width, height))
+        .unwrap();
+    jpeg
+}
+
+#[actix_rt::test]
+async fn trickplay_packs_bif_frames_or_falls_back_to_chapters() {
+    let plex = MockServer::start().await;
+    let frame = test_jpeg(320, 180);
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/10"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{
+                "ratingKey": "10", "duration": 900000,
+                "Media": [{"Part": [{"id": 5}]}],
+                "Chapter": [
+                    {"startTimeOffset": 0, "endTimeOffset": 300000, "thumb": "/library/media/4/chapterImages/1"},
+                    {"startTimeOffset": 300000, "endTimeOffset": 900000, "thumb": "/library/media/4/chapterImages/2"}
+                ]
+            }]}
+        })))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/parts/5/indexes/sd"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/photo/:/transcode"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(frame.clone()))
+        .expect(2)
+        .mount(&plex).await;
+
+    // A BIF with frames at 0s and 10s for a 20s item
+    let mut bif = vec![0x89, b'B', b'I', b'F', 0x0d, 0x0a, 0x1a, 0x0a];
+    bif.extend_from_slice(&0u32.to_le_bytes());
+    bif.extend_from_slice(&2u32.to_le_bytes());
+    bif.extend_from_slice(&1000u32.to_le_bytes());
+    bif.resize(64, 0);
+    let data_start = 64 + 3 * 8;
+    for (i, (time, offset)) in [(0, data_start), (10, data_start + frame.len()), (u32::MAX, data_start + 2 * frame.len())]
+        .into_iter()
+        .enumerate()
+    {
+        assert_eq!(bif.len(), 64 + i * 8);
+        bif.extend_from_slice(&time.to_le_bytes());
+        bif.extend_from_slice(&(offset as u32).to_le_bytes());
+    }
+    bif.extend_from_slice(&frame);
+    bif.extend_from_slice(&frame);
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/11"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{"ratingKey": "11", "duration": 20000, "Media": [{"Part": [{"id": 6}]}]}]}
+        })))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/parts/6/indexes/sd"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(bif.clone()))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/12"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{"ratingKey": "12", "duration": 20000, "Media": [{"Part": [{"id": 7}]}]}]}
+        })))
+        .mount(&plex).await;
+
+    let app = test_app!(mock_config(&plex.uri(), "test-token"));
+
+    let req = test::TestRequest::get().uri("/api/media/10/trickplay.vtt").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let vtt = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(vtt.starts_with("WEBVTT"));
+    assert!(vtt.contains("00:00:00.000 --> 00:05:00.000\n/api/media/trickplay/5/0.jpg?v=chapters#xywh=0,0,240,135"), "{}", vtt);
+    assert!(vtt.contains("00:05:00.000 --> 00:15:00.000\n/api/media/trickplay/5/0.jpg?v=chapters#xywh=240,0,240,135"), "{}", vtt);
+
+    // Served from the cache the second time; the photo mock expects two calls
+    let req = test::TestRequest::get().uri("/api/media/10/trickplay.vtt").to_request();
+    assert_eq!(test::call_and_read_body(&app, req).await, vtt.as_bytes());
+
+    let req = test::TestRequest::get().uri("/api/media/trickplay/5/0.jpg").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let sheet = image::load_from_memory(&test::read_body(resp).await).unwrap();
+    assert_eq!((sheet.width(), sheet.height()), (480, 135));
+
+    let req = test::TestRequest::get().uri("/api/media/11/trickplay.vtt").to_request();
+    let vtt = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    assert!(vtt.contains("00:00:00.000 --> 00:00:10.000\n/api/media/trickplay/6/0.jpg?v=bif#xywh=0,0,240,135"), "{}", vtt);
+    assert!(vtt.contains("00:00:10.000 --> 00:00:20.000\n/api/media/trickplay/6/0.jpg?v=bif#xywh=240,0,240,135"), "{}", vtt);
+
+    let req = test::TestRequest::get().uri("/api/media/trickplay/6/3.jpg").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+
+    // Neither an index nor chapters: nothing to build from
+    let req = test::TestRequest::get().uri("/api/media/12/trickplay.vtt").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+
+    // Once Plex has built the index, the chapter sheets are replaced
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/10"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{"ratingKey": "10", "duration": 20000, "Media": [{"Part": [{"id": 5, "indexes": "sd"}]}]}]}
+        })))
+        .with_priority(1)
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/parts/5/indexes/sd"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(bif))
+        .with_priority(1)
+        .mount(&plex).await;
+    let req = test::TestRequest::get().uri("/api/media/10/trickplay.vtt").to_request();
+    let vtt = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    assert!(vtt.contains("00:00:10.000 --> 00:00:20.000\n/api/media/trickplay/5/0.jpg?v=bif#xywh=240,0,240,135"), "{}", vtt);
+}
+
+#[actix_rt::test]
+async fn images_resize_convert_and_cache_on_disk() {
+    let plex = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/10/thumb"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(test_jpeg(400, 600)))
+        .expect(1)
+        .mount(&plex).await;
+    let mut logo = Vec::new();
+    image::RgbaImage::new(200, 100)
+        .write_to(&mut std::io::Cursor::new(&mut logo), image::ImageFormat::Png)
+        .unwrap();
+    // w=50 fetches TMDB's smallest size; the blurhash needs the original
+    Mock::given(method("GET"))
+        .and(path("/w92/logo.png"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(logo.clone()))
+        .expect(1)
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/original/logo.png"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(logo))
+        .expect(1)
+        .mount(&plex).await;
+
+    let app = test_app!(mock_config(&plex.uri(), "test-token"), &plex.uri());
+
+    let thumb = || test::TestRequest::get()
+        .uri("/api/media/10/thumb?width=100")
+        .insert_header(("Accept", "image/webp,image/*"));
+    let resp = test::call_service(&app, thumb().to_request()).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("content-type").unwrap(), "image/webp");
+    assert_eq!(resp.headers().get("vary").unwrap(), "Accept");
+    assert_eq!(resp.headers().get("cache-control").unwrap(), "public, max-age=86400", "bare /thumb paths aren't versioned");
+    let etag = resp.headers().get("etag").unwrap().clone();
+    let img = image::load_from_memory(&test::read_body(resp).await).unwrap();
+    assert_eq!((img.width(), img.height()), (100, 150));
+
+    // Second time from disk (the Plex mock expects one call), then 304
+    let resp = test::call_service(&app, thumb().to_request()).await;
+    assert_eq!(resp.status(), 200);
+    let resp = test::call_service(&app, thumb().insert_header(("If-None-Match", etag)).to_request()).await;
+    assert_eq!(resp.status(), 304);
+
+    let req = test::TestRequest::get()
+        .uri("/api/images?source=tmdb&path=/logo.png&w=50&format=jpeg")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("content-type").unwrap(), "image/png", "transparency survives a JPEG request");
+    assert!(resp.headers().get("cache-control").unwrap().to_str().unwrap().contains("immutable"));
+
+    let req = test::TestRequest::get().uri("/api/images/blurhash?source=tmdb&path=/logo.png").to_request();
+    let hash: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(hash["width"], 200);
+    assert_eq!(hash["height"], 100);
+    assert!(!hash["blurhash"].as_str().unwrap().is_empty());
+
+    let req = test::TestRequest::get().uri("/api/images?source=remote&path=https://example.com/a.jpg").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 403, "only known artwork hosts are proxied");
+
+    for path in ["/accounts", "/library/sections/1/all", "/library/metadata/10/thumb?X-Plex-Token=x", "/library/metadata/10/../../accounts"] {
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/images?source=plex&path={}", path.replace('?', "%3F").replace('=', "%3D")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400, "{} is not artwork", path);
+    }
+
+    let req = test::TestRequest::get().uri("/api/images?source=tmdb&path=/logo.png&width=99999").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+}
+
 #[actix_rt::test]
 async fn media_get_related() {
     let mock_server = MockServer::start().await;
@@ -625,48 +1270,339 @@ async fn media_stream_transcode() {
 }
 
 #[actix_rt::test]
-async fn media_stream_directstream() {
+async fn media_stream_maps_legacy_quality_values() {
     let mock_server = MockServer::start().await;
     Mock::given(method("GET"))
         .and(path("/library/metadata/50"))
         .respond_with(ResponseTemplate::new(200).set_body_json(json!({
             "MediaContainer": { "Metadata": [{
                 "ratingKey": "50",
-                "Media": [{ "Part": [{"key": "/library/parts/50/file.mkv"}], "videoCodec": "h264", "audioCodec": "truehd", "container": "mkv" }]
+                "Media": [{ "Part": [{"key": "/library/parts/50/file.mkv"}], "videoCodec": "h264" }]
             }] }
         })))
         .mount(&mock_server).await;
-
-    // Mock the decision endpoint — directstream must also call this to bust Plex cache
     Mock::given(method("GET"))
         .and(path("/video/:/transcode/universal/decision"))
-        .and(query_param("directStream", "1"))
         .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
-        .expect(1)
         .mount(&mock_server).await;
-
-    // Mock start.m3u8 for directstream
+    // quality=720 is the old name of the 720p profile
     Mock::given(method("GET"))
         .and(path("/video/:/transcode/universal/start.m3u8"))
-        .and(query_param("directStream", "1"))
+        .and(query_param("videoResolution", "1280x720"))
+        .and(query_param("maxVideoBitrate", "2000"))
         .respond_with(ResponseTemplate::new(200)
-            .set_body_string("#EXTM3U\nsession/ds-456/base/index.m3u8"))
+            .set_body_string("#EXTM3U\nsession/abc-123/base/index.m3u8"))
+        .expect(1)
         .mount(&mock_server).await;
 
     let app = test_app!(mock_config(&mock_server.uri(), "valid-token"));
 
     let req = test::TestRequest::get()
-        .uri("/api/media/50/stream?direct_play=false&direct_stream=true&quality=original")
+        .uri("/api/media/50/stream?direct_play=false&quality=720")
         .to_request();
     let resp = test::call_service(&app, req).await;
     assert_eq!(resp.status(), 200);
+}
 
-    let body: Value = test::read_body_json(resp).await;
+#[actix_rt::test]
+async fn media_stream_directstream() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/50"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{
+                "ratingKey": "50",
+                "Media": [{ "Part": [{"key": "/library/parts/50/file.mkv"}], "videoCodec": "h264", "audioCodec": "truehd", "container": "mkv" }]
+            }] }
+        })))
+        .mount(&mock_server).await;
+
+    // Mock the decision endpoint — directstream must also call this to bust Plex cache
+    Mock::given(method("GET"))
+        .and(path("/video/:/transcode/universal/decision"))
+        .and(query_param("directStream", "1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    // Mock start.m3u8 for directstream
+    Mock::given(method("GET"))
+        .and(path("/video/:/transcode/universal/start.m3u8"))
+        .and(query_param("directStream", "1"))
+        .respond_with(ResponseTemplate::new(200)
+            .set_body_string("#EXTM3U\nsession/ds-456/base/index.m3u8"))
+        .mount(&mock_server).await;
+
+    let app = test_app!(mock_config(&mock_server.uri(), "valid-token"));
+
+    let req = test::TestRequest::get()
+        .uri("/api/media/50/stream?direct_play=false&direct_stream=true&quality=original")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
     assert_eq!(body["type"], "directstream");
     assert!(body["url"].as_str().unwrap().contains("/api/media/transcode/"));
     assert!(body["session"].as_str().is_some(), "directstream should include session id");
 }
 
+#[actix_rt::test]
+async fn media_stream_auto_selects_profile_and_reports_decision() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/50"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{
+                "ratingKey": "50",
+                "Media": [{
+                    "Part": [{"key": "/library/parts/50/file.mkv"}],
+                    "videoCodec": "hevc", "audioCodec": "aac", "container": "mkv",
+                    "bitrate": 15000, "height": 1080
+                }]
+            }] }
+        })))
+        .mount(&mock_server).await;
+
+    Mock::given(method("GET"))
+        .and(path("/video/:/transcode/universal/decision"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": {
+                "generalDecisionCode": 1001,
+                "generalDecisionText": "Direct play not available; Conversion OK.",
+                "transcodeDecisionCode": 1001,
+                "transcodeDecisionText": "Direct play not available; Conversion OK.",
+                "Metadata": [{ "Media": [{ "Part": [{
+                    "decision": "transcode",
+                    "Stream": [
+                        {"streamType": 1, "codec": "h264", "decision": "transcode"},
+                        {"streamType": 2, "codec": "aac", "decision": "copy"}
+                    ]
+                }] }] }]
+            }
+        })))
+        .mount(&mock_server).await;
+
+    Mock::given(method("GET"))
+        .and(path("/video/:/transcode/universal/start.m3u8"))
+        .and(query_param("maxVideoBitrate", "4000"))
+        .respond_with(ResponseTemplate::new(200)
+            .set_body_string("#EXTM3U\nsession/auto-1/base/index.m3u8"))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(mock_config(&mock_server.uri(), "valid-token"));
+
+    let req = test::TestRequest::get()
+        .uri("/api/media/50/stream?quality=auto&bandwidth=5000&video_codecs=h264,vp9")
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["type"], "hls");
+    assert_eq!(body["profile"], "720p-high");
+    assert_eq!(body["selection"]["mode"], "hls");
+    assert!(body["selection"]["reason"].as_str().unwrap().contains("hevc"));
+    assert_eq!(body["decision"]["general"]["code"], 1001);
+    assert_eq!(body["decision"]["streams"][1]["decision"], "copy");
+}
+
+#[actix_rt::test]
+async fn media_stream_auto_direct_plays_compatible_media() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/50"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{
+                "ratingKey": "50",
+                "Media": [{
+                    "Part": [{"key": "/library/parts/50/file.mp4"}],
+                    "videoCodec": "h264", "audioCodec": "aac", "container": "mp4", "bitrate": 3000
+                }]
+            }] }
+        })))
+        .mount(&mock_server).await;
+
+    let app = test_app!(mock_config(&mock_server.uri(), "valid-token"));
+
+    let req = test::TestRequest::get()
+        .uri("/api/media/50/stream?profile=auto&bandwidth=20000")
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["type"], "direct");
+    assert_eq!(body["selection"]["mode"], "direct");
+}
+
+#[actix_rt::test]
+async fn settings_transcode_profiles_are_validated() {
+    let config = mock_config("http://plex.local:32400", "token");
+    let app = test_app!(config.clone());
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+
+    let req = test::TestRequest::put()
+        .uri("/api/settings/transcode-profiles")
+        .cookie(admin.clone())
+        .set_json(json!([{"name": "Mobile", "max_bitrate_kbps": 1500, "max_resolution": "720"}]))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+
+    let req = test::TestRequest::put()
+        .uri("/api/settings/transcode-profiles")
+        .cookie(admin)
+        .set_json(json!([{
+            "name": "Mobile", "max_bitrate_kbps": 1500, "max_resolution": "1280x720",
+            "subtitle_mode": "none", "max_audio_channels": 2
+        }]))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["transcode_profiles"][0]["name"], "Mobile");
+    assert_eq!(body["transcode_profiles"][0]["video_codecs"], json!(["h264"]));
+    assert_eq!(config.read().unwrap().transcode_profiles[0].subtitle_mode, SubtitleMode::None);
+}
+
+#[actix_rt::test]
+async fn media_stream_serves_text_subtitles_as_sidecar() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/50"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{
+                "ratingKey": "50",
+                "Media": [{ "Part": [{
+                    "id": 77,
+                    "key": "/library/parts/77/file.mkv",
+                    "Stream": [
+                        {"id": 1, "streamType": 1, "codec": "h264"},
+                        {"id": 3, "streamType": 3, "codec": "srt", "languageCode": "eng", "selected": true},
+                        {"id": 4, "streamType": 3, "codec": "pgs", "languageCode": "fre"}
+                    ]
+                }], "videoCodec": "h264" }]
+            }] }
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/video/:/transcode/universal/decision"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/video/:/transcode/universal/start.m3u8"))
+        .and(query_param("subtitles", "none"))
+        .respond_with(ResponseTemplate::new(200)
+            .set_body_string("#EXTM3U\nsession/sub-1/base/index.m3u8"))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(mock_config(&mock_server.uri(), "valid-token"));
+
+    let req = test::TestRequest::get()
+        .uri("/api/media/50/stream?direct_play=false&quality=720p")
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["type"], "hls");
+    let subs = body["subtitles"].as_array().unwrap();
+    assert_eq!(subs.len(), 1, "bitmap subtitles are not offered as sidecars");
+    assert_eq!(subs[0]["url"], "/api/media/50/parts/77/subtitles/3");
+    assert_eq!(subs[0]["selected"], true);
+}
+
+#[actix_rt::test]
+async fn media_stream_burns_bitmap_subtitles() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/50"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{
+                "ratingKey": "50",
+                "Media": [{ "Part": [{
+                    "id": 77,
+                    "key": "/library/parts/77/file.mkv",
+                    "Stream": [{"id": 4, "streamType": 3, "codec": "pgs", "selected": true}]
+                }], "videoCodec": "h264" }]
+            }] }
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/video/:/transcode/universal/decision"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/video/:/transcode/universal/start.m3u8"))
+        .and(query_param("subtitles", "burn"))
+        .respond_with(ResponseTemplate::new(200)
+            .set_body_string("#EXTM3U\nsession/sub-2/base/index.m3u8"))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(mock_config(&mock_server.uri(), "valid-token"));
+
+    let req = test::TestRequest::get()
+        .uri("/api/media/50/stream?direct_play=false&quality=720p")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+}
+
+#[actix_rt::test]
+async fn subtitle_endpoint_converts_srt_to_webvtt_with_offset() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/50"))
+        .and(query_param("X-Plex-Token", "server-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{
+                "ratingKey": "50",
+                "Media": [{ "Part": [
+                    {"id": 77, "Stream": [{"id": 3, "streamType": 3, "codec": "srt"}]},
+                    {"id": 78, "Stream": [{"id": 5, "streamType": 3, "codec": "srt"}]}
+                ]}]
+            }] }
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/51"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/library/streams/3"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            "1\r\n00:00:01,500 --> 00:00:03,000\r\n<font color=\"#fff\">Hello</font> <i>there</i>\r\n\r\n\
+             2\r\n00:01:02,250 --> 00:01:04,000\r\nSecond line\r\n",
+        ))
+        .mount(&mock_server).await;
+
+    let app = test_app!(mock_config(&mock_server.uri(), "valid-token"));
+
+    // Streams of another part, or of an item the user can't see, are refused
+    for uri in ["/api/media/50/parts/77/subtitles/5", "/api/media/51/parts/77/subtitles/3"] {
+        let req = test::TestRequest::get().uri(uri).cookie(user_cookie()).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_client_error(), "{} -> {}", uri, resp.status());
+    }
+
+    let req = test::TestRequest::get()
+        .uri("/api/media/50/parts/77/subtitles/3?offset=-2000")
+        .cookie(user_cookie())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("content-type").unwrap().to_str().unwrap().starts_with("text/vtt"));
+
+    let body = test::read_body(resp).await;
+    let vtt = String::from_utf8(body.to_vec()).unwrap();
+    assert!(vtt.starts_with("WEBVTT\n\n"));
+    // First cue is shifted to start at zero, the second keeps its length
+    assert!(vtt.contains("00:00:00.000 --> 00:00:01.000\nHello <i>there</i>"));
+    assert!(vtt.contains("00:01:00.250 --> 00:01:02.000\nSecond line"));
+}
+
+#[actix_rt::test]
+async fn subtitle_conversion_handles_ass() {
+    let ass = "[Script Info]\nTitle: Test\n\n[Events]\n\
+        Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+        Dialogue: 0,0:00:05.10,0:00:07.00,Default,,0,0,0,,{\\i1}Hi,{\\i0} there\\Nfriend\n";
+    let vtt = crate::plex::subtitles::to_webvtt(ass, 500);
+    assert_eq!(vtt, "WEBVTT\n\n00:00:05.600 --> 00:00:07.500\nHi, there\nfriend\n\n");
+}
+
 // ─── Plex Hubs ───────────────────────────────────────────────────────────────
 
 #[actix_rt::test]
@@ -752,6 +1688,125 @@ async fn hubs_recently_added() {
     assert_eq!(body[0]["title"], "Just Added");
 }
 
+#[actix_rt::test]
+async fn playlists_are_edited_with_the_user_token() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "machineIdentifier": "abc123" }
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("POST"))
+        .and(path("/playlists"))
+        .and(query_param("X-Plex-Token", "server-token"))
+        .and(query_param("smart", "0"))
+        .and(query_param("title", "Road Trip"))
+        .and(query_param("uri", "server://abc123/com.plexapp.plugins.library/library/metadata/10,11"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{"ratingKey": "500", "title": "Road Trip", "smart": false}] }
+        })))
+        .expect(1)
+        .mount(&mock_server).await;
+    Mock::given(method("POST"))
+        .and(path("/playlists"))
+        .and(query_param("X-Plex-Token", "server-token"))
+        .and(query_param("smart", "1"))
+        .and(query_param(
+            "uri",
+            "server://abc123/com.plexapp.plugins.library/library/sections/1/all?type=1&unwatched=1&genre=Horror&limit=25",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{"ratingKey": "501", "title": "Unwatched Horror", "smart": true}] }
+        })))
+        .expect(1)
+        .mount(&mock_server).await;
+    Mock::given(method("PUT"))
+        .and(path("/playlists/500/items/7/move"))
+        .and(query_param("after", "9"))
+        .and(query_param("X-Plex-Token", "server-token"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/playlists/500/items"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [
+                {"ratingKey": "11", "playlistItemID": 9},
+                {"ratingKey": "10", "playlistItemID": 7}
+            ]}
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("DELETE"))
+        .and(path("/playlists/500"))
+        .and(query_param("X-Plex-Token", "server-token"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(mock_config(&mock_server.uri(), "admin-token"));
+    let cookie = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+
+    let req = test::TestRequest::post()
+        .uri("/api/hubs/playlists")
+        .set_json(json!({"title": "Road Trip", "ratingKeys": [10, 11]}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401, "writes must not fall back to the admin token");
+
+    let req = test::TestRequest::post()
+        .uri("/api/hubs/playlists")
+        .cookie(cookie.clone())
+        .set_json(json!({"title": "Road Trip", "ratingKeys": [10, 11]}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["ratingKey"], "500");
+
+    let req = test::TestRequest::post()
+        .uri("/api/hubs/playlists")
+        .cookie(cookie.clone())
+        .set_json(json!({
+            "title": "Unwatched Horror",
+            "smart": {"section": 1, "itemType": "movie", "unwatched": true, "genre": "Horror", "limit": 25}
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+
+    let req = test::TestRequest::post()
+        .uri("/api/hubs/playlists")
+        .cookie(cookie.clone())
+        .set_json(json!({"title": "  ", "ratingKeys": [10]}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+
+    let req = test::TestRequest::put()
+        .uri("/api/hubs/playlists/500/items/7/move")
+        .cookie(cookie.clone())
+        .set_json(json!({"after": 9}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body[1]["playlistItemID"], 7);
+
+    let req = test::TestRequest::delete().uri("/api/hubs/playlists/500").cookie(cookie.clone()).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 204);
+
+    // Error bodies are cut to 200 bytes without splitting a character
+    Mock::given(method("DELETE"))
+        .and(path("/playlists/502"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("€".repeat(100)))
+        .mount(&mock_server).await;
+    let req = test::TestRequest::delete().uri("/api/hubs/playlists/502").cookie(cookie).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 500);
+}
+
 // ─── Plex Search ─────────────────────────────────────────────────────────────
 
 #[actix_rt::test]
@@ -840,15 +1895,360 @@ async fn unscrobble_marks_unwatched() {
     assert_eq!(body["success"], true);
 }
 
-// ─── Plex Error Handling ─────────────────────────────────────────────────────
-
 #[actix_rt::test]
-async fn plex_401_returns_unauthorized() {
+async fn rate_converts_stars_to_plex_scale() {
     let mock_server = MockServer::start().await;
-    Mock::given(method("GET"))
-        .and(path("/library/sections"))
-        .respond_with(
-            ResponseTemplate::new(401)
+    Mock::given(method("PUT"))
+        .and(path("/:/rate"))
+        .and(query_param("key", "50"))
+        .and(query_param("rating", "9"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(mock_config(&mock_server.uri(), "valid-token"));
+
+    let req = test::TestRequest::put()
+        .uri("/api/player/rate/50")
+        .set_json(json!({"stars": 4.3}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400, "only half stars are allowed");
+
+    let req = test::TestRequest::put()
+        .uri("/api/player/rate/50")
+        .set_json(json!({"stars": 4.5}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["userRating"], 9);
+}
+
+#[actix_rt::test]
+async fn local_watchlist_add_surfaces_tmdb_errors() {
+    let tmdb = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/movie/603"))
+        .respond_with(ResponseTemplate::new(429).set_body_json(json!({"status_message": "Slow down"})))
+        .mount(&tmdb).await;
+
+    let config = mock_config("http://127.0.0.1:1", "token");
+    config.write().unwrap().tmdb.api_key = "v3-key".to_string();
+    let app = test_app!(config, &tmdb.uri());
+
+    // A rate limited lookup fails the add instead of saving an untitled entry
+    let entry = json!({"tmdbId": 603, "mediaType": "movie"});
+    let req = test::TestRequest::post().uri("/api/watchlist").cookie(user_cookie()).set_json(&entry).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 502);
+    let req = test::TestRequest::get().uri("/api/watchlist").cookie(user_cookie()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body, json!([]));
+}
+
+#[actix_rt::test]
+async fn local_watchlist_tracks_radarr_availability() {
+    let radarr = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/movie"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"id": 7, "title": "The Matrix", "tmdbId": 603, "monitored": true, "hasFile": false}
+        ])))
+        .expect(1)
+        .mount(&radarr).await;
+
+    let app = test_app!(full_mock_config("http://127.0.0.1:1", "http://127.0.0.1:1", &radarr.uri()));
+
+    let req = test::TestRequest::get().uri("/api/watchlist").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+
+    let entry = json!({"tmdbId": 603, "mediaType": "movie", "title": "The Matrix", "year": 1999});
+    let req = test::TestRequest::post().uri("/api/watchlist").cookie(user_cookie()).set_json(&entry).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    let req = test::TestRequest::post().uri("/api/watchlist").cookie(user_cookie()).set_json(&entry).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200, "adding twice keeps one entry");
+
+    let req = test::TestRequest::get().uri("/api/watchlist").cookie(user_cookie()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["tmdbId"], 603);
+    assert_eq!(body[0]["availability"], "monitored");
+    assert_eq!(body[0]["arr"][0]["id"], 7);
+
+    // The movie list comes from the cache the Radarr routes share
+    let req = test::TestRequest::get().uri("/api/watchlist").cookie(user_cookie()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body[0]["availability"], "monitored");
+    let req = test::TestRequest::get().uri("/api/radarr/movie").cookie(user_cookie()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body[0]["id"], 7);
+
+    let req = test::TestRequest::delete().uri("/api/watchlist/movie/603").cookie(user_cookie()).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 204);
+    let req = test::TestRequest::delete().uri("/api/watchlist/movie/603").cookie(user_cookie()).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+}
+
+#[actix_rt::test]
+async fn stats_combine_plex_history_with_timeline_reports() {
+    let plex = MockServer::start().await;
+    let now = chrono::Utc::now().timestamp();
+    Mock::given(method("GET"))
+        .and(path("/:/timeline"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/status/sessions/history/all"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [
+                {"accountID": 1, "ratingKey": "10", "title": "Heat", "type": "movie", "viewedAt": now - 3600},
+                {"accountID": 5, "ratingKey": "21", "title": "Pilot", "type": "episode",
+                 "grandparentKey": "/library/metadata/20", "grandparentTitle": "The Wire", "viewedAt": now - 7200},
+                {"accountID": 5, "ratingKey": "22", "title": "The Detail", "type": "episode",
+                 "grandparentKey": "/library/metadata/20", "grandparentTitle": "The Wire", "viewedAt": now - 5400}
+            ]}
+        })))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/10,20,21,22"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [
+                {"ratingKey": "10", "duration": 7200000, "Genre": [{"tag": "Action"}]},
+                {"ratingKey": "20", "Genre": [{"tag": "Drama"}]},
+                {"ratingKey": "21", "duration": 1800000},
+                {"ratingKey": "22", "duration": 1800000}
+            ]}
+        })))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Account": [{"id": 1, "name": "owner"}, {"id": 5, "name": "friend"}] }
+        })))
+        .mount(&plex).await;
+
+    let app = test_app!(mock_config(&plex.uri(), "admin-token"));
+
+    for (session, method) in [("tab-a", "hls"), ("tab-b", "direct")] {
+        let req = test::TestRequest::put()
+            .uri("/api/player/timeline")
+            .insert_header(("X-Playarr-Session", session))
+            .set_json(json!({
+                "ratingKey": "10", "key": "/library/metadata/10",
+                "state": "playing", "time": 0, "duration": 7200000, "playMethod": method
+            }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+    }
+
+    let req = test::TestRequest::get().uri("/api/stats").cookie(user_cookie()).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401, "stats are admin only");
+
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+    let req = test::TestRequest::get().uri("/api/stats?days=400").cookie(admin.clone()).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+
+    let req = test::TestRequest::get().uri("/api/stats?days=7").cookie(admin).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["plays"], 3);
+    assert_eq!(body["hours"], 3.0);
+    assert_eq!(body["users"][0]["userId"], 0, "the owner's account 1 maps to the admin user");
+    assert_eq!(body["users"][0]["userName"], "owner");
+    assert_eq!(body["users"][1]["hours"], 1.0);
+    assert_eq!(body["hoursByDay"].as_array().unwrap().len(), 8);
+    assert_eq!(body["topShows"][0]["title"], "The Wire");
+    assert_eq!(body["topShows"][0]["plays"], 2);
+    assert_eq!(body["topGenres"][0]["genre"], "Drama");
+    assert_eq!(body["playMethods"]["transcode"], 1);
+    assert_eq!(body["playMethods"]["transcodeRatio"], 0.5);
+    assert_eq!(body["concurrentStreams"]["peak"], 2);
+}
+
+#[actix_rt::test]
+async fn sessions_merge_transcode_progress_and_terminate() {
+    let plex = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/status/sessions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [
+                {
+                    "ratingKey": "10", "title": "Heat", "type": "movie", "viewOffset": 1800000, "duration": 7200000,
+                    "User": {"id": "1", "title": "owner"},
+                    "Player": {"machineIdentifier": "playarr-3f2a9c1d-77e0", "product": "Playarr", "platform": "Chrome", "state": "playing", "local": true},
+                    "Session": {"id": "s-1", "bandwidth": 8000, "location": "lan"},
+                    "TranscodeSession": {"key": "/transcode/sessions/abc", "videoDecision": "transcode", "audioDecision": "copy", "speed": 1.1},
+                    "Media": [{"Part": [{"decision": "transcode", "Stream": [{"streamType": 1, "codec": "hevc"}]}]}]
+                },
+                {
+                    "ratingKey": "21", "title": "Pilot", "type": "episode", "grandparentTitle": "The Wire",
+                    "viewOffset": 0, "duration": 3600000,
+                    "User": {"id": "5", "title": "friend"},
+                    "Player": {"machineIdentifier": "tv-1", "title": "Living Room TV", "state": "paused"},
+                    "Session": {"id": "s-2", "bandwidth": 4000, "location": "wan"},
+                    "Media": [{"Part": [{"decision": "directplay"}]}]
+                }
+            ]}
+        })))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/transcode/sessions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "TranscodeSession": [
+                {"key": "/transcode/sessions/abc", "videoDecision": "transcode", "audioDecision": "copy", "speed": 2.5, "progress": 40.0}
+            ]}
+        })))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/status/sessions/terminate"))
+        .and(query_param("sessionId", "s-2"))
+        .and(query_param("reason", "Server maintenance"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&plex).await;
+
+    let app = test_app!(mock_config(&plex.uri(), "admin-token"));
+
+    let req = test::TestRequest::get().uri("/api/sessions").cookie(user_cookie()).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401, "sessions are admin only");
+
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+    let req = test::TestRequest::get().uri("/api/sessions").cookie(admin.clone()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["streamCount"], 2);
+    assert_eq!(body["transcodeCount"], 1);
+    assert_eq!(body["bandwidthKbps"], 12000);
+    let first = &body["sessions"][0];
+    assert_eq!(first["user"]["id"], 0, "the owner's account 1 maps to the admin user");
+    assert_eq!(first["player"]["playarrTab"], "3f2a9c1d-77e0");
+    assert_eq!(first["player"]["title"], "Browser tab 3f2a9c1d");
+    assert_eq!(first["transcode"]["speed"], 2.5, "live transcoder numbers win");
+    assert_eq!(first["video"]["codec"], "hevc");
+    assert_eq!(first["audio"]["decision"], "copy");
+    assert_eq!(first["progressPercent"], 25.0);
+    assert_eq!(body["sessions"][1]["player"]["title"], "Living Room TV");
+    assert!(body["sessions"][1]["transcode"].is_null());
+
+    let req = test::TestRequest::post()
+        .uri("/api/sessions/s-2/terminate")
+        .cookie(admin)
+        .set_json(json!({"message": "Server maintenance"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 204);
+}
+
+#[actix_rt::test]
+async fn offline_download_completes_and_serves_ranges_to_its_owner() {
+    let plex = MockServer::start().await;
+    // Everything is fetched with the user's own server token
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/10"))
+        .and(query_param("X-Plex-Token", "server-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [{
+                "ratingKey": "10", "title": "Heat", "year": 1995, "type": "movie", "duration": 7200000,
+                "Media": [{"container": "mkv", "Part": [{"key": "/library/parts/5/123/file.mkv", "size": 11}]}]
+            }]}
+        })))
+        .mount(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/parts/5/123/file.mkv"))
+        .and(query_param("X-Plex-Token", "server-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("hello world"))
+        .mount(&plex).await;
+    // An item in a library the user isn't shared
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/11"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&plex).await;
+
+    let app = test_app!(mock_config(&plex.uri(), "admin-token"));
+
+    let req = test::TestRequest::post()
+        .uri("/api/offline/jobs")
+        .cookie(user_cookie())
+        .set_json(json!({"ratingKey": "10", "profile": "potato"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400, "unknown profiles are rejected");
+
+    let req = test::TestRequest::post()
+        .uri("/api/offline/jobs")
+        .cookie(user_cookie())
+        .set_json(json!({"ratingKey": "11"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401, "items the user can't see in Plex can't be queued");
+
+    let req = test::TestRequest::post()
+        .uri("/api/offline/jobs")
+        .cookie(user_cookie())
+        .set_json(json!({"ratingKey": "10"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    let job: Value = test::read_body_json(resp).await;
+    assert_eq!(job["fileName"], "Heat (1995).mkv");
+    assert!(job.get("plexToken").is_none());
+    let id = job["id"].as_str().unwrap().to_string();
+
+    let mut status = Value::Null;
+    for _ in 0..50 {
+        let req = test::TestRequest::get().uri(&format!("/api/offline/jobs/{}", id)).cookie(user_cookie()).to_request();
+        let job: Value = test::call_and_read_body_json(&app, req).await;
+        status = job["status"].clone();
+        if status == "completed" {
+            assert_eq!(job["bytesDone"], 11);
+            assert!(job["expiresAt"].is_string());
+            break;
+        }
+        actix_web::rt::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(status, "completed");
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/offline/jobs/{}/file", id))
+        .cookie(user_cookie())
+        .insert_header(("Range", "bytes=6-"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 206);
+    assert!(resp.headers().get("content-disposition").unwrap().to_str().unwrap().contains("Heat (1995).mkv"));
+    assert_eq!(test::read_body(resp).await, "world");
+
+    let other = actix_web::cookie::Cookie::new("plex_user_token", "2:tv-token:server-token");
+    let req = test::TestRequest::get().uri(&format!("/api/offline/jobs/{}", id)).cookie(other).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404, "other users can't see the download");
+
+    let req = test::TestRequest::delete().uri(&format!("/api/offline/jobs/{}", id)).cookie(user_cookie()).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 204);
+    let req = test::TestRequest::get().uri("/api/offline/jobs").cookie(user_cookie()).to_request();
+    let jobs: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(jobs.as_array().unwrap().len(), 0);
+}
+
+// ─── Plex Error Handling ─────────────────────────────────────────────────────
+
+#[actix_rt::test]
+async fn plex_401_returns_unauthorized() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/library/sections"))
+        .respond_with(
+            ResponseTemplate::new(401)
                 .set_body_string("<html><body><h1>401 Unauthorized</h1></body></html>"),
         )
         .mount(&mock_server).await;
@@ -873,147 +2273,1020 @@ async fn plex_500_returns_internal_error() {
 
     let app = test_app!(mock_config(&mock_server.uri(), "valid-token"));
 
-    let req = test::TestRequest::get().uri("/api/libraries").to_request();
-    let resp = test::call_service(&app, req).await;
-    assert_eq!(resp.status(), 500);
+    let req = test::TestRequest::get().uri("/api/libraries").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 500);
+}
+
+// ─── Sonarr ──────────────────────────────────────────────────────────────────
+
+#[actix_rt::test]
+async fn sonarr_lookup() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/series/lookup"))
+        .and(query_param("term", "breaking"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"title": "Breaking Bad", "tvdbId": 81189}
+        ])))
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+
+    let req = test::TestRequest::get().uri("/api/sonarr/lookup?term=breaking").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body[0]["title"], "Breaking Bad");
+}
+
+#[actix_rt::test]
+async fn sonarr_calendar() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/calendar"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"seriesId": 1, "title": "S01E05", "airDate": "2026-02-15"}
+        ])))
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+
+    let req = test::TestRequest::get().uri("/api/sonarr/calendar").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body.as_array().unwrap().len() > 0);
+}
+
+#[actix_rt::test]
+async fn sonarr_queue() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/queue"))
+        .and(query_param("includeSeries", "true"))
+        .and(query_param("includeEpisode", "true"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "records": [{
+                "id": 1, "seriesId": 7, "episodeId": 21, "title": "Show.S01E01", "status": "downloading",
+                "series": {"id": 7, "title": "Show"},
+                "episode": {"id": 21, "title": "Pilot", "seasonNumber": 1, "episodeNumber": 1}
+            }],
+            "totalRecords": 1
+        })))
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+
+    let req = test::TestRequest::get().uri("/api/sonarr/queue").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["records"][0]["series"]["title"], "Show");
+    assert_eq!(body["records"][0]["episode"]["title"], "Pilot");
+    assert!(body["records"][0].get("movie").is_none());
+}
+
+#[actix_rt::test]
+async fn sonarr_episodes() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/episode"))
+        .and(query_param("seriesId", "1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"id": 1, "title": "Pilot", "seasonNumber": 1, "episodeNumber": 1}
+        ])))
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+
+    let req = test::TestRequest::get().uri("/api/sonarr/episodes?seriesId=1").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body[0]["title"], "Pilot");
+}
+
+fn two_sonarr_instances(main_url: &str, anime_url: &str) -> SharedConfig {
+    let config = full_mock_config("http://unused", main_url, "");
+    config.write().unwrap().sonarr.push(SonarrConfig {
+        name: "anime".to_string(),
+        url: anime_url.to_string(),
+        api_key: "anime-key".to_string(),
+        ..Default::default()
+    });
+    config
+}
+
+#[actix_rt::test]
+async fn sonarr_calendar_merges_instances() {
+    let main = MockServer::start().await;
+    let anime = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/calendar"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"title": "Main", "airDateUtc": "2026-02-16T01:00:00Z"}
+        ])))
+        .mount(&main).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/calendar"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"title": "Anime", "airDateUtc": "2026-02-15T01:00:00Z"}
+        ])))
+        .mount(&anime).await;
+
+    let app = test_app!(two_sonarr_instances(&main.uri(), &anime.uri()));
+
+    let req = test::TestRequest::get().uri("/api/sonarr/calendar").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.as_array().unwrap().len(), 2);
+    assert_eq!(body[0]["title"], "Anime");
+    assert_eq!(body[0]["instance"], "anime");
+    assert_eq!(body[1]["instance"], "default");
+
+    let req = test::TestRequest::get().uri("/api/sonarr/calendar?instance=anime").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["title"], "Anime");
+}
+
+#[actix_rt::test]
+async fn sonarr_lookup_dedupes_across_instances() {
+    let main = MockServer::start().await;
+    let anime = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/series/lookup"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"title": "Frieren", "tvdbId": 424536}
+        ])))
+        .mount(&main).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/series/lookup"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"title": "Frieren", "tvdbId": 424536, "id": 7},
+            {"title": "Mushishi", "tvdbId": 79634}
+        ])))
+        .mount(&anime).await;
+
+    let app = test_app!(two_sonarr_instances(&main.uri(), &anime.uri()));
+
+    let req = test::TestRequest::get().uri("/api/sonarr/lookup?term=f").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let items = body.as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["id"], 7, "copy from the instance that has it added wins");
+    assert_eq!(items[0]["instances"], json!(["default", "anime"]));
+}
+
+#[actix_rt::test]
+async fn sonarr_single_instance_routes_use_selector() {
+    let main = MockServer::start().await;
+    let anime = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/episode"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{"title": "From anime"}])))
+        .mount(&anime).await;
+
+    let app = test_app!(two_sonarr_instances(&main.uri(), &anime.uri()));
+
+    let req = test::TestRequest::get().uri("/api/sonarr/episodes?seriesId=1&instance=anime").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body[0]["title"], "From anime");
+
+    let req = test::TestRequest::get().uri("/api/sonarr/episodes?seriesId=1&instance=nope").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+}
+
+#[actix_rt::test]
+async fn sonarr_add_series_validates_body() {
+    let mock_server = MockServer::start().await;
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+
+    let req = test::TestRequest::post().uri("/api/sonarr/series")
+        .set_json(json!({"tvdbId": 81189, "title": "Breaking Bad", "rootFolderPath": "/tv"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("qualityProfileId"));
+
+    let req = test::TestRequest::post().uri("/api/sonarr/series")
+        .set_json(json!({"tvdbId": 81189, "title": "Breaking Bad", "qualityProfileId": 1, "rootFolderPath": "tv"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("absolute path"));
+
+    // Nothing reached Sonarr
+    assert!(mock_server.received_requests().await.unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn sonarr_series_tolerates_unknown_fields_and_projects() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/series"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"id": 1, "title": "Breaking Bad", "tvdbId": 81189, "brandNewField": {"nested": true}}
+        ])))
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+
+    let req = test::TestRequest::get().uri("/api/sonarr/series").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body[0]["title"], "Breaking Bad");
+    assert!(body[0].get("brandNewField").is_none());
+
+    let req = test::TestRequest::get().uri("/api/sonarr/series?fields=id,title").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body, json!([{"id": 1, "title": "Breaking Bad"}]));
+}
+
+#[actix_rt::test]
+async fn sonarr_update_series_merges_over_current() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/series/7"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 7, "title": "Show", "path": "/tv/Show", "monitored": true, "qualityProfileId": 1,
+            "seasons": [{"seasonNumber": 1, "monitored": true}, {"seasonNumber": 2, "monitored": true}]
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("PUT"))
+        .and(path("/api/v3/series/7"))
+        .and(wiremock::matchers::body_partial_json(json!({
+            "title": "Show", "path": "/tv/Show", "monitored": true, "qualityProfileId": 4,
+            "seasons": [{"seasonNumber": 1, "monitored": true}, {"seasonNumber": 2, "monitored": false}]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 7, "title": "Show", "qualityProfileId": 4})))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+
+    let req = test::TestRequest::put().uri("/api/sonarr/series/7")
+        .set_json(json!({"qualityProfileId": 4, "seasons": [{"seasonNumber": 2, "monitored": false}]}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["qualityProfileId"], 4);
+
+    let req = test::TestRequest::put().uri("/api/sonarr/series/7")
+        .set_json(json!({"seasons": [{"seasonNumber": 9, "monitored": false}]}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+}
+
+#[actix_rt::test]
+async fn sonarr_maps_upstream_status_codes() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/queue"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/series/99"))
+        .respond_with(ResponseTemplate::new(404).set_body_json(json!({"message": "NotFound"})))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/calendar"))
+        .respond_with(ResponseTemplate::new(503).set_body_string("database is locked"))
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+
+    let req = test::TestRequest::get().uri("/api/sonarr/queue").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("API key"));
+
+    let req = test::TestRequest::get().uri("/api/sonarr/series/99").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+
+    let req = test::TestRequest::get().uri("/api/sonarr/calendar").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 502);
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("database is locked"));
+}
+
+#[actix_rt::test]
+async fn sonarr_surfaces_validation_errors() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v3/series"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!([
+            {"propertyName": "Path", "errorMessage": "'Path' is already configured for an existing series", "severity": "error"},
+            {"propertyName": "TvdbId", "errorMessage": "This series has already been added", "severity": "error"}
+        ])))
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+
+    let req = test::TestRequest::post().uri("/api/sonarr/series")
+        .set_json(json!({"tvdbId": 81189, "title": "Breaking Bad", "qualityProfileId": 1, "rootFolderPath": "/tv"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: Value = test::read_body_json(resp).await;
+    let message = body["error"].as_str().unwrap();
+    assert!(message.contains("Path: 'Path' is already configured"));
+    assert!(message.contains("TvdbId: This series has already been added"));
+}
+
+#[actix_rt::test]
+async fn arr_routes_return_503_when_unconfigured() {
+    let app = test_app!(mock_config("http://plex.local:32400", "token"));
+
+    for uri in ["/api/sonarr/series", "/api/sonarr/queue", "/api/radarr/movie", "/api/radarr/qualityprofile"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 503, "{}", uri);
+    }
+}
+
+#[actix_rt::test]
+async fn sonarr_management_requires_admin() {
+    let mock_server = MockServer::start().await;
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+
+    let requests = [
+        test::TestRequest::delete().uri("/api/sonarr/series/1"),
+        test::TestRequest::delete().uri("/api/sonarr/episodefile/3"),
+        test::TestRequest::get().uri("/api/sonarr/rename?seriesId=1"),
+        test::TestRequest::get().uri("/api/sonarr/manualimport?downloadId=abc"),
+        test::TestRequest::put().uri("/api/sonarr/series/1/season/1/monitor").set_json(json!({"monitored": true})),
+    ];
+    for req in requests {
+        let resp = test::call_service(&app, req.cookie(user_cookie()).to_request()).await;
+        assert_eq!(resp.status(), 401);
+    }
+    assert!(mock_server.received_requests().await.unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn sonarr_episode_files_filter_by_season() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/episodefile"))
+        .and(query_param("seriesId", "7"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"id": 1, "seriesId": 7, "seasonNumber": 1, "relativePath": "S01E01.mkv"},
+            {"id": 2, "seriesId": 7, "seasonNumber": 2, "relativePath": "S02E01.mkv"}
+        ])))
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+
+    let req = test::TestRequest::get().uri("/api/sonarr/episodefile?seriesId=7").cookie(admin.clone()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.as_array().unwrap().len(), 2);
+
+    let req = test::TestRequest::get()
+        .uri("/api/sonarr/episodefile?seriesId=7&seasonNumber=2")
+        .cookie(admin)
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["id"], 2);
+}
+
+#[actix_rt::test]
+async fn sonarr_delete_series_forwards_delete_files() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v3/series/4"))
+        .and(query_param("deleteFiles", "true"))
+        .and(query_param("addImportListExclusion", "false"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+
+    let req = test::TestRequest::delete().uri("/api/sonarr/series/4?deleteFiles=true").cookie(admin).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 204);
+}
+
+#[actix_rt::test]
+async fn sonarr_season_pass_monitors_season_and_episodes() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/series/7"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 7, "title": "Show", "seasons": [{"seasonNumber": 1, "monitored": true}, {"seasonNumber": 2, "monitored": false}]
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("PUT"))
+        .and(path("/api/v3/series/7"))
+        .and(wiremock::matchers::body_partial_json(json!({
+            "seasons": [{"seasonNumber": 1, "monitored": true}, {"seasonNumber": 2, "monitored": true}]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 7, "title": "Show"})))
+        .expect(1)
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/episode"))
+        .and(query_param("seasonNumber", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{"id": 21}, {"id": 22}])))
+        .mount(&mock_server).await;
+    Mock::given(method("PUT"))
+        .and(path("/api/v3/episode/monitor"))
+        .and(wiremock::matchers::body_json(json!({"episodeIds": [21, 22], "monitored": true})))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+
+    let req = test::TestRequest::put().uri("/api/sonarr/series/7/season/2/monitor")
+        .cookie(admin)
+        .set_json(json!({"monitored": true}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+}
+
+#[actix_rt::test]
+async fn sonarr_manual_import_runs_command() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v3/command"))
+        .and(wiremock::matchers::body_partial_json(json!({
+            "name": "ManualImport", "importMode": "move",
+            "files": [{"path": "/downloads/Show.S01E01.mkv", "seriesId": 7, "episodeIds": [21]}]
+        })))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({"id": 55, "name": "ManualImport", "status": "queued"})))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+
+    let req = test::TestRequest::post().uri("/api/sonarr/manualimport")
+        .cookie(admin.clone())
+        .set_json(json!({"files": [{"path": "/downloads/Show.S01E01.mkv", "seriesId": 7, "episodeIds": []}]}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+
+    let req = test::TestRequest::post().uri("/api/sonarr/manualimport")
+        .cookie(admin)
+        .set_json(json!({"importMode": "move", "files": [{"path": "/downloads/Show.S01E01.mkv", "seriesId": 7, "episodeIds": [21]}]}))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["id"], 55);
+}
+
+#[actix_rt::test]
+async fn sonarr_command_is_tracked_until_completion() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v3/command"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+            "id": 42, "name": "SeriesSearch", "commandName": "Series Search", "status": "queued",
+            "queued": "2026-01-01T10:00:00Z", "body": {"seriesId": 7}
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/command/42"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 42, "name": "SeriesSearch", "commandName": "Series Search", "status": "completed",
+            "result": "successful", "message": "Series search completed. 3 reports downloaded.",
+            "queued": "2026-01-01T10:00:00Z", "started": "2026-01-01T10:00:01Z", "ended": "2026-01-01T10:00:13Z"
+        })))
+        .mount(&mock_server).await;
+
+    // The tracker is driven by hand here instead of by `run_polling`
+    let sc = full_mock_config("http://unused", &mock_server.uri(), "");
+    let tracker = web::Data::new(crate::commands::CommandTracker::new());
+    let sonarr = web::Data::new(SonarrClient::new(sc.clone()));
+    let radarr = web::Data::new(RadarrClient::new(sc.clone()));
+    let events = web::Data::new(EventBus::new());
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(sc))
+            .app_data(tracker.clone())
+            .app_data(sonarr.clone())
+            .app_data(radarr.clone())
+            .app_data(events.clone())
+            .service(
+                web::scope("/api")
+                    .service(web::scope("/sonarr").configure(crate::arr::handlers::configure::<crate::arr::Sonarr>))
+                    .configure(crate::events::configure)
+                    .configure(crate::commands::configure),
+            ),
+    )
+    .await;
+
+    let req = test::TestRequest::post().uri("/api/sonarr/command")
+        .cookie(user_cookie())
+        .set_json(json!({"name": "SeriesSearch", "seriesId": 7}))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["id"], 42);
+
+    // Listing reads the tracker; only polling asks Sonarr
+    let req = test::TestRequest::get().uri("/api/commands").cookie(user_cookie()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body[0]["status"], "queued");
+    let polled = |r: &wiremock::Request| r.url.path() == "/api/v3/command/42";
+    assert!(!mock_server.received_requests().await.unwrap().iter().any(polled));
+
+    tracker.refresh(&sonarr, &radarr, &events).await;
+    let req = test::TestRequest::get().uri("/api/commands").cookie(user_cookie()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body[0]["id"], "sonarr:default:42");
+    assert_eq!(body[0]["name"], "Series Search");
+    assert_eq!(body[0]["status"], "completed");
+    assert_eq!(body[0]["durationMs"], 12000);
+    assert_eq!(body[0]["triggeredBy"], 1);
+    assert_eq!(body[0]["grabbed"], 3);
+
+    let req = test::TestRequest::get().uri("/api/activity").cookie(user_cookie()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body[0]["kind"], "command");
+    assert_eq!(body[0]["title"], "Series Search completed in 12s, 3 releases grabbed");
+    assert_eq!(body[0]["message"], "Series search completed. 3 reports downloaded.");
+
+    let req = test::TestRequest::get().uri("/api/commands/sonarr:default:42").cookie(user_cookie()).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+}
+
+// ─── Radarr ──────────────────────────────────────────────────────────────────
+
+#[actix_rt::test]
+async fn radarr_lookup() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/movie/lookup"))
+        .and(query_param("term", "inception"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"title": "Inception", "tmdbId": 27205}
+        ])))
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", "", &mock_server.uri()));
+
+    let req = test::TestRequest::get().uri("/api/radarr/lookup?term=inception").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body[0]["title"], "Inception");
+}
+
+#[actix_rt::test]
+async fn radarr_calendar() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/calendar"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"title": "Upcoming Movie", "tmdbId": 12345}
+        ])))
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", "", &mock_server.uri()));
+
+    let req = test::TestRequest::get().uri("/api/radarr/calendar").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+}
+
+#[actix_rt::test]
+async fn radarr_queue() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/queue"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "records": [], "totalRecords": 0
+        })))
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", "", &mock_server.uri()));
+
+    let req = test::TestRequest::get().uri("/api/radarr/queue").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+}
+
+#[actix_rt::test]
+async fn radarr_releases_require_movie_id() {
+    let app = test_app!(full_mock_config("http://unused", "", "http://radarr.local"));
+
+    let req = test::TestRequest::get().uri("/api/radarr/release").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("movieId is required"));
+}
+
+#[actix_rt::test]
+async fn radarr_releases_filter_sort_and_page() {
+    let mock_server = MockServer::start().await;
+    let release = |guid: &str, indexer: &str, size: u64, seeders: u64, rejections: Vec<&str>| json!({
+        "guid": guid, "title": format!("Movie.2024.{}", guid), "indexer": indexer, "indexerId": 1,
+        "size": size, "seeders": seeders, "protocol": "torrent",
+        "quality": {"quality": {"id": 7, "name": "Bluray-1080p", "resolution": 1080}},
+        "languages": [{"id": 1, "name": "English"}],
+        "rejected": !rejections.is_empty(), "rejections": rejections,
+    });
+    Mock::given(method("GET"))
+        .and(path("/api/v3/release"))
+        .and(query_param("movieId", "5"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            release("a", "Alpha", 1_000, 5, vec![]),
+            release("b", "Beta", 3_000, 50, vec!["Size too large.", "Size too large", "Quality not wanted", "Blocklisted", "Too old"]),
+            release("c", "alpha", 2_000, 20, vec![]),
+            release("d", "Gamma", 4_000, 1, vec![]),
+        ])))
+        // Every page below is served from one indexer search
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", "", &mock_server.uri()));
+
+    let req = test::TestRequest::get().uri("/api/radarr/release?movieId=5&sort=seeders&pageSize=2").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["totalRecords"], 4);
+    assert_eq!(body["rejectedRecords"], 1);
+    assert_eq!(body["records"].as_array().unwrap().len(), 2);
+    assert_eq!(body["records"][0]["guid"], "b");
+    assert_eq!(body["records"][0]["rejectionSummary"], "Size too large; Quality not wanted (+2 more)");
+    assert!(body["records"][1].get("rejectionSummary").is_none());
+    assert_eq!(body["facets"]["indexers"].as_array().unwrap().len(), 4);
+
+    let req = test::TestRequest::get().uri("/api/radarr/release?movieId=5&indexer=ALPHA&sort=size&order=asc&page=2&pageSize=1").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["totalRecords"], 2);
+    assert_eq!(body["records"][0]["guid"], "c");
+
+    let req = test::TestRequest::get().uri("/api/radarr/release?movieId=5&rejected=false&minSize=1500").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let guids: Vec<&str> = body["records"].as_array().unwrap().iter().map(|r| r["guid"].as_str().unwrap()).collect();
+    assert_eq!(guids, vec!["c", "d"]);
+
+    let req = test::TestRequest::get().uri("/api/radarr/release?movieId=5&indexer=beta,Gamma").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["totalRecords"], 2);
+
+    let req = test::TestRequest::get().uri("/api/radarr/release?movieId=5&pageSize=0").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+}
+
+#[actix_rt::test]
+async fn radarr_add_collection_skips_owned_movies() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/collection/10"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 10, "title": "The Matrix Collection", "tmdbId": 2344, "monitored": false,
+            "movies": [
+                {"tmdbId": 603, "title": "The Matrix", "year": 1999},
+                {"tmdbId": 604, "title": "The Matrix Reloaded", "year": 2003},
+                {"tmdbId": 605, "title": "The Matrix Revolutions", "year": 2003}
+            ]
+        })))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/movie"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{"id": 1, "tmdbId": 603, "title": "The Matrix"}])))
+        .mount(&mock_server).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v3/movie/import"))
+        .and(wiremock::matchers::body_partial_json(json!([
+            {"tmdbId": 604, "qualityProfileId": 2, "rootFolderPath": "/movies", "year": 2003},
+            {"tmdbId": 605, "qualityProfileId": 2, "rootFolderPath": "/movies"}
+        ])))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"id": 2, "tmdbId": 604, "title": "The Matrix Reloaded"},
+            {"id": 3, "tmdbId": 605, "title": "The Matrix Revolutions"}
+        ])))
+        .expect(1)
+        .mount(&mock_server).await;
+    Mock::given(method("PUT"))
+        .and(path("/api/v3/collection/10"))
+        .and(wiremock::matchers::body_partial_json(json!({"monitored": true, "qualityProfileId": 2})))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", "", &mock_server.uri()));
+
+    let add = || test::TestRequest::post().uri("/api/radarr/collection/10/add")
+        .set_json(json!({"qualityProfileId": 2, "rootFolderPath": "/movies", "monitorCollection": true}));
+    let resp = test::call_service(&app, add().cookie(user_cookie()).to_request()).await;
+    assert_eq!(resp.status(), 401, "only the admin adds whole collections");
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+    let resp = test::call_service(&app, add().cookie(admin).to_request()).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["skipped"], 1);
+    assert_eq!(body["added"].as_array().unwrap().len(), 2);
+}
+
+#[actix_rt::test]
+async fn radarr_delete_movie_and_exclusions_require_admin() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v3/movie/3"))
+        .and(query_param("deleteFiles", "true"))
+        .and(query_param("addImportExclusion", "true"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v3/exclusions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 8, "tmdbId": 605, "movieTitle": "The Matrix Revolutions", "movieYear": 2003})))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", "", &mock_server.uri()));
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+
+    for uri in ["/api/radarr/exclusions", "/api/radarr/moviefile?movieId=3"] {
+        let req = test::TestRequest::get().uri(uri).cookie(user_cookie()).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401, "{} is admin only", uri);
+    }
+
+    let req = test::TestRequest::delete().uri("/api/radarr/movie/3?deleteFiles=true&addImportExclusion=true")
+        .cookie(user_cookie())
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+    let req = test::TestRequest::delete().uri("/api/radarr/movie/3?deleteFiles=true&addImportExclusion=true")
+        .cookie(admin.clone())
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 204);
+
+    let req = test::TestRequest::post().uri("/api/radarr/exclusions")
+        .cookie(admin.clone())
+        .set_json(json!({"tmdbId": 605}))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+    let req = test::TestRequest::post().uri("/api/radarr/exclusions")
+        .cookie(admin)
+        .set_json(json!({"tmdbId": 605, "movieTitle": "The Matrix Revolutions", "movieYear": 2003}))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["id"], 8);
 }
 
-// ─── Sonarr ──────────────────────────────────────────────────────────────────
+// ─── Unified search ──────────────────────────────────────────────────────────
 
 #[actix_rt::test]
-async fn sonarr_lookup() {
+async fn search_all_dedupes_and_tags_results() {
     let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/hubs/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"MediaContainer": {"Hub": [
+            {"type": "show", "Metadata": [
+                {"type": "show", "title": "Severance", "year": 2022, "ratingKey": "900", "Guid": [{"id": "tvdb://371980"}]}
+            ]}
+        ]}})))
+        .mount(&mock_server).await;
     Mock::given(method("GET"))
         .and(path("/api/v3/series/lookup"))
-        .and(query_param("term", "breaking"))
         .respond_with(ResponseTemplate::new(200).set_body_json(json!([
-            {"title": "Breaking Bad", "tvdbId": 81189}
+            {"id": 12, "title": "Severance", "tvdbId": 371980, "tmdbId": 95396, "imdbId": "tt11280740", "monitored": true, "year": 2022},
+            {"title": "Severance Pay", "tvdbId": 5555, "year": 2010},
+            {"id": 13, "title": "Severance Files", "tvdbId": 6666, "monitored": false, "year": 2005}
         ])))
         .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/search/multi"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": [
+            {"media_type": "tv", "id": 95396, "name": "Severance", "first_air_date": "2022-02-17", "popularity": 300.0},
+            {"media_type": "movie", "id": 1111, "title": "Severance", "release_date": "2006-08-25", "poster_path": "/p.jpg"},
+            {"media_type": "person", "id": 1, "name": "Someone"}
+        ]})))
+        .mount(&mock_server).await;
 
-    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+    let config = full_mock_config(&mock_server.uri(), &mock_server.uri(), "");
+    config.write().unwrap().tmdb = TmdbConfig {
+        api_key: "v3-key".to_string(),
+        read_access_token: String::new(),
+    };
+    let app = test_app!(config, &mock_server.uri());
 
-    let req = test::TestRequest::get().uri("/api/sonarr/lookup?term=breaking").to_request();
+    let req = test::TestRequest::get().uri("/api/search/all?q=severance").cookie(user_cookie()).to_request();
     let resp = test::call_service(&app, req).await;
     assert_eq!(resp.status(), 200);
-
     let body: Value = test::read_body_json(resp).await;
-    assert_eq!(body[0]["title"], "Breaking Bad");
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results.len(), 4);
+
+    // Plex, Sonarr and TMDB copies of the show fold into one, ranked first
+    assert_eq!(results[0]["title"], "Severance");
+    assert_eq!(results[0]["kind"], "show");
+    assert_eq!(results[0]["availability"], "in_library");
+    assert_eq!(results[0]["tmdbId"], 95396);
+    assert_eq!(results[0]["plexRatingKey"], "900");
+    assert_eq!(results[0]["arr"][0]["id"], 12);
+    assert_eq!(results[0]["sources"], json!(["plex", "sonarr", "tmdb"]));
+
+    assert_eq!(results[1]["kind"], "movie");
+    assert_eq!(results[1]["availability"], "not_tracked");
+    assert_eq!(results[1]["poster"], "/api/images?source=tmdb&width=342&path=/p.jpg");
+    assert_eq!(results[2]["title"], "Severance Pay");
+    // Added to Sonarr but unmonitored is not "monitored"
+    assert_eq!(results[3]["title"], "Severance Files");
+    assert_eq!(results[3]["availability"], "not_tracked");
+    assert_eq!(results[3]["arr"][0]["monitored"], false);
+    assert_eq!(body["skipped"], json!(["radarr"]));
+
+    // The Plex-only search next to it is still reachable
+    let req = test::TestRequest::get().uri("/api/search?q=severance").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
 }
 
 #[actix_rt::test]
-async fn sonarr_calendar() {
+async fn search_all_folds_copies_linked_by_a_later_source() {
     let mock_server = MockServer::start().await;
+    // Two libraries with different agents: one copy known by TMDB id, the other by IMDb id
     Mock::given(method("GET"))
-        .and(path("/api/v3/calendar"))
+        .and(path("/hubs/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"MediaContainer": {"Hub": [
+            {"type": "movie", "Metadata": [
+                {"type": "movie", "title": "The Matrix", "year": 1999, "ratingKey": "1", "Guid": [{"id": "tmdb://603"}]},
+                {"type": "movie", "title": "The Matrix", "year": 1999, "ratingKey": "2", "Guid": [{"id": "imdb://tt0133093"}]}
+            ]}
+        ]}})))
+        .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/movie/lookup"))
         .respond_with(ResponseTemplate::new(200).set_body_json(json!([
-            {"seriesId": 1, "title": "S01E05", "airDate": "2026-02-15"}
+            {"id": 7, "title": "The Matrix", "tmdbId": 603, "imdbId": "tt0133093", "monitored": true, "year": 1999}
         ])))
         .mount(&mock_server).await;
+    Mock::given(method("GET"))
+        .and(path("/search/multi"))
+        .respond_with(ResponseTemplate::new(401).set_body_json(json!({"status_message": "Invalid API key"})))
+        .mount(&mock_server).await;
 
-    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
-
-    let req = test::TestRequest::get().uri("/api/sonarr/calendar").to_request();
-    let resp = test::call_service(&app, req).await;
-    assert_eq!(resp.status(), 200);
-
-    let body: Value = test::read_body_json(resp).await;
-    assert!(body.as_array().unwrap().len() > 0);
+    let config = full_mock_config(&mock_server.uri(), "", &mock_server.uri());
+    config.write().unwrap().tmdb = TmdbConfig {
+        api_key: "bad-key".to_string(),
+        read_access_token: String::new(),
+    };
+    let app = test_app!(config, &mock_server.uri());
+
+    let req = test::TestRequest::get().uri("/api/search/all?q=matrix").cookie(user_cookie()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["plexRatingKey"], "1");
+    assert_eq!(results[0]["tmdbId"], 603);
+    assert_eq!(results[0]["imdbId"], "tt0133093");
+    assert_eq!(results[0]["arr"][0]["id"], 7);
+    assert_eq!(results[0]["sources"], json!(["plex", "radarr"]));
+    // A TMDB error status skips TMDB instead of passing as an empty result
+    assert_eq!(body["skipped"], json!(["sonarr", "tmdb"]));
 }
 
-#[actix_rt::test]
-async fn sonarr_queue() {
-    let mock_server = MockServer::start().await;
-    Mock::given(method("GET"))
-        .and(path("/api/v3/queue"))
-        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
-            "records": [], "totalRecords": 0
-        })))
-        .mount(&mock_server).await;
+// ─── Discover (TMDB) ─────────────────────────────────────────────────────────
 
-    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+#[actix_rt::test]
+async fn discover_returns_503_when_tmdb_unconfigured() {
+    let app = test_app!(mock_config("http://plex.local:32400", "token"));
 
-    let req = test::TestRequest::get().uri("/api/sonarr/queue").to_request();
-    let resp = test::call_service(&app, req).await;
-    assert_eq!(resp.status(), 200);
+    for uri in ["/api/discover/trending", "/api/discover/movie/603", "/api/discover/search?q=matrix"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 503, "{}", uri);
+    }
 }
 
 #[actix_rt::test]
-async fn sonarr_episodes() {
+async fn discover_uses_read_access_token_as_bearer() {
     let mock_server = MockServer::start().await;
     Mock::given(method("GET"))
-        .and(path("/api/v3/episode"))
-        .and(query_param("seriesId", "1"))
-        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
-            {"id": 1, "title": "Pilot", "seasonNumber": 1, "episodeNumber": 1}
-        ])))
+        .and(path("/movie/603"))
+        .and(wiremock::matchers::header("Authorization", "Bearer v4-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 603, "title": "The Matrix"})))
         .mount(&mock_server).await;
 
-    let app = test_app!(full_mock_config("http://unused", &mock_server.uri(), ""));
+    let config = mock_config("http://plex.local:32400", "token");
+    config.write().unwrap().tmdb = TmdbConfig {
+        api_key: "v3-key".to_string(),
+        read_access_token: "v4-token".to_string(),
+    };
+    let app = test_app!(config, &mock_server.uri());
 
-    let req = test::TestRequest::get().uri("/api/sonarr/episodes?seriesId=1").to_request();
+    let req = test::TestRequest::get().uri("/api/discover/movie/603").to_request();
     let resp = test::call_service(&app, req).await;
     assert_eq!(resp.status(), 200);
-
     let body: Value = test::read_body_json(resp).await;
-    assert_eq!(body[0]["title"], "Pilot");
+    assert_eq!(body["title"], "The Matrix");
 }
 
-// ─── Radarr ──────────────────────────────────────────────────────────────────
-
 #[actix_rt::test]
-async fn radarr_lookup() {
+async fn discover_does_not_cache_tmdb_errors() {
     let mock_server = MockServer::start().await;
     Mock::given(method("GET"))
-        .and(path("/api/v3/movie/lookup"))
-        .and(query_param("term", "inception"))
-        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
-            {"title": "Inception", "tmdbId": 27205}
-        ])))
+        .and(path("/trending/movie/week"))
+        .respond_with(ResponseTemplate::new(429).set_body_json(json!({"status_message": "Slow down"})))
+        .up_to_n_times(1)
+        .mount(&mock_server).await;
+    for list in ["/trending/movie/week", "/trending/tv/week"] {
+        Mock::given(method("GET"))
+            .and(path(list))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": [{"id": 603}]})))
+            .mount(&mock_server).await;
+    }
+    Mock::given(method("GET"))
+        .and(path("/search/multi"))
+        .and(query_param("query", "matrix"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": [
+            {"id": 603, "media_type": "movie", "title": "The Matrix"}
+        ]})))
         .mount(&mock_server).await;
 
-    let app = test_app!(full_mock_config("http://unused", "", &mock_server.uri()));
+    let config = mock_config("http://plex.local:32400", "token");
+    config.write().unwrap().tmdb = TmdbConfig {
+        api_key: "v3-key".to_string(),
+        read_access_token: String::new(),
+    };
+    let app = test_app!(config, &mock_server.uri());
 
-    let req = test::TestRequest::get().uri("/api/radarr/lookup?term=inception").to_request();
+    let req = test::TestRequest::get().uri("/api/discover/trending").to_request();
     let resp = test::call_service(&app, req).await;
-    assert_eq!(resp.status(), 200);
-
-    let body: Value = test::read_body_json(resp).await;
-    assert_eq!(body[0]["title"], "Inception");
+    assert_eq!(resp.status(), 502);
+    let req = test::TestRequest::get().uri("/api/discover/trending").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["movies"][0]["id"], 603);
+
+    // The query TMDB sees matches the one the cache is keyed on
+    let req = test::TestRequest::get().uri("/api/discover/search?q=%20matrix%20").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["movies"][0]["title"], "The Matrix");
 }
 
 #[actix_rt::test]
-async fn radarr_calendar() {
+async fn settings_update_tmdb_and_test_connection() {
     let mock_server = MockServer::start().await;
     Mock::given(method("GET"))
-        .and(path("/api/v3/calendar"))
-        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
-            {"title": "Upcoming Movie", "tmdbId": 12345}
-        ])))
+        .and(path("/configuration"))
+        .and(query_param("api_key", "v3-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"images": {}})))
         .mount(&mock_server).await;
+    for (key, title) in [("old-key", "Stale"), ("v3-key", "The Matrix")] {
+        Mock::given(method("GET"))
+            .and(path("/movie/603"))
+            .and(query_param("api_key", key))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 603, "title": title})))
+            .mount(&mock_server).await;
+    }
 
-    let app = test_app!(full_mock_config("http://unused", "", &mock_server.uri()));
+    let config = mock_config("http://plex.local:32400", "token");
+    config.write().unwrap().tmdb.api_key = "old-key".to_string();
+    let app = test_app!(config.clone(), &mock_server.uri());
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
 
-    let req = test::TestRequest::get().uri("/api/radarr/calendar").to_request();
-    let resp = test::call_service(&app, req).await;
-    assert_eq!(resp.status(), 200);
-}
+    let req = test::TestRequest::get().uri("/api/discover/movie/603").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["title"], "Stale");
 
-#[actix_rt::test]
-async fn radarr_queue() {
-    let mock_server = MockServer::start().await;
-    Mock::given(method("GET"))
-        .and(path("/api/v3/queue"))
-        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
-            "records": [], "totalRecords": 0
-        })))
-        .mount(&mock_server).await;
+    let req = test::TestRequest::put()
+        .uri("/api/settings/tmdb")
+        .cookie(admin.clone())
+        .set_json(json!({ "api_key": "v3-key" }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["tmdb"]["has_api_key"], true);
+    assert_eq!(body["tmdb"]["has_read_access_token"], false);
+    assert_eq!(config.read().unwrap().tmdb.api_key, "v3-key");
 
-    let app = test_app!(full_mock_config("http://unused", "", &mock_server.uri()));
+    // Nothing cached under the old key survives the change
+    let req = test::TestRequest::get().uri("/api/discover/movie/603").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["title"], "The Matrix");
 
-    let req = test::TestRequest::get().uri("/api/radarr/queue").to_request();
-    let resp = test::call_service(&app, req).await;
-    assert_eq!(resp.status(), 200);
+    let req = test::TestRequest::post()
+        .uri("/api/settings/test/tmdb")
+        .cookie(admin)
+        .set_json(json!({}))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["success"], true);
 }
 
 // ─── Downloads ───────────────────────────────────────────────────────────────
@@ -1114,6 +3387,170 @@ async fn watch_party_invalid_id_returns_400() {
     assert_eq!(resp.status(), 400);
 }
 
+// ─── Webhooks ────────────────────────────────────────────────────────────────
+
+fn webhook_config() -> SharedConfig {
+    let config = mock_config("http://plex.local:32400", "token");
+    {
+        let mut cfg = config.write().unwrap();
+        cfg.webhooks.sonarr_secret = "sonarr-secret".to_string();
+        cfg.webhooks.plex_secret = "plex-secret".to_string();
+    }
+    config
+}
+
+fn user_cookie() -> actix_web::cookie::Cookie<'static> {
+    actix_web::cookie::Cookie::new("plex_user_token", "1:tv-token:server-token")
+}
+
+#[actix_rt::test]
+async fn webhook_sonarr_download_appears_in_activity() {
+    let app = test_app!(webhook_config());
+
+    let req = test::TestRequest::post()
+        .uri("/api/webhooks/sonarr?secret=sonarr-secret")
+        .set_json(json!({
+            "eventType": "Download",
+            "series": {"title": "Breaking Bad", "tvdbId": 81189, "imdbId": "tt0903747"},
+            "episodes": [{"seasonNumber": 1, "episodeNumber": 2, "title": "Cat's in the Bag..."}],
+            "isUpgrade": false
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let req = test::TestRequest::get()
+        .uri("/api/activity")
+        .cookie(user_cookie())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body[0]["source"], "sonarr");
+    assert_eq!(body[0]["kind"], "download");
+    assert_eq!(body[0]["title"], "Breaking Bad S01E02 - Cat's in the Bag...");
+    assert_eq!(body[0]["media"]["tvdb_id"], 81189);
+}
+
+#[actix_rt::test]
+async fn webhook_wrong_secret_returns_401() {
+    let app = test_app!(webhook_config());
+
+    let req = test::TestRequest::post()
+        .uri("/api/webhooks/sonarr")
+        .insert_header(("X-Webhook-Secret", "nope"))
+        .set_json(json!({"eventType": "Test"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+}
+
+#[actix_rt::test]
+async fn webhook_without_configured_secret_returns_503() {
+    let app = test_app!(webhook_config());
+
+    let req = test::TestRequest::post()
+        .uri("/api/webhooks/radarr?secret=anything")
+        .set_json(json!({"eventType": "Test"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 503);
+}
+
+#[actix_rt::test]
+async fn webhook_plex_multipart_payload() {
+    let app = test_app!(webhook_config());
+
+    let payload = json!({
+        "event": "library.new",
+        "Account": {"title": "admin"},
+        "Metadata": {
+            "ratingKey": "4242",
+            "title": "The Matrix",
+            "Guid": [{"id": "imdb://tt0133093"}, {"id": "tmdb://603"}]
+        }
+    });
+    let boundary = "playarr-boundary";
+    let body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"payload\"\r\nContent-Type: application/json\r\n\r\n{p}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"thumb\"; filename=\"thumb.jpg\"\r\nContent-Type: image/jpeg\r\n\r\nJFIF\r\n\
+         --{b}--\r\n",
+        b = boundary,
+        p = payload
+    );
+
+    let req = test::TestRequest::post()
+        .uri("/api/webhooks/plex?secret=plex-secret")
+        .insert_header(("Content-Type", format!("multipart/form-data; boundary={}", boundary)))
+        .set_payload(body)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let req = test::TestRequest::get()
+        .uri("/api/activity?limit=1")
+        .cookie(user_cookie())
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body[0]["kind"], "library_new");
+    assert_eq!(body[0]["media"]["tmdb_id"], 603);
+    assert_eq!(body[0]["media"]["plex_rating_key"], "4242");
+}
+
+// ─── Response Cache ──────────────────────────────────────────────────────────
+
+#[actix_rt::test]
+async fn cache_serves_repeat_requests_without_upstream_call() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/movie"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{"id": 1, "title": "The Matrix"}])))
+        .expect(1)
+        .mount(&mock_server).await;
+
+    let app = test_app!(full_mock_config("http://unused", "", &mock_server.uri()));
+    let movies_request = |etag: Option<&str>| {
+        let mut req = test::TestRequest::get().uri("/api/radarr/movie");
+        if let Some(tag) = etag {
+            req = req.insert_header(("If-None-Match", tag.to_string()));
+        }
+        req.to_request()
+    };
+
+    let resp = test::call_service(&app, movies_request(None)).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("X-Cache").unwrap(), "MISS");
+    let etag = resp.headers().get("ETag").unwrap().to_str().unwrap().to_string();
+
+    let resp = test::call_service(&app, movies_request(None)).await;
+    assert_eq!(resp.headers().get("X-Cache").unwrap(), "HIT");
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body[0]["title"], "The Matrix");
+
+    let resp = test::call_service(&app, movies_request(Some(&etag))).await;
+    assert_eq!(resp.status(), 304);
+}
+
+#[actix_rt::test]
+async fn cache_flush_requires_admin() {
+    let app = test_app!(mock_config("http://plex.local:32400", "token"));
+
+    let req = test::TestRequest::delete()
+        .uri("/api/cache")
+        .cookie(user_cookie())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+
+    let req = test::TestRequest::delete()
+        .uri("/api/cache")
+        .cookie(actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token"))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["removed"], 0);
+}
+
 // ─── Live Plex Integration Tests ─────────────────────────────────────────────
 // These tests require a running Plex server with valid config at the default
 // config path. Run with: cargo test -- --ignored
@@ -1547,3 +3984,155 @@ async fn live_plex_transcode_resolution_changes() {
     }
 }
 
+
+// ─── Calendar ────────────────────────────────────────────────────────────────
+
+async fn mount_plex_library(plex: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/library/sections"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Directory": [
+                { "key": "1", "type": "movie" },
+                { "key": "2", "type": "show" }
+            ]}
+        })))
+        .mount(plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/sections/1/all"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [
+                { "ratingKey": "100", "Guid": [{ "id": "tmdb://27205" }] }
+            ]}
+        })))
+        .mount(plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/sections/2/all"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [
+                { "ratingKey": "200", "guid": "com.plexapp.agents.thetvdb://81189?lang=en" }
+            ]}
+        })))
+        .mount(plex).await;
+    Mock::given(method("GET"))
+        .and(path("/library/metadata/200/allLeaves"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MediaContainer": { "Metadata": [
+                { "ratingKey": "201", "parentIndex": 1, "index": 5 }
+            ]}
+        })))
+        .mount(plex).await;
+}
+
+#[actix_rt::test]
+async fn calendar_merges_episodes_and_movie_releases() {
+    let plex = MockServer::start().await;
+    let sonarr = MockServer::start().await;
+    let radarr = MockServer::start().await;
+    mount_plex_library(&plex).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/calendar"))
+        .and(query_param("end", "2026-02-21"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "id": 5, "seasonNumber": 1, "episodeNumber": 5, "title": "Gray Matter",
+              "airDateUtc": "2026-02-17T02:00:00Z", "hasFile": true, "monitored": true,
+              "series": { "title": "Breaking Bad", "tvdbId": 81189, "runtime": 47 } },
+            { "id": 6, "seasonNumber": 1, "episodeNumber": 6, "title": "Crazy Handful of Nothin'",
+              "airDateUtc": "2026-02-18T02:00:00Z", "hasFile": false, "monitored": true,
+              "series": { "title": "Breaking Bad", "tvdbId": 81189 } }
+        ])))
+        .mount(&sonarr).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/calendar"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "id": 1, "title": "Inception", "tmdbId": 27205,
+              "inCinemas": "2026-02-16T00:00:00Z", "digitalRelease": "2026-05-01T00:00:00Z" }
+        ])))
+        .mount(&radarr).await;
+
+    let app = test_app!(full_mock_config(&plex.uri(), &sonarr.uri(), &radarr.uri()));
+
+    let req = test::TestRequest::get()
+        .uri("/api/calendar?start=2026-02-15&end=2026-02-20")
+        .cookie(user_cookie())
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let events = body.as_array().unwrap();
+    assert_eq!(events.len(), 3, "digital release is outside the window: {body}");
+
+    assert_eq!(events[0]["kind"], "cinema_release");
+    assert_eq!(events[0]["all_day"], true);
+    assert_eq!(events[0]["in_plex"], true);
+    assert_eq!(events[0]["plex_rating_key"], "100");
+
+    assert_eq!(events[1]["kind"], "episode");
+    assert_eq!(events[1]["subtitle"], "S01E05 · Gray Matter");
+    assert_eq!(events[1]["end"], "2026-02-17T02:47:00Z");
+    assert_eq!(events[1]["in_plex"], true);
+    assert_eq!(events[1]["plex_rating_key"], "201");
+
+    assert_eq!(events[2]["in_plex"], false);
+}
+
+#[actix_rt::test]
+async fn calendar_rejects_bad_range() {
+    let app = test_app!(mock_config("http://plex.local:32400", "token"));
+    let req = test::TestRequest::get()
+        .uri("/api/calendar?start=2026-02-20&end=2026-02-15")
+        .cookie(user_cookie())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+}
+
+#[actix_rt::test]
+async fn calendar_ical_feed_uses_token() {
+    let sonarr = MockServer::start().await;
+    let air = (chrono::Utc::now() + chrono::Duration::days(1)).format("%Y-%m-%dT20:00:00Z").to_string();
+    Mock::given(method("GET"))
+        .and(path("/api/v3/calendar"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "id": 9, "seasonNumber": 2, "episodeNumber": 1, "title": "Pilot, Part 1",
+              "airDateUtc": air, "series": { "title": "Severance" } }
+        ])))
+        .mount(&sonarr).await;
+
+    let app = test_app!(full_mock_config("http://unused", &sonarr.uri(), ""));
+
+    let admin = actix_web::cookie::Cookie::new("plex_user_token", "0:tv-token:server-token");
+    let req = test::TestRequest::get().uri("/api/calendar/feed").cookie(admin.clone()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let feed_path = body["path"].as_str().unwrap().to_string();
+    assert!(feed_path.ends_with(".ics"));
+
+    // Calendar apps can't send cookies; the token alone must work
+    let req = test::TestRequest::get().uri(&feed_path).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let ics = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(ics.contains("SUMMARY:Severance - S02E01 · Pilot\\, Part 1\r\n"));
+    assert!(ics.contains("UID:sonarr-default-episode-9@playarr\r\n"));
+
+    // Every user has a feed of their own, and resetting it leaves the others alone
+    let req = test::TestRequest::get().uri("/api/calendar/feed").cookie(user_cookie()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let user_path = body["path"].as_str().unwrap().to_string();
+    assert_ne!(user_path, feed_path);
+    let req = test::TestRequest::post().uri("/api/calendar/feed/reset").cookie(user_cookie()).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_ne!(body["path"], user_path.as_str());
+    let req = test::TestRequest::get().uri(&user_path).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+    let req = test::TestRequest::get().uri(&feed_path).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let req = test::TestRequest::post().uri("/api/calendar/feed/reset").cookie(admin).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_ne!(body["path"], feed_path.as_str());
+
+    let req = test::TestRequest::get().uri(&feed_path).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+}
@@ -0,0 +1,404 @@
+//! Watchlists. `/api/watchlist/plex` mirrors the account's Plex Discover
+//! watchlist through plex.tv; `/api/watchlist` is Playarr's own list keyed by
+//! TMDB id, for titles nobody has requested yet. Local entries carry the ids
+//! the Radarr/Sonarr add flow needs and say whether an *arr already has them.
+
+use std::collections::HashMap;
+
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use log::debug;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::arr::client::ArrClient;
+use crate::arr::ArrService;
+use crate::cache::{self, CacheRoute, ResponseCache};
+use crate::config::json_store::JsonFileStore;
+use crate::config::models::ArrInstanceConfig;
+use crate::discover::client::TmdbClient;
+use crate::http_error::{Error, Result};
+use crate::plex::client::PlexClient;
+use crate::radarr::client::RadarrClient;
+use crate::radarr::models::Movie;
+use crate::search::{ArrEntry, Availability};
+use crate::sonarr::client::SonarrClient;
+use crate::sonarr::models::Series;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaType {
+    Movie,
+    Tv,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchlistEntry {
+    pub tmdb_id: u64,
+    pub media_type: MediaType,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poster_path: Option<String>,
+    /// Sonarr adds series by TVDB id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tvdb_id: Option<u64>,
+    pub added_at: DateTime<Utc>,
+}
+
+/// Local watchlists per Plex user id, written to `watchlist.json` in the data
+/// directory on every change.
+pub struct WatchlistStore {
+    entries: JsonFileStore<HashMap<i64, Vec<WatchlistEntry>>>,
+}
+
+impl WatchlistStore {
+    pub fn new() -> Self {
+        Self { entries: JsonFileStore::open("watchlist.json") }
+    }
+
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self { entries: JsonFileStore::in_memory() }
+    }
+
+    /// Newest first.
+    pub fn list(&self, user: i64) -> Vec<WatchlistEntry> {
+        self.entries
+            .read()
+            .map(|e| e.get(&user).cloned().unwrap_or_default())
+            .unwrap_or_default()
+    }
+
+    /// Add `entry` unless it is already listed. Returns the stored entry and
+    /// whether it was new.
+    pub fn add(&self, user: i64, entry: WatchlistEntry) -> Result<(WatchlistEntry, bool)> {
+        let mut entries = self.entries.write().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+        let list = entries.entry(user).or_default();
+        if let Some(existing) = list.iter().find(|e| e.tmdb_id == entry.tmdb_id && e.media_type == entry.media_type) {
+            return Ok((existing.clone(), false));
+        }
+        list.insert(0, entry.clone());
+        drop(entries);
+        self.entries.save();
+        Ok((entry, true))
+    }
+
+    pub fn remove(&self, user: i64, media_type: MediaType, tmdb_id: u64) -> Result<bool> {
+        let mut entries = self.entries.write().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+        let Some(list) = entries.get_mut(&user) else { return Ok(false) };
+        let before = list.len();
+        list.retain(|e| !(e.tmdb_id == tmdb_id && e.media_type == media_type));
+        let removed = list.len() != before;
+        drop(entries);
+        if removed {
+            self.entries.save();
+        }
+        Ok(removed)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddEntryRequest {
+    tmdb_id: u64,
+    media_type: MediaType,
+    /// Looked up on TMDB when left out.
+    title: Option<String>,
+    year: Option<u32>,
+    poster_path: Option<String>,
+    tvdb_id: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WatchlistItem {
+    #[serde(flatten)]
+    entry: WatchlistEntry,
+    /// Absent when Radarr/Sonarr could not be asked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    availability: Option<Availability>,
+    arr: Vec<ArrEntry>,
+}
+
+fn signed_in(req: &HttpRequest) -> Result<(i64, String)> {
+    PlexClient::user_from_request(req).ok_or_else(|| Error::Unauthorized("Not signed in".to_string()))
+}
+
+/// Fill in title, year, poster and TVDB id from TMDB.
+async fn enrich(tmdb: &TmdbClient, request: &mut AddEntryRequest) -> Result<()> {
+    let kind = match request.media_type {
+        MediaType::Movie => "movie",
+        MediaType::Tv => "tv",
+    };
+    let path = format!("/{}/{}", kind, request.tmdb_id);
+    let resp = tmdb
+        .get(&path)?
+        .query(&[("append_to_response", "external_ids")])
+        .send()
+        .await
+        .map_err(|e| Error::UpstreamError(format!("TMDB request failed: {}", e)))?;
+    match resp.status() {
+        s if s.is_success() => {}
+        reqwest::StatusCode::NOT_FOUND => {
+            return Err(Error::NotFound(format!("TMDB has no {} with id {}", kind, request.tmdb_id)))
+        }
+        reqwest::StatusCode::UNAUTHORIZED => {
+            return Err(Error::Unauthorized("TMDB rejected the configured credentials".to_string()))
+        }
+        s => return Err(Error::UpstreamError(format!("TMDB returned HTTP {} for {}", s.as_u16(), path))),
+    }
+    let body: Value = resp.json().await.map_err(|e| anyhow::anyhow!("TMDB parse failed: {}", e))?;
+
+    let title = body["title"].as_str().or(body["name"].as_str()).map(String::from);
+    let date = body["release_date"].as_str().or(body["first_air_date"].as_str()).unwrap_or_default();
+    request.title = request.title.take().filter(|t| !t.trim().is_empty()).or(title);
+    request.year = request.year.or_else(|| date.get(..4)?.parse().ok());
+    request.poster_path = request.poster_path.take().or_else(|| body["poster_path"].as_str().map(String::from));
+    request.tvdb_id = request.tvdb_id.or(body["external_ids"]["tvdb_id"].as_u64());
+    Ok(())
+}
+
+/// `path` from every instance, through the same cache entries as the
+/// `/api/sonarr/series` and `/api/radarr/movie` lists. `None` when the
+/// service is unconfigured or no instance answered.
+async fn cached_each<S: ArrService, T: Serialize + DeserializeOwned>(
+    client: &ArrClient<S>,
+    cache: &ResponseCache,
+    req: &HttpRequest,
+    route: CacheRoute,
+    path: &str,
+) -> Option<Vec<(ArrInstanceConfig, T)>> {
+    let targets = client.targets(None).ok()?;
+    let lists = join_all(targets.into_iter().map(|instance| async move {
+        let key = ResponseCache::key(route, &format!("{}:{}", instance.label(), path), req);
+        let entry = cache.get_or_fetch(key, route, || async {
+            let list: T = client.send_json(client.get(&instance, path), path).await?;
+            Ok(serde_json::to_value(list).map_err(anyhow::Error::from)?)
+        }).await;
+        let list = entry.and_then(|e| Ok(serde_json::from_value::<T>(e.body).map_err(anyhow::Error::from)?));
+        list.map(|list| (instance, list))
+            .inspect_err(|e| debug!("Watchlist could not read {}: {}", S::NAME, e))
+            .ok()
+    }))
+    .await;
+    let lists: Vec<_> = lists.into_iter().flatten().collect();
+    (!lists.is_empty()).then_some(lists)
+}
+
+/// Where each entry stands in Radarr/Sonarr. Unconfigured or failing
+/// services leave their entries without an availability.
+async fn annotate(
+    entries: Vec<WatchlistEntry>,
+    req: &HttpRequest,
+    cache: &ResponseCache,
+    sonarr: &SonarrClient,
+    radarr: &RadarrClient,
+) -> Vec<WatchlistItem> {
+    let wants = |kind: MediaType| entries.iter().any(|e| e.media_type == kind);
+    let movies = async {
+        if !wants(MediaType::Movie) {
+            return None;
+        }
+        cached_each::<_, Vec<Movie>>(radarr, cache, req, cache::RADARR, "/movie").await
+    };
+    let series = async {
+        if !wants(MediaType::Tv) {
+            return None;
+        }
+        cached_each::<_, Vec<Series>>(sonarr, cache, req, cache::SONARR, "/series").await
+    };
+    let (movies, series) = tokio::join!(movies, series);
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let mut arr = Vec::new();
+            let mut in_library = false;
+            let known = match entry.media_type {
+                MediaType::Movie => movies.as_ref().map(|instances| {
+                    for (instance, movie) in instances
+                        .iter()
+                        .flat_map(|(i, movies)| movies.iter().map(move |m| (i, m)))
+                        .filter(|(_, m)| m.tmdb_id == entry.tmdb_id)
+                    {
+                        in_library |= movie.has_file;
+                        arr.push(ArrEntry {
+                            instance: instance.label().to_string(),
+                            id: movie.id.unwrap_or(0),
+                            monitored: movie.monitored,
+                        });
+                    }
+                }),
+                MediaType::Tv => series.as_ref().map(|instances| {
+                    for (instance, show) in instances
+                        .iter()
+                        .flat_map(|(i, series)| series.iter().map(move |s| (i, s)))
+                        .filter(|(_, s)| s.tmdb_id == entry.tmdb_id || entry.tvdb_id.is_some_and(|id| id == s.tvdb_id))
+                    {
+                        in_library |= show.statistics.as_ref().and_then(|s| s["episodeFileCount"].as_u64()).unwrap_or(0) > 0;
+                        arr.push(ArrEntry {
+                            instance: instance.label().to_string(),
+                            id: show.id.unwrap_or(0),
+                            monitored: show.monitored,
+                        });
+                    }
+                }),
+            };
+            let availability = known.map(|_| match (in_library, arr.is_empty()) {
+                (true, _) => Availability::InLibrary,
+                (false, false) => Availability::Monitored,
+                (false, true) => Availability::NotTracked,
+            });
+            WatchlistItem { entry, availability, arr }
+        })
+        .collect()
+}
+
+#[get("/watchlist")]
+async fn list_local(
+    req: HttpRequest,
+    store: web::Data<WatchlistStore>,
+    cache: web::Data<ResponseCache>,
+    sonarr: web::Data<SonarrClient>,
+    radarr: web::Data<RadarrClient>,
+) -> Result<impl Responder> {
+    let (user, _) = signed_in(&req)?;
+    Ok(HttpResponse::Ok().json(annotate(store.list(user), &req, &cache, &sonarr, &radarr).await))
+}
+
+/// Add a title by TMDB id. Returns 201 for a new entry, 200 if it was
+/// already listed.
+#[post("/watchlist")]
+async fn add_local(
+    req: HttpRequest,
+    store: web::Data<WatchlistStore>,
+    tmdb: web::Data<TmdbClient>,
+    body: web::Json<AddEntryRequest>,
+) -> Result<impl Responder> {
+    let (user, _) = signed_in(&req)?;
+    let mut request = body.into_inner();
+    if request.tmdb_id == 0 {
+        return Err(Error::BadRequest("tmdbId is required".to_string()));
+    }
+    let needs_lookup = request.title.as_deref().is_none_or(|t| t.trim().is_empty())
+        || (request.media_type == MediaType::Tv && request.tvdb_id.is_none());
+    if needs_lookup && tmdb.auth().is_some() {
+        enrich(&tmdb, &mut request).await?;
+    }
+    let title = request
+        .title
+        .filter(|t| !t.trim().is_empty())
+        .ok_or_else(|| Error::BadRequest("title is required while TMDB is not configured".to_string()))?;
+
+    let entry = WatchlistEntry {
+        tmdb_id: request.tmdb_id,
+        media_type: request.media_type,
+        title,
+        year: request.year,
+        poster_path: request.poster_path,
+        tvdb_id: request.tvdb_id,
+        added_at: Utc::now(),
+    };
+    let (entry, created) = store.add(user, entry)?;
+    Ok(if created { HttpResponse::Created().json(entry) } else { HttpResponse::Ok().json(entry) })
+}
+
+#[delete("/watchlist/{media_type}/{tmdb_id}")]
+async fn remove_local(
+    req: HttpRequest,
+    store: web::Data<WatchlistStore>,
+    path: web::Path<(MediaType, u64)>,
+) -> Result<impl Responder> {
+    let (user, _) = signed_in(&req)?;
+    let (media_type, tmdb_id) = path.into_inner();
+    if !store.remove(user, media_type, tmdb_id)? {
+        return Err(Error::NotFound(format!("TMDB id {} is not on your watchlist", tmdb_id)));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Discover ratingKeys are opaque hex ids like `5d776825880197001ec967c6`.
+fn discover_key(key: &str) -> Result<&str> {
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(Error::BadRequest(format!("'{}' is not a Plex Discover ratingKey", key)));
+    }
+    Ok(key)
+}
+
+async fn send_discover(req: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let resp = req
+        .send()
+        .await
+        .map_err(|e| Error::UpstreamError(format!("Plex Discover request failed: {}", e)))?;
+    match resp.status() {
+        s if s.is_success() => Ok(resp),
+        reqwest::StatusCode::UNAUTHORIZED => Err(Error::Unauthorized(
+            "plex.tv rejected the account token. Please sign in again.".to_string(),
+        )),
+        reqwest::StatusCode::NOT_FOUND => Err(Error::NotFound("Not found on Plex Discover".to_string())),
+        s => Err(Error::UpstreamError(format!("Plex Discover returned HTTP {}", s.as_u16()))),
+    }
+}
+
+/// The account's Plex watchlist, as shown in the Plex apps.
+#[get("/watchlist/plex")]
+async fn list_plex(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+) -> Result<impl Responder> {
+    let (_, token) = signed_in(&req)?;
+    let upstream = plex
+        .discover_get("/library/sections/watchlist/all", &token)
+        .query(&[("includeGuids", "1"), ("X-Plex-Container-Size", "300")]);
+    let body: Value = send_discover(upstream)
+        .await?
+        .json()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to parse Plex Discover response: {}", e))?;
+    let items = &body["MediaContainer"]["Metadata"];
+    Ok(HttpResponse::Ok().json(if items.is_null() { serde_json::json!([]) } else { items.clone() }))
+}
+
+#[put("/watchlist/plex/{key}")]
+async fn add_plex(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let (_, token) = signed_in(&req)?;
+    let key = path.into_inner();
+    let upstream = plex
+        .discover_put("/actions/addToWatchlist", &token)
+        .query(&[("ratingKey", discover_key(&key)?)]);
+    send_discover(upstream).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[delete("/watchlist/plex/{key}")]
+async fn remove_plex(
+    req: HttpRequest,
+    plex: web::Data<PlexClient>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let (_, token) = signed_in(&req)?;
+    let key = path.into_inner();
+    let upstream = plex
+        .discover_put("/actions/removeFromWatchlist", &token)
+        .query(&[("ratingKey", discover_key(&key)?)]);
+    send_discover(upstream).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_plex)
+        .service(add_plex)
+        .service(remove_plex)
+        .service(list_local)
+        .service(add_local)
+        .service(remove_local);
+}
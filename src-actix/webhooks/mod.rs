@@ -0,0 +1,168 @@
+pub mod payloads;
+
+use actix_multipart::Multipart;
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use futures_util::StreamExt;
+use serde::Deserialize;
+
+use crate::config::SharedConfig;
+use crate::events::{EventBus, EventSource};
+use crate::http_error::{self, Result};
+use payloads::{ArrPayload, PlexPayload};
+
+/// Largest Plex JSON payload we are willing to buffer (thumbnails are skipped).
+const MAX_PLEX_PAYLOAD: usize = 1024 * 1024;
+
+#[derive(Deserialize)]
+struct WebhookQuery {
+    secret: Option<String>,
+}
+
+/// Compare two secrets without short-circuiting on the first mismatch.
+fn secrets_match(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Check the secret sent via `?secret=` or the `X-Webhook-Secret` header
+/// against the configured secret for `source`.
+fn verify_secret(
+    req: &HttpRequest,
+    query: &WebhookQuery,
+    config: &SharedConfig,
+    source: EventSource,
+) -> Result<()> {
+    let expected = {
+        let cfg = config.read().map_err(|e| anyhow::anyhow!("Lock error: {}", e))?;
+        match source {
+            EventSource::Sonarr => cfg.webhooks.sonarr_secret.clone(),
+            EventSource::Radarr => cfg.webhooks.radarr_secret.clone(),
+            EventSource::Plex => cfg.webhooks.plex_secret.clone(),
+        }
+    };
+    if expected.is_empty() {
+        return Err(http_error::Error::ServiceUnavailable(format!(
+            "{:?} webhooks are not configured. Generate a secret in Settings first.",
+            source
+        )));
+    }
+
+    let provided = query.secret.clone().or_else(|| {
+        req.headers()
+            .get("X-Webhook-Secret")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+    });
+
+    match provided {
+        Some(p) if secrets_match(&p, &expected) => Ok(()),
+        _ => Err(http_error::Error::Unauthorized("Invalid webhook secret".to_string())),
+    }
+}
+
+async fn receive_arr(
+    req: HttpRequest,
+    query: web::Query<WebhookQuery>,
+    config: web::Data<SharedConfig>,
+    events: web::Data<EventBus>,
+    body: web::Json<ArrPayload>,
+    source: EventSource,
+) -> Result<HttpResponse> {
+    verify_secret(&req, &query, &config, source)?;
+    events.publish(body.into_inner().into_event(source));
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "success": true })))
+}
+
+#[post("/sonarr")]
+async fn sonarr_webhook(
+    req: HttpRequest,
+    query: web::Query<WebhookQuery>,
+    config: web::Data<SharedConfig>,
+    events: web::Data<EventBus>,
+    body: web::Json<ArrPayload>,
+) -> Result<impl Responder> {
+    receive_arr(req, query, config, events, body, EventSource::Sonarr).await
+}
+
+#[post("/radarr")]
+async fn radarr_webhook(
+    req: HttpRequest,
+    query: web::Query<WebhookQuery>,
+    config: web::Data<SharedConfig>,
+    events: web::Data<EventBus>,
+    body: web::Json<ArrPayload>,
+) -> Result<impl Responder> {
+    receive_arr(req, query, config, events, body, EventSource::Radarr).await
+}
+
+/// Plex posts `multipart/form-data` with the JSON in a `payload` field and an
+/// optional `thumb` JPEG. Plain JSON bodies are accepted too for easier testing.
+async fn read_plex_payload(req: &HttpRequest, payload: web::Payload) -> Result<Vec<u8>> {
+    let content_type = req
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+
+    if !content_type.starts_with("multipart/") {
+        let mut payload = payload;
+        let mut body = Vec::new();
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk.map_err(|e| http_error::Error::BadRequest(e.to_string()))?;
+            if body.len() + chunk.len() > MAX_PLEX_PAYLOAD {
+                return Err(http_error::Error::BadRequest("Payload too large".to_string()));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        return Ok(body);
+    }
+
+    let mut multipart = Multipart::new(req.headers(), payload);
+    let mut json = None;
+    while let Some(field) = multipart.next().await {
+        let mut field = field.map_err(|e| http_error::Error::BadRequest(e.to_string()))?;
+        let is_payload = field.name() == Some("payload");
+        let mut data = Vec::new();
+        // Drain every field (including the thumbnail) so the stream advances.
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| http_error::Error::BadRequest(e.to_string()))?;
+            if is_payload {
+                if data.len() + chunk.len() > MAX_PLEX_PAYLOAD {
+                    return Err(http_error::Error::BadRequest("Payload too large".to_string()));
+                }
+                data.extend_from_slice(&chunk);
+            }
+        }
+        if is_payload {
+            json = Some(data);
+        }
+    }
+
+    json.ok_or_else(|| http_error::Error::BadRequest("Missing 'payload' field".to_string()))
+}
+
+#[post("/plex")]
+async fn plex_webhook(
+    req: HttpRequest,
+    query: web::Query<WebhookQuery>,
+    config: web::Data<SharedConfig>,
+    events: web::Data<EventBus>,
+    payload: web::Payload,
+) -> Result<impl Responder> {
+    verify_secret(&req, &query, &config, EventSource::Plex)?;
+
+    let body = read_plex_payload(&req, payload).await?;
+    let parsed: PlexPayload = serde_json::from_slice(&body)
+        .map_err(|e| http_error::Error::BadRequest(format!("Invalid Plex payload: {}", e)))?;
+
+    events.publish(parsed.into_event());
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "success": true })))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/webhooks")
+            .service(sonarr_webhook)
+            .service(radarr_webhook)
+            .service(plex_webhook),
+    );
+}
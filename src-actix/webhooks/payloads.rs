@@ -0,0 +1,196 @@
+//! Typed views of the webhook bodies sent by Sonarr, Radarr and Plex.
+//! Only the fields Playarr uses are modelled; everything else is ignored.
+
+use serde::Deserialize;
+
+use crate::events::{ActivityEvent, EventKind, EventSource, MediaIds};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArrPayload {
+    pub event_type: String,
+    #[serde(default)]
+    pub series: Option<ArrMedia>,
+    #[serde(default)]
+    pub movie: Option<ArrMedia>,
+    #[serde(default)]
+    pub episodes: Vec<ArrEpisode>,
+    #[serde(default)]
+    pub release: Option<ArrRelease>,
+    #[serde(default)]
+    pub is_upgrade: bool,
+    /// Health events carry a human readable message and a level.
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub level: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArrMedia {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub year: Option<u32>,
+    #[serde(default)]
+    pub tmdb_id: Option<u64>,
+    #[serde(default)]
+    pub tvdb_id: Option<u64>,
+    #[serde(default)]
+    pub imdb_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArrEpisode {
+    #[serde(default)]
+    pub season_number: u32,
+    #[serde(default)]
+    pub episode_number: u32,
+    #[serde(default)]
+    pub title: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArrRelease {
+    #[serde(default)]
+    pub release_title: Option<String>,
+    #[serde(default)]
+    pub quality: Option<String>,
+}
+
+fn arr_kind(event_type: &str) -> EventKind {
+    match event_type {
+        "Grab" => EventKind::Grab,
+        "Download" => EventKind::Download,
+        "Rename" => EventKind::Rename,
+        "Health" | "HealthRestored" => EventKind::Health,
+        "Test" => EventKind::Test,
+        _ => EventKind::Other,
+    }
+}
+
+impl ArrPayload {
+    pub fn into_event(self, source: EventSource) -> ActivityEvent {
+        let kind = arr_kind(&self.event_type);
+        let media = self.series.as_ref().or(self.movie.as_ref());
+
+        let mut title = match media {
+            Some(m) => match m.year {
+                Some(year) if self.movie.is_some() => format!("{} ({})", m.title, year),
+                _ => m.title.clone(),
+            },
+            None => self.event_type.clone(),
+        };
+        if let Some(first) = self.episodes.first() {
+            title.push_str(&format!(" S{:02}E{:02}", first.season_number, first.episode_number));
+            if self.episodes.len() > 1 {
+                title.push_str(&format!(" (+{} more)", self.episodes.len() - 1));
+            } else if !first.title.is_empty() {
+                title.push_str(&format!(" - {}", first.title));
+            }
+        }
+
+        let message = match kind {
+            EventKind::Health => match (&self.level, &self.message) {
+                (Some(level), Some(msg)) => Some(format!("[{}] {}", level, msg)),
+                (_, msg) => msg.clone(),
+            },
+            EventKind::Download if self.is_upgrade => Some("Upgraded".to_string()),
+            _ => self.release.as_ref().and_then(|r| match (&r.release_title, &r.quality) {
+                (Some(t), Some(q)) => Some(format!("{} [{}]", t, q)),
+                (Some(t), None) => Some(t.clone()),
+                _ => None,
+            }),
+        };
+
+        let mut event = ActivityEvent::new(source, kind, &self.event_type, title);
+        event.message = message;
+        if let Some(m) = media {
+            event.media = MediaIds {
+                tmdb_id: m.tmdb_id.filter(|id| *id > 0),
+                tvdb_id: m.tvdb_id.filter(|id| *id > 0),
+                imdb_id: m.imdb_id.clone().filter(|id| !id.is_empty()),
+                plex_rating_key: None,
+            };
+        }
+        event
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlexPayload {
+    pub event: String,
+    #[serde(rename = "Account", default)]
+    pub account: Option<PlexAccount>,
+    #[serde(rename = "Metadata", default)]
+    pub metadata: Option<PlexMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlexAccount {
+    #[serde(default)]
+    pub title: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlexMetadata {
+    #[serde(default)]
+    pub rating_key: Option<String>,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub grandparent_title: Option<String>,
+    #[serde(rename = "Guid", default)]
+    pub guids: Vec<PlexGuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlexGuid {
+    pub id: String,
+}
+
+impl PlexPayload {
+    pub fn into_event(self) -> ActivityEvent {
+        let kind = match self.event.as_str() {
+            "media.play" => EventKind::MediaPlay,
+            "library.new" => EventKind::LibraryNew,
+            _ => EventKind::Other,
+        };
+
+        let title = match &self.metadata {
+            Some(m) => match &m.grandparent_title {
+                Some(show) => format!("{} - {}", show, m.title),
+                None => m.title.clone(),
+            },
+            None => self.event.clone(),
+        };
+
+        let mut event = ActivityEvent::new(EventSource::Plex, kind, &self.event, title);
+        if let Some(account) = self.account.filter(|a| !a.title.is_empty()) {
+            event.message = Some(account.title);
+        }
+        if let Some(m) = self.metadata {
+            let mut ids = MediaIds {
+                plex_rating_key: m.rating_key,
+                ..Default::default()
+            };
+            // Plex agent GUIDs look like "tmdb://603", "tvdb://81189", "imdb://tt0133093"
+            for guid in &m.guids {
+                if let Some((scheme, value)) = guid.id.split_once("://") {
+                    match scheme {
+                        "tmdb" => ids.tmdb_id = value.parse().ok(),
+                        "tvdb" => ids.tvdb_id = value.parse().ok(),
+                        "imdb" => ids.imdb_id = Some(value.to_string()),
+                        _ => {}
+                    }
+                }
+            }
+            event.media = ids;
+        }
+        event
+    }
+}
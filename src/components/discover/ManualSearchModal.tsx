@@ -1,15 +1,15 @@
-import {useMemo, useState} from "react";
+import {useEffect, useMemo, useState} from "react";
 import {
     Modal, ModalContent, ModalHeader, ModalBody,
     Spinner, Button, Chip, Tooltip, Input, Checkbox,
     Table, TableHeader, TableColumn, TableBody, TableRow, TableCell,
-    Popover, PopoverTrigger, PopoverContent
+    Popover, PopoverTrigger, PopoverContent, Pagination
 } from "@heroui/react";
 import {Icon} from "@iconify-icon/react";
 import {useQueryClient} from "@tanstack/react-query";
 import {toast} from "sonner";
 import {api} from "../../lib/api.ts";
-import {useSonarrReleases, useRadarrReleases} from "../../hooks/useDiscover.ts";
+import {useSonarrReleases, useRadarrReleases, type ReleaseFilters} from "../../hooks/useDiscover.ts";
 import type {ReleaseResource} from "../../lib/types.ts";
 
 interface SortDescriptor
@@ -168,13 +168,6 @@ export default function ManualSearchModal({
         seasonNumber: sonarrSeasonNumber
     } : null;
 
-    const {data: sonarrReleases, isLoading: sonarrLoading} = useSonarrReleases(sonarrParams);
-    const {data: radarrReleases, isLoading: radarrLoading} = useRadarrReleases(isRadarr && isOpen ? radarrMovieId! : null);
-
-    const releases = isSonarr ? sonarrReleases : radarrReleases;
-    const isLoading = isSonarr ? sonarrLoading : radarrLoading;
-    const service = isSonarr ? "sonarr" : "radarr";
-
     // Sorting
     const [sortDescriptor, setSortDescriptor] = useState<SortDescriptor>({column: "age", direction: "ascending"});
 
@@ -187,83 +180,41 @@ export default function ManualSearchModal({
     const [languageFilter, setLanguageFilter] = useState<Set<string>>(new Set());
     const [indexerFilter, setIndexerFilter] = useState<Set<string>>(new Set());
 
-    // Derive unique values for filter dropdowns
-    const uniqueValues = useMemo(() =>
-    {
-        if (!releases) return {sources: [], qualities: [], languages: [], indexers: []};
-        const sources = [...new Set(releases.map(r => r.protocol === "usenet" ? "Usenet" : "Torrent"))].sort();
-        const qualities = [...new Set(releases.map(r => r.quality?.quality?.name || "Unknown"))].sort();
-        const languages = [...new Set(releases.map(r => r.languages?.[0]?.name || "English"))].sort();
-        const indexers = [...new Set(releases.map(r => r.indexer))].sort();
-        return {sources, qualities, languages, indexers};
-    }, [releases]);
+    // Paging; any filter or sort change goes back to the first page
+    const [page, setPage] = useState(1);
+    useEffect(() => setPage(1), [titleSearch, sourceFilter, qualityFilter, languageFilter, indexerFilter, sortDescriptor]);
 
-    // Filter + sort releases
-    const processedReleases = useMemo(() =>
-    {
-        if (!releases) return [];
-        let filtered = [...releases];
+    // Filtering, sorting and paging happen on the server
+    const filters = useMemo<ReleaseFilters>(() => ({
+        page,
+        search: titleSearch,
+        protocols: [...sourceFilter].map(s => s.toLowerCase()),
+        qualities: [...qualityFilter],
+        languages: [...languageFilter],
+        indexers: [...indexerFilter],
+        sort: sortDescriptor.column === "peers" ? "seeders" : sortDescriptor.column as ReleaseFilters["sort"],
+        order: sortDescriptor.direction === "ascending" ? "asc" : "desc",
+    }), [page, titleSearch, sourceFilter, qualityFilter, languageFilter, indexerFilter, sortDescriptor]);
 
-        if (titleSearch.trim())
-        {
-            const q = titleSearch.toLowerCase();
-            filtered = filtered.filter(r => r.title.toLowerCase().includes(q));
-        }
-        if (sourceFilter.size > 0)
-        {
-            filtered = filtered.filter(r => sourceFilter.has(r.protocol === "usenet" ? "Usenet" : "Torrent"));
-        }
-        if (qualityFilter.size > 0)
-        {
-            filtered = filtered.filter(r => qualityFilter.has(r.quality?.quality?.name || "Unknown"));
-        }
-        if (languageFilter.size > 0)
-        {
-            filtered = filtered.filter(r => languageFilter.has(r.languages?.[0]?.name || "English"));
-        }
-        if (indexerFilter.size > 0)
-        {
-            filtered = filtered.filter(r => indexerFilter.has(r.indexer));
-        }
+    const {data: sonarrPage, isLoading: sonarrLoading} = useSonarrReleases(sonarrParams, filters);
+    const {data: radarrPage, isLoading: radarrLoading} = useRadarrReleases(isRadarr && isOpen ? radarrMovieId! : null, filters);
 
-        // Sort by column
-        filtered.sort((a, b) =>
-        {
-            const col = sortDescriptor.column as string;
-            const dir = sortDescriptor.direction === "ascending" ? 1 : -1;
-
-            let cmp = 0;
-            switch (col)
-            {
-                case "age":
-                    cmp = a.ageMinutes - b.ageMinutes;
-                    break;
-                case "title":
-                    cmp = a.title.localeCompare(b.title);
-                    break;
-                case "indexer":
-                    cmp = a.indexer.localeCompare(b.indexer);
-                    break;
-                case "size":
-                    cmp = a.size - b.size;
-                    break;
-                case "peers":
-                    cmp = (a.seeders ?? 0) - (b.seeders ?? 0);
-                    break;
-                case "quality":
-                    cmp = (a.quality?.quality?.name || "").localeCompare(b.quality?.quality?.name || "");
-                    break;
-                case "language":
-                    cmp = (a.languages?.[0]?.name || "").localeCompare(b.languages?.[0]?.name || "");
-                    break;
-            }
-            return cmp * dir;
-        });
+    const releasePage = isSonarr ? sonarrPage : radarrPage;
+    const releases = releasePage?.records;
+    const isLoading = isSonarr ? sonarrLoading : radarrLoading;
+    const service = isSonarr ? "sonarr" : "radarr";
 
-        return filtered;
-    }, [releases, titleSearch, sourceFilter, qualityFilter, languageFilter, indexerFilter, sortDescriptor]);
+    // Filter dropdowns list every value in the unfiltered results
+    const uniqueValues = useMemo(() =>
+    {
+        const facets = releasePage?.facets;
+        if (!facets) return {sources: [], qualities: [], languages: [], indexers: []};
+        const sources = [...new Set(facets.protocols.map(p => p === "usenet" ? "Usenet" : "Torrent"))].sort();
+        return {sources, qualities: facets.qualities, languages: facets.languages, indexers: facets.indexers};
+    }, [releasePage]);
 
-    const hasTorrents = releases?.some(r => r.protocol === "torrent") ?? false;
+    const totalPages = releasePage ? Math.max(1, Math.ceil(releasePage.totalRecords / releasePage.pageSize)) : 1;
+    const hasTorrents = releasePage?.facets.protocols.includes("torrent") ?? false;
     const activeFilterCount = [sourceFilter, qualityFilter, languageFilter, indexerFilter].filter(s => s.size > 0).length;
 
     const handleClose = () =>
@@ -277,6 +228,7 @@ export default function ManualSearchModal({
         setLanguageFilter(new Set());
         setIndexerFilter(new Set());
         setSortDescriptor({column: "age", direction: "ascending"});
+        setPage(1);
     };
 
     return (
@@ -285,11 +237,11 @@ export default function ManualSearchModal({
                 <ModalHeader className="flex items-center gap-3 pb-2">
                     <Icon icon="mdi:magnify" width="20"/>
                     <span>{title}</span>
-                    {releases && (
+                    {releasePage && (
                         <Chip size="sm" variant="flat" className="ml-2">
-                            {processedReleases.length === releases.length
-                                ? `${releases.length} results`
-                                : `${processedReleases.length} of ${releases.length} results`}
+                            {releasePage.totalRecords === releasePage.unfilteredRecords
+                                ? `${releasePage.unfilteredRecords} results`
+                                : `${releasePage.totalRecords} of ${releasePage.unfilteredRecords} results`}
                         </Chip>
                     )}
                 </ModalHeader>
@@ -299,7 +251,7 @@ export default function ManualSearchModal({
                             <Spinner size="lg"/>
                             <p className="text-sm text-default-400">Searching indexers...</p>
                         </div>
-                    ) : !releases || releases.length === 0 ? (
+                    ) : !releasePage || releasePage.unfilteredRecords === 0 ? (
                         <div className="flex flex-col items-center justify-center py-16 gap-3">
                             <Icon icon="mdi:magnify-close" width="48" className="text-default-300"/>
                             <p className="text-sm text-default-400">No releases found</p>
@@ -366,7 +318,7 @@ export default function ManualSearchModal({
                                     ) : (
                                         <TableColumn key="peers_hidden" width={0}> </TableColumn>
                                     )}
-                                    <TableColumn key="language" allowsSorting width={100}>
+                                    <TableColumn key="language" width={100}>
                                         <span className="inline-flex items-center">
                                             LANGUAGE
                                             <ColumnFilter label="Language" allValues={uniqueValues.languages} selected={languageFilter} onSelectionChange={setLanguageFilter}/>
@@ -380,7 +332,7 @@ export default function ManualSearchModal({
                                     </TableColumn>
                                     <TableColumn key="actions" width={80} align="center"> </TableColumn>
                                 </TableHeader>
-                                <TableBody items={processedReleases}>
+                                <TableBody items={releases ?? []} emptyContent="No releases match the filters">
                                     {(release) =>
                                     {
                                         const isRejected = (release.rejections?.length ?? 0) > 0;
@@ -443,6 +395,11 @@ export default function ManualSearchModal({
                                     }}
                                 </TableBody>
                             </Table>
+                            {totalPages > 1 && (
+                                <div className="flex justify-center py-3">
+                                    <Pagination size="sm" total={totalPages} page={page} onChange={setPage}/>
+                                </div>
+                            )}
                         </>
                     )}
                 </ModalBody>
@@ -1,7 +1,7 @@
 import {Button, Tooltip} from "@heroui/react";
 import {Icon} from "@iconify-icon/react";
 import {formatTimestamp} from "../../lib/utils.ts";
-import type {BifData, PlexStream, WatchPartyParticipant} from "../../lib/types.ts";
+import type {BifData, PlexStream, TrickplayCue, WatchPartyParticipant} from "../../lib/types.ts";
 import PlayerSettingsMenu, {type QualityGroup} from "./PlayerSettingsMenu.tsx";
 import SeekBar from "./SeekBar.tsx";
 import ParticipantsPopover from "./ParticipantsPopover.tsx";
@@ -29,6 +29,7 @@ interface PlayerControlsProps
     quality: string;
     qualityGroups?: QualityGroup[];
     bifData: BifData | null;
+    trickplay: TrickplayCue[] | null;
     onTogglePlay: () => void;
     onSeek: (time: number) => void;
     onVolumeChange: (vol: number) => void;
@@ -69,6 +70,7 @@ export default function PlayerControls({
                                            quality,
                                            qualityGroups,
                                            bifData,
+                                           trickplay,
                                            onTogglePlay,
                                            onSeek,
                                            onVolumeChange,
@@ -109,6 +111,7 @@ export default function PlayerControls({
                 bufferedTime={bufferedTime}
                 duration={duration}
                 bifData={bifData}
+                trickplay={trickplay}
                 onSeek={onSeek}
                 onDragChange={onDragChange}
             />
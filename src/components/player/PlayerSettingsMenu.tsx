@@ -20,6 +20,7 @@ export interface QualityGroup {
 }
 
 export const QUALITY_GROUPS: QualityGroup[] = [
+    {resolution: "auto", label: "Auto", variants: [], description: "Picked from your browser and connection"},
     {resolution: "original", label: "Original", variants: []},
     {resolution: "1080p", label: "1080p", variants: [
         {key: "1080p-high", label: "High", bitrate: "20 Mbps"},
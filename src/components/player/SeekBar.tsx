@@ -1,7 +1,8 @@
 import {useCallback, useEffect, useMemo, useRef, useState} from "react";
 import {formatTimestamp} from "../../lib/utils.ts";
 import {getBifImageAtTime} from "../../lib/bif-parser.ts";
-import type {BifData} from "../../lib/types.ts";
+import {getTrickplayCueAtTime} from "../../lib/trickplay.ts";
+import type {BifData, TrickplayCue} from "../../lib/types.ts";
 
 interface SeekBarProps
 {
@@ -9,13 +10,14 @@ interface SeekBarProps
     bufferedTime: number;
     duration: number;
     bifData: BifData | null;
+    trickplay?: TrickplayCue[] | null;
     onSeek: (time: number) => void;
     onDragChange?: (isDragging: boolean) => void;
 }
 
 const TOOLTIP_WIDTH = 160; // w-40 = 10rem = 160px
 
-export default function SeekBar({currentTime, bufferedTime, duration, bifData, onSeek, onDragChange}: SeekBarProps)
+export default function SeekBar({currentTime, bufferedTime, duration, bifData, trickplay, onSeek, onDragChange}: SeekBarProps)
 {
     const containerRef = useRef<HTMLDivElement>(null);
     const isDraggingRef = useRef(false);
@@ -114,6 +116,10 @@ export default function SeekBar({currentTime, bufferedTime, duration, bifData, o
         return () => URL.revokeObjectURL(url);
     }, [hoverTime, bifData, hoverPosition]);
 
+    const trickplayCue = hoverPosition !== null && !bifData && trickplay
+        ? getTrickplayCueAtTime(trickplay, hoverTime * 1000)
+        : null;
+
     return (
         <div
             ref={containerRef}
@@ -128,6 +134,22 @@ export default function SeekBar({currentTime, bufferedTime, duration, bifData, o
                     className="absolute bottom-full mb-2 pointer-events-none z-10 flex flex-col items-center"
                     style={tooltipStyle}
                 >
+                    {trickplayCue && (
+                        <div
+                            className="rounded border border-white/20 mb-1 shrink-0 overflow-hidden"
+                            style={{width: TOOLTIP_WIDTH, height: TOOLTIP_WIDTH * trickplayCue.height / trickplayCue.width}}
+                        >
+                            <div
+                                style={{
+                                    width: trickplayCue.width,
+                                    height: trickplayCue.height,
+                                    background: `url(${trickplayCue.url}) -${trickplayCue.x}px -${trickplayCue.y}px no-repeat`,
+                                    transform: `scale(${TOOLTIP_WIDTH / trickplayCue.width})`,
+                                    transformOrigin: "top left",
+                                }}
+                            />
+                        </div>
+                    )}
                     {thumbnailUrl && (
                         <img
                             src={thumbnailUrl}
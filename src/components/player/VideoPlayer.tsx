@@ -5,7 +5,7 @@ import {plexApi} from "../../lib/plex.ts";
 import {checkDirectPlayability, type PlayRecommendation} from "../../lib/codec-support.ts";
 import {QUALITY_GROUPS, ALL_QUALITY_KEYS} from "./PlayerSettingsMenu.tsx";
 import {parseBif} from "../../lib/bif-parser.ts";
-import type {PlexMediaItem, StreamInfo, PlexStream, BifData, WsMessage} from "../../lib/types.ts";
+import type {PlexMediaItem, StreamInfo, PlexStream, BifData, TrickplayCue, WsMessage} from "../../lib/types.ts";
 import {useAuth} from "../../providers/AuthProvider.tsx";
 import {usePlayer} from "../../providers/PlayerProvider.tsx";
 import {useWatchPartyContext} from "../../providers/WatchPartyProvider.tsx";
@@ -76,6 +76,7 @@ export default function VideoPlayer({item, onNext, onPrevious, hasNext, hasPrevi
         return saved && ALL_QUALITY_KEYS.includes(saved) ? saved : "original";
     });
     const [bifData, setBifData] = useState<BifData | null>(null);
+    const [trickplay, setTrickplay] = useState<TrickplayCue[] | null>(null);
     const [showQueue, setShowQueue] = useState(false);
     const [isSeeking, setIsSeeking] = useState(false);
     const [bufferingUsers, setBufferingUsers] = useState<Set<number>>(new Set());
@@ -219,8 +220,9 @@ export default function VideoPlayer({item, onNext, onPrevious, hasNext, hasPrevi
             state,
             time: Math.floor(video.currentTime * 1000),
             duration: Math.floor((video.duration || 0) * 1000),
+            playMethod: streamInfo?.type,
         }).catch(() => {});
-    }, [item.ratingKey, item.key, isGuest]);
+    }, [item.ratingKey, item.key, isGuest, streamInfo?.type]);
 
     // Set up Web Audio API for volume amplification (supports >100%).
     // createMediaElementSource permanently binds to the element — it can
@@ -270,13 +272,21 @@ export default function VideoPlayer({item, onNext, onPrevious, hasNext, hasPrevi
         };
     }, []); // eslint-disable-line react-hooks/exhaustive-deps
 
-    // Load BIF data for timeline previews
+    // Load BIF data for timeline previews, falling back to server-built sprites
     useEffect(() => {
         let cancelled = false;
+        setBifData(null);
+        setTrickplay(null);
         plexApi.getBifData(item.ratingKey).then((buffer) => {
-            if (cancelled || !buffer) return;
-            const parsed = parseBif(buffer);
-            setBifData(parsed);
+            if (cancelled) return;
+            const parsed = buffer ? parseBif(buffer) : null;
+            if (parsed) {
+                setBifData(parsed);
+                return;
+            }
+            plexApi.getTrickplay(item.ratingKey).then((cues) => {
+                if (!cancelled) setTrickplay(cues);
+            });
         });
         return () => { cancelled = true; };
     }, [item.ratingKey]);
@@ -485,7 +495,7 @@ export default function VideoPlayer({item, onNext, onPrevious, hasNext, hasPrevi
     const handleVideoError = useCallback(() => {
         if (isTransitioningRef.current) return;
         console.warn("Video playback error, falling back to transcode");
-        setQuality(prev => prev === "original" ? "1080p" : prev);
+        setQuality(prev => prev === "original" || prev === "auto" ? "1080p" : prev);
     }, []);
 
     // Timeline reporting every 10 seconds.
@@ -1214,7 +1224,12 @@ export default function VideoPlayer({item, onNext, onPrevious, hasNext, hasPrevi
                     }
                     toggleFullscreen();
                 }}
-            />
+            >
+                {/* Text subtitles arrive as WebVTT sidecars; bitmap ones are burned in by Plex */}
+                {streamInfo?.subtitles?.filter(s => s.selected).map(s => (
+                    <track key={s.id} kind="subtitles" src={s.url} srcLang={s.language ?? undefined} label={s.title ?? undefined} default/>
+                ))}
+            </video>
 
             <PlayerOverlay
                 item={item}
@@ -1253,6 +1268,7 @@ export default function VideoPlayer({item, onNext, onPrevious, hasNext, hasPrevi
                 quality={quality}
                 qualityGroups={availableQualityGroups}
                 bifData={bifData}
+                trickplay={trickplay}
                 onTogglePlay={togglePlay}
                 onSeek={handleSeek}
                 onVolumeChange={handleVolumeChange}
@@ -1,4 +1,4 @@
-import {useQuery} from "@tanstack/react-query";
+import {useQuery, keepPreviousData} from "@tanstack/react-query";
 import {api} from "../lib/api.ts";
 import type {
     TmdbMovieDetail,
@@ -10,7 +10,7 @@ import type {
     RadarrMovie,
     QualityProfile,
     RootFolder,
-    ReleaseResource,
+    ReleasePage,
     QueueResponse,
     ServiceUrls,
 } from "../lib/types.ts";
@@ -123,27 +123,56 @@ export function useRadarrMovieByTmdb(tmdbId: number | undefined) {
     return allMovies?.find(m => m.tmdbId === tmdbId);
 }
 
-// Release hooks (manual search)
-export function useSonarrReleases(params: { episodeId?: number; seriesId?: number; seasonNumber?: number } | null) {
-    const searchParams: Record<string, string> = {};
+// Release hooks (manual search). The server caches each search briefly and
+// filters, sorts and pages it, so the modal asks for one page at a time.
+const RELEASE_PAGE_SIZE = "50";
+
+export interface ReleaseFilters {
+    page: number;
+    search?: string;
+    indexers?: string[];
+    languages?: string[];
+    qualities?: string[];
+    /** `torrent` or `usenet` */
+    protocols?: string[];
+    sort?: "age" | "title" | "indexer" | "size" | "seeders" | "quality";
+    order?: "asc" | "desc";
+}
+
+function releaseParams(filters: ReleaseFilters): Record<string, string> {
+    const params: Record<string, string> = {page: String(filters.page), pageSize: RELEASE_PAGE_SIZE};
+    if (filters.search?.trim()) params.search = filters.search.trim();
+    if (filters.indexers?.length) params.indexer = filters.indexers.join(",");
+    if (filters.languages?.length) params.language = filters.languages.join(",");
+    if (filters.qualities?.length) params.quality = filters.qualities.join(",");
+    if (filters.protocols?.length) params.protocol = filters.protocols.join(",");
+    if (filters.sort) params.sort = filters.sort;
+    if (filters.order) params.order = filters.order;
+    return params;
+}
+
+export function useSonarrReleases(params: { episodeId?: number; seriesId?: number; seasonNumber?: number } | null, filters: ReleaseFilters) {
+    const searchParams = releaseParams(filters);
     if (params?.episodeId) searchParams.episodeId = String(params.episodeId);
     if (params?.seriesId) searchParams.seriesId = String(params.seriesId);
     if (params?.seasonNumber !== undefined) searchParams.seasonNumber = String(params.seasonNumber);
 
     return useQuery({
-        queryKey: ["sonarr", "releases", params],
-        queryFn: () => api.get<ReleaseResource[]>("/sonarr/release", searchParams),
+        queryKey: ["sonarr", "releases", params, filters],
+        queryFn: () => api.get<ReleasePage>("/sonarr/release", searchParams),
         enabled: !!params,
         staleTime: 0,
+        placeholderData: keepPreviousData,
     });
 }
 
-export function useRadarrReleases(movieId: number | null) {
+export function useRadarrReleases(movieId: number | null, filters: ReleaseFilters) {
     return useQuery({
-        queryKey: ["radarr", "releases", movieId],
-        queryFn: () => api.get<ReleaseResource[]>("/radarr/release", {movieId: String(movieId)}),
+        queryKey: ["radarr", "releases", movieId, filters],
+        queryFn: () => api.get<ReleasePage>("/radarr/release", {movieId: String(movieId), ...releaseParams(filters)}),
         enabled: !!movieId,
         staleTime: 0,
+        placeholderData: keepPreviousData,
     });
 }
 
@@ -29,6 +29,10 @@ async function request<T>(path: string, options: FetchOptions = {}): Promise<T>
         throw new Error(error.error || `Request failed: ${response.status}`);
     }
 
+    if (response.status === 204) {
+        return undefined as T;
+    }
+
     return response.json();
 }
 
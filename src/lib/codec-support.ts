@@ -58,3 +58,12 @@ export function checkDirectPlayability(
         reason: `Video codec "${videoCodec}" not supported by browser`,
     };
 }
+
+/** Codec and container lists sent to `/media/{id}/stream?profile=auto`. */
+export function browserCapabilities(): Record<string, string> {
+    return {
+        video_codecs: [...BROWSER_VIDEO_CODECS].join(","),
+        audio_codecs: [...BROWSER_AUDIO_CODECS].join(","),
+        containers: [...BROWSER_CONTAINERS].join(","),
+    };
+}
@@ -1,4 +1,6 @@
 import { api } from "./api.ts";
+import { browserCapabilities } from "./codec-support.ts";
+import { parseTrickplayVtt } from "./trickplay.ts";
 import type {
     PlexPin,
     PinPollResult,
@@ -6,6 +8,8 @@ import type {
     PlexLibrary,
     PlexMediaItem,
     LibraryItems,
+    LibraryFilters,
+    FirstCharacter,
     StreamInfo,
     SearchHub,
     TimelineUpdate,
@@ -15,6 +19,20 @@ import type {
     GenreGroup,
     PlexCollection,
     PlexPlaylist,
+    SmartPlaylistRules,
+    MetadataEdit,
+    MatchCandidate,
+    TmdbCollectionSource,
+    TmdbCollectionBuild,
+    WatchlistEntry,
+    WatchlistItem,
+    ViewingStats,
+    WatchHistoryEntry,
+    SessionsSnapshot,
+    PlayIntent,
+    OfflineJob,
+    ImageBlurhash,
+    TrickplayCue,
 } from "./types.ts";
 
 // Generate a unique session ID per browser tab so each tab gets its own
@@ -31,6 +49,24 @@ function getPlaybackSessionId(): string {
 export const playbackSessionId = getPlaybackSessionId();
 const sessionHeaders = { "X-Playarr-Session": playbackSessionId };
 
+function filterParams(filters: LibraryFilters): Record<string, string> {
+    return Object.fromEntries(
+        Object.entries(filters)
+            .filter(([, value]) => value !== undefined && value !== "")
+            .map(([key, value]) => [key, String(value)]),
+    );
+}
+
+/**
+ * The browser's downlink estimate in kbps for `quality=auto`. Browsers cap
+ * the estimate at 10 Mbps, so at the cap nothing is sent rather than
+ * ruling out direct play of anything above it.
+ */
+function measuredBandwidth(): Record<string, string> {
+    const downlink = (navigator as Navigator & { connection?: { downlink?: number } }).connection?.downlink;
+    return downlink && downlink < 10 ? { bandwidth: String(Math.round(downlink * 1000)) } : {};
+}
+
 // Status
 export const plexApi = {
     getStatus: () => api.get<{ setup_complete: boolean; version: string; debug: boolean }>("/status"),
@@ -53,13 +89,20 @@ export const plexApi = {
     // Libraries
     getLibraries: () => api.get<PlexLibrary[]>("/libraries"),
 
-    getLibraryItems: (key: string, start = 0, size = 50, sort?: string) =>
+    getLibraryItems: (key: string, start = 0, size = 50, sort?: string, filters: LibraryFilters = {}) =>
         api.get<LibraryItems>(`/libraries/${key}/items`, {
             start: start.toString(),
             size: size.toString(),
             ...(sort ? { sort } : {}),
+            ...filterParams(filters),
         }),
 
+    getLibraryFirstCharacters: (key: string, filters: LibraryFilters = {}) =>
+        api.get<FirstCharacter[]>(`/libraries/${key}/firstCharacter`, filterParams(filters)),
+
+    getRandomLibraryItem: (key: string, filters: LibraryFilters = {}) =>
+        api.get<PlexMediaItem>(`/libraries/${key}/random`, filterParams(filters)),
+
     getLibraryRecent: (key: string) =>
         api.get<PlexMediaItem[]>(`/libraries/${key}/recent`),
 
@@ -68,10 +111,33 @@ export const plexApi = {
 
     getChildren: (id: string) => api.get<PlexMediaItem[]>(`/media/${id}/children`),
 
+    // Metadata fixes (admin)
+    editMetadata: (id: string, edit: MetadataEdit) => api.put<PlexMediaItem>(`/media/${id}/metadata`, edit),
+
+    getMatches: (id: string, params: { title?: string; year?: string; agent?: string; language?: string } = {}) =>
+        api.get<MatchCandidate[]>(`/media/${id}/matches`, params),
+
+    applyMatch: (id: string, match: { guid: string; name: string; year?: number }) =>
+        api.put(`/media/${id}/match`, match),
+
+    unmatch: (id: string) => api.put(`/media/${id}/unmatch`),
+
+    refreshMetadata: (id: string) => api.post(`/media/${id}/refresh`),
+
+    analyzeMedia: (id: string) => api.post(`/media/${id}/analyze`),
+
+    getArtwork: (id: string, kind: "posters" | "arts") =>
+        api.get<Array<{ key: string; thumb?: string; selected?: boolean; provider?: string }>>(`/media/${id}/${kind}`),
+
+    selectArtwork: (id: string, kind: "poster" | "art", url: string) => api.put(`/media/${id}/${kind}`, {url}),
+
     getAllEpisodes: (showId: string) => api.get<PlexMediaItem[]>(`/media/${showId}/allLeaves`),
 
     getShowOnDeck: (id: string) => api.get<PlexMediaItem | null>(`/media/${id}/onDeck`),
 
+    // Resume point, intro/credits markers and the next episode, for an item, season or show
+    getPlayIntent: (id: string) => api.get<PlayIntent>(`/media/${id}/play-intent`),
+
     getRelated: (id: string) => api.get<PlexMediaItem[]>(`/media/${id}/related`),
 
     getTmdbVideos: (tmdbId: number, mediaType: "movie" | "tv") =>
@@ -83,6 +149,7 @@ export const plexApi = {
     getStreamUrl: (id: string, quality?: string, directPlay = true, directStream = false) =>
         api.get<StreamInfo>(`/media/${id}/stream`, {
             ...(quality ? { quality } : {}),
+            ...(quality === "auto" ? {...browserCapabilities(), ...measuredBandwidth()} : {}),
             direct_play: directPlay.toString(),
             ...(directStream ? { direct_stream: "true" } : {}),
         }, sessionHeaders),
@@ -101,6 +168,21 @@ export const plexApi = {
         }
     },
 
+    /** Server-built sprite sheet previews, for items without a Plex BIF index. */
+    getTrickplay: async (id: string): Promise<TrickplayCue[] | null> => {
+        try {
+            const response = await fetch(`/api/media/${id}/trickplay.vtt`, { credentials: "same-origin" });
+            if (!response.ok) return null;
+            return parseTrickplayVtt(await response.text());
+        } catch {
+            return null;
+        }
+    },
+
+    /** Blurhash placeholder for an image served by `/api/images`. */
+    getBlurhash: (source: "plex" | "tmdb" | "sonarr" | "radarr" | "remote", path: string) =>
+        api.get<ImageBlurhash>("/images/blurhash", {source, path}),
+
     // Hubs
     getContinueWatching: () => api.get<PlexMediaItem[]>("/hubs/continue-watching"),
 
@@ -124,12 +206,119 @@ export const plexApi = {
     getLibraryCollections: (key: string) =>
         api.get<PlexCollection[]>(`/libraries/${key}/collections`),
 
+    createCollection: (key: string, title: string, ratingKeys: number[]) =>
+        api.post<PlexCollection>(`/libraries/${key}/collections`, {title, ratingKeys}),
+
+    buildCollectionFromTmdb: (key: string, source: TmdbCollectionSource) =>
+        api.post<TmdbCollectionBuild>(`/libraries/${key}/collections/tmdb`, source),
+
+    deleteCollection: (key: string, id: string) => api.delete(`/libraries/${key}/collections/${id}`),
+
+    addToCollection: (key: string, id: string, ratingKeys: number[]) =>
+        api.post(`/libraries/${key}/collections/${id}/items`, {ratingKeys}),
+
+    removeFromCollection: (key: string, id: string, ratingKey: string) =>
+        api.delete(`/libraries/${key}/collections/${id}/items/${ratingKey}`),
+
+    setCollectionSort: (key: string, id: string, sort: "release" | "alpha" | "custom") =>
+        api.put(`/libraries/${key}/collections/${id}/sort`, {sort}),
+
+    setCollectionVisibility: (key: string, id: string, visibility: { library?: boolean; home?: boolean; shared?: boolean }) =>
+        api.put(`/libraries/${key}/collections/${id}/visibility`, visibility),
+
+    getCollectionPosters: (key: string, id: string) =>
+        api.get<Array<{ key: string; thumb?: string; selected?: boolean; provider?: string }>>(`/libraries/${key}/collections/${id}/posters`),
+
+    selectCollectionPoster: (key: string, id: string, url: string) =>
+        api.put(`/libraries/${key}/collections/${id}/poster`, {url}),
+
+    uploadCollectionPoster: async (key: string, id: string, file: File) => {
+        const response = await fetch(`/api/libraries/${key}/collections/${id}/poster`, {
+            method: "POST",
+            credentials: "same-origin",
+            headers: {"Content-Type": file.type},
+            body: file,
+        });
+        if (!response.ok) throw new Error(`Poster upload failed: ${response.status}`);
+    },
+
     getPlaylists: () => api.get<PlexPlaylist[]>("/hubs/playlists"),
 
     getPlaylistMetadata: (id: string) => api.get<PlexPlaylist>(`/hubs/playlists/${id}`),
 
     getPlaylistItems: (id: string) => api.get<PlexMediaItem[]>(`/hubs/playlists/${id}/items`),
 
+    createPlaylist: (title: string, ratingKeys: number[]) =>
+        api.post<PlexPlaylist>("/hubs/playlists", {title, ratingKeys}),
+
+    createSmartPlaylist: (title: string, smart: SmartPlaylistRules) =>
+        api.post<PlexPlaylist>("/hubs/playlists", {title, smart}),
+
+    renamePlaylist: (id: string, title: string) => api.put(`/hubs/playlists/${id}`, {title}),
+
+    deletePlaylist: (id: string) => api.delete(`/hubs/playlists/${id}`),
+
+    updateSmartPlaylist: (id: string, smart: SmartPlaylistRules) => api.put(`/hubs/playlists/${id}/rules`, smart),
+
+    addToPlaylist: (id: string, ratingKeys: number[]) =>
+        api.post<PlexMediaItem[]>(`/hubs/playlists/${id}/items`, {ratingKeys}),
+
+    removeFromPlaylist: (id: string, playlistItemId: number) =>
+        api.delete(`/hubs/playlists/${id}/items/${playlistItemId}`),
+
+    movePlaylistItem: (id: string, playlistItemId: number, after?: number) =>
+        api.put<PlexMediaItem[]>(`/hubs/playlists/${id}/items/${playlistItemId}/move`, {after}),
+
+    // Watchlist
+    getPlexWatchlist: () => api.get<PlexMediaItem[]>("/watchlist/plex"),
+
+    addToPlexWatchlist: (ratingKey: string) => api.put(`/watchlist/plex/${ratingKey}`),
+
+    removeFromPlexWatchlist: (ratingKey: string) => api.delete(`/watchlist/plex/${ratingKey}`),
+
+    getWatchlist: () => api.get<WatchlistItem[]>("/watchlist"),
+
+    addToWatchlist: (entry: Pick<WatchlistEntry, "tmdbId" | "mediaType"> & Partial<WatchlistEntry>) =>
+        api.post<WatchlistEntry>("/watchlist", entry),
+
+    removeFromWatchlist: (mediaType: WatchlistEntry["mediaType"], tmdbId: number) =>
+        api.delete(`/watchlist/${mediaType}/${tmdbId}`),
+
+    // Statistics (admin), and watch history (own, or anyone's for the admin)
+    getStats: (days = 30, user?: number) =>
+        api.get<ViewingStats>("/stats", user === undefined ? {days: String(days)} : {days: String(days), user: String(user)}),
+
+    getWatchHistory: (days = 30, limit = 50, user?: number) =>
+        api.get<WatchHistoryEntry[]>("/stats/history", {
+            days: String(days),
+            limit: String(limit),
+            ...(user === undefined ? {} : {user: String(user)}),
+        }),
+
+    // Now playing (admin). The socket pushes a SessionsSnapshot whenever it changes.
+    getSessions: () => api.get<SessionsSnapshot>("/sessions"),
+
+    sessionsSocketUrl: () => {
+        const protocol = window.location.protocol === "https:" ? "wss:" : "ws:";
+        return `${protocol}//${window.location.host}/api/sessions/ws`;
+    },
+
+    terminateSession: (sessionId: string, message?: string) =>
+        api.post(`/sessions/${encodeURIComponent(sessionId)}/terminate`, {message}),
+
+    // Offline downloads. Leave out the profile to keep the original file.
+    getOfflineJobs: (all = false) =>
+        api.get<OfflineJob[]>("/offline/jobs", all ? {all: "true"} : undefined),
+
+    getOfflineJob: (id: string) => api.get<OfflineJob>(`/offline/jobs/${id}`),
+
+    queueOfflineDownload: (ratingKey: string, profile?: string) =>
+        api.post<OfflineJob>("/offline/jobs", {ratingKey, profile}),
+
+    deleteOfflineJob: (id: string) => api.delete(`/offline/jobs/${id}`),
+
+    offlineFileUrl: (id: string) => `/api/offline/jobs/${id}/file`,
+
     // Search
     search: (query: string) => api.get<SearchHub[]>("/search", { q: query }),
 
@@ -141,6 +330,10 @@ export const plexApi = {
 
     unscrobble: (id: string) => api.put(`/player/unscrobble/${id}`, undefined, sessionHeaders),
 
+    // Half-star steps from 0.5 to 5; null clears the rating
+    rate: (id: string, stars: number | null) =>
+        api.put<{ success: boolean; userRating: number }>(`/player/rate/${id}`, {stars}),
+
     // Send stop signal via sendBeacon (reliable during page unload/SPA navigation)
     sendStopBeacon: (ratingKey: string, key: string, timeMs: number, durationMs: number) => {
         const body = JSON.stringify({
@@ -0,0 +1,40 @@
+import type { TrickplayCue } from "./types";
+
+function parseTimestamp(value: string): number {
+    const [h, m, s] = value.trim().split(":");
+    return (Number(h) * 3600 + Number(m) * 60 + Number(s)) * 1000;
+}
+
+/** Parse the server's trickplay WebVTT (`url#xywh=x,y,w,h` cues). */
+export function parseTrickplayVtt(text: string): TrickplayCue[] {
+    const cues: TrickplayCue[] = [];
+    for (const block of text.split(/\n\n+/)) {
+        const [timing, target] = block.trim().split("\n");
+        if (!timing?.includes("-->") || !target) continue;
+
+        const [start, end] = timing.split("-->");
+        const [url, fragment] = target.split("#xywh=");
+        const [x, y, width, height] = (fragment ?? "").split(",").map(Number);
+        if ([x, y, width, height].some(Number.isNaN)) continue;
+
+        cues.push({ startMs: parseTimestamp(start), endMs: parseTimestamp(end), url, x, y, width, height });
+    }
+    return cues;
+}
+
+export function getTrickplayCueAtTime(cues: TrickplayCue[], timeMs: number): TrickplayCue | null {
+    let lo = 0;
+    let hi = cues.length - 1;
+    let best: TrickplayCue | null = null;
+
+    while (lo <= hi) {
+        const mid = (lo + hi) >>> 1;
+        if (cues[mid].startMs <= timeMs) {
+            best = cues[mid];
+            lo = mid + 1;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    return best;
+}
@@ -193,6 +193,42 @@ export interface StreamInfo {
     session?: string;
     media: PlexMedia;
     part: PlexPart;
+    /** Text subtitle streams available as WebVTT sidecars. */
+    subtitles?: SidecarSubtitle[];
+    /** Transcode profile used (hls only). */
+    profile?: string;
+    /** Why Plex chose direct play, direct stream or transcode. */
+    decision?: StreamDecision | null;
+    /** Present when `quality=auto` let the server choose the mode. */
+    selection?: { mode: StreamInfo["type"]; profile?: string; reason: string } | null;
+}
+
+export interface SidecarSubtitle {
+    id: number;
+    codec: string;
+    language: string | null;
+    title: string | null;
+    selected: boolean;
+    url: string;
+}
+
+export interface StreamDecision {
+    general: { code: number | null; text: string | null };
+    direct_play: { code: number | null; text: string | null };
+    transcode: { code: number | null; text: string | null };
+    part_decision: string | null;
+    streams: { type: "video" | "audio" | "subtitle" | "other"; codec: string | null; decision: string | null }[];
+}
+
+/** One tile of a trickplay sprite sheet, from `/api/media/{id}/trickplay.vtt`. */
+export interface TrickplayCue {
+    startMs: number;
+    endMs: number;
+    url: string;
+    x: number;
+    y: number;
+    width: number;
+    height: number;
 }
 
 export interface BifIndex {
@@ -216,6 +252,28 @@ export interface LibraryItems {
     size: number;
 }
 
+export interface LibraryFilters {
+    unwatched?: boolean;
+    genre?: string;
+    yearFrom?: number;
+    yearTo?: number;
+    decade?: number;
+    contentRating?: string;
+    resolution?: "4k" | "1080" | "720" | "480" | "sd";
+    hdr?: boolean;
+    audioLanguage?: string;
+    actor?: number;
+    director?: number;
+    addedSince?: string;
+}
+
+export interface FirstCharacter {
+    character: string;
+    key: string;
+    size: number;
+    offset: number;
+}
+
 export interface SearchHub {
     type: string;
     hubIdentifier: string;
@@ -253,6 +311,8 @@ export interface TimelineUpdate {
     state: "playing" | "paused" | "stopped";
     time: number;
     duration: number;
+    /** Stream type in use, for the transcode statistics. */
+    playMethod?: StreamInfo["type"];
 }
 
 // Download types
@@ -418,6 +478,51 @@ export interface PlexCollection {
     maxYear?: number;
 }
 
+export interface MetadataEdit {
+    title?: string;
+    titleSort?: string;
+    originalTitle?: string;
+    summary?: string;
+    tagline?: string;
+    locks?: Record<string, boolean>;
+}
+
+export interface MatchCandidate {
+    guid: string;
+    name: string;
+    year?: number;
+    score?: number;
+    thumb?: string;
+    summary?: string;
+}
+
+export interface TmdbCollectionSource {
+    tmdbCollectionId?: number;
+    tmdbListId?: string;
+    title?: string;
+    collection?: number;
+}
+
+export interface TmdbCollectionEntry {
+    tmdbId: number;
+    title: string;
+    ratingKey?: string;
+}
+
+export interface TmdbCollectionBuild {
+    collection?: string;
+    title: string;
+    matched: TmdbCollectionEntry[];
+    missing: TmdbCollectionEntry[];
+}
+
+export interface SmartPlaylistRules extends LibraryFilters {
+    section: number;
+    itemType: "movie" | "episode";
+    sort?: string;
+    limit?: number;
+}
+
 export interface PlexPlaylist {
     ratingKey: string;
     key: string;
@@ -430,6 +535,129 @@ export interface PlexPlaylist {
     leafCount?: number;
 }
 
+export interface WatchlistEntry {
+    tmdbId: number;
+    mediaType: "movie" | "tv";
+    title: string;
+    year?: number;
+    posterPath?: string;
+    tvdbId?: number;
+    addedAt: string;
+}
+
+export interface WatchlistItem extends WatchlistEntry {
+    availability?: "not_tracked" | "monitored" | "in_library";
+    arr: { instance: string; id: number; monitored: boolean }[];
+}
+
+export interface WatchHistoryEntry {
+    userId: number;
+    userName?: string;
+    ratingKey: string;
+    title: string;
+    type: string;
+    grandparentTitle?: string;
+    showKey?: string;
+    thumb?: string;
+    viewedAt: string;
+}
+
+export interface ViewingStats {
+    from: string;
+    to: string;
+    plays: number;
+    hours: number;
+    users: { userId: number; userName?: string; plays: number; hours: number; lastWatched: string }[];
+    hoursByDay: { date: string; hours: number }[];
+    hoursByWeek: { date: string; hours: number }[];
+    topShows: { ratingKey: string; title: string; plays: number; hours: number }[];
+    topGenres: { genre: string; plays: number }[];
+    playMethods: { direct: number; directStream: number; transcode: number; transcodeRatio?: number };
+    concurrentStreams: { peak: number; peakAt?: string; byDay: { date: string; peak: number }[] };
+}
+
+export interface StreamDecision {
+    decision: "directplay" | "copy" | "transcode";
+    codec?: string;
+    detail?: string;
+}
+
+export interface NowPlayingSession {
+    sessionId: string;
+    user: { id?: number; name: string; thumb?: string };
+    ratingKey: string;
+    title: string;
+    type: string;
+    grandparentTitle?: string;
+    thumb?: string;
+    state: "playing" | "paused" | "buffering";
+    viewOffsetMs: number;
+    durationMs: number;
+    progressPercent: number;
+    player: {
+        title: string;
+        product: string;
+        platform: string;
+        address?: string;
+        local: boolean;
+        machineIdentifier: string;
+        /** Playback session id of the browser tab, for Playarr's own player. */
+        playarrTab?: string;
+    };
+    location: string;
+    bandwidthKbps: number;
+    decision: StreamDecision["decision"];
+    video: StreamDecision;
+    audio: StreamDecision;
+    transcode?: { speed?: number; progress?: number; throttled: boolean; hardware: boolean };
+}
+
+export interface SessionsSnapshot {
+    sessions: NowPlayingSession[];
+    streamCount: number;
+    transcodeCount: number;
+    bandwidthKbps: number;
+}
+
+export interface PlayIntent {
+    /** For a show or season, the episode to pick up at. */
+    item: PlexMediaItem;
+    resume: { offsetMs: number; prompt: boolean };
+    markers: {
+        intro?: { startMs: number; endMs: number };
+        credits?: { startMs: number; endMs: number };
+    };
+    /** When to show "up next", if there is a next episode. */
+    nextUpAtMs?: number;
+    next?: PlexMediaItem;
+}
+
+export interface ImageBlurhash {
+    blurhash: string;
+    /** Size of the original image. */
+    width: number;
+    height: number;
+}
+
+export interface OfflineJob {
+    id: string;
+    userId: number;
+    ratingKey: string;
+    title: string;
+    /** Transcode profile; absent for the original file. */
+    profile?: string;
+    status: "queued" | "downloading" | "completed" | "failed";
+    bytesDone: number;
+    /** Exact for originals, an estimate for transcodes. */
+    bytesTotal?: number;
+    progress: number;
+    error?: string;
+    fileName: string;
+    createdAt: string;
+    completedAt?: string;
+    expiresAt?: string;
+}
+
 // TMDB detail types
 export interface TmdbGenre {
     id: number;
@@ -624,6 +852,18 @@ export interface ReleaseResource {
     downloadUrl?: string;
     infoUrl?: string;
     indexerFlags?: number;
+    customFormatScore?: number;
+    rejectionSummary?: string;
+}
+
+export interface ReleasePage {
+    page: number;
+    pageSize: number;
+    totalRecords: number;
+    unfilteredRecords: number;
+    rejectedRecords: number;
+    facets: { indexers: string[]; languages: string[]; qualities: string[]; protocols: string[] };
+    records: ReleaseResource[];
 }
 
 // Queue types (download progress)
@@ -632,6 +872,9 @@ export interface QueueItem {
     seriesId?: number;
     episodeId?: number;
     movieId?: number;
+    series?: SonarrSeries;
+    episode?: SonarrEpisode;
+    movie?: RadarrMovie;
     title: string;
     status: string;
     trackedDownloadStatus?: string;
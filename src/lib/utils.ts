@@ -55,15 +55,18 @@ export function formatSpeed(bytesPerSecond: number): string {
  */
 export function plexImage(path: string | undefined, width = 300, height = 450): string {
     if (!path) return "";
-    return `/api/media/image?path=${encodeURIComponent(path)}&width=${width}&height=${height}`;
+    return `/api/images?source=plex&path=${encodeURIComponent(path)}&width=${width}&height=${height}`;
 }
 
 /**
- * Get TMDB image URL
+ * Get a TMDB image URL, resized and cached by the server. `size` takes TMDB's
+ * names ("w500", "h632", "original").
  */
 export function tmdbImage(path: string | null, size = "w500"): string {
     if (!path) return "";
-    return `https://image.tmdb.org/t/p/${size}${path}`;
+    const match = /^([wh])(\d+)$/.exec(size);
+    const dimension = match ? `&${match[1] === "w" ? "width" : "height"}=${match[2]}` : "";
+    return `/api/images?source=tmdb&path=${encodeURIComponent(path)}${dimension}`;
 }
 
 /**
@@ -99,5 +102,6 @@ export function shuffleArray<T>(arr: T[]): T[] {
  */
 export function arrPosterUrl(images: { coverType: string; url?: string; remoteUrl?: string }[]): string | null {
     const poster = images.find(img => img.coverType === "poster");
-    return poster?.remoteUrl || poster?.url || null;
+    if (poster?.remoteUrl) return `/api/images?source=remote&path=${encodeURIComponent(poster.remoteUrl)}&width=342`;
+    return poster?.url || null;
 }